@@ -0,0 +1,78 @@
+//! Throughput of card layout, pdf export and search. Run with `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use spellcard_generator::db::{Query, SimpleSpellDB, SpellDB};
+use spellcard_generator::render::{
+    build_spell_scene, write_to_pdf, OwnedFontConfig, RenderOptions,
+};
+use spellcard_generator::search_syntax;
+use spellcard_generator::spell::Spell;
+use spellcard_generator::system::Pathfinder2e;
+use std::rc::Rc;
+
+/// Cards in exported deck.
+const DECK_SIZE: usize = 200;
+
+fn load_db() -> SimpleSpellDB {
+    SimpleSpellDB::new(Rc::new(Pathfinder2e)).expect("Built-in data is valid")
+}
+
+/// Layout of every built-in spell. Fonts only measure text, so drawing is not included.
+fn layout(c: &mut Criterion) {
+    let spells = load_db().search(&Query::default());
+    let fonts = OwnedFontConfig::<()>::new(&mut (), &Pathfinder2e).unwrap();
+    let config = fonts.config();
+    let options = RenderOptions::default();
+    let mut group = c.benchmark_group("build_spell_scene");
+    group.sample_size(20);
+    group.bench_function("all spells", |b| {
+        b.iter(|| {
+            for spell in &spells {
+                let _ = black_box(build_spell_scene(&config, spell, &options));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn export(c: &mut Criterion) {
+    let spells: Vec<Spell> = load_db()
+        .search(&Query::default())
+        .iter()
+        .take(DECK_SIZE)
+        .map(|spell| Spell::clone(spell))
+        .collect();
+    let options = RenderOptions::default();
+    let mut group = c.benchmark_group("write_to_pdf");
+    group.sample_size(10);
+    group.bench_function(format!("{DECK_SIZE} cards"), |b| {
+        b.iter_batched(
+            Vec::new,
+            |mut output| write_to_pdf(&mut output, &spells, &options).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn search(c: &mut Criterion) {
+    let db = load_db();
+    let queries = [
+        ("everything", ""),
+        ("name", "fire"),
+        ("alternatives", "fire OR cold -cantrip"),
+        ("trait", "trait:cantrip"),
+        ("rank and tradition", "rank:3 tr:arcane"),
+        ("defense", "save:reflex"),
+    ];
+    let mut group = c.benchmark_group("search");
+    for (name, text) in queries {
+        let mut query = Query::default();
+        search_syntax::apply(text, &mut query);
+        group.bench_function(name, |b| b.iter(|| black_box(db.search(&query))));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, layout, export, search);
+criterion_main!(benches);
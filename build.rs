@@ -0,0 +1,19 @@
+use flate2::{write::GzEncoder, Compression};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Built-in spell data is embedded gzip compressed, see `system::pf2e`: raw dataset is
+/// several megabytes.
+fn main() {
+    let source = "nethys_data/spells.json";
+    println!("cargo:rerun-if-changed={source}");
+    let data = std::fs::read(source).expect("Built-in spell data must be present");
+    let mut encoder = GzEncoder::new(vec![], Compression::best());
+    encoder
+        .write_all(&data)
+        .expect("Compression to memory cannot fail");
+    let compressed = encoder.finish().expect("Compression to memory cannot fail");
+    let output = PathBuf::from(std::env::var_os("OUT_DIR").expect("OUT_DIR is set by cargo"));
+    std::fs::write(output.join("spells.json.gz"), compressed)
+        .expect("Unable to write compressed spell data");
+}
@@ -0,0 +1,127 @@
+use crate::spell::Spell;
+use anyhow::{anyhow, Result};
+use std::path::Path;
+
+/// Phrases replaced by default. Longer phrases must go before their prefixes.
+const DEFAULT_RULES: &[(&str, &str)] = &[
+    ("persistent damage", "persist. dmg"),
+    ("saving throws", "saves"),
+    ("saving throw", "save"),
+    ("Hit Points", "HP"),
+    ("Armor Class", "AC"),
+    ("Difficulty Class", "DC"),
+    ("spell attack roll", "spell atk"),
+    ("circumstance bonus", "circ. bonus"),
+    ("circumstance penalty", "circ. penalty"),
+    ("minutes", "min."),
+    ("minute", "min."),
+    ("feet", "ft."),
+];
+
+/// Dictionary of phrases replaced with shorter forms before layout,
+/// so more spells fit on single card.
+pub struct Abbreviations {
+    /// Pairs of (phrase, abbreviation), longest phrases first.
+    rules: Vec<(String, String)>,
+}
+
+impl Default for Abbreviations {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_RULES
+                .iter()
+                .map(|(phrase, short)| (phrase.to_string(), short.to_string()))
+                .collect(),
+        )
+    }
+}
+
+impl Abbreviations {
+    fn new(mut rules: Vec<(String, String)>) -> Self {
+        rules.sort_by_key(|(phrase, _)| std::cmp::Reverse(phrase.len()));
+        Self { rules }
+    }
+
+    /// Load dictionary from file, falling back to built-in one if file does not exist.
+    pub fn load_or_default(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::parse(&std::fs::read_to_string(path)?)
+            .map_err(|e| e.context(format!("Unable to load `{}`", path.display())))
+    }
+
+    /// Parse dictionary with one `phrase => abbreviation` rule per line.
+    ///
+    /// Empty lines and lines starting with `#` are ignored.
+    pub fn parse(source: &str) -> Result<Self> {
+        let rules = source
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(i, line)| {
+                let (phrase, short) = line
+                    .split_once("=>")
+                    .ok_or_else(|| anyhow!("Line {}: expected `phrase => abbreviation`", i + 1))?;
+                Ok((phrase.trim().to_string(), short.trim().to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::new(rules))
+    }
+
+    /// Copy of spell with abbreviations applied to all text which goes on a card body.
+    pub fn apply(&self, spell: &Spell) -> Spell {
+        let mut result = spell.clone();
+        result.description = self.apply_text(&spell.description);
+        result.heightened = spell.heightened.as_deref().map(|h| self.apply_text(h));
+        result.extras = spell.extras.iter().map(|e| self.apply_text(e)).collect();
+        for property in &mut result.properties {
+            property.value = self.apply_text(&property.value);
+        }
+        result
+    }
+
+    pub fn apply_text(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (phrase, short) in &self.rules {
+            text = replace_phrase(&text, phrase, short);
+            text = replace_phrase(&text, &capitalize(phrase), &capitalize(short));
+        }
+        text
+    }
+}
+
+/// Replace occurrences of `phrase` which are not part of a longer word.
+fn replace_phrase(text: &str, phrase: &str, replacement: &str) -> String {
+    if phrase.is_empty() {
+        return text.to_string();
+    }
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find(phrase) {
+        let (before, after) = (&rest[..index], &rest[index + phrase.len()..]);
+        let is_word_start = !before
+            .chars()
+            .next_back()
+            .is_some_and(char::is_alphanumeric);
+        let is_word_end = !after.chars().next().is_some_and(char::is_alphanumeric);
+        result.push_str(before);
+        if is_word_start && is_word_end {
+            result.push_str(replacement);
+        } else {
+            result.push_str(phrase);
+        }
+        rest = after;
+    }
+    result.push_str(rest);
+    result
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
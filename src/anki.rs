@@ -0,0 +1,64 @@
+use crate::html::{actions_text, escape, spell_html, spell_type_name};
+use crate::spell::Spell;
+use anyhow::Result;
+use std::collections::HashSet;
+use std::io::Write;
+
+/// Write Anki notes for `spells`, going into deck `name`: spell name, rank and action cost
+/// on the front, and the whole spell as html on the back, with traits as tags. Spells
+/// selected several times get a single note.
+///
+/// Output is a tab separated text file with Anki file headers, imported with `File > Import`
+/// into `Basic` note type.
+pub fn write_notes<'a, T: Write>(
+    mut output: T,
+    name: &str,
+    spells: impl IntoIterator<Item = &'a Spell>,
+) -> Result<()> {
+    writeln!(output, "#separator:tab")?;
+    writeln!(output, "#html:true")?;
+    writeln!(output, "#notetype:Basic")?;
+    writeln!(output, "#deck:{}", single_line(name))?;
+    writeln!(output, "#tags column:3")?;
+    let mut written = HashSet::new();
+    for spell in spells {
+        if !written.insert(spell.id) {
+            continue;
+        }
+        let mut cost = vec![format!(
+            "{} {}",
+            spell_type_name(&spell.spell_type),
+            spell.level
+        )];
+        cost.extend(actions_text(&spell.actions));
+        let front = format!(
+            "<b>{}</b><br>{}",
+            escape(&spell.name),
+            escape(&cost.join(", "))
+        );
+        let tags = spell
+            .traits
+            .iter()
+            .map(|name| name.split_whitespace().collect::<Vec<_>>().join("_"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(
+            output,
+            "{}\t{}\t{}",
+            field(&front),
+            field(&spell_html(spell)),
+            field(&tags)
+        )?;
+    }
+    Ok(())
+}
+
+/// Field quoted for Anki, which reads quotes doubled inside quoted fields.
+fn field(text: &str) -> String {
+    format!("\"{}\"", single_line(text).replace('"', "\"\""))
+}
+
+/// Html text with line breaks and tabs, which separate notes and fields, turned into spaces.
+fn single_line(text: &str) -> String {
+    text.replace(['\n', '\r', '\t'], " ")
+}
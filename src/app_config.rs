@@ -0,0 +1,248 @@
+use crate::config;
+use crate::output::write_replacing;
+use crate::render::{PageLayout, PageSize, RenderOptions, ThemeFonts};
+use anyhow::{anyhow, bail, Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// Render defaults and data read from `config.toml` by both command line and app window.
+/// Every setting is optional, missing ones keep built-in values:
+///
+/// ```toml
+/// [page]
+/// size = "Letter"
+///
+/// # Everything in millimeters.
+/// [card]
+/// width = 63
+/// height = 88
+/// margin = 1      # between card border and text
+/// spacing = 2     # between cards on a page
+///
+/// # Relative paths start at config directory.
+/// [fonts]
+/// text = "fonts/Text.ttf"
+/// bold = "fonts/Bold.ttf"
+/// italic = "fonts/Italic.ttf"
+///
+/// [data]
+/// bundle = "/home/me/homebrew.json"
+///
+/// # Saved by app window, see `write_setting`.
+/// [app]
+/// system = "Pathfinder 2e"
+/// touch_mode = false
+/// network = false
+///
+/// [sync]
+/// url = "https://example.org/manifest.json"
+/// key = "…"   # 64 hex digits
+/// ```
+///
+/// Config is the defaults layer of `OptionsChain`. It only replaces built-in values, so
+/// settings an export profile changes, like card size, still take precedence.
+#[derive(Debug, Default, Clone)]
+pub struct AppConfig {
+    pub page_size: Option<PageSize>,
+    pub card_width: Option<f32>,
+    pub card_height: Option<f32>,
+    pub card_margin: Option<f32>,
+    pub card_spacing: Option<f32>,
+    /// Fonts replacing built-in Helvetica. Template fonts of a profile replace them in turn.
+    pub fonts: ThemeFonts,
+    /// Bundle file loaded after ones of bundles directory, see
+    /// `SimpleSpellDB::set_extra_bundles`.
+    pub bundle: Option<PathBuf>,
+    /// Name of game system selected in settings.
+    pub system: Option<String>,
+    /// Whether touch friendly layout is enabled in settings.
+    pub touch_mode: bool,
+    /// Whether networked features are allowed, see `network::from_settings`. Off if missing.
+    pub network: bool,
+    /// Url of bundle sync manifest, see `sync::Manifest`.
+    pub sync_url: Option<String>,
+    /// Public key of sync manifest publisher, see `sync::PublisherKey`.
+    pub sync_key: Option<String>,
+}
+
+impl AppConfig {
+    /// Read config from `path`, built-in defaults if there is no such file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default())
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let base = path.parent().unwrap_or(Path::new("."));
+        Self::parse(&source, base).with_context(|| format!("Unable to read {}", path.display()))
+    }
+
+    /// Parse config, with relative paths resolved against `base` directory. Font files are
+    /// read right away.
+    pub fn parse(source: &str, base: &Path) -> Result<Self> {
+        let table = source.parse::<Table>()?;
+        let mut result = Self::default();
+        for (name, value) in &table {
+            let Value::Table(section) = value else {
+                bail!("`{name}` must be a section, like `[{name}]`");
+            };
+            for key in section.keys() {
+                let known = match name.as_str() {
+                    "page" => ["size"].as_slice(),
+                    "card" => &["width", "height", "margin", "spacing"],
+                    "fonts" => &["text", "bold", "italic"],
+                    "data" => &["bundle"],
+                    "app" => &["system", "touch_mode", "network"],
+                    "sync" => &["url", "key"],
+                    _ => bail!("Unknown section `[{name}]`"),
+                };
+                if !known.contains(&key.as_str()) {
+                    bail!("Unknown setting `{name}.{key}`");
+                }
+            }
+        }
+        if let Some(size) = string(&table, "page", "size")? {
+            result.page_size =
+                Some(PageSize::parse(size).ok_or_else(|| anyhow!("Unknown page size `{size}`"))?);
+        }
+        result.card_width = length(&table, "width")?;
+        result.card_height = length(&table, "height")?;
+        result.card_margin = length(&table, "margin")?;
+        result.card_spacing = length(&table, "spacing")?;
+        if matches!(result.card_width, Some(width) if width <= 0.0)
+            || matches!(result.card_height, Some(height) if height <= 0.0)
+        {
+            bail!("Card size must be above zero");
+        }
+        let built_in = PageLayout::default();
+        let margin = result.card_margin.unwrap_or(built_in.margin);
+        let width = result.card_width.unwrap_or(built_in.card_width);
+        let height = result.card_height.unwrap_or(built_in.card_height);
+        if margin * 2.0 >= width.min(height) {
+            bail!("`card.margin` must be less than half of card width and height");
+        }
+        let font = |key| -> Result<Option<&'static [u8]>> {
+            let Some(path) = string(&table, "fonts", key)? else {
+                return Ok(None);
+            };
+            let path = base.join(path);
+            let data = std::fs::read(&path)
+                .with_context(|| format!("Unable to read font {}", path.display()))?;
+            Ok(Some(Box::leak(data.into_boxed_slice())))
+        };
+        result.fonts = ThemeFonts {
+            text: font("text")?,
+            bold: font("bold")?,
+            italic: font("italic")?,
+        };
+        result.bundle = string(&table, "data", "bundle")?.map(|path| base.join(path));
+        result.system = string(&table, "app", "system")?.map(str::to_string);
+        result.touch_mode = boolean(&table, "app", "touch_mode")?;
+        result.network = boolean(&table, "app", "network")?;
+        result.sync_url = string(&table, "sync", "url")?.map(str::to_string);
+        result.sync_key = string(&table, "sync", "key")?.map(str::to_string);
+        Ok(result)
+    }
+
+    /// Replace built-in values in `options` with ones set in config. Values differing from
+    /// built-in ones, like set by an export profile, are kept.
+    pub fn apply(&self, options: &mut RenderOptions) {
+        let built_in = PageLayout::default();
+        let page = &mut options.page;
+        replace_built_in(&mut page.page_size, built_in.page_size, self.page_size);
+        replace_built_in(&mut page.card_width, built_in.card_width, self.card_width);
+        replace_built_in(
+            &mut page.card_height,
+            built_in.card_height,
+            self.card_height,
+        );
+        replace_built_in(&mut page.margin, built_in.margin, self.card_margin);
+        replace_built_in(
+            &mut page.card_spacing,
+            built_in.card_spacing,
+            self.card_spacing,
+        );
+        let fonts = &mut options.theme.fonts;
+        fonts.text = self.fonts.text.or(fonts.text);
+        fonts.bold = self.fonts.bold.or(fonts.bold);
+        fonts.italic = self.fonts.italic.or(fonts.italic);
+    }
+}
+
+/// Save `value` as `key` of `section` in config file at `path`, like touch mode toggled in
+/// app window. Rest of the file, comments included, is kept as it is.
+pub fn write_setting(
+    path: &Path,
+    section: &str,
+    key: &str,
+    value: impl Into<toml_edit::Value>,
+) -> Result<()> {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(error.into()),
+    };
+    let mut document = source
+        .parse::<toml_edit::DocumentMut>()
+        .with_context(|| format!("Unable to read {}", path.display()))?;
+    let Some(table) = document
+        .entry(section)
+        .or_insert_with(toml_edit::table)
+        .as_table_like_mut()
+    else {
+        bail!("`{section}` must be a section, like `[{section}]`");
+    };
+    table.insert(key, toml_edit::value(value));
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    write_replacing(path, |mut file| {
+        Ok(file.write_all(document.to_string().as_bytes())?)
+    })
+}
+
+/// Save setting into `config.toml` of config directory, see `write_setting`.
+pub fn save_setting(section: &str, key: &str, value: impl Into<toml_edit::Value>) -> Result<()> {
+    let path = config::app_config_path().ok_or_else(|| anyhow!("Config directory is unknown"))?;
+    write_setting(&path, section, key, value)
+}
+
+fn replace_built_in<T: PartialEq>(value: &mut T, built_in: T, configured: Option<T>) {
+    if let (true, Some(configured)) = (*value == built_in, configured) {
+        *value = configured;
+    }
+}
+
+fn string<'a>(table: &'a Table, section: &str, key: &str) -> Result<Option<&'a str>> {
+    match table.get(section).and_then(|section| section.get(key)) {
+        None => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(_) => bail!("`{section}.{key}` must be text in quotes"),
+    }
+}
+
+/// Flag, off if missing.
+fn boolean(table: &Table, section: &str, key: &str) -> Result<bool> {
+    match table.get(section).and_then(|section| section.get(key)) {
+        None => Ok(false),
+        Some(Value::Boolean(value)) => Ok(*value),
+        Some(_) => bail!("`{section}.{key}` must be `true` or `false`"),
+    }
+}
+
+/// Length in `Mm` of `[card]` section.
+fn length(table: &Table, key: &str) -> Result<Option<f32>> {
+    let value = match table.get("card").and_then(|section| section.get(key)) {
+        None => return Ok(None),
+        Some(Value::Integer(value)) => *value as f32,
+        Some(Value::Float(value)) => *value as f32,
+        Some(_) => bail!("`card.{key}` must be a number of millimeters"),
+    };
+    if value < 0.0 {
+        bail!("`card.{key}` must not be negative");
+    }
+    Ok(Some(value))
+}
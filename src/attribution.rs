@@ -0,0 +1,52 @@
+use crate::spell::Spell;
+use crate::system::GameSystem;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Paragraph of license attribution page.
+#[derive(Debug, Clone)]
+pub struct Attribution {
+    pub heading: String,
+    pub text: String,
+}
+
+/// License attribution of content used in deck. Built-in spells get license notice of
+/// `system` with list of their sources, bundle spells get license text of their bundle.
+pub fn deck_attribution<'a>(
+    spells: impl IntoIterator<Item = &'a Spell>,
+    system: &dyn GameSystem,
+) -> Vec<Attribution> {
+    let mut builtin_sources = BTreeSet::new();
+    let mut bundles = BTreeMap::new();
+    for spell in spells {
+        match &spell.bundle {
+            Some(bundle) => {
+                bundles.insert(bundle.as_str(), spell.license.as_deref());
+            }
+            None => {
+                builtin_sources.insert(spell.source.as_str());
+            }
+        }
+    }
+
+    let mut result = vec![];
+    if !builtin_sources.is_empty() {
+        let sources = builtin_sources.into_iter().collect::<Vec<_>>();
+        result.push(Attribution {
+            heading: system.name().to_string(),
+            text: format!(
+                "{}\n\nSources: {}.",
+                system.license_notice(),
+                sources.join(", ")
+            ),
+        });
+    }
+    for (bundle, license) in bundles {
+        result.push(Attribution {
+            heading: format!("Bundle {bundle}"),
+            text: license
+                .unwrap_or("No license information given in bundle.")
+                .to_string(),
+        });
+    }
+    result
+}
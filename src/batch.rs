@@ -0,0 +1,186 @@
+use crate::app_config::AppConfig;
+use crate::db::SimpleSpellDB;
+use crate::deck::{self, DeckEntry, DeckFile, DeckMetadata};
+use crate::json_utils::ObjectExt;
+use crate::output::write_replacing;
+use crate::profiles::Profile;
+use crate::render::{write_groups_to_pdf, DeckGroup};
+use crate::spell::Spell;
+use anyhow::{anyhow, bail, Context, Result};
+use json::JsonValue;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Deck listed in a batch manifest, see `parse_manifest`.
+#[derive(Debug, Clone)]
+pub struct BatchDeck {
+    /// Deck file saved by the app, or plain spell list read by `deck::resolve_spell_list`.
+    pub file: PathBuf,
+    /// Name on divider page. Deck name saved in the file, or file name, if not given.
+    pub name: Option<String>,
+    /// Pdf of the deck then decks are rendered separately, next to `file` if not given.
+    pub output: Option<PathBuf>,
+}
+
+/// Deck with spells found, ready to render.
+struct LoadedDeck {
+    spells: Vec<(Rc<Spell>, DeckEntry)>,
+    metadata: DeckMetadata,
+    export: Option<Profile>,
+    output: PathBuf,
+}
+
+/// Parse manifest listing decks, with relative paths resolved against `base` directory:
+///
+/// ```json
+/// {"decks": [
+///     {"file": "alice.json", "name": "Alice", "output": "alice.pdf"},
+///     "bob.txt"
+/// ]}
+/// ```
+///
+/// Decks given as text only name their file.
+pub fn parse_manifest(source: &str, base: &Path) -> Result<Vec<BatchDeck>> {
+    let manifest = json::parse(source)?;
+    let JsonValue::Object(manifest) = manifest else {
+        bail!("Manifest must be an object with `decks` array");
+    };
+    let decks = manifest
+        .get("decks")
+        .ok_or_else(|| anyhow!("Field `decks` missing"))?;
+    let JsonValue::Array(decks) = decks else {
+        bail!("Field `decks` must be an array");
+    };
+    decks
+        .iter()
+        .enumerate()
+        .map(|(i, deck)| {
+            parse_deck(deck, base).with_context(|| format!("Then parsing deck {}", i + 1))
+        })
+        .collect()
+}
+
+fn parse_deck(deck: &JsonValue, base: &Path) -> Result<BatchDeck> {
+    let deck = match deck {
+        JsonValue::Short(_) | JsonValue::String(_) => BatchDeck {
+            file: base.join(deck.as_str().unwrap_or_default()),
+            name: None,
+            output: None,
+        },
+        JsonValue::Object(deck) => BatchDeck {
+            file: base.join(deck.get_typed::<String>("file")?),
+            name: deck.get_typed_maybe("name")?,
+            output: deck
+                .get_typed_maybe::<String>("output")?
+                .map(|output| base.join(output)),
+        },
+        _ => bail!("Expected file name or object"),
+    };
+    Ok(deck)
+}
+
+/// Render every deck of `decks` to its own pdf, or all into `combined` pdf with a divider page
+/// in front of every deck. Deck files use export settings saved in them, spell lists and
+/// decks saved without settings use `profile`, or defaults without it.
+///
+/// Spells which are not found are logged and skipped, as in `render` command.
+pub fn render_batch(
+    db: &SimpleSpellDB,
+    config: &AppConfig,
+    decks: &[BatchDeck],
+    combined: Option<&Path>,
+    profile: Option<&Profile>,
+) -> Result<()> {
+    let decks = decks
+        .iter()
+        .map(|deck| {
+            load_deck(db, deck, profile)
+                .with_context(|| format!("Unable to load deck `{}`", deck.file.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let Some(combined) = combined else {
+        for deck in decks {
+            write_replacing(&deck.output, |file| {
+                deck::write_spells_pdf(
+                    file,
+                    db,
+                    config,
+                    &deck.spells,
+                    deck.metadata,
+                    deck.export.as_ref(),
+                )
+            })?;
+            tracing::info!("Rendered `{}`", deck.output.display());
+        }
+        return Ok(());
+    };
+    let resolved = decks
+        .iter()
+        .map(|deck| {
+            deck::deck_options(
+                db,
+                config,
+                &deck.spells,
+                deck.metadata.clone(),
+                deck.export.as_ref(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let groups = resolved
+        .iter()
+        .map(|(spells, options)| DeckGroup {
+            spells: spells.clone(),
+            options,
+        })
+        .collect::<Vec<_>>();
+    let title = combined
+        .file_stem()
+        .map_or("Spells".into(), |stem| stem.to_string_lossy());
+    write_replacing(combined, |file| {
+        Ok(write_groups_to_pdf(file, &title, &groups)?)
+    })?;
+    tracing::info!(decks = groups.len(), "Rendered `{}`", combined.display());
+    Ok(())
+}
+
+fn load_deck(
+    db: &SimpleSpellDB,
+    deck: &BatchDeck,
+    profile: Option<&Profile>,
+) -> Result<LoadedDeck> {
+    let source = std::fs::read_to_string(&deck.file)?;
+    let is_deck_file = deck
+        .file
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    let (resolved, mut metadata, export) = if is_deck_file {
+        let deck_file = DeckFile::parse(&source)?;
+        let resolved = deck::resolve(db, deck_file.entries);
+        (resolved, deck_file.metadata, deck_file.export)
+    } else {
+        let resolved = deck::resolve_spell_list(db, &source);
+        (resolved, DeckMetadata::default(), None)
+    };
+    let stem = deck
+        .file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if let Some(name) = &deck.name {
+        metadata.name = name.clone();
+    } else if metadata.name.trim().is_empty() {
+        metadata.name = stem;
+    }
+    for mismatch in &resolved.mismatches {
+        tracing::warn!("{}: {mismatch}", metadata.name);
+    }
+    Ok(LoadedDeck {
+        spells: resolved.spells,
+        metadata,
+        export: export.or_else(|| profile.cloned()),
+        output: deck
+            .output
+            .clone()
+            .unwrap_or_else(|| deck.file.with_extension("pdf")),
+    })
+}
@@ -0,0 +1,534 @@
+//! Drawing of scenes with cairo, used by preview and printing.
+
+use crate::rich_text::{Color, FontKind, FontProvider, Primitive, RasterImage, Scene};
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::vector::Vector2F;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+/// Smallest gap between card and preview edges, in pixels.
+const PREVIEW_PADDING: f64 = 30.0;
+/// Resolution of printer simulated by `PrintSimulation::Laser`, in dots per inch.
+const LASER_DPI: f64 = 600.0;
+/// Number of rasters kept by `paint_laser_dots`, enough for every preview on screen.
+const LASER_CACHE_SIZE: usize = 4;
+
+/// Rasterized scene kept by `paint_laser_dots`.
+struct LaserDots {
+    /// See `scene_key`.
+    key: u64,
+    width: i32,
+    height: i32,
+    surface: cairo::ImageSurface,
+}
+
+thread_local! {
+    /// Recently drawn laser rasters, oldest first. Previews are redrawn on every resize
+    /// and pointer motion over traits, while their scene stays the same.
+    static LASER_CACHE: RefCell<Vec<LaserDots>> = const { RefCell::new(Vec::new()) };
+}
+
+/// How preview simulates the printed card, to spot what gets lost on paper before printing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PrintSimulation {
+    /// Card as designed, in screen colors.
+    #[default]
+    Off,
+    /// Colors turned into shades of gray of the same lightness.
+    Grayscale,
+    /// Black and white laser printer: card is rasterized at `LASER_DPI` with line widths of
+    /// the pdf, and every dot is either black or white. Light colors and borders, and lines
+    /// thinner than half a dot, disappear, small text loses its thin strokes.
+    Laser,
+}
+
+impl PrintSimulation {
+    pub const ALL: [PrintSimulation; 3] = [
+        PrintSimulation::Off,
+        PrintSimulation::Grayscale,
+        PrintSimulation::Laser,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PrintSimulation::Off => "Screen colors",
+            PrintSimulation::Grayscale => "Grayscale",
+            PrintSimulation::Laser => "600 dpi laser printer",
+        }
+    }
+}
+
+/// Show `message` in place of a card which cannot be laid out.
+pub fn draw_error(context: &cairo::Context, message: &str) {
+    context.set_source_rgb(0.8, 0.0, 0.0);
+    context.set_font_size(14.0);
+    context.move_to(30.0, 30.0);
+    context.show_text(message).expect("Cannot render text");
+}
+
+/// Draw `scene` scaled to fit `width` by `height` surface, centered, on white card background.
+/// If `overlay` is set, section bands and overflow are drawn on top, over the card as changed
+/// by `simulation`.
+///
+/// Returns transform from scene to surface coordinates, to find what is under the pointer.
+pub fn draw_scene(
+    context: &cairo::Context,
+    width: i32,
+    height: i32,
+    scene: Scene<'_, CairoFont>,
+    overlay: bool,
+    simulation: PrintSimulation,
+) -> cairo::Matrix {
+    let width = width as f64;
+    let height = height as f64;
+    let (min_x, max_x, min_y, max_y) = card_bounds(&scene);
+    let scene_width = max_x - min_x;
+    let scene_height = max_y - min_y;
+    let padding = PREVIEW_PADDING;
+    let x_scale = (width - padding * 2.0) / scene_width;
+    let y_scale = (height - padding * 2.0) / scene_height;
+    let (scale, x_offset, y_offset) = if x_scale < y_scale {
+        (
+            x_scale,
+            padding - min_x,
+            (height - scene_height * x_scale) * 0.5 - min_y,
+        )
+    } else {
+        (
+            y_scale,
+            (width - scene_width * y_scale) * 0.5 - min_x,
+            padding - min_y,
+        )
+    };
+
+    let (x_offset, y_offset) = snap_offset(context, x_offset, y_offset);
+    context.translate(x_offset, y_offset);
+    context.scale(scale, scale);
+    paint_card(context, &scene, overlay, simulation);
+    cairo::Matrix::new(scale, 0.0, 0.0, scale, x_offset, y_offset)
+}
+
+/// Draw `scene` at `pixels_per_pt`, centered in `width` by `height` surface, on white card
+/// background. Card larger than the surface is aligned to its top left corner instead, so
+/// card start stays visible. `overlay` and `simulation` are drawn, and transform returned,
+/// like by `draw_scene`.
+pub fn draw_scene_at_scale(
+    context: &cairo::Context,
+    width: i32,
+    height: i32,
+    scene: Scene<'_, CairoFont>,
+    overlay: bool,
+    simulation: PrintSimulation,
+    pixels_per_pt: f64,
+) -> cairo::Matrix {
+    let (min_x, max_x, min_y, max_y) = card_bounds(&scene);
+    let left = ((width as f64 - (max_x - min_x) * pixels_per_pt) * 0.5).max(PREVIEW_PADDING);
+    let top = ((height as f64 - (max_y - min_y) * pixels_per_pt) * 0.5).max(PREVIEW_PADDING);
+    let (left, top) = snap_offset(
+        context,
+        left - min_x * pixels_per_pt,
+        top - min_y * pixels_per_pt,
+    );
+    context.translate(left, top);
+    context.scale(pixels_per_pt, pixels_per_pt);
+    paint_card(context, &scene, overlay, simulation);
+    cairo::Matrix::new(pixels_per_pt, 0.0, 0.0, pixels_per_pt, left, top)
+}
+
+/// Card outline as `(min_x, max_x, min_y, max_y)`: the extent of shapes, text is inside
+/// of it.
+fn card_bounds(scene: &Scene<'_, CairoFont>) -> (f64, f64, f64, f64) {
+    scene
+        .primitives
+        .iter()
+        .filter(|primitive| !matches!(primitive, Primitive::Text(_)))
+        .map(|primitive| primitive.bounds())
+        .fold(
+            (f64::MAX, f64::MIN, f64::MAX, f64::MIN),
+            |(min_x, max_x, min_y, max_y), b| {
+                (
+                    min_x.min(b.min_x() as f64),
+                    max_x.max(b.max_x() as f64),
+                    min_y.min(b.min_y() as f64),
+                    max_y.max(b.max_y() as f64),
+                )
+            },
+        )
+}
+
+/// Card background, `scene` changed by `simulation` and, if `overlay` is set, layout overlay
+/// in scene coordinates.
+fn paint_card(
+    context: &cairo::Context,
+    scene: &Scene<'_, CairoFont>,
+    overlay: bool,
+    simulation: PrintSimulation,
+) {
+    let (min_x, max_x, min_y, max_y) = card_bounds(scene);
+    if simulation == PrintSimulation::Laser {
+        paint_laser_dots(context, scene);
+    } else {
+        context.set_source_rgb(1.0, 1.0, 1.0);
+        context.rectangle(min_x, min_y, max_x - min_x, max_y - min_y);
+        context.fill().expect("Could not fill");
+        paint_primitives(context, scene, true);
+    }
+    if simulation == PrintSimulation::Grayscale {
+        // Saturation of gray source is zero, lightness of the card is kept.
+        context.save().expect("Could not save context");
+        context.rectangle(min_x, min_y, max_x - min_x, max_y - min_y);
+        context.clip();
+        context.set_operator(cairo::Operator::HslSaturation);
+        context.set_source_rgb(0.5, 0.5, 0.5);
+        context.paint().expect("Could not paint");
+        context.restore().expect("Could not restore context");
+    }
+
+    if overlay {
+        draw_layout_overlay(context, scene);
+    }
+}
+
+/// Draw `scene` in its own coordinates, measured in `Pt` from top left corner of card content.
+pub fn paint_scene(context: &cairo::Context, scene: &Scene<'_, CairoFont>) {
+    paint_primitives(context, scene, false);
+}
+
+/// Draw primitives of `scene`. Lines without thickness are half a point wide, or with
+/// `crisp` set a single device pixel wide and centered on pixels, so thin borders stay sharp
+/// on screens of any scale instead of smearing over two pixels. Filled paths, like sigils and
+/// boxes, keep their points, so snapping never shifts a fill off its outline.
+fn paint_primitives(context: &cairo::Context, scene: &Scene<'_, CairoFont>, crisp: bool) {
+    for primitive in &scene.primitives {
+        match primitive {
+            Primitive::Text(text) => {
+                let color = text.color;
+                context.set_source_rgb(color.r as f64, color.g as f64, color.b as f64);
+                context.set_font_size(text.font_size as f64 * 0.97);
+                context.set_font_face(&text.font.font_ref().font);
+                let pos = text.rect.lower_left();
+                context.move_to(pos.x() as f64, pos.y() as f64);
+                context.show_text(&text.text).expect("Cannot render text");
+            }
+            Primitive::Path(poly) => {
+                let Some((first, rest)) = poly.points.split_first() else {
+                    continue;
+                };
+                let hairline = crisp && poly.stroke && poly.fill.is_none() && poly.thickness <= 0.0;
+                let thickness = if poly.thickness > 0.0 {
+                    poly.thickness as f64
+                } else if hairline {
+                    device_pixel(context)
+                } else {
+                    0.5
+                };
+                context.set_line_width(thickness);
+                let point = |point: Vector2F| {
+                    let (x, y) = (point.x() as f64, point.y() as f64);
+                    if hairline {
+                        snap_to_pixel_center(context, x, y)
+                    } else {
+                        (x, y)
+                    }
+                };
+                let (x, y) = point(*first);
+                context.move_to(x, y);
+                for &next in rest {
+                    let (x, y) = point(next);
+                    context.line_to(x, y);
+                }
+                if let Some(fill) = poly.fill {
+                    context.set_source_rgb(fill.r as f64, fill.g as f64, fill.b as f64);
+                    context.fill_preserve().expect("Could not fill");
+                }
+                if poly.stroke {
+                    context.set_source_rgb(0.0, 0.0, 0.0);
+                    context.stroke().expect("Cannot draw line");
+                } else {
+                    context.new_path();
+                }
+            }
+            Primitive::Rect(rect, fill) => {
+                context.set_source_rgb(fill.r as f64, fill.g as f64, fill.b as f64);
+                context.rectangle(
+                    rect.origin_x() as f64,
+                    rect.origin_y() as f64,
+                    rect.width() as f64,
+                    rect.height() as f64,
+                );
+                context.fill().expect("Could not fill");
+            }
+            Primitive::Image(rect, image) => {
+                let Some(surface) = image_surface(image) else {
+                    continue;
+                };
+                context.save().expect("Could not save context");
+                context.translate(rect.origin_x() as f64, rect.origin_y() as f64);
+                context.scale(
+                    rect.width() as f64 / image.width as f64,
+                    rect.height() as f64 / image.height as f64,
+                );
+                context
+                    .set_source_surface(&surface, 0.0, 0.0)
+                    .expect("Could not use image");
+                context.paint().expect("Could not draw image");
+                context.restore().expect("Could not restore context");
+            }
+        }
+    }
+}
+
+/// Card of `scene` with white background, rasterized like `PrintSimulation::Laser` and drawn
+/// over card bounds. Rasters are cached by scene content and size, see `LASER_CACHE`.
+fn paint_laser_dots(context: &cairo::Context, scene: &Scene<'_, CairoFont>) {
+    let (min_x, max_x, min_y, max_y) = card_bounds(scene);
+    let dots_per_pt = LASER_DPI / 72.0;
+    let width = ((max_x - min_x) * dots_per_pt).ceil() as i32;
+    let height = ((max_y - min_y) * dots_per_pt).ceil() as i32;
+    let key = scene_key(scene);
+    let surface = LASER_CACHE.with_borrow_mut(|cache| {
+        let found = cache
+            .iter()
+            .position(|dots| (dots.key, dots.width, dots.height) == (key, width, height));
+        let dots = match found {
+            Some(index) => cache.remove(index),
+            None => LaserDots {
+                key,
+                width,
+                height,
+                surface: rasterize_laser_dots(scene, width, height)?,
+            },
+        };
+        let surface = dots.surface.clone();
+        if cache.len() == LASER_CACHE_SIZE {
+            cache.remove(0);
+        }
+        cache.push(dots);
+        Some(surface)
+    });
+    let Some(surface) = surface else {
+        return;
+    };
+    context.save().expect("Could not save context");
+    context.translate(min_x, min_y);
+    context.scale(1.0 / dots_per_pt, 1.0 / dots_per_pt);
+    context
+        .set_source_surface(&surface, 0.0, 0.0)
+        .expect("Could not use image");
+    context.paint().expect("Could not draw image");
+    context.restore().expect("Could not restore context");
+}
+
+/// Card of `scene` rasterized at `LASER_DPI` into `width` by `height` dots, each either black
+/// or white.
+fn rasterize_laser_dots(
+    scene: &Scene<'_, CairoFont>,
+    width: i32,
+    height: i32,
+) -> Option<cairo::ImageSurface> {
+    let (min_x, _, min_y, _) = card_bounds(scene);
+    let dots_per_pt = LASER_DPI / 72.0;
+    let mut surface = cairo::ImageSurface::create(cairo::Format::Rgb24, width, height).ok()?;
+    {
+        let dots = cairo::Context::new(&surface).expect("Could not draw card");
+        dots.scale(dots_per_pt, dots_per_pt);
+        dots.translate(-min_x, -min_y);
+        dots.set_source_rgb(1.0, 1.0, 1.0);
+        dots.paint().expect("Could not paint");
+        // Printer gets line widths of the pdf, not ones of the screen.
+        paint_primitives(&dots, scene, false);
+    }
+    let stride = surface.stride() as usize;
+    // Surface is marked dirty once `data` is dropped.
+    if let Ok(mut data) = surface.data() {
+        threshold(&mut data, stride, width as usize);
+    }
+    Some(surface)
+}
+
+/// Hash of everything `paint_primitives` draws of `scene`. Fonts are told apart by their
+/// files, which stay loaded while fonts are in use.
+fn scene_key(scene: &Scene<'_, CairoFont>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let floats = |values: &[f32], hasher: &mut DefaultHasher| {
+        values.iter().for_each(|value| value.to_bits().hash(hasher))
+    };
+    let rect = |rect: RectF| {
+        [
+            rect.origin_x(),
+            rect.origin_y(),
+            rect.width(),
+            rect.height(),
+        ]
+    };
+    let color = |color: Color| [color.r, color.g, color.b];
+    for primitive in &scene.primitives {
+        match primitive {
+            Primitive::Text(text) => {
+                (0, text.text.as_ref(), text.font.data().as_ptr()).hash(&mut hasher);
+                floats(&rect(text.rect), &mut hasher);
+                floats(&[text.font_size], &mut hasher);
+                floats(&color(text.color), &mut hasher);
+            }
+            Primitive::Path(poly) => {
+                (1, poly.points.len(), poly.stroke, poly.fill.is_some()).hash(&mut hasher);
+                for point in &poly.points {
+                    floats(&[point.x(), point.y()], &mut hasher);
+                }
+                floats(&color(poly.fill.unwrap_or(Color::BLACK)), &mut hasher);
+                floats(&[poly.thickness], &mut hasher);
+            }
+            Primitive::Rect(area, fill) => {
+                2.hash(&mut hasher);
+                floats(&rect(*area), &mut hasher);
+                floats(&color(*fill), &mut hasher);
+            }
+            Primitive::Image(area, image) => {
+                (3, image.width, &image.pixels).hash(&mut hasher);
+                floats(&rect(*area), &mut hasher);
+            }
+        }
+    }
+    hasher.finish()
+}
+
+/// Turn every pixel of `Rgb24` image `data` black or white, by its lightness.
+fn threshold(data: &mut [u8], stride: usize, width: usize) {
+    const WHITE: u32 = 0x00ff_ffff;
+    for row in data.chunks_mut(stride) {
+        for pixel in row[..width * 4].chunks_exact_mut(4) {
+            let [_, r, g, b] = u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]])
+                .to_be_bytes()
+                .map(u32::from);
+            let lightness = (r * 299 + g * 587 + b * 114) / 1000;
+            let dot = if lightness < 128 { 0 } else { WHITE };
+            pixel.copy_from_slice(&dot.to_ne_bytes());
+        }
+    }
+}
+
+/// Device scale of the surface drawn to, like 2 on HiDPI monitors. GTK sets it to the scale
+/// factor of the monitor, rounded up for fractional scales, which the compositor then scales
+/// down.
+fn device_scale(context: &cairo::Context) -> (f64, f64) {
+    context.target().device_scale()
+}
+
+/// Width of one device pixel in user units of `context`.
+fn device_pixel(context: &cairo::Context) -> f64 {
+    let (scale, _) = device_scale(context);
+    // Current transformation does not include device scale, which is kept by the surface.
+    let (dx, dy) = context
+        .user_to_device_distance(1.0, 0.0)
+        .unwrap_or((1.0, 0.0));
+    1.0 / (dx.hypot(dy) * scale)
+}
+
+/// User space point `x`, `y` moved to the center of the device pixel it falls into.
+fn snap_to_pixel_center(context: &cairo::Context, x: f64, y: f64) -> (f64, f64) {
+    let (x_scale, y_scale) = device_scale(context);
+    let (device_x, device_y) = context.user_to_device(x, y);
+    let snap = |value: f64, scale: f64| ((value * scale).floor() + 0.5) / scale;
+    context
+        .device_to_user(snap(device_x, x_scale), snap(device_y, y_scale))
+        .unwrap_or((x, y))
+}
+
+/// Translation by `x`, `y` of untransformed `context` rounded to whole device pixels, so card
+/// edges fall on pixel boundaries.
+fn snap_offset(context: &cairo::Context, x: f64, y: f64) -> (f64, f64) {
+    let (x_scale, y_scale) = device_scale(context);
+    (
+        (x * x_scale).round() / x_scale,
+        (y * y_scale).round() / y_scale,
+    )
+}
+
+/// Copy `image` into cairo surface, `None` for empty or malformed images.
+fn image_surface(image: &RasterImage) -> Option<cairo::ImageSurface> {
+    if image.width == 0 || image.pixels.len() != image.width * image.height * 3 {
+        return None;
+    }
+    let mut surface = cairo::ImageSurface::create(
+        cairo::Format::Rgb24,
+        image.width as i32,
+        image.height as i32,
+    )
+    .ok()?;
+    let stride = surface.stride() as usize;
+    // Surface is marked dirty once `data` is dropped.
+    let mut data = surface.data().ok()?;
+    for (y, row) in image.pixels.chunks(image.width * 3).enumerate() {
+        for (x, rgb) in row.chunks(3).enumerate() {
+            let pixel = u32::from_be_bytes([0, rgb[0], rgb[1], rgb[2]]);
+            data[y * stride + x * 4..][..4].copy_from_slice(&pixel.to_ne_bytes());
+        }
+    }
+    drop(data);
+    Some(surface)
+}
+
+/// Draw translucent bands over each section, labeled with its height,
+/// and mark part of content which does not fit into bounding box.
+fn draw_layout_overlay(context: &cairo::Context, scene: &Scene<'_, CairoFont>) {
+    const PALETTE: [(f64, f64, f64); 4] = [
+        (0.2, 0.4, 1.0),
+        (0.2, 0.8, 0.3),
+        (1.0, 0.7, 0.0),
+        (0.7, 0.3, 0.9),
+    ];
+    let width = scene.bounding_box.width() as f64;
+    let box_height = scene.bounding_box.height() as f64;
+    context.set_font_size(5.0);
+    for (i, section) in scene.sections.iter().enumerate() {
+        let (r, g, b) = PALETTE[i % PALETTE.len()];
+        let top = section.top as f64;
+        let height = (section.bottom - section.top) as f64;
+        context.set_source_rgba(r, g, b, 0.2);
+        context.rectangle(0.0, top, width, height);
+        context.fill().expect("Could not fill");
+        context.set_source_rgba(r * 0.5, g * 0.5, b * 0.5, 1.0);
+        context.move_to(width - 40.0, top + 5.0);
+        context
+            .show_text(&format!("{} {:.1}pt", section.name, height))
+            .expect("Cannot render text");
+    }
+
+    let content_height = scene.content_height as f64;
+    let overflow = content_height - box_height;
+    if overflow > 0.0 {
+        context.set_source_rgba(1.0, 0.0, 0.0, 0.3);
+        context.rectangle(0.0, box_height, width, overflow);
+        context.fill().expect("Could not fill");
+    }
+    context.set_source_rgb(0.8, 0.0, 0.0);
+    context.move_to(0.0, box_height + 8.0);
+    context
+        .show_text(&format!(
+            "used {content_height:.1}pt of {box_height:.1}pt, overflow {:.1}pt",
+            overflow.max(0.0)
+        ))
+        .expect("Cannot render text");
+}
+
+/// Font drawn through cairo, loaded from the same font files as used for pdf.
+pub struct CairoFont {
+    font: cairo::FontFace,
+}
+
+impl FontProvider for CairoFont {
+    type Init = freetype::Library;
+
+    fn build_font(provider_source: &mut Self::Init, font: FontKind) -> anyhow::Result<Self> {
+        let bytes = font.bytes();
+        let mut data = Vec::with_capacity(bytes.len());
+        data.extend_from_slice(bytes);
+        let data = Rc::new(data);
+        let font = provider_source.new_memory_face(data, 0)?;
+        Ok(CairoFont {
+            font: cairo::FontFace::create_from_ft(&font)?,
+        })
+    }
+}
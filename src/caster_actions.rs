@@ -0,0 +1,33 @@
+use crate::json_utils::{JsonValueExt, ObjectExt};
+use crate::spell::Actions;
+use anyhow::{Context, Result};
+
+/// Actions listed on caster reference card, see `static/caster_actions.json`.
+const DATA: &str = include_str!("../static/caster_actions.json");
+
+/// Common action of spellcasters, like `Sustain`.
+#[derive(Debug, Clone)]
+pub struct CasterAction {
+    pub name: String,
+    pub actions: Actions,
+    pub traits: Vec<String>,
+    /// Short markdown summary of the rules.
+    pub text: String,
+}
+
+/// Actions summarized on caster reference card, in order they are printed.
+pub fn caster_actions() -> Result<Vec<CasterAction>> {
+    let data = json::parse(DATA).context("Unable to parse caster actions")?;
+    data.as_array()?
+        .iter()
+        .map(|action| {
+            let action = action.as_object()?;
+            Ok(CasterAction {
+                name: action.get_typed("name")?,
+                actions: Actions::parse(action.get_typed("actions")?),
+                traits: action.get_typed_maybe("traits")?.unwrap_or_default(),
+                text: action.get_typed("text")?,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,419 @@
+use crate::app_config::AppConfig;
+use crate::batch;
+use crate::csv;
+use crate::db::{Query, SimpleSpellDB, SpellDB};
+use crate::deck::{self, DeckMetadata};
+use crate::handout::{self, HandoutFormat};
+use crate::output::write_replacing;
+use crate::profiles;
+use crate::render::Renderer;
+use crate::search_syntax;
+use crate::spell::{Spell, Tradition};
+use crate::system::GameSystem;
+use crate::validate;
+use anyhow::{anyhow, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use json::JsonValue;
+use std::io::{IsTerminal, Read, Write};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Command line arguments. Without a subcommand the app window is opened.
+#[derive(Parser)]
+#[command(version, about = "Printable spell cards for Pathfinder 2e")]
+pub struct Cli {
+    /// Log debug messages and enable layout debugging tools.
+    #[arg(long, global = true)]
+    pub debug: bool,
+    /// Export profile to use, by name.
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+    /// Game system, like `Starfinder 2e playtest`. Defaults to the one selected last time.
+    #[arg(long, global = true)]
+    pub system: Option<String>,
+    /// Serve http api instead of opening the window, on `127.0.0.1:8787` unless address
+    /// is given. Only in builds with `serve` feature.
+    #[arg(long, value_name = "ADDRESS")]
+    pub serve: Option<Option<String>>,
+    /// Origin of web pages allowed to call http api, like `http://localhost:30000`.
+    /// Browsers are refused without it.
+    #[arg(long, value_name = "ORIGIN", requires = "serve")]
+    pub serve_origin: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Render spells to pdf without opening the window.
+    Render {
+        /// File listing a spell name or id per line, `-` reads standard input.
+        input: String,
+        /// Pdf file to write.
+        #[arg(short, long, required_unless_present = "dry_run")]
+        output: Option<PathBuf>,
+        /// Print spells which do not fit a single card and the page count, without writing pdf.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print spells matching filters, all spells without any.
+    Search {
+        /// Words of spell name or traits. Search syntax of the app, like `rank:3`, works too.
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        rank: Option<u8>,
+        /// Spell must be on this tradition's list. Repeat to require several.
+        #[arg(long, value_parser = parse_tradition)]
+        tradition: Vec<Tradition>,
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+    /// Write spells as a Markdown or plain text document, for handouts instead of cards.
+    Export {
+        /// File listing a spell name or id per line, `-` reads standard input.
+        input: String,
+        /// File to write, standard output by default.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Markdown)]
+        format: ExportFormat,
+    },
+    /// Check homebrew bundle file for errors, reporting every one with a suggested fix.
+    Validate {
+        /// Bundle json file, like the ones in bundles directory.
+        file: PathBuf,
+    },
+    /// Render several decks listed in a manifest file, see `batch::parse_manifest`.
+    Batch {
+        /// Json file listing deck files and spell lists, like
+        /// `{"decks": [{"file": "alice.json", "output": "alice.pdf"}, "bob.txt"]}`.
+        manifest: PathBuf,
+        /// Write all decks into this pdf, each after a divider page with its name, instead of
+        /// a pdf per deck.
+        #[arg(long, value_name = "PDF")]
+        combined: Option<PathBuf>,
+    },
+    /// Render deck file to pdf again every time it, or a bundle file, changes.
+    Watch {
+        /// Deck file saved by the app, or written by hand.
+        deck: PathBuf,
+        /// Pdf file to write, next to the deck by default.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// How `search` prints found spells.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Format {
+    /// Aligned columns, for reading.
+    Table,
+    /// Array of spell objects.
+    Json,
+    /// Header row followed by a row per spell.
+    Csv,
+    /// Spell id per line, to pipe into `render -`.
+    Ids,
+}
+
+/// How `export` writes spells.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    Markdown,
+    /// Plain text without markup.
+    Text,
+}
+
+/// Render spells listed in `input` file, see `deck::resolve_spell_list`, to pdf at `output`.
+/// Spells which are not found are reported and skipped.
+pub fn render(
+    db: &SimpleSpellDB,
+    config: &AppConfig,
+    input: &str,
+    output: &Path,
+    profile: Option<&str>,
+) -> Result<()> {
+    let (deck, export) = read_spell_list(db, input, profile)?;
+    let (cards, options) = deck::deck_options(
+        db,
+        config,
+        &deck.spells,
+        DeckMetadata::default(),
+        export.as_ref(),
+    );
+    // Page counter is only shown to people watching, not written into logs of scripts.
+    let terminal = std::io::stderr().is_terminal();
+    write_replacing(output, |file| {
+        Renderer::new(&options)
+            .on_page_finished(|page, pages| {
+                if terminal {
+                    eprint!("\rWriting page {page} of {pages}");
+                    if page == pages {
+                        eprintln!();
+                    }
+                }
+                ControlFlow::Continue(())
+            })
+            .render(file, cards)?;
+        Ok(())
+    })?;
+    tracing::info!(
+        spells = deck.spells.len(),
+        "Rendered to `{}`",
+        output.display()
+    );
+    Ok(())
+}
+
+/// Print spells of list `input` taking a double card, ones not fitting even that, and the
+/// number of pages, as `render` would lay them out.
+pub fn dry_run(
+    db: &SimpleSpellDB,
+    config: &AppConfig,
+    input: &str,
+    profile: Option<&str>,
+) -> Result<()> {
+    let (deck, export) = read_spell_list(db, input, profile)?;
+    let report = deck::dry_run_spells(
+        db,
+        config,
+        &deck.spells,
+        DeckMetadata::default(),
+        export.as_ref(),
+    )?;
+    println!("Spells exceeding one card ({}):", report.double.len());
+    for name in &report.double {
+        println!("    {name}");
+    }
+    println!("Spells exceeding two cards ({}):", report.too_long.len());
+    for (name, overflow) in &report.too_long {
+        println!("    {name}, {overflow:.0} pt too long");
+    }
+    println!("Estimated pages: {}", report.page_count);
+    Ok(())
+}
+
+/// Write spells listed in `input` file as a document to `output`, standard output without it.
+pub fn export(
+    db: &SimpleSpellDB,
+    input: &str,
+    output: Option<&Path>,
+    format: ExportFormat,
+) -> Result<()> {
+    let (deck, _) = read_spell_list(db, input, None)?;
+    let format = match format {
+        ExportFormat::Markdown => HandoutFormat::Markdown,
+        ExportFormat::Text => HandoutFormat::Text,
+    };
+    let spells = deck.spells.iter().map(|(spell, _)| &**spell);
+    match output {
+        Some(output) => write_replacing(output, |file| {
+            let mut file = std::io::BufWriter::new(file);
+            handout::write_handout(&mut file, format, spells)?;
+            writeln!(file)?;
+            file.flush()?;
+            Ok(())
+        })?,
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            handout::write_handout(&mut stdout, format, spells)?;
+            writeln!(stdout)?;
+        }
+    }
+    Ok(())
+}
+
+/// Spells of list `input`, with mismatched lines logged, and export profile named `profile`.
+fn read_spell_list(
+    db: &SimpleSpellDB,
+    input: &str,
+    profile: Option<&str>,
+) -> Result<(deck::ResolvedDeck, Option<profiles::Profile>)> {
+    let source = if input == "-" {
+        let mut source = String::new();
+        std::io::stdin()
+            .read_to_string(&mut source)
+            .context("Unable to read standard input")?;
+        source
+    } else {
+        std::fs::read_to_string(input).with_context(|| format!("Unable to read `{input}`"))?
+    };
+    let deck = deck::resolve_spell_list(db, &source);
+    for mismatch in &deck.mismatches {
+        tracing::warn!("{mismatch}");
+    }
+    Ok((deck, find_profile(profile)?))
+}
+
+/// Export profile named `name`, `None` without a name.
+fn find_profile(name: Option<&str>) -> Result<Option<profiles::Profile>> {
+    let Some(name) = name else {
+        return Ok(None);
+    };
+    let profile = profiles::find_profile(name)?;
+    Ok(Some(
+        profile.ok_or_else(|| anyhow!("Profile `{name}` not found"))?,
+    ))
+}
+
+/// Render decks listed in `manifest` file, to a pdf each or into `combined` one.
+pub fn batch(
+    db: &SimpleSpellDB,
+    config: &AppConfig,
+    manifest: &Path,
+    combined: Option<&Path>,
+    profile: Option<&str>,
+) -> Result<()> {
+    let source = std::fs::read_to_string(manifest)
+        .with_context(|| format!("Unable to read `{}`", manifest.display()))?;
+    let base = manifest.parent().unwrap_or(Path::new("."));
+    let decks = batch::parse_manifest(&source, base)
+        .with_context(|| format!("Unable to parse `{}`", manifest.display()))?;
+    batch::render_batch(
+        db,
+        config,
+        &decks,
+        combined,
+        find_profile(profile)?.as_ref(),
+    )
+}
+
+/// Print problems of bundle `file`. Fails if there are any, so scripts can check bundles.
+pub fn validate(system: &dyn GameSystem, file: &Path) -> Result<()> {
+    let source = std::fs::read_to_string(file)
+        .with_context(|| format!("Unable to read `{}`", file.display()))?;
+    let validation = validate::validate_bundle(system, &source);
+    for problem in &validation.problems {
+        println!("{}:{problem}", file.display());
+    }
+    match validation.problems.len() {
+        0 => {
+            println!(
+                "{}: {} spells, no problems",
+                file.display(),
+                validation.spell_count
+            );
+            Ok(())
+        }
+        count => Err(anyhow!(
+            "Found {count} problems in {} spells",
+            validation.spell_count
+        )),
+    }
+}
+
+/// Print spells matching filters to standard output, sorted by rank and name.
+pub fn search(
+    db: &SimpleSpellDB,
+    name: Option<&str>,
+    rank: Option<u8>,
+    traditions: &[Tradition],
+    format: Format,
+) -> Result<()> {
+    let mut query = Query::default();
+    if let Some(name) = name {
+        search_syntax::apply(name, &mut query);
+    }
+    query.spell_rank = rank.or(query.spell_rank);
+    for tradition in traditions {
+        match tradition {
+            Tradition::Arcane => query.is_arcane = true,
+            Tradition::Primal => query.is_primal = true,
+            Tradition::Divine => query.is_divine = true,
+            Tradition::Occult => query.is_occult = true,
+        }
+    }
+    let mut spells = db.search(&query);
+    spells.sort_by(|a, b| (a.level, &a.name).cmp(&(b.level, &b.name)));
+    let mut output = std::io::stdout().lock();
+    match format {
+        Format::Table => write_table(&mut output, &spells)?,
+        Format::Json => {
+            let spells = spells.iter().map(|spell| spell_json(spell)).collect();
+            writeln!(
+                output,
+                "{}",
+                json::stringify_pretty(JsonValue::Array(spells), 2)
+            )?;
+        }
+        Format::Csv => {
+            csv::write_row(&mut output, &SEARCH_COLUMNS)?;
+            for spell in &spells {
+                csv::write_row(&mut output, &spell_row(spell))?;
+            }
+        }
+        Format::Ids => {
+            for spell in &spells {
+                writeln!(output, "{}", spell.id)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+const SEARCH_COLUMNS: [&str; 5] = ["id", "name", "rank", "traditions", "source"];
+
+fn spell_row(spell: &Spell) -> [String; 5] {
+    [
+        spell.id.to_string(),
+        spell.name.clone(),
+        spell.level.to_string(),
+        traditions(spell).join(", "),
+        spell.source.clone(),
+    ]
+}
+
+fn spell_json(spell: &Spell) -> JsonValue {
+    let mut result = JsonValue::new_object();
+    result["id"] = spell.id.into();
+    result["name"] = spell.name.as_str().into();
+    result["rank"] = spell.level.into();
+    result["traditions"] = traditions(spell).into();
+    result["traits"] = spell.traits.clone().into();
+    result["source"] = spell.source.as_str().into();
+    if let Some(url) = &spell.url {
+        result["url"] = url.as_str().into();
+    }
+    result
+}
+
+fn traditions(spell: &Spell) -> Vec<&'static str> {
+    Tradition::ALL
+        .into_iter()
+        .filter(|tradition| spell.traditions.contains(*tradition))
+        .map(Tradition::as_str)
+        .collect()
+}
+
+/// Columns padded to the widest value. Last column is not padded.
+fn write_table(output: &mut impl Write, spells: &[Rc<Spell>]) -> Result<()> {
+    let header = SEARCH_COLUMNS.map(str::to_string);
+    let rows = std::iter::once(header)
+        .chain(spells.iter().map(|spell| spell_row(spell)))
+        .collect::<Vec<_>>();
+    let mut widths = [0; SEARCH_COLUMNS.len()];
+    for row in &rows {
+        for (width, field) in widths.iter_mut().zip(row) {
+            *width = (*width).max(field.chars().count());
+        }
+    }
+    for row in &rows {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(field, width)| format!("{field:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        writeln!(output, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+fn parse_tradition(name: &str) -> Result<Tradition, String> {
+    Tradition::parse(name).ok_or_else(|| {
+        let names = Tradition::ALL.map(|tradition| tradition.as_str().to_lowercase());
+        format!("expected one of {}", names.join(", "))
+    })
+}
@@ -0,0 +1,78 @@
+use crate::system::GameSystem;
+use std::path::{Path, PathBuf};
+
+const APP_DIR_NAME: &str = "spellcards";
+
+/// Directory with user configuration, like `~/.config/spellcards`, `%APPDATA%\spellcards`
+/// on Windows and `~/Library/Application Support/spellcards` on macOS.
+///
+/// Returns `None` if home directory cannot be determined.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| {
+            PathBuf::from(home)
+                .join("Library")
+                .join("Application Support")
+        })
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map(|base| base.join(APP_DIR_NAME))
+}
+
+/// Render defaults and data bundle, see `AppConfig` for format.
+pub fn app_config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.toml"))
+}
+
+/// User-editable abbreviation dictionary, see `Abbreviations::parse` for format.
+pub fn abbreviations_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("abbreviations.txt"))
+}
+
+/// Named export profiles, see `profiles` module for format.
+pub fn profiles_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("profiles.json"))
+}
+
+/// Directory with diagnostic log files, see `logging` module.
+pub fn logs_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("logs"))
+}
+
+/// Crash report written by panic hook, see `crash` module.
+pub fn crash_report_path() -> Option<PathBuf> {
+    logs_dir().map(|dir| dir.join("crash.txt"))
+}
+
+/// Crash report already offered to user.
+pub fn last_crash_report_path() -> Option<PathBuf> {
+    logs_dir().map(|dir| dir.join("last_crash.txt"))
+}
+
+/// Save file of config directory, like crash report, creating the directory if needed.
+pub fn write_setting(path: &Path, value: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, value)
+}
+
+/// Directory with installed template packs, one subdirectory per template. See `templates`
+/// module.
+pub fn templates_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("templates"))
+}
+
+/// Directory user can drop homebrew spell bundles (`*.json`) of `system` into.
+pub fn bundles_dir(system: &dyn GameSystem) -> Option<PathBuf> {
+    let dir = config_dir()?.join("bundles");
+    Some(match system.bundles_subdir() {
+        Some(subdir) => dir.join(subdir),
+        None => dir,
+    })
+}
@@ -0,0 +1,69 @@
+use crate::config;
+use crate::spell::Spell;
+use std::backtrace::Backtrace;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Spell shown in preview, included in crash reports.
+static ACTIVE_SPELL: Mutex<Option<String>> = Mutex::new(None);
+
+/// Remember `spell` as the one user works with, so crash report can name it.
+pub fn set_active_spell(spell: &Spell) {
+    if let Ok(mut active) = ACTIVE_SPELL.lock() {
+        *active = Some(format!("{} (id {})", spell.name, spell.id));
+    }
+}
+
+/// Install panic hook saving crash report to `config::crash_report_path` before default
+/// hook runs. Panics inside GTK callbacks abort the program, so the report is shown
+/// on next start instead, see `take_report`.
+pub fn install_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let report = build_report(&info.to_string());
+        tracing::error!("{info}");
+        if let Some(path) = config::crash_report_path() {
+            if let Err(error) = config::write_setting(&path, &report) {
+                tracing::error!("Unable to save crash report. {error}");
+            }
+        }
+        default_hook(info);
+    }));
+}
+
+/// Report left by a crash of previous run. It is moved to `config::last_crash_report_path`,
+/// so it is offered only once, and the new path is returned.
+pub fn take_report() -> Option<PathBuf> {
+    let pending = config::crash_report_path()?;
+    if !pending.exists() {
+        return None;
+    }
+    let last = config::last_crash_report_path()?;
+    match std::fs::rename(&pending, &last) {
+        Ok(()) => Some(last),
+        Err(error) => {
+            tracing::warn!("Unable to move crash report. {error}");
+            None
+        }
+    }
+}
+
+fn build_report(message: &str) -> String {
+    // Lock may be poisoned, or held by panicking thread.
+    let active_spell = match ACTIVE_SPELL.try_lock() {
+        Ok(active) => active.clone(),
+        Err(_) => None,
+    };
+    format!(
+        "Spell Card generator {} crashed.\n\
+        Platform: {} {}\n\
+        Active spell: {}\n\n\
+        {message}\n\n\
+        Backtrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        active_spell.as_deref().unwrap_or("none"),
+        Backtrace::force_capture(),
+    )
+}
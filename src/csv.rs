@@ -0,0 +1,57 @@
+use anyhow::{bail, Result};
+use std::io::{self, Write};
+
+/// Write single CSV row, quoting fields which need it.
+pub fn write_row<T: AsRef<str>>(output: &mut impl Write, fields: &[T]) -> io::Result<()> {
+    let row = fields
+        .iter()
+        .map(|field| escape(field.as_ref()))
+        .collect::<Vec<_>>()
+        .join(",");
+    writeln!(output, "{row}")
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Parse CSV document into rows of fields. Empty lines are skipped.
+pub fn parse(source: &str) -> Result<Vec<Vec<String>>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|field| !field.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if in_quotes {
+        bail!("Unterminated quoted field");
+    }
+    row.push(field);
+    if row.iter().any(|field| !field.is_empty()) {
+        rows.push(row);
+    }
+    Ok(rows)
+}
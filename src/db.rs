@@ -1,23 +1,72 @@
-use crate::json_utils::JsonValueExt;
-use crate::spell::{Spell, Traditions};
-use anyhow::Result;
+use crate::error::ParseError;
+use crate::gzip;
+use crate::json_utils::ObjectExt;
+use crate::references::ReferenceKind;
+use crate::spell::{Rarity, Spell, Tradition, Traditions};
+use crate::system::GameSystem;
+use json::JsonValue;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::Arc;
+
+/// Names of the property holding saving throw, spelling differs between systems.
+pub const DEFENSE_PROPERTIES: &[&str] = &["Defence", "Defense"];
+/// Traits too common to make spells related.
+const GENERIC_TRAITS: &[&str] = &[
+    "cantrip",
+    "concentrate",
+    "manipulate",
+    "uncommon",
+    "rare",
+    "unique",
+];
+/// Weights of `similarity` components.
+const REFERENCED_WEIGHT: u32 = 10;
+const REFERENCED_BY_WEIGHT: u32 = 5;
+const SHARED_TRAIT_WEIGHT: u32 = 2;
+const SHARED_CONDITION_WEIGHT: u32 = 1;
 
 #[derive(Debug, Clone, Default)]
 pub struct Query {
-    pub name_query: String,
+    /// Lowercase phrases searched in spell name and traits. Every group must have
+    /// a phrase found, see `search_syntax::apply`.
+    pub phrases: Vec<Vec<String>>,
+    /// Lowercase phrases which must not be found in spell name or traits.
+    pub excluded: Vec<String>,
+    /// Lowercase traits spell must have.
+    pub traits: Vec<String>,
+    /// Lowercase traits spell must not have.
+    pub excluded_traits: Vec<String>,
+    /// Lowercase text defense property, like `basic Reflex`, must contain.
+    pub defense: Option<String>,
     pub spell_rank: Option<u8>,
     pub is_arcane: bool,
     pub is_primal: bool,
     pub is_divine: bool,
     pub is_occult: bool,
+    /// Sources spell must be published in. Any source is allowed if empty.
+    pub sources: Vec<String>,
+    pub rarity: Option<Rarity>,
+    /// Spell list spells must be on, see `Spell::is_on_list`.
+    pub spell_list: Option<Tradition>,
 }
 
 impl Query {
     fn test(&self, spell: &Spell) -> bool {
-        self.test_name(&spell.name)
+        self.test_text(spell)
+            && self.test_traits(&spell.traits)
+            && self.test_defense(spell)
             && self.test_rank(spell.level)
             && self.test_tradition(&spell.traditions)
+            && self.test_source(&spell.source)
+            && self.rarity.is_none_or(|rarity| rarity == spell.rarity)
+            && self.spell_list.is_none_or(|list| spell.is_on_list(list))
+    }
+
+    fn test_source(&self, source: &str) -> bool {
+        self.sources.is_empty() || self.sources.iter().any(|s| s == source)
     }
 
     fn test_rank(&self, rank: u8) -> bool {
@@ -28,9 +77,39 @@ impl Query {
         }
     }
 
-    fn test_name(&self, name: &str) -> bool {
-        name.to_lowercase()
-            .contains(&self.name_query.to_lowercase())
+    fn test_text(&self, spell: &Spell) -> bool {
+        let name = spell.name.to_lowercase();
+        let traits = spell
+            .traits
+            .iter()
+            .map(|trait_| trait_.to_lowercase())
+            .collect::<Vec<_>>();
+        let contains = |phrase: &String| {
+            name.contains(phrase.as_str())
+                || traits.iter().any(|trait_| trait_.contains(phrase.as_str()))
+        };
+        self.phrases.iter().all(|group| group.iter().any(contains))
+            && !self.excluded.iter().any(contains)
+    }
+
+    fn test_traits(&self, traits: &[String]) -> bool {
+        let has = |wanted: &String| {
+            traits
+                .iter()
+                .any(|trait_| trait_.eq_ignore_ascii_case(wanted))
+        };
+        self.traits.iter().all(has) && !self.excluded_traits.iter().any(has)
+    }
+
+    fn test_defense(&self, spell: &Spell) -> bool {
+        let Some(defense) = &self.defense else {
+            return true;
+        };
+        spell
+            .properties
+            .iter()
+            .filter(|property| DEFENSE_PROPERTIES.contains(&property.name.as_str()))
+            .any(|property| property.value.to_lowercase().contains(defense.as_str()))
     }
 
     fn test_tradition(&self, traditions: &Traditions) -> bool {
@@ -44,22 +123,626 @@ impl Query {
 
 pub trait SpellDB {
     fn search(&self, query: &Query) -> Vec<Rc<Spell>>;
+    /// Original json object spell was parsed from, pretty printed.
+    fn raw_data(&self, spell_id: usize) -> Option<String>;
+    /// All distinct spell sources, sorted.
+    fn sources(&self) -> Vec<String>;
+    /// Spells with exactly matching name, ignoring case. Several spells share the name when
+    /// legacy and remastered versions differ in rank, or bundles reprint a spell, see
+    /// `deck::resolve`.
+    fn find_by_name(&self, name: &str) -> Vec<Rc<Spell>>;
+    fn get(&self, spell_id: usize) -> Option<Rc<Spell>>;
 }
 
 /// Simplest possible implementation of spell database. Hella inefficient.
 pub struct SimpleSpellDB {
-    spells: Vec<Spell>,
+    /// Parses built-in data and bundles.
+    system: Rc<dyn GameSystem>,
+    /// Shared with background searches, see `spells_snapshot`.
+    spells: Arc<Vec<Spell>>,
+    /// Source objects by spell id. Kept around for debugging data issues.
+    raw_objects: HashMap<usize, JsonValue>,
+    /// Language of merged localized bundles, `None` if there are none.
+    language: Option<String>,
+    /// Built-in spells with their objects replaced by translations or bundle spells of the
+    /// same name and source, by id. Restored then bundles are reloaded.
+    originals: HashMap<usize, (Spell, JsonValue)>,
+    /// Bundle files loaded after ones of bundles directory, see `set_extra_bundles`.
+    extra_bundles: Vec<PathBuf>,
+    /// Ids of built-in spells merged into duplicates, with id of the spell kept, see
+    /// `merge_duplicates`. Decks and spell lists saved with merged ids still find the spell.
+    merged_ids: HashMap<usize, usize>,
+    /// Names of `spells` shared by several sources, see `shared_names`. Updated whenever
+    /// spells change, so searches do not compute it on every keystroke.
+    shared_names: Arc<HashSet<String>>,
+}
+
+/// Built-in spell dropped in favor of another printing, see
+/// `SimpleSpellDB::merge_duplicates`.
+#[derive(Debug, Clone)]
+struct MergedDuplicate {
+    name: String,
+    rank: u8,
+    kept_source: String,
+    dropped_source: String,
+    kept_id: usize,
+    dropped_id: usize,
+}
+
+/// Bundle fields besides spells, see `SimpleSpellDB::merge_bundle`.
+struct BundleHeader {
+    license: Option<Arc<str>>,
+    language: Option<String>,
+}
+
+/// Outcome of loading single bundle file.
+pub struct BundleReport {
+    pub path: PathBuf,
+    pub result: Result<MergeStats, ParseError>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct MergeStats {
+    pub added: usize,
+    pub replaced: usize,
+    /// Spells of built-in data or other bundles replaced by bundle spells of the same name
+    /// and source, see `SimpleSpellDB::merge_bundle`.
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Spell replaced by a bundle spell of the same name and source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub name: String,
+    pub source: String,
+    /// Label of the bundle replaced spell came from, `None` for built-in spell.
+    pub bundle: Option<String>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.source)?;
+        match &self.bundle {
+            Some(bundle) => write!(f, " of bundle `{bundle}`"),
+            None => write!(f, " of built-in data"),
+        }
+    }
+}
+
+/// Summary of loaded spell data.
+#[derive(Debug, Clone, Default)]
+pub struct DataInfo {
+    pub builtin_spells: usize,
+    /// Latest `release_date` among built-in spells, identifies data snapshot.
+    pub latest_release: Option<String>,
+    /// Bundle labels with their spell counts, sorted by label.
+    pub bundles: Vec<(String, usize)>,
+}
+
+impl fmt::Display for BundleReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path.display();
+        match &self.result {
+            Ok(stats) => {
+                write!(
+                    f,
+                    "{path}: {added} added, {replaced} replaced",
+                    added = stats.added,
+                    replaced = stats.replaced
+                )?;
+                for (i, conflict) in stats.conflicts.iter().enumerate() {
+                    let separator = if i == 0 { ". Overrides " } else { ", " };
+                    write!(f, "{separator}{conflict}")?;
+                }
+                Ok(())
+            }
+            Err(error) => write!(f, "{path}: rejected. {error:#}"),
+        }
+    }
 }
 
 impl SimpleSpellDB {
-    pub fn new(data: &'static str) -> Result<Self> {
-        let spells = json::parse(data)?
-            .as_array()?
+    /// Database with built-in spells of `system`.
+    pub fn new(system: Rc<dyn GameSystem>) -> Result<Self, ParseError> {
+        let mut result = Self {
+            system,
+            spells: Arc::new(vec![]),
+            raw_objects: HashMap::new(),
+            language: None,
+            originals: HashMap::new(),
+            extra_bundles: vec![],
+            merged_ids: HashMap::new(),
+            shared_names: Arc::default(),
+        };
+        let data = result
+            .system
+            .builtin_data()
+            .map_err(|error| ParseError::new(format!("Unable to read built-in data: {error:#}")))?;
+        let data = parse_json(&data)?;
+        let mut spells = vec![];
+        for (spell, obj) in result.parse_spells(&data)? {
+            result.raw_objects.insert(spell.id, obj);
+            spells.push(spell);
+        }
+        result.spells = Arc::new(spells);
+        for merged in result.merge_duplicates() {
+            tracing::info!(
+                "Merged duplicate spell `{}` (rank {}): kept {} of {}, dropped {} of {}",
+                merged.name,
+                merged.rank,
+                merged.kept_id,
+                merged.kept_source,
+                merged.dropped_id,
+                merged.dropped_source,
+            );
+        }
+        result.shared_names = Arc::new(shared_names(&result.spells));
+        Ok(result)
+    }
+
+    /// Drop built-in spells listed several times, which would show up twice in search, like
+    /// a spell reprinted in remastered rulebooks. Spells are the same then their names match,
+    /// ignoring case and punctuation, and so do ranks. Of the same spells the one kept is
+    /// of the highest priority source: remasters, which link their legacy version by
+    /// `legacy_id`, come before spells without links, followed by legacy spells, which link
+    /// their remaster by `remaster_id`. Spells of the same priority are told apart by the
+    /// latest `release_date`, so newer books win, then by the smallest id.
+    ///
+    /// Dropped ids keep finding the kept spell through `get`.
+    fn merge_duplicates(&mut self) -> Vec<MergedDuplicate> {
+        let field = |id: usize, key: &str| {
+            self.raw_objects
+                .get(&id)
+                .and_then(|object| object[key].as_str())
+        };
+        let priority = |spell: &Spell| {
+            let printing = if field(spell.id, "legacy_id").is_some() {
+                2
+            } else if field(spell.id, "remaster_id").is_some() {
+                0
+            } else {
+                1
+            };
+            (
+                printing,
+                field(spell.id, "release_date").map(str::to_string),
+                std::cmp::Reverse(spell.id),
+            )
+        };
+        let key = |spell: &Spell| (normalized_name(&spell.name), spell.level);
+        let mut best = HashMap::new();
+        for (i, spell) in self.spells.iter().enumerate() {
+            match best.get(&key(spell)) {
+                Some(&other) if priority(&self.spells[other]) >= priority(spell) => {}
+                _ => {
+                    best.insert(key(spell), i);
+                }
+            }
+        }
+        let mut merged = vec![];
+        for spell in self.spells.iter() {
+            let kept = &self.spells[best[&key(spell)]];
+            if kept.id != spell.id {
+                merged.push(MergedDuplicate {
+                    name: kept.name.clone(),
+                    rank: kept.level,
+                    kept_source: kept.source.clone(),
+                    dropped_source: spell.source.clone(),
+                    kept_id: kept.id,
+                    dropped_id: spell.id,
+                });
+            }
+        }
+        for duplicate in &merged {
+            self.raw_objects.remove(&duplicate.dropped_id);
+            self.merged_ids
+                .insert(duplicate.dropped_id, duplicate.kept_id);
+        }
+        let merged_ids = &self.merged_ids;
+        Arc::make_mut(&mut self.spells).retain(|spell| !merged_ids.contains_key(&spell.id));
+        merged
+    }
+
+    pub fn system(&self) -> Rc<dyn GameSystem> {
+        self.system.clone()
+    }
+
+    /// All spells, for searching on another thread. Cheap: spells are copied only if
+    /// database changes while snapshot is alive.
+    pub fn spells_snapshot(&self) -> Arc<Vec<Spell>> {
+        self.spells.clone()
+    }
+
+    /// Lowercase names shared by spells of different sources, see `shared_names`.
+    pub fn shared_names(&self) -> Arc<HashSet<String>> {
+        self.shared_names.clone()
+    }
+
+    fn parse_spells(&self, data: &JsonValue) -> Result<ParsedSpells, ParseError> {
+        let JsonValue::Array(items) = data else {
+            return Err(ParseError::new("Expected array of spells"));
+        };
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, obj)| match obj {
+                JsonValue::Object(object) => Ok((self.system.parse_spell(object)?, obj.clone())),
+                _ => Err(ParseError::new(format!(
+                    "Item {i} of spells is not an object"
+                ))),
+            })
+            .collect()
+    }
+
+    /// Merge spells from bundle into database, labeling them with `label`.
+    ///
+    /// Bundle is either json array of spells, or object with license attribution
+    /// printed in exported decks: `{"license": "...", "spells": [...]}`.
+    ///
+    /// Bundle is validated as a whole: if any spell fails to parse, nothing is merged.
+    /// Spell with the same name as one of the same bundle, or the same name and source as
+    /// a built-in spell or one of another bundle, replaces it and takes its id, so homebrew
+    /// can override official spells and bundles loaded later override earlier ones.
+    /// Replaced spells of other bundles or built-in data are listed in
+    /// `MergeStats::conflicts`. Spells without source are of source named after `label`.
+    ///
+    /// Object may also give `"language"` of a localized bundle, like `"de"`. Its spells
+    /// name English spell they translate in `"original"` field, or keep English name, and
+    /// replace built-in spell in place, keeping its id. Built-in spells left without
+    /// translation stay in English and are marked `untranslated`, so localized decks have
+    /// no holes. Localized bundles of different languages are not merged together.
+    pub fn merge_bundle(&mut self, label: &str, data: &str) -> Result<MergeStats, ParseError> {
+        let mut stats = MergeStats::default();
+        let (header, parsed) = self.parse_bundle(data)?;
+        if let Some(language) = &header.language {
+            match &self.language {
+                Some(loaded) if loaded != language => {
+                    return Err(ParseError::field(
+                        "language",
+                        format!(
+                            "Bundle language `{language}` differs from already loaded `{loaded}`"
+                        ),
+                    ));
+                }
+                _ => self.language = Some(language.clone()),
+            }
+        }
+        let spells = Arc::make_mut(&mut self.spells);
+        for (mut spell, obj) in parsed {
+            spell.bundle = Some(label.to_string());
+            spell.license = header.license.clone();
+            let original = header.language.as_ref().and_then(|_| {
+                let name = obj["original"].as_str().unwrap_or(&spell.name);
+                spells.iter().position(|other| {
+                    other.bundle.is_none()
+                        && other.level == spell.level
+                        && other.name.eq_ignore_ascii_case(name)
+                })
+            });
+            if spell.source.is_empty() {
+                spell.source = match original {
+                    Some(index) => spells[index].source.clone(),
+                    None => label.to_string(),
+                };
+            }
+            let existing = spells.iter().position(|other| {
+                other.name.eq_ignore_ascii_case(&spell.name)
+                    && (other.bundle == spell.bundle
+                        || other.source.eq_ignore_ascii_case(&spell.source))
+            });
+            if let Some(index) = existing.filter(|&index| spells[index].bundle != spell.bundle) {
+                let other = &spells[index];
+                // Spell kept in English by a translation replaces its original in place.
+                if original != Some(index) {
+                    stats.conflicts.push(Conflict {
+                        name: other.name.clone(),
+                        source: other.source.clone(),
+                        bundle: other.bundle.clone(),
+                    });
+                }
+            }
+            match existing.or(original) {
+                Some(index) => {
+                    spell.id = spells[index].id;
+                    let replaced = std::mem::replace(&mut spells[index], spell);
+                    let replaced_obj = self.raw_objects.insert(replaced.id, obj);
+                    if replaced.bundle.is_none() {
+                        let mut replaced = replaced;
+                        replaced.untranslated = false;
+                        let replaced_obj = replaced_obj.unwrap_or(JsonValue::Null);
+                        self.originals.insert(replaced.id, (replaced, replaced_obj));
+                    }
+                    stats.replaced += 1;
+                }
+                None => {
+                    if self.raw_objects.contains_key(&spell.id)
+                        || self.merged_ids.contains_key(&spell.id)
+                    {
+                        spell.id = free_id(&self.raw_objects, &self.merged_ids);
+                    }
+                    self.raw_objects.insert(spell.id, obj);
+                    spells.push(spell);
+                    stats.added += 1;
+                }
+            }
+        }
+        if self.language.is_some() {
+            for spell in spells.iter_mut().filter(|spell| spell.bundle.is_none()) {
+                spell.untranslated = true;
+            }
+        }
+        self.shared_names = Arc::new(shared_names(spells));
+        Ok(stats)
+    }
+
+    /// Check that bundle would be merged without errors. Returns number of its spells.
+    pub fn validate_bundle(&self, data: &str) -> Result<usize, ParseError> {
+        Ok(self.parse_bundle(data)?.1.len())
+    }
+
+    /// Header and spells of bundle, see `merge_bundle` for format.
+    fn parse_bundle(&self, data: &str) -> Result<(BundleHeader, ParsedSpells), ParseError> {
+        let data = parse_json(data)?;
+        let (header, spells) = match &data {
+            JsonValue::Object(object) => {
+                let header = BundleHeader {
+                    license: header_field(object, "license")?.map(Arc::from),
+                    language: header_field(object, "language")?,
+                };
+                let spells = object
+                    .get("spells")
+                    .ok_or_else(|| ParseError::field("spells", "Missing"))?;
+                (header, spells)
+            }
+            _ => (
+                BundleHeader {
+                    license: None,
+                    language: None,
+                },
+                &data,
+            ),
+        };
+        Ok((header, self.parse_spells(spells)?))
+    }
+
+    /// Bundle files loaded by `load_bundles` after ones of bundles directory, like the one
+    /// set in `config.toml`. They can be anywhere, but must be named like bundles.
+    pub fn set_extra_bundles(&mut self, paths: Vec<PathBuf>) {
+        self.extra_bundles = paths;
+    }
+
+    /// Replace all bundle spells with ones found in `*.json` files inside `dir`, followed
+    /// by extra bundles. Without `dir` only extra bundles are loaded.
+    ///
+    /// Files are merged in alphabetical order, with file name without `.json` or `.json.gz`
+    /// used as bundle label. Files of a label already loaded are reported as failed.
+    pub fn load_bundles(&mut self, dir: Option<&Path>) -> std::io::Result<Vec<BundleReport>> {
+        let raw_objects = &mut self.raw_objects;
+        let spells = Arc::make_mut(&mut self.spells);
+        for spell in spells.iter_mut() {
+            if let Some((original, obj)) = self.originals.remove(&spell.id) {
+                raw_objects.insert(original.id, obj);
+                *spell = original;
+            }
+            spell.untranslated = false;
+        }
+        self.language = None;
+        spells.retain(|spell| {
+            if spell.bundle.is_some() {
+                raw_objects.remove(&spell.id);
+            }
+            spell.bundle.is_none()
+        });
+        self.shared_names = Arc::new(shared_names(spells));
+        // Files of the same label, like `homebrew.json` and `homebrew.json.gz`, would merge
+        // into one bundle, so only the first one is loaded.
+        let mut labels = HashMap::<String, PathBuf>::new();
+        let reports = self
+            .bundle_paths(dir)?
+            .into_iter()
+            .map(|path| {
+                let label = bundle_label(&path).map(str::to_string);
+                let loaded = label.as_ref().and_then(|label| labels.get(label));
+                let result = match (&label, loaded) {
+                    (Some(label), Some(loaded)) => Err(ParseError::new(format!(
+                        "Bundle `{label}` is already loaded from {}",
+                        loaded.display()
+                    ))),
+                    _ => {
+                        if let Some(label) = label {
+                            labels.insert(label, path.clone());
+                        }
+                        self.load_bundle_file(&path)
+                    }
+                };
+                BundleReport { path, result }
+            })
+            .collect();
+        Ok(reports)
+    }
+
+    /// Bundle files `load_bundles` merges from `dir`, in order.
+    pub fn bundle_paths(&self, dir: Option<&Path>) -> std::io::Result<Vec<PathBuf>> {
+        let mut paths = if let Some(dir) = dir.filter(|dir| dir.exists()) {
+            std::fs::read_dir(dir)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<std::io::Result<Vec<_>>>()?
+        } else {
+            vec![]
+        };
+        paths.retain(|path| bundle_label(path).is_some());
+        paths.sort();
+        paths.extend(self.extra_bundles.iter().cloned());
+        Ok(paths)
+    }
+
+    fn load_bundle_file(&mut self, path: &Path) -> Result<MergeStats, ParseError> {
+        let label = bundle_label(path)
+            .ok_or_else(|| ParseError::new("Bundle file name must be valid utf-8"))?;
+        let data = gzip::read_to_string(path)
+            .map_err(|error| ParseError::new(format!("Unable to read file: {error:#}")))?;
+        self.merge_bundle(label, &data)
+    }
+
+    /// Up to `limit` spells most similar to `spell`, see `similarity`. Among equally
+    /// similar, spells of closer rank go first.
+    pub fn related_spells(&self, spell: &Spell, limit: usize) -> Vec<Rc<Spell>> {
+        let mut scored = self
+            .spells
             .iter()
-            .map(|obj| Spell::parse(obj.as_object()?))
-            .collect::<anyhow::Result<Vec<_>>>()?;
-        Ok(Self { spells })
+            .filter(|other| !other.name.eq_ignore_ascii_case(&spell.name))
+            .map(|other| (similarity(spell, other), other))
+            .filter(|(score, _)| *score >= SHARED_TRAIT_WEIGHT)
+            .collect::<Vec<_>>();
+        scored.sort_by_key(|(score, other)| {
+            (
+                std::cmp::Reverse(*score),
+                other.level.abs_diff(spell.level),
+                other.name.clone(),
+            )
+        });
+        scored.dedup_by(|(_, a), (_, b)| a.name.eq_ignore_ascii_case(&b.name));
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, other)| Rc::new(other.clone()))
+            .collect()
+    }
+
+    pub fn data_info(&self) -> DataInfo {
+        let mut info = DataInfo::default();
+        let mut bundles = BTreeMap::<&str, usize>::new();
+        for spell in self.spells.iter() {
+            match &spell.bundle {
+                Some(bundle) => *bundles.entry(bundle).or_default() += 1,
+                None => {
+                    info.builtin_spells += 1;
+                    let release = self
+                        .raw_objects
+                        .get(&spell.id)
+                        .and_then(|object| object["release_date"].as_str());
+                    if release > info.latest_release.as_deref() {
+                        info.latest_release = release.map(str::to_string);
+                    }
+                }
+            }
+        }
+        info.bundles = bundles
+            .into_iter()
+            .map(|(label, count)| (label.to_string(), count))
+            .collect();
+        info
+    }
+}
+
+/// How related `other` is to `spell`: spells referencing each other are most related, then
+/// ones sharing specific traits, like `Fire`, and mentioning the same conditions.
+fn similarity(spell: &Spell, other: &Spell) -> u32 {
+    let references = |from: &Spell, to: &Spell| {
+        from.references.iter().any(|reference| {
+            reference.kind == ReferenceKind::Spell && to.name.eq_ignore_ascii_case(&reference.name)
+        })
+    };
+    let mut score = 0;
+    if references(spell, other) {
+        score += REFERENCED_WEIGHT;
+    }
+    if references(other, spell) {
+        score += REFERENCED_BY_WEIGHT;
+    }
+    let shared_traits = spell
+        .traits
+        .iter()
+        .filter(|trait_| !GENERIC_TRAITS.contains(&trait_.to_lowercase().as_str()))
+        .filter(|trait_| other.traits.iter().any(|t| t.eq_ignore_ascii_case(trait_)))
+        .count() as u32;
+    score += shared_traits * SHARED_TRAIT_WEIGHT;
+    let shared_conditions = spell
+        .references
+        .iter()
+        .filter(|reference| reference.kind == ReferenceKind::Condition)
+        .filter(|reference| other.references.contains(reference))
+        .count() as u32;
+    score + shared_conditions * SHARED_CONDITION_WEIGHT
+}
+
+/// Label of bundle file: name without `.json` or `.json.gz` extension. `None` for other files.
+fn bundle_label(path: &Path) -> Option<&str> {
+    let name = path.file_name()?.to_str()?;
+    name.strip_suffix(".json")
+        .or_else(|| name.strip_suffix(".json.gz"))
+}
+
+/// Spells with json objects they were parsed from.
+type ParsedSpells = Vec<(Spell, JsonValue)>;
+
+fn parse_json(data: &str) -> Result<JsonValue, ParseError> {
+    json::parse(data).map_err(|error| ParseError::new(format!("Not valid json: {error}")))
+}
+
+/// Optional text field of bundle object, like `license`.
+fn header_field(object: &json::object::Object, key: &str) -> Result<Option<String>, ParseError> {
+    object
+        .get_typed_maybe(key)
+        .map_err(|error| ParseError::field(key, format!("{:#}", error.root_cause())))
+}
+
+/// Name compared by `SimpleSpellDB::merge_duplicates`: lowercase words, without punctuation.
+fn normalized_name(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Id not used by any spell, nor by merged duplicates, which still resolve to kept spells.
+fn free_id(raw_objects: &HashMap<usize, JsonValue>, merged_ids: &HashMap<usize, usize>) -> usize {
+    raw_objects
+        .keys()
+        .chain(merged_ids.keys())
+        .max()
+        .map_or(0, |id| id + 1)
+}
+
+/// Lowercase names shared by spells of different sources, like legacy and remastered
+/// versions of a spell, which are told apart by source.
+pub fn shared_names(spells: &[Spell]) -> HashSet<String> {
+    let mut sources = HashMap::<String, &str>::new();
+    let mut shared = HashSet::new();
+    for spell in spells {
+        let name = spell.name.to_lowercase();
+        match sources.get(&name) {
+            Some(source) if *source != spell.source => {
+                shared.insert(name);
+            }
+            Some(_) => {}
+            None => {
+                sources.insert(name, &spell.source);
+            }
+        }
     }
+    shared
+}
+
+/// Spells matching `query`. Returns `None` if `cancelled` reports true before search is
+/// finished, which is checked every few spells.
+pub fn search_spells(
+    spells: &[Spell],
+    query: &Query,
+    cancelled: impl Fn() -> bool,
+) -> Option<Vec<Spell>> {
+    const CHECK_INTERVAL: usize = 64;
+    let mut result = vec![];
+    for (i, spell) in spells.iter().enumerate() {
+        if i.is_multiple_of(CHECK_INTERVAL) && cancelled() {
+            return None;
+        }
+        if query.test(spell) {
+            result.push(spell.clone());
+        }
+    }
+    Some(result)
 }
 
 impl SpellDB for SimpleSpellDB {
@@ -70,4 +753,45 @@ impl SpellDB for SimpleSpellDB {
             .map(|spell| Rc::new(spell.clone()))
             .collect()
     }
+
+    fn raw_data(&self, spell_id: usize) -> Option<String> {
+        self.raw_objects
+            .get(&spell_id)
+            .map(|obj| json::stringify_pretty(obj.clone(), 2))
+    }
+
+    fn sources(&self) -> Vec<String> {
+        let mut sources: Vec<String> = self
+            .spells
+            .iter()
+            .map(|spell| spell.source.clone())
+            .filter(|source| !source.is_empty())
+            .collect();
+        sources.sort();
+        sources.dedup();
+        sources
+    }
+
+    fn find_by_name(&self, name: &str) -> Vec<Rc<Spell>> {
+        let name = name.trim();
+        // Translated spells are found by English name too, so saved decks keep working.
+        let translated = |spell: &Spell| {
+            self.originals
+                .get(&spell.id)
+                .is_some_and(|(original, _)| original.name.eq_ignore_ascii_case(name))
+        };
+        self.spells
+            .iter()
+            .filter(|spell| spell.name.eq_ignore_ascii_case(name) || translated(spell))
+            .map(|spell| Rc::new(spell.clone()))
+            .collect()
+    }
+
+    fn get(&self, spell_id: usize) -> Option<Rc<Spell>> {
+        let spell_id = self.merged_ids.get(&spell_id).copied().unwrap_or(spell_id);
+        self.spells
+            .iter()
+            .find(|spell| spell.id == spell_id)
+            .map(|spell| Rc::new(spell.clone()))
+    }
 }
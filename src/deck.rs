@@ -0,0 +1,631 @@
+use crate::app_config::AppConfig;
+use crate::csv;
+use crate::db::{SimpleSpellDB, SpellDB};
+use crate::json_utils::{JsonValueExt, ObjectExt};
+use crate::options_chain::OptionsChain;
+use crate::profiles::Profile;
+use crate::render::{dry_run, write_to_pdf, CardOverride, DryRun, RenderOptions};
+use crate::spell::{Spell, Tradition};
+use anyhow::{anyhow, bail, Result};
+use json::JsonValue;
+use std::fmt;
+use std::io::Write;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CSV_HEADER: [&str; 5] = ["name", "rank", "count", "notes", "source"];
+/// Format version, first byte of share code payload.
+const SHARE_CODE_VERSION: u8 = 1;
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+/// Most copies of one spell in a deck. Larger counts are rejected rather than laid out as
+/// thousands of cards.
+pub const MAX_CARD_COUNT: u32 = 99;
+/// Most cards of a deck file, about a hundred pages of standard cards.
+pub const MAX_DECK_CARDS: usize = 1000;
+
+/// Selected spell, as stored in interchange formats.
+#[derive(Debug, Clone)]
+pub struct DeckEntry {
+    pub name: String,
+    pub rank: Option<u8>,
+    /// Book spell is from, tells apart spells sharing the name.
+    pub source: Option<String>,
+    pub count: u32,
+    pub notes: String,
+    /// Card is printed without heightened section.
+    pub omit_heightened: bool,
+}
+
+/// Description of a deck, shown in deck properties and exported pdf.
+#[derive(Debug, Clone, Default)]
+pub struct DeckMetadata {
+    pub name: String,
+    /// Character deck is prepared for.
+    pub character: String,
+    pub notes: String,
+    /// Date deck was created, as `YYYY-MM-DD`.
+    pub created: String,
+    /// Spell list of character's class. Spells off the list are flagged in selection.
+    pub spell_list: Option<Tradition>,
+    /// Rank spells are cast at, set on level up. Cards of lower rank spells with heightened
+    /// entries are printed heightened to it, see `heighten::heighten`.
+    pub cast_rank: Option<u8>,
+}
+
+/// Deck saved to a file: selected spells together with export settings, so reopening
+/// the deck and exporting it reproduces the same physical cards.
+///
+/// Stored as json object:
+///
+/// ```json
+/// {
+///     "metadata": {
+///         "name": "Fire", "character": "Ezren", "notes": "", "created": "2024-03-01",
+///         "spell_list": "arcane", "cast_rank": 5
+///     },
+///     "spells": [{
+///         "name": "Fireball", "rank": 3, "source": "Player Core", "count": 2, "notes": "",
+///         "omit_heightened": true
+///     }],
+///     "export": { "name": "Home printer", "page_size": "Letter" }
+/// }
+/// ```
+///
+/// `export` has the same format as export profiles, see `profiles` module.
+#[derive(Debug, Clone)]
+pub struct DeckFile {
+    pub metadata: DeckMetadata,
+    pub entries: Vec<DeckEntry>,
+    /// Settings deck was saved with. Missing in hand-written decks.
+    pub export: Option<Profile>,
+}
+
+/// Spells of imported deck found in database, and entries which were not.
+pub struct ResolvedDeck {
+    pub spells: Vec<(Rc<Spell>, DeckEntry)>,
+    pub mismatches: Vec<Mismatch>,
+}
+
+pub enum Mismatch {
+    NotFound(DeckEntry),
+    /// Spell was found, but has different rank. It is imported anyway.
+    Rank {
+        entry: DeckEntry,
+        rank: u8,
+    },
+    /// Spells of different sources share the name, and entry does not tell which one is
+    /// meant. None is imported.
+    Ambiguous {
+        entry: DeckEntry,
+        candidates: Vec<Rc<Spell>>,
+    },
+}
+
+impl DeckEntry {
+    pub fn new(spell: &Spell, count: u32, notes: impl Into<String>) -> Self {
+        Self {
+            name: spell.name.clone(),
+            rank: Some(spell.level),
+            source: Some(spell.source.clone()),
+            count,
+            notes: notes.into(),
+            omit_heightened: false,
+        }
+    }
+
+    /// Card options set by the entry. Unchecked `omit_heightened` keeps the option of the
+    /// deck, rather than forcing heightened entries onto the card.
+    pub fn card_override(&self) -> CardOverride {
+        CardOverride {
+            omit_heightened: self.omit_heightened.then_some(true),
+            ..CardOverride::default()
+        }
+    }
+}
+
+impl DeckFile {
+    pub fn write<T: Write>(&self, mut output: T) -> Result<()> {
+        let spells = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut spell = JsonValue::new_object();
+                spell["name"] = entry.name.as_str().into();
+                if let Some(rank) = entry.rank {
+                    spell["rank"] = rank.into();
+                }
+                if let Some(source) = &entry.source {
+                    spell["source"] = source.as_str().into();
+                }
+                spell["count"] = entry.count.into();
+                spell["notes"] = entry.notes.as_str().into();
+                spell["omit_heightened"] = entry.omit_heightened.into();
+                spell
+            })
+            .collect();
+        let mut metadata = JsonValue::new_object();
+        metadata["name"] = self.metadata.name.as_str().into();
+        metadata["character"] = self.metadata.character.as_str().into();
+        metadata["notes"] = self.metadata.notes.as_str().into();
+        metadata["created"] = self.metadata.created.as_str().into();
+        if let Some(spell_list) = self.metadata.spell_list {
+            metadata["spell_list"] = spell_list.as_str().to_lowercase().into();
+        }
+        if let Some(cast_rank) = self.metadata.cast_rank {
+            metadata["cast_rank"] = cast_rank.into();
+        }
+        let mut deck = JsonValue::new_object();
+        deck["metadata"] = metadata;
+        deck["spells"] = JsonValue::Array(spells);
+        if let Some(export) = &self.export {
+            deck["export"] = export.to_json();
+        }
+        deck.write_pretty(&mut output, 2)?;
+        Ok(())
+    }
+
+    /// Deck of `write` output. Entry counts must be from 1 to `MAX_CARD_COUNT`, and the deck
+    /// must have at most `MAX_DECK_CARDS` cards, so a small file cannot ask for millions.
+    pub fn parse(source: &str) -> Result<Self> {
+        let deck = json::parse(source)?;
+        let deck = deck.as_object()?;
+        let metadata = match deck.get("metadata") {
+            Some(metadata) => {
+                let parse = || -> Result<DeckMetadata> {
+                    let metadata = metadata.as_object()?;
+                    let field = |name| -> Result<String> {
+                        Ok(metadata.get_typed_maybe(name)?.unwrap_or_default())
+                    };
+                    let spell_list = match metadata.get_typed_maybe::<String>("spell_list")? {
+                        Some(name) => Some(Tradition::parse(&name).ok_or_else(|| {
+                            anyhow!("Field `spell_list` contains unknown tradition `{name}`")
+                        })?),
+                        None => None,
+                    };
+                    Ok(DeckMetadata {
+                        name: field("name")?,
+                        character: field("character")?,
+                        notes: field("notes")?,
+                        created: field("created")?,
+                        spell_list,
+                        cast_rank: metadata.get_typed_maybe("cast_rank")?,
+                    })
+                };
+                parse().map_err(|e| e.context("While parsing field `metadata`"))?
+            }
+            None => DeckMetadata::default(),
+        };
+        let entries = deck
+            .get("spells")
+            .ok_or_else(|| anyhow!("Field `spells` is missing"))?
+            .as_array()?
+            .iter()
+            .enumerate()
+            .map(|(i, spell)| {
+                let parse = || -> Result<DeckEntry> {
+                    let spell = spell.as_object()?;
+                    Ok(DeckEntry {
+                        name: spell.get_typed("name")?,
+                        rank: spell.get_typed_maybe("rank")?,
+                        source: spell.get_typed_maybe("source")?,
+                        count: check_count(spell.get_typed_maybe("count")?.unwrap_or(1))?,
+                        notes: spell.get_typed_maybe("notes")?.unwrap_or_default(),
+                        omit_heightened: spell.get_typed_maybe("omit_heightened")?.unwrap_or(false),
+                    })
+                };
+                parse().map_err(|e| e.context(format!("While parsing spell at index `{i}`")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let cards = entries
+            .iter()
+            .map(|entry| entry.count as usize)
+            .sum::<usize>();
+        if cards > MAX_DECK_CARDS {
+            bail!("Deck has {cards} cards, at most {MAX_DECK_CARDS} are allowed");
+        }
+        let export = deck
+            .get("export")
+            .map(|export| export.as_object().and_then(Profile::parse))
+            .transpose()
+            .map_err(|e| e.context("While parsing field `export`"))?;
+        Ok(Self {
+            metadata,
+            entries,
+            export,
+        })
+    }
+}
+
+/// `count` of deck entry, if it is from 1 to `MAX_CARD_COUNT`.
+fn check_count(count: u32) -> Result<u32> {
+    if !(1..=MAX_CARD_COUNT).contains(&count) {
+        bail!("Field `count` must be from 1 to {MAX_CARD_COUNT}, not {count}");
+    }
+    Ok(count)
+}
+
+/// Current date as `YYYY-MM-DD`, in UTC.
+pub fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs() / 86_400) as i64;
+    // Civil date from day number, see http://howardhinnant.github.io/date_algorithms.html
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months starting from March, so leap day is the last one.
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Write entries as CSV with `name,rank,count,notes,source` header.
+pub fn write_csv<T: Write>(mut output: T, entries: &[DeckEntry]) -> Result<()> {
+    csv::write_row(&mut output, &CSV_HEADER)?;
+    for entry in entries {
+        csv::write_row(
+            &mut output,
+            &[
+                entry.name.clone(),
+                entry.rank.map(|rank| rank.to_string()).unwrap_or_default(),
+                entry.count.to_string(),
+                entry.notes.clone(),
+                entry.source.clone().unwrap_or_default(),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Parse CSV with header row. Only `name` column is required, and columns may go in any order,
+/// so spreadsheets exported from elsewhere can be imported.
+pub fn parse_csv(source: &str) -> Result<Vec<DeckEntry>> {
+    let mut rows = csv::parse(source)?.into_iter();
+    let header = rows.next().ok_or_else(|| anyhow!("File is empty"))?;
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|title| title.trim().eq_ignore_ascii_case(name))
+    };
+    let [name, rank, count, notes, spell_source] = CSV_HEADER.map(column);
+    let name = name.ok_or_else(|| anyhow!("Column `name` missing"))?;
+
+    rows.enumerate()
+        .map(|(i, row)| {
+            let line = i + 2;
+            let field = |index: Option<usize>| {
+                index
+                    .and_then(|index| row.get(index))
+                    .map(|field| field.trim())
+                    .filter(|field| !field.is_empty())
+            };
+            let entry_name = field(Some(name))
+                .ok_or_else(|| anyhow!("Line {line}: spell name is empty"))?
+                .to_string();
+            let rank = field(rank)
+                .map(|rank| rank.parse::<u8>())
+                .transpose()
+                .map_err(|_| anyhow!("Line {line}: rank must be a number"))?;
+            let count = field(count)
+                .map(|count| count.parse::<u32>())
+                .transpose()
+                .map_err(|_| anyhow!("Line {line}: count must be a number"))?
+                .unwrap_or(1);
+            Ok(DeckEntry {
+                name: entry_name,
+                rank,
+                source: field(spell_source).map(str::to_string),
+                count,
+                notes: field(notes).unwrap_or_default().to_string(),
+                omit_heightened: false,
+            })
+        })
+        .collect()
+}
+
+/// Find spells of `entries` in database by name.
+///
+/// Of spells sharing the name, ones of entry's source are taken, then ones of entry's rank.
+/// If spells of different sources are left, entry is reported as ambiguous.
+pub fn resolve(db: &impl SpellDB, entries: Vec<DeckEntry>) -> ResolvedDeck {
+    let mut spells = vec![];
+    let mut mismatches = vec![];
+    for entry in entries {
+        let mut candidates = db.find_by_name(&entry.name);
+        // Source of a saved deck may be renamed in newer data, then it is ignored.
+        narrow(&mut candidates, |spell| {
+            entry
+                .source
+                .as_ref()
+                .is_some_and(|source| spell.source.eq_ignore_ascii_case(source))
+        });
+        narrow(&mut candidates, |spell| Some(spell.level) == entry.rank);
+        if candidates
+            .iter()
+            .any(|spell| spell.source != candidates[0].source)
+        {
+            mismatches.push(Mismatch::Ambiguous { entry, candidates });
+            continue;
+        }
+        match candidates.into_iter().next() {
+            Some(spell) => {
+                if entry.rank.is_some_and(|rank| rank != spell.level) {
+                    mismatches.push(Mismatch::Rank {
+                        entry: entry.clone(),
+                        rank: spell.level,
+                    });
+                }
+                spells.push((spell, entry));
+            }
+            None => mismatches.push(Mismatch::NotFound(entry)),
+        }
+    }
+    ResolvedDeck { spells, mismatches }
+}
+
+/// Find spells of plain list, with a spell name or id per line, like `Fireball` or `1530`.
+/// Lines starting with `#` are comments. Spells listed several times get a card each.
+pub fn resolve_spell_list(db: &impl SpellDB, source: &str) -> ResolvedDeck {
+    let mut spells = vec![];
+    let mut mismatches = vec![];
+    for line in source.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry = DeckEntry {
+            name: line.to_string(),
+            rank: None,
+            source: None,
+            count: 1,
+            notes: String::new(),
+            omit_heightened: false,
+        };
+        let by_id = line.parse().ok().and_then(|id| db.get(id));
+        match by_id {
+            Some(spell) => spells.push((spell, entry)),
+            None => {
+                let deck = resolve(db, vec![entry]);
+                spells.extend(deck.spells);
+                mismatches.extend(deck.mismatches);
+            }
+        }
+    }
+    ResolvedDeck { spells, mismatches }
+}
+
+/// Keep only `spells` matching `predicate`, unless none does.
+fn narrow(spells: &mut Vec<Rc<Spell>>, predicate: impl Fn(&Spell) -> bool) {
+    if spells.iter().any(|spell| predicate(spell)) {
+        spells.retain(|spell| predicate(spell));
+    }
+}
+
+/// Render `deck_file` to pdf, with export settings saved in it. Spells which are not found
+/// are skipped, and returned along with rank mismatches.
+pub fn write_deck_pdf<T: Write>(
+    output: T,
+    db: &SimpleSpellDB,
+    config: &AppConfig,
+    deck_file: DeckFile,
+) -> Result<Vec<Mismatch>> {
+    let deck = resolve(db, deck_file.entries);
+    write_spells_pdf(
+        output,
+        db,
+        config,
+        &deck.spells,
+        deck_file.metadata,
+        deck_file.export.as_ref(),
+    )?;
+    Ok(deck.mismatches)
+}
+
+/// Render found spells of a deck to pdf, with `export` settings or default ones.
+pub fn write_spells_pdf<T: Write>(
+    output: T,
+    db: &SimpleSpellDB,
+    config: &AppConfig,
+    spells: &[(Rc<Spell>, DeckEntry)],
+    metadata: DeckMetadata,
+    export: Option<&Profile>,
+) -> Result<()> {
+    let (cards, options) = deck_options(db, config, spells, metadata, export);
+    write_to_pdf(output, cards, &options)?;
+    Ok(())
+}
+
+/// Measure cards of found spells like `write_spells_pdf` lays them out, without writing pdf.
+pub fn dry_run_spells(
+    db: &SimpleSpellDB,
+    config: &AppConfig,
+    spells: &[(Rc<Spell>, DeckEntry)],
+    metadata: DeckMetadata,
+    export: Option<&Profile>,
+) -> Result<DryRun> {
+    let (cards, options) = deck_options(db, config, spells, metadata, export);
+    Ok(dry_run(cards, &options)?)
+}
+
+/// Cards of found spells, repeated by their counts, and options resolved for them.
+pub fn deck_options<'a>(
+    db: &SimpleSpellDB,
+    config: &AppConfig,
+    spells: &'a [(Rc<Spell>, DeckEntry)],
+    metadata: DeckMetadata,
+    export: Option<&Profile>,
+) -> (Vec<&'a Spell>, RenderOptions) {
+    let mut chain = OptionsChain {
+        profile: export.cloned(),
+        deck: metadata,
+        ..OptionsChain::new(
+            RenderOptions {
+                system: db.system(),
+                ..RenderOptions::default()
+            },
+            config,
+        )
+    };
+    let mut cards = vec![];
+    for (spell, entry) in spells {
+        chain.spells.insert(spell.id, entry.card_override());
+        cards.extend(std::iter::repeat_n(&**spell, entry.count as usize));
+    }
+    (cards, chain.resolve())
+}
+
+/// Encode spell ids with counts into short url-safe text, which can be pasted into chat.
+///
+/// Only ids are stored, so codes of homebrew spells only work with the same bundles loaded.
+pub fn encode_share_code(spells: &[(usize, u32)]) -> String {
+    let mut spells = spells.to_vec();
+    spells.sort();
+    let mut payload = vec![SHARE_CODE_VERSION];
+    let mut previous_id = 0;
+    for (id, count) in spells {
+        // Ids are stored as differences, which are small for sorted ids.
+        push_varint(&mut payload, (id - previous_id) as u64);
+        push_varint(&mut payload, count as u64);
+        previous_id = id;
+    }
+    encode_base64(&payload)
+}
+
+/// Decode spell ids with counts from `encode_share_code` output. Counts are from 1 to
+/// `MAX_CARD_COUNT`.
+pub fn decode_share_code(code: &str) -> Result<Vec<(usize, u32)>> {
+    let payload = decode_base64(code.trim())?;
+    let (version, mut rest) = payload
+        .split_first()
+        .ok_or_else(|| anyhow!("Share code is empty"))?;
+    if *version != SHARE_CODE_VERSION {
+        bail!("Share code version {version} is not supported");
+    }
+    let mut result = vec![];
+    let mut id = 0usize;
+    while !rest.is_empty() {
+        let step = usize::try_from(read_varint(&mut rest)?).ok();
+        id = step
+            .and_then(|step| id.checked_add(step))
+            .ok_or_else(|| anyhow!("Share code is damaged"))?;
+        let count = read_varint(&mut rest)?;
+        if !(1..=u64::from(MAX_CARD_COUNT)).contains(&count) {
+            bail!(
+                "Share code is damaged: spell count {count} is not between 1 and {MAX_CARD_COUNT}"
+            );
+        }
+        result.push((id, count as u32));
+    }
+    Ok(result)
+}
+
+/// Find spells of decoded share code in database. Returns found spells with counts, and
+/// number of ids missing from database.
+pub fn resolve_share_code(
+    db: &impl SpellDB,
+    spells: &[(usize, u32)],
+) -> (Vec<(Rc<Spell>, u32)>, usize) {
+    let mut missing = 0;
+    let mut result = vec![];
+    for (id, count) in spells {
+        match db.get(*id) {
+            Some(spell) => result.push((spell, *count)),
+            None => missing += 1,
+        }
+    }
+    (result, missing)
+}
+
+fn push_varint(output: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        output.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+fn read_varint(input: &mut &[u8]) -> Result<u64> {
+    let mut result = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = input
+            .split_first()
+            .ok_or_else(|| anyhow!("Share code is truncated"))?;
+        *input = rest;
+        result |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+    }
+    bail!("Share code is damaged")
+}
+
+fn encode_base64(data: &[u8]) -> String {
+    let mut result = String::new();
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| {
+            bits | (*byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..=chunk.len() {
+            let index = (bits >> (18 - 6 * i)) & 0x3f;
+            result.push(BASE64_ALPHABET[index as usize] as char);
+        }
+    }
+    result
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>> {
+    let mut result = vec![];
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+    for c in text.bytes() {
+        let value = BASE64_ALPHABET
+            .iter()
+            .position(|x| *x == c)
+            .ok_or_else(|| anyhow!("Share code contains invalid character `{}`", c as char))?;
+        bits = (bits << 6) | value as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            result.push((bits >> bit_count) as u8);
+            bits &= (1 << bit_count) - 1;
+        }
+    }
+    Ok(result)
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::NotFound(entry) => write!(f, "`{}`: not found", entry.name),
+            Mismatch::Rank { entry, rank } => write!(
+                f,
+                "`{}`: rank {} in file, but {rank} in database",
+                entry.name,
+                entry.rank.unwrap_or_default()
+            ),
+            Mismatch::Ambiguous { entry, candidates } => {
+                let mut sources = candidates
+                    .iter()
+                    .map(|spell| spell.source.as_str())
+                    .collect::<Vec<_>>();
+                sources.sort_unstable();
+                sources.dedup();
+                write!(
+                    f,
+                    "`{}`: found in {}, source must be given",
+                    entry.name,
+                    sources.join(", ")
+                )
+            }
+        }
+    }
+}
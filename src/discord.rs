@@ -0,0 +1,194 @@
+use crate::html::{actions_text, attribute, spell_type_name};
+use crate::spell::Spell;
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+/// Discord rejects longer messages.
+pub const MESSAGE_LIMIT: usize = 2000;
+
+/// Spell as Discord markdown, split into messages no longer than `MESSAGE_LIMIT` characters.
+/// Messages are split between paragraphs or lines where possible, keeping markup intact.
+pub fn spell_messages(spell: &Spell) -> Vec<String> {
+    let mut text = format!("**{}**", escape(&spell.name));
+    text.push_str(&format!(
+        " {} {}",
+        spell_type_name(&spell.spell_type),
+        spell.level
+    ));
+    if let Some(actions) = actions_text(&spell.actions) {
+        text.push_str(&format!(", {actions}"));
+    }
+    text.push('\n');
+    if !spell.traits.is_empty() {
+        text.push_str(&format!("*{}*\n", escape(&spell.traits.join(", "))));
+    }
+    let traditions = spell.traditions.names();
+    if !traditions.is_empty() {
+        text.push_str(&format!("**Traditions** {}\n", traditions.join(", ")));
+    }
+    for property in &spell.properties {
+        text.push_str(&format!(
+            "**{}** {}\n",
+            escape(&property.name),
+            escape(&property.value)
+        ));
+    }
+    let sections = std::iter::once(&spell.description)
+        .chain(&spell.heightened)
+        .chain(&spell.extras);
+    for section in sections {
+        text.truncate(text.trim_end().len());
+        text.push_str("\n\n");
+        push_markdown(&mut text, section);
+    }
+    split_messages(&text, MESSAGE_LIMIT)
+}
+
+fn push_markdown(output: &mut String, markdown: &str) {
+    let markdown = markdown.replace("<br />", "\n\n");
+    for event in Parser::new(&markdown) {
+        match event {
+            Event::Text(text) => output.push_str(&escape(&text)),
+            Event::Start(Tag::Strong) | Event::End(TagEnd::Strong) => output.push_str("**"),
+            // Links point to nethys pages, so only their text is kept, like on cards.
+            Event::Start(Tag::Emphasis | Tag::Link { .. })
+            | Event::End(TagEnd::Emphasis | TagEnd::Link) => output.push('*'),
+            Event::Start(Tag::Item) => output.push_str("• "),
+            Event::SoftBreak | Event::HardBreak | Event::End(TagEnd::Item) => output.push('\n'),
+            Event::End(TagEnd::Paragraph) => output.push_str("\n\n"),
+            Event::Html(html) | Event::InlineHtml(html) => push_html(output, &html),
+            _ => {}
+        }
+    }
+}
+
+/// Replace tags with markdown equivalents. Unknown tags are dropped, leaving text inside them.
+fn push_html(output: &mut String, html: &str) {
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        output.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        let name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        match name.as_str() {
+            "actions" => {
+                if let Some(actions) = attribute(tag, "string") {
+                    output.push_str(&format!("**{actions}**"));
+                }
+            }
+            "li" => output.push_str("\n• "),
+            "br" | "tr" => output.push('\n'),
+            "td" => output.push_str(" | "),
+            _ => {}
+        }
+        rest = &rest[start + end + 1..];
+    }
+    output.push_str(rest);
+}
+
+/// Escape characters Discord treats as markup.
+fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '*' | '_' | '~' | '`' | '|') {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Split text into messages no longer than `limit` characters. Messages end at paragraph
+/// breaks, then line breaks, then spaces, preferring places outside of bold and italic
+/// markup. Markup open at the end of a message is closed there and reopened in the next one.
+fn split_messages(text: &str, limit: usize) -> Vec<String> {
+    let text = text.trim();
+    let breaks = breaks(text);
+    let mut messages = vec![];
+    let mut start = 0;
+    let mut reopen: Vec<&str> = vec![];
+    while start < text.len() {
+        let prefix = reopen.concat();
+        let length = |end: usize, open: &[&str]| {
+            prefix.len() + text[start..end].chars().count() + open.concat().len()
+        };
+        if length(text.len(), &[]) <= limit {
+            messages.push(prefix + &text[start..]);
+            break;
+        }
+        let best = breaks
+            .iter()
+            .skip_while(|place| place.start <= start)
+            .take_while(|place| length(place.start, &[]) <= limit)
+            .filter(|place| length(place.start, &place.open) <= limit)
+            .max_by_key(|place| (place.open.is_empty(), place.rank, place.start));
+        let (end, next, open) = match best {
+            Some(place) => (place.start, place.end, place.open.clone()),
+            // Word longer than a message is cut.
+            None => {
+                let cut = text[start..]
+                    .char_indices()
+                    .nth(limit.saturating_sub(2 * prefix.len()).max(1))
+                    .map_or(text.len(), |(cut, _)| start + cut);
+                (cut, cut, reopen.clone())
+            }
+        };
+        let closing: String = open.iter().rev().copied().collect();
+        messages.push(prefix + &text[start..end] + &closing);
+        start = next;
+        reopen = open;
+    }
+    messages
+}
+
+/// Whitespace where a message can end.
+struct Break {
+    /// Byte range of the whitespace.
+    start: usize,
+    end: usize,
+    /// 2 for paragraph breaks, 1 for line breaks and 0 for spaces.
+    rank: usize,
+    /// Markup open before the whitespace.
+    open: Vec<&'static str>,
+}
+
+fn breaks(text: &str) -> Vec<Break> {
+    let mut result = vec![];
+    let mut open: Vec<&'static str> = vec![];
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == '*' {
+            let marker = match chars.next_if(|(_, c)| *c == '*') {
+                Some(_) => "**",
+                None => "*",
+            };
+            match open.iter().rposition(|open| *open == marker) {
+                Some(position) => {
+                    open.remove(position);
+                }
+                None => open.push(marker),
+            }
+        } else if c.is_whitespace() {
+            let mut end = index + c.len_utf8();
+            let mut lines = usize::from(c == '\n');
+            while let Some((next, c)) = chars.next_if(|(_, c)| c.is_whitespace()) {
+                end = next + c.len_utf8();
+                lines += usize::from(c == '\n');
+            }
+            result.push(Break {
+                start: index,
+                end,
+                rank: lines.min(2),
+                open: open.clone(),
+            });
+        }
+    }
+    result
+}
@@ -0,0 +1,154 @@
+use std::fmt;
+
+/// Spell data which could not be read, by `Spell::parse` or when loading bundles into
+/// `SimpleSpellDB`.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    /// Name of the spell, `None` for problems of the bundle itself and spells without a name.
+    pub spell: Option<String>,
+    /// Id of the spell, `None` if it is missing or invalid itself.
+    pub spell_id: Option<usize>,
+    /// Field of spell or bundle object, like `level`. `None` if the problem is not about one
+    /// field.
+    pub field: Option<String>,
+    /// What is wrong, without spell name and field.
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            spell: None,
+            spell_id: None,
+            field: None,
+            message: message.into(),
+        }
+    }
+
+    /// Problem of `field` of spell or bundle object.
+    pub fn field(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: Some(field.to_string()),
+            ..Self::new(message)
+        }
+    }
+
+    /// Problem with field, without spell name, like "Field `level`: Wrong type".
+    pub fn detail(&self) -> String {
+        match &self.field {
+            Some(field) => format!("Field `{field}`: {}", self.message),
+            None => self.message.clone(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(spell) = &self.spell {
+            write!(f, "Unable to parse spell `{spell}`")?;
+            if let Some(id) = self.spell_id {
+                write!(f, " (spell-{id})")?;
+            }
+            write!(f, ": ")?;
+        }
+        write!(f, "{}", self.detail())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Card which could not be laid out, or fonts it needs.
+#[derive(Debug, Clone)]
+pub enum LayoutError {
+    /// Font could not be loaded. `font` is its role, like `bold` or `Pathfinder 2e glyph`.
+    Font { font: String, message: String },
+    /// Word wider than a line of the card, like a long url.
+    WordTooWide {
+        spell_id: usize,
+        spell: String,
+        word: String,
+    },
+    /// Content exceeds even a double card.
+    DoesNotFit { spell_id: usize, spell: String },
+}
+
+impl LayoutError {
+    /// Spell the card is for, `None` for font problems.
+    pub fn spell_id(&self) -> Option<usize> {
+        match self {
+            LayoutError::Font { .. } => None,
+            LayoutError::WordTooWide { spell_id, .. }
+            | LayoutError::DoesNotFit { spell_id, .. } => Some(*spell_id),
+        }
+    }
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::Font { font, message } => {
+                write!(f, "Unable to load {font} font: {message}")
+            }
+            LayoutError::WordTooWide { spell, word, .. } => {
+                write!(
+                    f,
+                    "Spell `{spell}` contains `{word}`, which is too wide for a card!"
+                )
+            }
+            LayoutError::DoesNotFit { spell, .. } => {
+                write!(f, "Spell `{spell}` does not fit card format!")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+/// Pdf which could not be written.
+#[derive(Debug)]
+pub enum ExportError {
+    Layout(LayoutError),
+    /// Writing to the output failed.
+    Io(std::io::Error),
+    /// Pdf library failed to build or post-process the document.
+    Pdf(String),
+    /// Callback of `Renderer` stopped the export.
+    Cancelled,
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Layout(error) => write!(f, "{error}"),
+            ExportError::Io(error) => write!(f, "Unable to write pdf: {error}"),
+            ExportError::Pdf(message) => write!(f, "Unable to build pdf: {message}"),
+            ExportError::Cancelled => write!(f, "Export was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<LayoutError> for ExportError {
+    fn from(error: LayoutError) -> Self {
+        ExportError::Layout(error)
+    }
+}
+
+impl From<std::io::Error> for ExportError {
+    fn from(error: std::io::Error) -> Self {
+        ExportError::Io(error)
+    }
+}
+
+impl From<printpdf::Error> for ExportError {
+    fn from(error: printpdf::Error) -> Self {
+        ExportError::Pdf(error.to_string())
+    }
+}
+
+impl From<printpdf::lopdf::Error> for ExportError {
+    fn from(error: printpdf::lopdf::Error) -> Self {
+        ExportError::Pdf(error.to_string())
+    }
+}
@@ -0,0 +1,47 @@
+use crate::html::spell_html;
+use crate::spell::Spell;
+use anyhow::Result;
+use json::JsonValue;
+use std::collections::HashSet;
+use std::io::Write;
+
+/// `CONST.JOURNAL_ENTRY_PAGE_FORMATS.HTML` in Foundry.
+const HTML_FORMAT: u8 = 1;
+/// Foundry orders pages by `sort`, and uses this step for new ones.
+const SORT_STEP: usize = 100_000;
+
+/// Write Foundry VTT journal entry named `name`, with a text page for every spell. Spells
+/// selected several times get single page.
+///
+/// File is imported with `Import Data` from journal entry context menu, and the entry can
+/// then be dragged into a compendium.
+pub fn write_journal<'a, T: Write>(
+    mut output: T,
+    name: &str,
+    spells: impl IntoIterator<Item = &'a Spell>,
+) -> Result<()> {
+    let mut written = HashSet::new();
+    let mut pages = vec![];
+    for spell in spells {
+        if !written.insert(spell.id) {
+            continue;
+        }
+        let mut text = JsonValue::new_object();
+        text["format"] = HTML_FORMAT.into();
+        text["content"] = spell_html(spell).into();
+        let mut page = JsonValue::new_object();
+        page["name"] = spell.name.as_str().into();
+        page["type"] = "text".into();
+        page["text"] = text;
+        page["sort"] = (written.len() * SORT_STEP).into();
+        // Content starts with spell name already.
+        page["title"]["show"] = false.into();
+        pages.push(page);
+    }
+
+    let mut journal = JsonValue::new_object();
+    journal["name"] = name.into();
+    journal["pages"] = JsonValue::Array(pages);
+    journal.write_pretty(&mut output, 2)?;
+    Ok(())
+}
@@ -0,0 +1,70 @@
+/// Bonus for a match following previous matched character.
+const CONSECUTIVE_BONUS: i32 = 4;
+/// Bonus for a match at the start of a word.
+const WORD_START_BONUS: i32 = 3;
+
+/// Score of `text` matching `query`, ignoring case: higher is better. `None` if characters
+/// of `query` do not all appear in `text` in the same order. Whitespace in `query` is
+/// ignored, so `exp pdf` matches `Export pdf`.
+///
+/// Every matched character scores a point, with bonuses for runs of consecutive characters
+/// and for word starts, so `pp` prefers `Preview pages` over `Export pdf`. Of equally
+/// matching texts the shorter one scores higher. Empty `query` matches everything equally.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let query = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<_>>();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let text = text.chars().collect::<Vec<_>>();
+    let lower = text
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect::<Vec<_>>();
+    let char_score = |j: usize| {
+        let word_start = j == 0 || !text[j - 1].is_alphanumeric();
+        1 + if word_start { WORD_START_BONUS } else { 0 }
+    };
+
+    // `best[j]`: best score of query prefix matched so far, with its last character
+    // matched at `text[j]`.
+    let mut best = vec![None; text.len()];
+    for (i, &q) in query.iter().enumerate() {
+        let mut next = vec![None; text.len()];
+        // Best score of shorter prefix ending before `j - 1`.
+        let mut earlier: Option<i32> = if i == 0 { Some(0) } else { None };
+        for j in 0..text.len() {
+            if lower[j] == q {
+                let consecutive = (j > 0)
+                    .then(|| best[j - 1])
+                    .flatten()
+                    .map(|score: i32| score + CONSECUTIVE_BONUS);
+                next[j] = earlier.max(consecutive).map(|score| score + char_score(j));
+            }
+            if i > 0 && j > 0 {
+                earlier = earlier.max(best[j - 1]);
+            }
+        }
+        best = next;
+    }
+    // Length counts less than any bonus.
+    best.into_iter()
+        .flatten()
+        .max()
+        .map(|score| score * 256 - text.len().min(255) as i32)
+}
+
+/// Indices of `candidates` matching `query`, best match first. Equally matching candidates
+/// keep given order.
+pub fn fuzzy_filter<'a>(query: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+    let mut matches = candidates
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, text)| Some((fuzzy_score(query, text)?, i)))
+        .collect::<Vec<_>>();
+    matches.sort_by_key(|&(score, i)| (-score, i));
+    matches.into_iter().map(|(_, i)| i).collect()
+}
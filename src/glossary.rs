@@ -0,0 +1,34 @@
+use crate::json_utils::{JsonValueExt, ObjectExt};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Traits with short rules text, see `static/traits.json`.
+const DATA: &str = include_str!("../static/traits.json");
+
+/// Glossary by lowercase trait name, parsed on first use.
+static TRAITS: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Short rules text of trait `name`, ignoring case, like shown in preview tooltips. `None`
+/// for traits the glossary does not cover, like class ones.
+pub fn trait_text(name: &str) -> Option<&'static str> {
+    let traits = TRAITS.get_or_init(|| {
+        parse_traits().unwrap_or_else(|error| {
+            tracing::warn!("{error:#}");
+            HashMap::new()
+        })
+    });
+    traits.get(&name.trim().to_lowercase()).map(String::as_str)
+}
+
+fn parse_traits() -> Result<HashMap<String, String>> {
+    let data = json::parse(DATA).context("Unable to parse traits glossary")?;
+    data.as_array()?
+        .iter()
+        .map(|entry| {
+            let entry = entry.as_object()?;
+            let name = entry.get_typed::<String>("name")?;
+            Ok((name.to_lowercase(), entry.get_typed("text")?))
+        })
+        .collect()
+}
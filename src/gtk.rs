@@ -1,33 +1,142 @@
+mod app_menu;
+mod card_text_editor;
+mod command_palette;
+mod data_sync;
+mod deck_properties;
+mod disambiguate;
+mod duplex_settings;
+mod level_up;
+mod lint_checklist;
+mod page_preview;
+mod print;
+mod profile_settings;
+mod related_spells;
 mod search_spells;
 mod selected_spell;
+mod spell_inspector;
 
-use crate::db::{Query, SimpleSpellDB, SpellDB};
-use crate::render::{build_spell_scene, write_to_pdf, OwnedFontConfig};
-use crate::rich_text::{FontProvider, Scene};
-use crate::spell::Spell;
+use crate::abbreviations::Abbreviations;
+use crate::anki;
+use crate::app_config::{self, AppConfig};
+use crate::cairo_render::{
+    draw_error, draw_scene, draw_scene_at_scale, CairoFont, PrintSimulation,
+};
+use crate::config;
+use crate::crash;
+use crate::db::{search_spells, Query, SimpleSpellDB, SpellDB};
+use crate::deck::{self, DeckMetadata, Mismatch};
+use crate::discord;
+use crate::foundry;
+use crate::glossary;
+use crate::lint::{lint_spells, LintLimits};
+use crate::options_chain::OptionsChain;
+use crate::output::write_replacing;
+use crate::render::{
+    build_spell_scene, fit_spell, layout_report, legibility_report, write_calibration_page,
+    write_to_pdf, FontConfig, OwnedFontConfig, RenderOptions, ThemeFonts,
+};
+use crate::roll20;
+use crate::search_syntax;
+use crate::spell::{Rarity, Spell};
+use crate::system::{all_systems, GameSystem};
+use card_text_editor::CardTextEditor;
+use duplex_settings::DuplexSettings;
 use freetype::Library;
 use gtk4::{gdk, gio, prelude::*, ApplicationWindow};
 use gtk4::{glib, Application, Widget};
+use pathfinder_geometry::vector::Vector2F;
+use profile_settings::ProfileSettings;
+use related_spells::RelatedSpells;
 use search_spells::SpellCollection;
 use selected_spell::SelectedSpellCollection;
-use std::cell::RefCell;
+use spell_inspector::SpellInspector;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashSet, VecDeque};
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
 const APP_ID: &str = "org.hukumka.SpellcardGenerator";
+/// Added to main window in touch mode, see `static/gtk.css`.
+const TOUCH_MODE_CLASS: &str = "touch_mode";
+/// Entries of command palette: title and window action.
+const PALETTE_COMMANDS: &[(&str, &str)] = &[
+    ("Export pdf", "export"),
+    ("Print", "print"),
+    ("Preview pages", "preview-pages"),
+    ("Export calibration page", "export-calibration"),
+    ("Export Roll20 handouts", "export-roll20"),
+    ("Export Foundry journal", "export-foundry"),
+    ("Export Anki notes", "export-anki"),
+    ("Export CSV", "export-csv"),
+    ("Import CSV", "import-csv"),
+    ("Save deck", "save-deck"),
+    ("Open deck", "open-deck"),
+    ("Deck properties", "deck-properties"),
+    ("Level up", "level-up"),
+    ("Copy share code", "copy-share-code"),
+    ("Paste share code", "paste-share-code"),
+    ("Toggle abbreviate text", "toggle-abbreviate"),
+    ("Toggle layout overlay", "toggle-layout-overlay"),
+    ("Toggle 100% print size", "toggle-print-size"),
+    ("Toggle pinned preview", "toggle-pin-preview"),
+    ("Toggle only legal spells", "toggle-legal-only"),
+    ("Toggle touch mode", "toggle-touch-mode"),
+    ("Open settings", "open-settings"),
+    ("Reload bundles", "reload-bundles"),
+    ("Sync data", "sync-data"),
+    ("Spell data", "data-info"),
+    ("Open log", "open-log"),
+    ("Keyboard shortcuts", "show-help-overlay"),
+    ("About", "about"),
+];
+/// Pause in typing after which search runs.
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(150);
+/// Number of suggestions under preview.
+const RELATED_SPELLS_LIMIT: usize = 8;
+/// Resolution assumed for monitors not reporting their physical size.
+const FALLBACK_DPI: f64 = 96.0;
 
-pub fn run_gtk_app(db: SimpleSpellDB) -> glib::ExitCode {
+/// Run application. If `debug` is set, spell inspector panel is shown under preview.
+///
+/// `profile` is the name of export profile selected at start.
+pub fn run_gtk_app(
+    db: SimpleSpellDB,
+    config: AppConfig,
+    debug: bool,
+    profile: Option<String>,
+) -> glib::ExitCode {
     let app = Application::builder().application_id(APP_ID).build();
-    let db = Rc::new(db);
-    app.connect_activate(move |app| build_ui(Rc::clone(&db), app));
+    let db = Rc::new(RefCell::new(db));
+    let config = Rc::new(RefCell::new(config));
+    app.connect_activate(move |app| {
+        build_ui(
+            Rc::clone(&db),
+            Rc::clone(&config),
+            app,
+            debug,
+            profile.as_deref(),
+        )
+    });
     app.connect_startup(|_| load_css());
-    app.run()
+    // Command line arguments are handled by us, so gtk should not see them.
+    app.run_with_args::<&str>(&[])
 }
 
+/// Add application stylesheet. Without a display, like then started by a packaging tool,
+/// it is skipped and widgets keep the default look.
 fn load_css() {
+    let Some(display) = gdk::Display::default() else {
+        tracing::warn!("No display, application stylesheet is not loaded");
+        return;
+    };
     let provider = gtk4::CssProvider::new();
     provider.load_from_data(include_str!("../static/gtk.css"));
     gtk4::style_context_add_provider_for_display(
-        &gdk::Display::default().expect("Could not connect to a display."),
+        &display,
         &provider,
         gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
     );
@@ -35,27 +144,134 @@ fn load_css() {
 
 #[derive(Clone)]
 struct AppState {
-    db: Rc<SimpleSpellDB>,
+    db: Rc<RefCell<SimpleSpellDB>>,
+    /// Settings of `config.toml`, applied over built-in render options. Settings changed in
+    /// the window are saved to the file and updated here.
+    config: Rc<RefCell<AppConfig>>,
+    /// Last query user searched with. Used to refresh results then database changes.
+    query: Rc<RefCell<Query>>,
+    /// Incremented for every search, so running one can notice it is outdated.
+    search_generation: Arc<AtomicU64>,
+    /// Search waiting for user to stop typing.
+    search_timeout: Rc<RefCell<Option<glib::SourceId>>>,
+    /// Saved with deck, and used for exported pdf title.
+    deck_metadata: Rc<RefCell<DeckMetadata>>,
     selected_spells: SelectedSpellCollection,
     search_results: SpellCollection,
     active_spell: Rc<RefCell<Option<Rc<Spell>>>>,
+    /// Spell frozen in the second preview pane, with card text edits made before pinning.
+    pinned_spell: Rc<RefCell<Option<Rc<Spell>>>>,
+    /// Whether previewed spell is pinned next to preview, to compare with while browsing.
+    pin_preview: gtk4::CheckButton,
+    /// Cards suggested for previewed spell.
+    related_spells: RelatedSpells,
+    /// Only present in debug mode.
+    inspector: Option<SpellInspector>,
+    text_editor: CardTextEditor,
+    profile_settings: ProfileSettings,
+    abbreviations: Rc<Abbreviations>,
+    /// Whether abbreviations are applied to preview and export.
+    abbreviate: gtk4::CheckButton,
+    /// Whether section heights and overflow are shown on preview.
+    layout_overlay: gtk4::CheckButton,
+    /// Whether preview shows cards at their printed size instead of fitting the pane.
+    print_size: gtk4::CheckButton,
+    /// Items match `PrintSimulation::ALL`.
+    print_simulation: gtk4::DropDown,
+    /// Whether search is limited to spell list of the deck.
+    legal_only: gtk4::CheckButton,
     window: ApplicationWindow,
 }
 
 impl AppState {
-    fn new(db: Rc<SimpleSpellDB>, main_window: &ApplicationWindow) -> (Self, impl IsA<Widget>) {
+    fn new(
+        db: Rc<RefCell<SimpleSpellDB>>,
+        config: Rc<RefCell<AppConfig>>,
+        main_window: &ApplicationWindow,
+        debug: bool,
+        profile: Option<&str>,
+    ) -> (Self, impl IsA<Widget>) {
         let (selected_spells, selected_spells_widget) = SelectedSpellCollection::new();
         let (search_results, search_results_widget) = SpellCollection::new();
+        let (inspector, inspector_widget) = if debug {
+            let (inspector, widget) = SpellInspector::new();
+            (Some(inspector), Some(widget))
+        } else {
+            (None, None)
+        };
+        let (text_editor, text_editor_widget) = CardTextEditor::new();
+        let (related_spells, related_spells_widget) = RelatedSpells::new();
+        let (duplex_settings, duplex_settings_widget) = DuplexSettings::new();
+        let (profile_settings, profile_settings_widget) =
+            ProfileSettings::new(config::profiles_path(), duplex_settings, profile);
         let active_spell = Rc::new(RefCell::new(None));
+        let abbreviations = config::abbreviations_path()
+            .map(|path| Abbreviations::load_or_default(&path))
+            .unwrap_or_else(|| Ok(Abbreviations::default()))
+            .unwrap_or_else(|error| {
+                tracing::warn!("{error:#}. Using default abbreviations.");
+                Abbreviations::default()
+            });
+        let abbreviate = gtk4::CheckButton::builder()
+            .label("Abbreviate text")
+            .build();
+        let layout_overlay = gtk4::CheckButton::builder().label("Layout overlay").build();
+        let print_size = gtk4::CheckButton::builder()
+            .label("100% print size")
+            .tooltip_text("Show card at its physical size, to judge legibility of small text")
+            .build();
+        let print_simulation = gtk4::DropDown::from_strings(
+            &PrintSimulation::ALL.map(|simulation| simulation.label()),
+        );
+        print_simulation.set_tooltip_text(Some(
+            "Show card as printed in black and white, to catch borders and text too light or thin to print",
+        ));
+        let legal_only = gtk4::CheckButton::builder()
+            .label("Only legal spells")
+            .tooltip_text("Only spells on the spell list set in deck properties")
+            .build();
+        let pin_preview = gtk4::CheckButton::builder()
+            .label("Pin")
+            .tooltip_text("Keep this card next to preview, to compare while browsing")
+            .build();
         let result = Self {
             db,
+            config,
+            query: Rc::new(RefCell::new(Query::default())),
+            search_generation: Arc::new(AtomicU64::new(0)),
+            search_timeout: Rc::new(RefCell::new(None)),
+            deck_metadata: Rc::new(RefCell::new(DeckMetadata {
+                created: deck::today(),
+                ..DeckMetadata::default()
+            })),
             selected_spells,
             search_results,
             active_spell,
+            pinned_spell: Rc::new(RefCell::new(None)),
+            pin_preview,
+            related_spells,
+            inspector,
+            text_editor,
+            profile_settings,
+            abbreviations: Rc::new(abbreviations),
+            abbreviate,
+            layout_overlay,
+            print_size,
+            print_simulation,
+            legal_only,
             window: main_window.clone(),
         };
 
-        let widget = result.build_widget(selected_spells_widget, search_results_widget);
+        let widget = result.build_widget(
+            selected_spells_widget,
+            search_results_widget,
+            text_editor_widget,
+            related_spells_widget,
+            duplex_settings_widget,
+            profile_settings_widget,
+            inspector_widget,
+        );
+        result.apply_spell_list();
         (result, widget)
     }
 
@@ -63,156 +279,1152 @@ impl AppState {
         &self,
         selected_spells: impl IsA<Widget>,
         search_results: impl IsA<Widget>,
+        text_editor: impl IsA<Widget>,
+        related_spells: impl IsA<Widget>,
+        duplex_settings: impl IsA<Widget>,
+        profile_settings: impl IsA<Widget>,
+        inspector: Option<impl IsA<Widget>>,
     ) -> impl IsA<Widget> {
+        let touch_mode = self.build_touch_mode_toggle();
         let layout = gtk4::Box::builder()
             .orientation(gtk4::Orientation::Horizontal)
             .build();
+        layout.append(&self.build_search_sidebar(search_results, &touch_mode));
+        layout.append(&self.build_preview_pane(related_spells, text_editor, inspector));
+        layout.append(&self.build_selection_sidebar(
+            selected_spells,
+            duplex_settings,
+            profile_settings,
+        ));
 
-        let left_sidebar = gtk4::Box::builder()
+        self.connect_spell_added();
+        self.connect_spell_removed();
+        self.connect_export_actions();
+        self.connect_deck_actions();
+        self.connect_menu_actions();
+        self.connect_toggle_actions(&[
+            ("toggle-abbreviate", &self.abbreviate),
+            ("toggle-layout-overlay", &self.layout_overlay),
+            ("toggle-print-size", &self.print_size),
+            ("toggle-pin-preview", &self.pin_preview),
+            ("toggle-legal-only", &self.legal_only),
+            ("toggle-touch-mode", &touch_mode),
+        ]);
+        self.connect_command_palette();
+        layout
+    }
+
+    /// Left sidebar: settings, search filters and search results.
+    fn build_search_sidebar(
+        &self,
+        search_results: impl IsA<Widget>,
+        touch_mode: &gtk4::CheckButton,
+    ) -> gtk4::Box {
+        let sidebar = gtk4::Box::builder()
             .orientation(gtk4::Orientation::Vertical)
             .css_classes(["search_sidebar"])
             .build();
 
+        let sources = self.db.borrow().sources();
+        let system = self.db.borrow().system();
+        let settings = gtk4::Box::builder()
+            .orientation(gtk4::Orientation::Vertical)
+            .build();
+        settings.append(&self.build_system_selector(&*system));
+        settings.append(touch_mode);
+        settings.append(&self.build_network_toggle());
+        let settings_expander = gtk4::Expander::builder()
+            .label("Settings")
+            .child(&settings)
+            .build();
+        sidebar.append(&settings_expander);
+        app_menu::add_window_action(&self.window, "open-settings", move || {
+            settings_expander.set_expanded(true)
+        });
+
         let app_state = self.clone();
-        left_sidebar.append(&build_search(move |query| {
-            let result = app_state.db.search(&query);
-            app_state.search_results.set_spells(&result);
-        }));
-        self.search_results
-            .set_spells(&self.db.search(&Query::default()));
-        left_sidebar.append(&search_results);
+        sidebar.append(&build_search(
+            &sources,
+            &*system,
+            touch_mode,
+            &self.legal_only,
+            move |query| {
+                app_state.query.replace(query);
+                app_state.schedule_search();
+            },
+        ));
+        self.refresh_search();
+        sidebar.append(&search_results);
+        sidebar
+    }
+
+    /// Middle pane: previews of active and pinned spell, related spells and card text editor.
+    fn build_preview_pane(
+        &self,
+        related_spells: impl IsA<Widget>,
+        text_editor: impl IsA<Widget>,
+        inspector: Option<impl IsA<Widget>>,
+    ) -> gtk4::Box {
+        let spell_preview_widget = self.build_preview_widget(self.active_spell.clone(), true);
+        let pinned_preview_widget = self.build_preview_widget(self.pinned_spell.clone(), false);
+        pinned_preview_widget.set_visible(false);
+
+        let discord_active_button = gtk4::Button::builder()
+            .label("Copy as Discord markdown")
+            .tooltip_text("Copy previewed spell as Discord messages, one message per click")
+            .hexpand(true)
+            .build();
+        let preview_bar = gtk4::Box::builder()
+            .orientation(gtk4::Orientation::Horizontal)
+            .build();
+        preview_bar.append(&discord_active_button);
+        preview_bar.append(&self.pin_preview);
+
+        let preview_pane = gtk4::Box::builder()
+            .orientation(gtk4::Orientation::Vertical)
+            .hexpand(true)
+            .build();
+        let previews = gtk4::Box::builder()
+            .orientation(gtk4::Orientation::Horizontal)
+            .homogeneous(true)
+            .build();
+        previews.append(&spell_preview_widget);
+        previews.append(&pinned_preview_widget);
+        preview_pane.append(&previews);
+        preview_pane.append(&related_spells);
+        preview_pane.append(&preview_bar);
+        preview_pane.append(&text_editor);
+        if let Some(inspector) = inspector {
+            preview_pane.append(&inspector);
+        }
 
-        let spell_preview_widget = self.build_search_preview_widget();
+        let both_previews = [spell_preview_widget.clone(), pinned_preview_widget.clone()];
+        let redraw = move || {
+            both_previews
+                .iter()
+                .for_each(|preview| preview.queue_draw())
+        };
+        let redraw_moved = redraw.clone();
+        self.abbreviate.connect_toggled(move |_| redraw_moved());
+        let redraw_moved = redraw.clone();
+        self.layout_overlay.connect_toggled(move |_| redraw_moved());
+        let redraw_moved = redraw.clone();
+        self.print_size.connect_toggled(move |_| redraw_moved());
+        let redraw_moved = redraw.clone();
+        self.print_simulation
+            .connect_selected_notify(move |_| redraw_moved());
+        let preview = spell_preview_widget.clone();
+        self.text_editor
+            .connect_changed(move || preview.queue_draw());
+        self.profile_settings.connect_changed(redraw);
+        self.connect_pin_preview(pinned_preview_widget);
+        self.connect_level_up(spell_preview_widget.clone().upcast());
+        self.connect_spell_activated(spell_preview_widget);
+        self.connect_discord_copy(discord_active_button, |app_state| {
+            let active_spell = app_state.active_spell.borrow();
+            active_spell
+                .iter()
+                .map(|spell| app_state.text_editor.apply(spell).into_owned())
+                .collect()
+        });
+        preview_pane
+    }
 
-        let right_sidebar = gtk4::Box::builder()
+    /// Right sidebar: selected spells with export settings. Other exports, deck files and
+    /// share codes are in application menu.
+    fn build_selection_sidebar(
+        &self,
+        selected_spells: impl IsA<Widget>,
+        duplex_settings: impl IsA<Widget>,
+        profile_settings: impl IsA<Widget>,
+    ) -> gtk4::Box {
+        let sidebar = gtk4::Box::builder()
             .orientation(gtk4::Orientation::Vertical)
             .css_classes(["search_sidebar"])
             .build();
         let export_button = gtk4::Button::builder()
             .label("Export")
+            .action_name("win.export")
+            .css_classes(["export_button"])
+            .build();
+        let discord_selection_button = gtk4::Button::builder()
+            .label("Copy selection for Discord")
+            .tooltip_text("Copy selected spells as Discord messages, one message per click")
             .css_classes(["export_button"])
             .build();
-        right_sidebar.append(&selected_spells);
-        right_sidebar.append(&export_button);
+        sidebar.append(&selected_spells);
+        sidebar.append(&self.abbreviate);
+        sidebar.append(&self.layout_overlay);
+        sidebar.append(&self.print_size);
+        sidebar.append(&self.print_simulation);
+        sidebar.append(&duplex_settings);
+        sidebar.append(&profile_settings);
+        sidebar.append(&export_button);
+        sidebar.append(&discord_selection_button);
+        self.connect_discord_copy(discord_selection_button, |app_state| {
+            let mut copied = HashSet::new();
+            let mut spells = app_state.edited_spells();
+            spells.retain(|spell| copied.insert(spell.id));
+            spells
+        });
+        sidebar
+    }
 
-        layout.append(&left_sidebar);
-        layout.append(&spell_preview_widget);
-        layout.append(&right_sidebar);
+    /// Register window actions exporting selected spells, used by application menu.
+    fn connect_export_actions(&self) {
+        let app_state = self.clone();
+        app_menu::add_window_action(&self.window, "export", move || {
+            let app_state_moved = app_state.clone();
+            app_state.check_before_export(move || {
+                app_state_moved.show_save_dialog("pdf", |app_state, file| {
+                    app_state.save_selected_spells(file)
+                })
+            });
+        });
+        self.connect_print();
+        self.connect_page_preview();
+        self.add_export_action("export-calibration", "pdf", |app_state, file| {
+            let path = local_path(&file)?;
+            let options = app_state.render_options();
+            write_replacing(&path, |file| Ok(write_calibration_page(file, &options)?))
+        });
+        self.add_export_action("export-roll20", "zip", |app_state, file| {
+            let path = local_path(&file)?;
+            let spells = app_state.edited_spells();
+            write_replacing(&path, |file| roll20::write_handouts(file, spells.iter()))
+        });
+        self.add_export_action("export-foundry", "json", |app_state, file| {
+            let path = local_path(&file)?;
+            let name = path
+                .file_stem()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "Spells".to_string());
+            let spells = app_state.edited_spells();
+            write_replacing(&path, |file| {
+                foundry::write_journal(file, &name, spells.iter())
+            })
+        });
+        self.add_export_action("export-anki", "txt", |app_state, file| {
+            let path = local_path(&file)?;
+            let name = path
+                .file_stem()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "Spells".to_string());
+            let spells = app_state.edited_spells();
+            write_replacing(&path, |file| anki::write_notes(file, &name, spells.iter()))
+        });
+        self.add_export_action("export-csv", "csv", |app_state, file| {
+            let path = local_path(&file)?;
+            let entries = app_state.selected_spells.deck_entries();
+            write_replacing(&path, |file| deck::write_csv(file, &entries))
+        });
+    }
 
-        self.connect_spell_activated(spell_preview_widget);
-        self.connect_spell_added();
-        self.connect_spell_removed();
-        self.connect_export_dialog(export_button);
+    /// Register window actions saving and opening decks, importing spells and sharing them.
+    fn connect_deck_actions(&self) {
+        self.add_export_action("save-deck", "json", |app_state, file| {
+            let path = local_path(&file)?;
+            let deck = deck::DeckFile {
+                metadata: app_state.deck_metadata.borrow().clone(),
+                entries: app_state.selected_spells.deck_entries(),
+                export: Some(app_state.profile_settings.current_profile()),
+            };
+            write_replacing(&path, |file| deck.write(file))
+        });
+        self.add_import_action("open-deck", "Open deck", "json", Self::open_deck);
+        self.add_import_action("import-csv", "Import CSV", "csv", Self::import_csv);
+        let app_state = self.clone();
+        app_menu::add_window_action(&self.window, "deck-properties", move || {
+            let app_state_moved = app_state.clone();
+            deck_properties::show_deck_properties(
+                &app_state.window,
+                &*app_state.db.borrow().system(),
+                app_state.deck_metadata.clone(),
+                move || app_state_moved.apply_spell_list(),
+            )
+        });
+        self.connect_share_code();
+        self.connect_reload_bundles();
+    }
 
-        layout
+    /// Refresh search results after a short pause in typing.
+    fn schedule_search(&self) {
+        if let Some(pending) = self.search_timeout.take() {
+            pending.remove();
+        }
+        let app_state = self.clone();
+        let pending = glib::timeout_add_local_once(SEARCH_DEBOUNCE, move || {
+            // Source is destroyed after firing, so it must not be removed anymore.
+            app_state.search_timeout.take();
+            app_state.refresh_search();
+        });
+        self.search_timeout.replace(Some(pending));
     }
 
-    fn connect_export_dialog(&self, button: gtk4::Button) {
-        let selected_spells = self.selected_spells.clone();
+    /// Search on a background thread, so typing stays fluid with large databases.
+    /// Newer search cancels one still running, and results of stale ones are dropped.
+    fn refresh_search(&self) {
+        let generation = self.search_generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let spells = self.db.borrow().spells_snapshot();
+        let shared_names = self.db.borrow().shared_names();
+        let mut query = self.query.borrow().clone();
+        if self.legal_only.is_active() {
+            query.spell_list = self.deck_metadata.borrow().spell_list;
+        }
+        let current_generation = self.search_generation.clone();
+        let app_state = self.clone();
+        glib::spawn_future_local(async move {
+            let found = gio::spawn_blocking(move || {
+                search_spells(&spells, &query, || {
+                    current_generation.load(Ordering::SeqCst) != generation
+                })
+            })
+            .await;
+            if app_state.search_generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            if let Ok(Some(found)) = found {
+                let found = found.into_iter().map(Rc::new).collect::<Vec<_>>();
+                app_state.search_results.set_spells(&found, &shared_names);
+            }
+        });
+    }
+
+    /// Flag selected spells off the spell list of the deck, and refresh search limited to it.
+    fn apply_spell_list(&self) {
+        let spell_list = self.deck_metadata.borrow().spell_list;
+        self.selected_spells.set_spell_list(spell_list);
+        self.legal_only.set_sensitive(spell_list.is_some());
+        if self.legal_only.is_active() {
+            self.schedule_search();
+        }
+    }
+
+    fn connect_print(&self) {
+        let app_state = self.clone();
+        app_menu::add_window_action(&self.window, "print", move || {
+            let app_state_moved = app_state.clone();
+            app_state.check_before_export(move || {
+                let result = print::print_spells(
+                    &app_state_moved.window,
+                    app_state_moved.edited_spells(),
+                    app_state_moved.render_options(),
+                );
+                if let Err(error) = result {
+                    gtk4::AlertDialog::builder()
+                        .message("Error then printing")
+                        .detail(error.to_string())
+                        .build()
+                        .show(Some(&app_state_moved.window));
+                }
+            });
+        });
+    }
+
+    /// Check selected spells for likely card problems, then call `export`: right away if
+    /// none are found, otherwise once user goes on from the checklist of them.
+    fn check_before_export(&self, export: impl Fn() + 'static) {
+        let findings = lint_spells(&self.edited_spells(), &LintLimits::default());
+        if findings.is_empty() {
+            export();
+        } else {
+            lint_checklist::show_lint_checklist(&self.window, findings, export);
+        }
+    }
+
+    fn connect_page_preview(&self) {
+        let app_state = self.clone();
+        app_menu::add_window_action(&self.window, "preview-pages", move || {
+            let result = page_preview::show_page_preview(
+                &app_state.window,
+                app_state.edited_spells(),
+                app_state.render_options(),
+            );
+            if let Err(error) = result {
+                gtk4::AlertDialog::builder()
+                    .message("Unable to preview pages")
+                    .detail(error.to_string())
+                    .build()
+                    .show(Some(&app_state.window));
+            }
+        });
+    }
+
+    /// Register window actions of help section of application menu.
+    fn connect_menu_actions(&self) {
+        let app_state = self.clone();
+        app_menu::add_window_action(&self.window, "about", move || {
+            app_menu::show_about(&app_state.window, &app_state.db.borrow())
+        });
+        let app_state = self.clone();
+        app_menu::add_window_action(&self.window, "data-info", move || {
+            app_menu::show_data_info(&app_state.window, &app_state.db.borrow())
+        });
+        let app_state = self.clone();
+        app_menu::add_window_action(&self.window, "sync-data", move || {
+            data_sync::show_data_sync(
+                &app_state.window,
+                app_state.db.clone(),
+                app_state.config.clone(),
+            )
+        });
+        let allowed = self.config.borrow().network;
+        app_menu::set_action_enabled(&self.window, "sync-data", allowed);
+        let app_state = self.clone();
+        app_menu::add_window_action(&self.window, "open-log", move || {
+            app_menu::open_log(&app_state.window)
+        });
+    }
+
+    /// Register window actions flipping check buttons. Insensitive ones are left as is.
+    fn connect_toggle_actions(&self, checks: &[(&str, &gtk4::CheckButton)]) {
+        for &(name, check) in checks {
+            let check = check.clone();
+            app_menu::add_window_action(&self.window, name, move || {
+                if check.is_sensitive() {
+                    check.set_active(!check.is_active());
+                }
+            });
+        }
+    }
+
+    /// Action showing command palette with `PALETTE_COMMANDS` and spells of the database.
+    fn connect_command_palette(&self) {
+        let app_state = self.clone();
+        app_menu::add_window_action(&self.window, "command-palette", move || {
+            let spells = app_state.db.borrow().spells_snapshot();
+            let names = spells.iter().map(|spell| spell.name.clone()).collect();
+            let selected_spells = app_state.selected_spells.clone();
+            command_palette::show_command_palette(
+                &app_state.window,
+                PALETTE_COMMANDS,
+                names,
+                move |i| selected_spells.add_spell(Rc::new(spells[i].clone())),
+            );
+        });
+    }
+
+    /// Action choosing deck casting rank. Preview is redrawn with the new rank.
+    fn connect_level_up(&self, preview: Widget) {
+        let app_state = self.clone();
+        app_menu::add_window_action(&self.window, "level-up", move || {
+            let preview = preview.clone();
+            level_up::show_level_up(
+                &app_state.window,
+                app_state.deck_metadata.clone(),
+                app_state.edited_spells(),
+                move || preview.queue_draw(),
+            )
+        });
+    }
+
+    /// Dropdown saving selected game system to settings. Database is loaded at start,
+    /// so new system is used after restart.
+    fn build_system_selector(&self, current: &dyn GameSystem) -> gtk4::DropDown {
+        let systems = all_systems();
+        let names = systems
+            .iter()
+            .map(|system| system.name())
+            .collect::<Vec<_>>();
+        let selector = gtk4::DropDown::from_strings(&names);
+        selector.set_tooltip_text(Some("Game system"));
+        let current_index = names.iter().position(|name| *name == current.name());
+        selector.set_selected(current_index.unwrap_or(0) as u32);
+        let window = self.window.clone();
+        selector.connect_selected_notify(move |selector| {
+            let Some(system) = systems.get(selector.selected() as usize) else {
+                return;
+            };
+            let detail = match app_config::save_setting("app", "system", system.name()) {
+                Ok(()) => "Restart the application to switch game system.".to_string(),
+                Err(error) => format!("Unable to save setting. {error:#}"),
+            };
+            gtk4::AlertDialog::builder()
+                .message(system.name())
+                .detail(detail)
+                .build()
+                .show(Some(&window));
+        });
+        selector
+    }
+
+    /// Check button allowing networked features, like data sync. Off by default, so nothing
+    /// is sent anywhere unless user asks for it. State is saved to settings.
+    fn build_network_toggle(&self) -> gtk4::CheckButton {
+        let toggle = gtk4::CheckButton::builder()
+            .label("Allow network access")
+            .tooltip_text("Needed for data sync. Without it nothing is downloaded or sent")
+            .active(self.config.borrow().network)
+            .build();
         let window = self.window.clone();
-        button.connect_clicked(move |_| {
-            let filter = gtk4::FileFilter::new();
-            filter.add_suffix("pdf");
-            filter.add_mime_type("pdf");
-            let filters = gio::ListStore::new::<gtk4::FileFilter>();
-            filters.append(&filter);
+        let app_config = self.config.clone();
+        toggle.connect_toggled(move |toggle| {
+            let enabled = toggle.is_active();
+            app_menu::set_action_enabled(&window, "sync-data", enabled);
+            app_config.borrow_mut().network = enabled;
+            if let Err(error) = app_config::save_setting("app", "network", enabled) {
+                tracing::warn!("Unable to save network setting. {error:#}");
+            }
+        });
+        toggle
+    }
+
+    /// Check button switching touch friendly layout, with larger rows and buttons, and
+    /// rank picker under search. State is saved to settings.
+    fn build_touch_mode_toggle(&self) -> gtk4::CheckButton {
+        let enabled = self.config.borrow().touch_mode;
+        let toggle = gtk4::CheckButton::builder()
+            .label("Touch mode")
+            .tooltip_text("Larger rows and buttons, and rank picker, for tablets")
+            .active(enabled)
+            .build();
+        if enabled {
+            self.window.add_css_class(TOUCH_MODE_CLASS);
+        }
+        let window = self.window.clone();
+        let app_config = self.config.clone();
+        toggle.connect_toggled(move |toggle| {
+            let enabled = toggle.is_active();
+            if enabled {
+                window.add_css_class(TOUCH_MODE_CLASS);
+            } else {
+                window.remove_css_class(TOUCH_MODE_CLASS);
+            }
+            app_config.borrow_mut().touch_mode = enabled;
+            if let Err(error) = app_config::save_setting("app", "touch_mode", enabled) {
+                tracing::warn!("Unable to save touch mode setting. {error:#}");
+            }
+        });
+        toggle
+    }
+
+    fn connect_reload_bundles(&self) {
+        let app_state = self.clone();
+        app_menu::add_window_action(&self.window, "reload-bundles", move || {
+            let Some(dir) = config::bundles_dir(&*app_state.db.borrow().system()) else {
+                return;
+            };
+            let message = match app_state.db.borrow_mut().load_bundles(Some(&dir)) {
+                Ok(reports) if reports.is_empty() => {
+                    format!("No bundles found in {}", dir.display())
+                }
+                Ok(reports) => reports
+                    .iter()
+                    .map(|report| report.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                Err(error) => error.to_string(),
+            };
+            app_state.refresh_search();
+            gtk4::AlertDialog::builder()
+                .message("Bundles reloaded")
+                .detail(message)
+                .build()
+                .show(Some(&app_state.window));
+        });
+    }
+
+    /// Register window action `name` asking for file to export to, see `show_save_dialog`.
+    fn add_export_action(
+        &self,
+        name: &str,
+        suffix: &'static str,
+        save: impl Fn(&AppState, gio::File) -> anyhow::Result<()> + Clone + 'static,
+    ) {
+        let app_state = self.clone();
+        app_menu::add_window_action(&self.window, name, move || {
+            app_state.show_save_dialog(suffix, save.clone())
+        });
+    }
+
+    /// Ask for file to export to, and pass it to `save`. Dialog suggests a name with `suffix`,
+    /// which is added back if removed. Then a file of the name with suffix exists, replacing
+    /// it is confirmed again, as the dialog only asked about the picked name.
+    fn show_save_dialog(
+        &self,
+        suffix: &'static str,
+        save: impl Fn(&AppState, gio::File) -> anyhow::Result<()> + 'static,
+    ) {
+        let filters = file_filters(suffix);
+        let cancelable: Option<&gio::Cancellable> = None;
+        let app_state = self.clone();
+        gtk4::FileDialog::builder()
+            .title("Save as")
+            .filters(&filters)
+            .initial_name(format!("spells.{suffix}"))
+            .build()
+            .save(Some(&self.window), cancelable, move |file| {
+                let Ok(file) = file else {
+                    return;
+                };
+                match with_suffix(&file, suffix) {
+                    Some(file) if file.query_exists(gio::Cancellable::NONE) => {
+                        app_state.confirm_replace(file, save);
+                    }
+                    suffixed => app_state.run_save(suffixed.unwrap_or(file), &save),
+                }
+            });
+    }
+
+    /// Ask whether to replace existing `file`, and pass it to `save` if so.
+    fn confirm_replace(
+        &self,
+        file: gio::File,
+        save: impl Fn(&AppState, gio::File) -> anyhow::Result<()> + 'static,
+    ) {
+        let name = file.parse_name();
+        let cancelable: Option<&gio::Cancellable> = None;
+        let app_state = self.clone();
+        gtk4::AlertDialog::builder()
+            .message(format!("Replace {name}?"))
+            .detail("A file with this name already exists.")
+            .buttons(["Cancel", "Replace"])
+            .cancel_button(0)
+            .default_button(0)
+            .build()
+            .choose(Some(&self.window), cancelable, move |button| {
+                if matches!(button, Ok(1)) {
+                    app_state.run_save(file, &save);
+                }
+            });
+    }
+
+    /// Pass `file` to `save`, showing error if it fails.
+    fn run_save(
+        &self,
+        file: gio::File,
+        save: &impl Fn(&AppState, gio::File) -> anyhow::Result<()>,
+    ) {
+        if let Err(error) = save(self, file) {
+            gtk4::AlertDialog::builder()
+                .detail(error.to_string())
+                .message("Error then exporting")
+                .build()
+                .show(Some(&self.window));
+        }
+    }
+
+    /// Copy Discord messages for `spells` to clipboard, one message per click, since long
+    /// spells do not fit single message. Button label shows which part is next.
+    fn connect_discord_copy(
+        &self,
+        button: gtk4::Button,
+        spells: impl Fn(&AppState) -> Vec<Spell> + 'static,
+    ) {
+        let app_state = self.clone();
+        let label = button.label().unwrap_or_default();
+        let pending = RefCell::new(VecDeque::new());
+        let total = Cell::new(0);
+        button.connect_clicked(move |button| {
+            let mut pending = pending.borrow_mut();
+            if pending.is_empty() {
+                *pending = spells(&app_state)
+                    .iter()
+                    .flat_map(discord::spell_messages)
+                    .collect();
+                total.set(pending.len());
+            }
+            let Some(message) = pending.pop_front() else {
+                return;
+            };
+            button.clipboard().set_text(&message);
+            if pending.is_empty() {
+                button.set_label(&label);
+            } else {
+                let next = total.get() - pending.len() + 1;
+                button.set_label(&format!("Copy next part ({next}/{})", total.get()));
+            }
+        });
+    }
+
+    /// Register window action `name` opening file with `suffix` and passing it to `open`,
+    /// which returns report on spells not matched in database, empty if all were.
+    fn add_import_action(
+        &self,
+        name: &str,
+        title: &'static str,
+        suffix: &'static str,
+        open: fn(&AppState, gio::File) -> anyhow::Result<String>,
+    ) {
+        let app_state = self.clone();
+        app_menu::add_window_action(&self.window, name, move || {
             let cancelable: Option<&gio::Cancellable> = None;
-            let selected_spells_moved = selected_spells.clone();
-            let window_moved = window.clone();
+            let window = app_state.window.clone();
+            let app_state = app_state.clone();
             gtk4::FileDialog::builder()
-                .title("Save as")
-                .filters(&filters)
+                .title(title)
+                .filters(&file_filters(suffix))
                 .build()
-                .save(Some(&window), cancelable, move |file| {
+                .open(Some(&window), cancelable, move |file| {
                     if let Ok(file) = file {
-                        if let Err(error) = Self::save_selected_spells(file, &selected_spells_moved)
-                        {
-                            gtk4::AlertDialog::builder()
-                                .detail(error.to_string())
-                                .message("Error then exporting")
-                                .build()
-                                .show(Some(&window_moved));
-                        }
+                        let (message, detail) = match open(&app_state, file) {
+                            Ok(mismatches) if mismatches.is_empty() => return,
+                            Ok(mismatches) => ("Some spells were not matched", mismatches),
+                            Err(error) => ("Error then importing", format!("{error:#}")),
+                        };
+                        gtk4::AlertDialog::builder()
+                            .message(message)
+                            .detail(detail)
+                            .build()
+                            .show(Some(&app_state.window));
                     }
                 });
         });
     }
 
-    fn save_selected_spells(
-        file: gio::File,
-        spells: &SelectedSpellCollection,
-    ) -> anyhow::Result<()> {
-        let path = file
-            .path()
-            .ok_or_else(|| anyhow::anyhow!("Cannot obtain path"))?;
-        let file = std::fs::File::create(path)?;
-        let spells = spells.collect_spells();
-        write_to_pdf(file, spells.iter().map(|s| s.as_ref()))?;
+    fn connect_share_code(&self) {
+        let selected_spells = self.selected_spells.clone();
+        let window = self.window.clone();
+        app_menu::add_window_action(&self.window, "copy-share-code", move || {
+            let code = deck::encode_share_code(&selected_spells.spell_counts());
+            window.clipboard().set_text(&code);
+        });
+        let app_state = self.clone();
+        app_menu::add_window_action(&self.window, "paste-share-code", move || {
+            let app_state = app_state.clone();
+            let cancelable: Option<&gio::Cancellable> = None;
+            app_state.window.clipboard().read_text_async(cancelable, move |text| {
+                let text = text.ok().flatten().unwrap_or_default();
+                let message = match deck::decode_share_code(&text) {
+                    Ok(spells) => {
+                        let (spells, missing) =
+                            deck::resolve_share_code(&*app_state.db.borrow(), &spells);
+                        for (spell, count) in spells {
+                            app_state.selected_spells.add_entry(spell, count, "");
+                        }
+                        if missing == 0 {
+                            return;
+                        }
+                        format!("{missing} spells of the code are not in database. They may come from homebrew bundles.")
+                    }
+                    Err(error) => format!("Clipboard does not contain valid share code. {error}"),
+                };
+                gtk4::AlertDialog::builder()
+                    .message("Share code")
+                    .detail(message)
+                    .build()
+                    .show(Some(&app_state.window));
+            });
+        });
+    }
+
+    /// Add spells from CSV file to selection. Returns report on entries which did not match
+    /// database exactly, empty if all did.
+    fn import_csv(&self, file: gio::File) -> anyhow::Result<String> {
+        let path = local_path(&file)?;
+        let entries = deck::parse_csv(&std::fs::read_to_string(path)?)?;
+        let deck = deck::resolve(&*self.db.borrow(), entries);
+        for (spell, entry) in deck.spells {
+            self.selected_spells
+                .add_entry(spell, entry.count, &entry.notes);
+        }
+        Ok(self.resolve_mismatches(deck.mismatches))
+    }
+
+    /// Replace selection and export settings with ones saved in deck file. Returns report
+    /// on spells which did not match database exactly, empty if all did.
+    fn open_deck(&self, file: gio::File) -> anyhow::Result<String> {
+        let path = local_path(&file)?;
+        let deck_file = deck::DeckFile::parse(&std::fs::read_to_string(path)?)?;
+        let deck = deck::resolve(&*self.db.borrow(), deck_file.entries);
+        self.selected_spells.replace_entries(deck.spells);
+        self.deck_metadata.replace(deck_file.metadata);
+        self.apply_spell_list();
+        if let Some(export) = deck_file.export {
+            self.profile_settings.show_profile(export);
+        }
+        Ok(self.resolve_mismatches(deck.mismatches))
+    }
+
+    /// Ask which spell is meant by entries with name shared by several sources, and add
+    /// chosen ones to selection. Returns report on other mismatches, empty if there are none.
+    fn resolve_mismatches(&self, mismatches: Vec<Mismatch>) -> String {
+        let mut ambiguous = vec![];
+        let mut report = vec![];
+        for mismatch in mismatches {
+            match mismatch {
+                Mismatch::Ambiguous { entry, candidates } => ambiguous.push((entry, candidates)),
+                mismatch => report.push(mismatch.to_string()),
+            }
+        }
+        if !ambiguous.is_empty() {
+            let selected_spells = self.selected_spells.clone();
+            disambiguate::show_disambiguation(&self.window, ambiguous, move |chosen| {
+                for (spell, entry) in chosen {
+                    selected_spells.add_deck_entry(spell, &entry);
+                }
+            });
+        }
+        report.join("\n")
+    }
+
+    /// Abbreviations to apply, if user enabled them.
+    fn active_abbreviations(&self) -> Option<Rc<Abbreviations>> {
+        self.abbreviate
+            .is_active()
+            .then(|| self.abbreviations.clone())
+    }
+
+    /// Render options reflecting current state of controls.
+    fn render_options(&self) -> RenderOptions {
+        OptionsChain {
+            profile: Some(self.profile_settings.current_profile()),
+            deck: self.deck_metadata.borrow().clone(),
+            spells: self.selected_spells.collect_overrides(),
+            ..OptionsChain::new(
+                RenderOptions {
+                    system: self.db.borrow().system(),
+                    abbreviations: self.active_abbreviations(),
+                    ..RenderOptions::default()
+                },
+                &self.config.borrow(),
+            )
+        }
+        .resolve()
+    }
+
+    /// Selected spells, repeated by their count, with user edits of card text applied.
+    fn edited_spells(&self) -> Vec<Spell> {
+        self.selected_spells
+            .collect_spells()
+            .iter()
+            .map(|spell| self.text_editor.apply(spell).into_owned())
+            .collect()
+    }
+
+    fn save_selected_spells(&self, file: gio::File) -> anyhow::Result<()> {
+        let path = local_path(&file)?;
+        let spells = self.edited_spells();
+        let options = self.render_options();
+        let manifest = write_replacing(&path, |file| {
+            Ok(write_to_pdf(file, spells.iter(), &options)?)
+        })?;
+        // Manifest is saved next to the document, so cut cards can be checked against it.
+        if options.numbering {
+            write_replacing(&path.with_extension("csv"), |file| {
+                Ok(manifest.write_csv(file)?)
+            })?;
+        }
+        self.report_legibility(&spells, &options)
+    }
+
+    /// Warn about exported cards with text smaller than minimum font size of the profile.
+    fn report_legibility(&self, spells: &[Spell], options: &RenderOptions) -> anyhow::Result<()> {
+        let font_config = OwnedFontConfig::<CairoFont>::with_fonts(
+            &mut Library::init()?,
+            &*options.system,
+            options.theme.fonts,
+        )?;
+        let warnings = legibility_report(&font_config.config(), spells, options);
+        if warnings.is_empty() {
+            return Ok(());
+        }
+        let detail = warnings
+            .iter()
+            .map(|warning| warning.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        gtk4::AlertDialog::builder()
+            .message(format!(
+                "Text smaller than {}Pt on {} cards",
+                options.min_font_size,
+                warnings.len()
+            ))
+            .detail(detail)
+            .build()
+            .show(Some(&self.window));
         Ok(())
     }
 
     fn connect_spell_activated(&self, widget: impl IsA<Widget>) {
         let active_spell = self.active_spell.clone();
+        let inspector = self.inspector.clone();
+        let text_editor = self.text_editor.clone();
+        let related_spells = self.related_spells.clone();
+        let db = self.db.clone();
         self.search_results.connect_spell_selected(move |spell| {
+            if let Some(inspector) = &inspector {
+                inspector.show_spell(spell.as_ref(), db.borrow().raw_data(spell.id));
+            }
+            related_spells.show_spells(db.borrow().related_spells(&spell, RELATED_SPELLS_LIMIT));
+            text_editor.show_spell(spell.clone());
+            crash::set_active_spell(&spell);
             active_spell.replace(Some(spell));
             widget.queue_draw();
         });
     }
 
+    /// Pinning freezes previewed spell, as currently edited, in `pinned_preview`. Without
+    /// previewed spell there is nothing to pin, so the check is turned back off.
+    fn connect_pin_preview(&self, pinned_preview: gtk4::DrawingArea) {
+        let app_state = self.clone();
+        self.pin_preview.connect_toggled(move |check| {
+            let pinned = if check.is_active() {
+                let active_spell = app_state.active_spell.borrow();
+                active_spell
+                    .as_ref()
+                    .map(|spell| Rc::new(app_state.text_editor.apply(spell).into_owned()))
+            } else {
+                None
+            };
+            if check.is_active() && pinned.is_none() {
+                // Toggles again, clearing the pane.
+                check.set_active(false);
+                return;
+            }
+            pinned_preview.set_visible(pinned.is_some());
+            app_state.pinned_spell.replace(pinned);
+            pinned_preview.queue_draw();
+        });
+    }
+
     fn connect_spell_added(&self) {
         let selected_spells = self.selected_spells.clone();
         let spell_added = move |spell: Rc<Spell>| {
             selected_spells.add_spell(spell);
         };
+        self.related_spells.connect_spell_added(spell_added.clone());
         self.search_results.connect_spell_added(spell_added);
     }
 
     fn connect_spell_removed(&self) {}
 
-    fn build_search_preview_widget(&self) -> impl IsA<Widget> {
+    /// Spell as preview draws it: with card text edits applied if `edited`, and prepared and
+    /// fit like for export.
+    fn preview_spell(
+        &self,
+        config: &FontConfig<'_, CairoFont>,
+        spell: &Spell,
+        edited: bool,
+        options: &RenderOptions,
+    ) -> Spell {
+        let spell = if edited {
+            self.text_editor.apply(spell)
+        } else {
+            Cow::Borrowed(spell)
+        };
+        fit_spell(config, options.prepare_spell(&spell), options).into_owned()
+    }
+
+    /// Drawing area previewing card of `spell` with current render options. With `edited`
+    /// set card text edits are applied, pinned spells carry theirs already.
+    fn build_preview_widget(
+        &self,
+        spell: Rc<RefCell<Option<Rc<Spell>>>>,
+        edited: bool,
+    ) -> gtk4::DrawingArea {
         let spell_preview = gtk4::DrawingArea::builder()
             .width_request(400)
             .hexpand(true)
-            .vexpand_set(true)
+            .vexpand(true)
             .build();
 
-        let active_spell = self.active_spell.clone();
         let font_config: OwnedFontConfig<CairoFont> =
-            OwnedFontConfig::new(&mut Library::init().unwrap()).unwrap();
-
-        spell_preview.set_draw_func(move |_, context, w, h| {
-            if let Some(spell) = active_spell.as_ref().borrow().as_ref() {
-                let config = font_config.config();
-                let (scene, _) = build_spell_scene(&config, spell.as_ref())
-                    .expect("Scene must not be too large");
-                draw_scene(context, w, h, scene);
+            OwnedFontConfig::new(&mut Library::init().unwrap(), &*self.db.borrow().system())
+                .unwrap();
+        // Fonts are reloaded then theme changes them.
+        let font_config = Rc::new(RefCell::new((ThemeFonts::default(), font_config)));
+        // Transform the card was drawn with last, `None` if it could not be laid out.
+        let drawn = Rc::new(Cell::new(None::<cairo::Matrix>));
+        spell_preview.set_has_tooltip(true);
+        let app_state = self.clone();
+        let hovered_spell = spell.clone();
+        let hovered_fonts = font_config.clone();
+        let hovered_transform = drawn.clone();
+        spell_preview.connect_query_tooltip(move |_, x, y, _, tooltip| {
+            let (Some(transform), Some(spell)) =
+                (hovered_transform.get(), hovered_spell.borrow().clone())
+            else {
+                return false;
+            };
+            let Ok(inverse) = transform.try_invert() else {
+                return false;
+            };
+            let (x, y) = inverse.transform_point(x as f64, y as f64);
+            // Card is laid out again, as drawing does not keep it.
+            let options = app_state.render_options();
+            let font_config = hovered_fonts.borrow();
+            let config = font_config.1.config();
+            let spell = app_state.preview_spell(&config, &spell, edited, &options);
+            let Ok((scene, _)) = build_spell_scene(&config, &spell, &options) else {
+                return false;
+            };
+            let Some(hit) = scene.hit_test(Vector2F::new(x as f32, y as f32)) else {
+                return false;
+            };
+            let Some(text) = hit
+                .source
+                .and_then(|source| source.strip_prefix("trait:"))
+                .and_then(glossary::trait_text)
+            else {
+                return false;
+            };
+            let primitive = &scene.primitives[hit.index];
+            let name = primitive.as_text().map_or("", |chunk| &chunk.text);
+            tooltip.set_markup(Some(&format!(
+                "<b>{}</b>\n{}",
+                glib::markup_escape_text(name),
+                glib::markup_escape_text(text)
+            )));
+            // Tooltip is asked again then pointer leaves the trait, so next one gets its own.
+            let rect = primitive.bounds();
+            let (left, top) = transform.transform_point(rect.min_x() as f64, rect.min_y() as f64);
+            let (right, bottom) =
+                transform.transform_point(rect.max_x() as f64, rect.max_y() as f64);
+            tooltip.set_tip_area(&gdk::Rectangle::new(
+                left.floor() as i32,
+                top.floor() as i32,
+                (right - left).ceil() as i32,
+                (bottom - top).ceil() as i32,
+            ));
+            true
+        });
+        let app_state = self.clone();
+        // Window moved to a monitor of other scale or resolution needs sharper drawing, and
+        // print size preview a different scale.
+        spell_preview.connect_scale_factor_notify(|area| area.queue_draw());
+        spell_preview.connect_realize(|area| {
+            let Some(surface) = area.native().and_then(|native| native.surface()) else {
+                return;
+            };
+            let area = area.downgrade();
+            surface.connect_enter_monitor(move |_, _| {
+                if let Some(area) = area.upgrade() {
+                    area.queue_draw();
+                }
+            });
+        });
+
+        spell_preview.set_draw_func(move |area, context, w, h| {
+            if let Some(spell) = spell.as_ref().borrow().as_ref() {
+                let options = app_state.render_options();
+                let mut font_config = font_config.borrow_mut();
+                if !font_config.0.same_files(&options.theme.fonts) {
+                    let fonts = options.theme.fonts;
+                    match OwnedFontConfig::with_fonts(
+                        &mut Library::init().unwrap(),
+                        &*options.system,
+                        fonts,
+                    ) {
+                        Ok(config) => font_config.1 = config,
+                        Err(error) => tracing::warn!("Unable to load theme fonts. {error:#}"),
+                    }
+                    font_config.0 = fonts;
+                }
+                let config = font_config.1.config();
+                let spell = app_state.preview_spell(&config, spell, edited, &options);
+                match build_spell_scene(&config, &spell, &options) {
+                    Ok((scene, _)) => {
+                        let overlay = app_state.layout_overlay.is_active();
+                        let simulation = PrintSimulation::ALL
+                            .get(app_state.print_simulation.selected() as usize)
+                            .copied()
+                            .unwrap_or_default();
+                        let transform = if app_state.print_size.is_active() {
+                            let scale = pixels_per_pt(area);
+                            draw_scene_at_scale(context, w, h, scene, overlay, simulation, scale)
+                        } else {
+                            draw_scene(context, w, h, scene, overlay, simulation)
+                        };
+                        drawn.set(Some(transform));
+                    }
+                    Err(error) => {
+                        let report = layout_report(&config, &spell, &options);
+                        let message = if report.overflow_pt > 0.0 {
+                            format!("{error} Overflows by {:.1}Pt.", report.overflow_pt)
+                        } else {
+                            error.to_string()
+                        };
+                        draw_error(context, &message);
+                        drawn.set(None);
+                    }
+                }
             }
         });
         spell_preview
     }
 }
 
-fn build_ui(db: Rc<SimpleSpellDB>, app: &Application) {
+fn build_ui(
+    db: Rc<RefCell<SimpleSpellDB>>,
+    config: Rc<RefCell<AppConfig>>,
+    app: &Application,
+    debug: bool,
+    profile: Option<&str>,
+) {
     let window = ApplicationWindow::builder()
         .application(app)
         .default_height(600)
         .title("Spell Card generator")
         .build();
-    let (_, main_widget) = AppState::new(db, &window);
+    window.set_titlebar(Some(&app_menu::build_header_bar(&window)));
+    let (_, main_widget) = AppState::new(db, config, &window, debug, profile);
     window.set_child(Some(&main_widget));
 
     window.present();
+    if let Some(report) = crash::take_report() {
+        app_menu::show_crash_report(&window, report);
+    }
 }
 
-fn build_search(on_search: impl Fn(Query) + Clone + 'static) -> impl IsA<Widget> {
+/// Pixels of the monitor showing `widget` per point of paper, for preview at print size.
+/// Monitors not reporting their physical size are taken to be 96 dpi.
+fn pixels_per_pt(widget: &impl IsA<Widget>) -> f64 {
+    let monitor_scale = || {
+        let surface = widget.native()?.surface()?;
+        let monitor = widget.display().monitor_at_surface(&surface)?;
+        let width_mm = monitor.width_mm();
+        (width_mm > 0).then(|| monitor.geometry().width() as f64 / width_mm as f64)
+    };
+    match monitor_scale() {
+        Some(pixels_per_mm) => pixels_per_mm * 25.4 / 72.0,
+        None => FALLBACK_DPI / 72.0,
+    }
+}
+
+/// Local path of file picked in a file dialog. Native dialogs of Windows and macOS, and
+/// portals, may return files without one, like network locations.
+fn local_path(file: &gio::File) -> anyhow::Result<PathBuf> {
+    file.path()
+        .ok_or_else(|| anyhow::anyhow!("Only local files are supported, not {}", file.uri()))
+}
+
+/// `file` with `suffix` appended, if it has no extension. Native save dialogs of Windows and
+/// macOS do not add one from the selected filter.
+fn with_suffix(file: &gio::File, suffix: &str) -> Option<gio::File> {
+    file.path()
+        .filter(|path| path.extension().is_none())
+        .map(|path| gio::File::for_path(path.with_extension(suffix)))
+}
+
+fn file_filters(suffix: &str) -> gio::ListStore {
+    let filter = gtk4::FileFilter::new();
+    filter.add_suffix(suffix);
+    filter.add_mime_type(suffix);
+    let filters = gio::ListStore::new::<gtk4::FileFilter>();
+    filters.append(&filter);
+    filters
+}
+
+/// Search bar with filters. Rank picker buttons are shown then `touch_mode` is active.
+/// `legal_only` is placed under filters, and toggling it repeats search.
+fn build_search(
+    sources: &[String],
+    system: &dyn GameSystem,
+    touch_mode: &gtk4::CheckButton,
+    legal_only: &gtk4::CheckButton,
+    on_search: impl Fn(Query) + Clone + 'static,
+) -> impl IsA<Widget> {
     // Creating widgets and layout
     let search = gtk4::SearchEntry::builder()
         .placeholder_text("spell name")
+        .hexpand(true)
         .build();
-    let is_arcane = gtk4::CheckButton::builder().label("Arcane").build();
-    let is_primal = gtk4::CheckButton::builder().label("Primal").build();
-    let is_divine = gtk4::CheckButton::builder().label("Divine").build();
-    let is_occult = gtk4::CheckButton::builder().label("Occult").build();
+    let [arcane, primal, divine, occult] = system.tradition_labels();
+    let is_arcane = gtk4::CheckButton::builder().label(arcane).build();
+    let is_primal = gtk4::CheckButton::builder().label(primal).build();
+    let is_divine = gtk4::CheckButton::builder().label(divine).build();
+    let is_occult = gtk4::CheckButton::builder().label(occult).build();
     let rank = gtk4::Entry::builder()
         .input_purpose(gtk4::InputPurpose::Digits)
         .max_length(2)
@@ -232,8 +1444,49 @@ fn build_search(on_search: impl Fn(Query) + Clone + 'static) -> impl IsA<Widget>
     subbar.append(&is_divine);
     subbar.append(&is_occult);
 
-    layout.append(&search);
+    // Typing rank needs a keyboard, so touch mode offers buttons for it.
+    let rank_picker = gtk4::FlowBox::builder()
+        .selection_mode(gtk4::SelectionMode::None)
+        .min_children_per_line(6)
+        .max_children_per_line(6)
+        .build();
+    touch_mode
+        .bind_property("active", &rank_picker, "visible")
+        .sync_create()
+        .build();
+    let ranks = std::iter::once(("Any".to_string(), String::new()))
+        .chain((1..=10).map(|rank| (rank.to_string(), rank.to_string())));
+    let mut group: Option<gtk4::ToggleButton> = None;
+    for (label, text) in ranks {
+        let button = gtk4::ToggleButton::builder().label(label).build();
+        match &group {
+            Some(first) => button.set_group(Some(first)),
+            None => {
+                button.set_active(true);
+                group = Some(button.clone());
+            }
+        }
+        let rank = rank.clone();
+        button.connect_toggled(move |button| {
+            if button.is_active() {
+                rank.set_text(&text);
+            }
+        });
+        rank_picker.append(&button);
+    }
+
+    let advanced = AdvancedSearch::new(sources);
+
+    let search_bar = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    search_bar.append(&search);
+    search_bar.append(&build_syntax_hint());
+    layout.append(&search_bar);
     layout.append(&subbar);
+    layout.append(legal_only);
+    layout.append(&rank_picker);
+    layout.append(&advanced.widget);
 
     // Handles user inputs
     let search_captured = search.clone();
@@ -242,6 +1495,7 @@ fn build_search(on_search: impl Fn(Query) + Clone + 'static) -> impl IsA<Widget>
     let is_divine_captured = is_divine.clone();
     let is_occult_captured = is_occult.clone();
     let rank_captured = rank.clone();
+    let advanced_captured = advanced.clone();
 
     let search_signal_handler = move || {
         let rank = rank_captured.text().parse::<u8>().ok();
@@ -249,15 +1503,21 @@ fn build_search(on_search: impl Fn(Query) + Clone + 'static) -> impl IsA<Widget>
         let is_primal = is_primal_captured.is_active();
         let is_occult = is_occult_captured.is_active();
         let is_divine = is_divine_captured.is_active();
-        let query = search_captured.text();
-        on_search(Query {
-            name_query: query.to_string(),
-            spell_rank: rank,
-            is_arcane,
-            is_primal,
-            is_divine,
-            is_occult,
-        });
+        let sources = advanced_captured.selected_sources();
+        let rarity = advanced_captured.selected_rarity();
+        let mut query = Query {
+            sources,
+            ..Query::default()
+        };
+        search_syntax::apply(&search_captured.text(), &mut query);
+        // Prefixes typed in search entry take precedence over widgets.
+        query.spell_rank = query.spell_rank.or(rank);
+        query.is_arcane |= is_arcane;
+        query.is_primal |= is_primal;
+        query.is_divine |= is_divine;
+        query.is_occult |= is_occult;
+        query.rarity = query.rarity.or(rarity);
+        on_search(query);
     };
     search.connect_search_changed(make_const_callback(&search_signal_handler));
     is_occult.connect_toggled(make_const_callback(&search_signal_handler));
@@ -265,6 +1525,13 @@ fn build_search(on_search: impl Fn(Query) + Clone + 'static) -> impl IsA<Widget>
     is_arcane.connect_toggled(make_const_callback(&search_signal_handler));
     is_divine.connect_toggled(make_const_callback(&search_signal_handler));
     rank.connect_changed(make_const_callback(&search_signal_handler));
+    legal_only.connect_toggled(make_const_callback(&search_signal_handler));
+    for check in &advanced.source_checks {
+        check.connect_toggled(make_const_callback(&search_signal_handler));
+    }
+    advanced
+        .rarity
+        .connect_selected_notify(make_const_callback(&search_signal_handler));
     // Disable any inputs but numbers
     rank.delegate()
         .unwrap()
@@ -277,92 +1544,103 @@ fn build_search(on_search: impl Fn(Query) + Clone + 'static) -> impl IsA<Widget>
     layout
 }
 
-/// Convinience function when working with gkt widgets.
-///
-/// Convert argument-less function reference into callback that takes appropriate widget.
-fn make_const_callback<T>(callback: &(impl Fn() + 'static + Clone)) -> impl Fn(&T) + 'static {
-    let cb = callback.clone();
-    move |_| cb()
+/// Button showing search syntax in a popover.
+fn build_syntax_hint() -> gtk4::MenuButton {
+    let grid = gtk4::Grid::builder()
+        .column_spacing(10)
+        .row_spacing(2)
+        .build();
+    for (row, line) in search_syntax::SYNTAX_HINT.lines().enumerate() {
+        let (example, meaning) = line.split_once('\t').unwrap_or((line, ""));
+        let example = gtk4::Label::builder()
+            .label(example)
+            .xalign(0.0)
+            .css_classes(["monospace"])
+            .build();
+        let meaning = gtk4::Label::builder().label(meaning).xalign(0.0).build();
+        grid.attach(&example, 0, row as i32, 1, 1);
+        grid.attach(&meaning, 1, row as i32, 1, 1);
+    }
+    let popover = gtk4::Popover::builder().child(&grid).build();
+    gtk4::MenuButton::builder()
+        .icon_name("dialog-question-symbolic")
+        .tooltip_text("Search syntax")
+        .popover(&popover)
+        .build()
 }
 
-fn draw_scene(context: &cairo::Context, width: i32, height: i32, scene: Scene<'_, CairoFont>) {
-    let width = width as f64;
-    let height = height as f64;
-    let (min_x, max_x, min_y, max_y) = scene
-        .polygons
-        .iter()
-        .flat_map(|poly| poly.points.iter())
-        .fold(
-            (f64::MAX, f64::MIN, f64::MAX, f64::MIN),
-            |(min_x, max_x, min_y, max_y), b| {
-                let x = b.x() as f64;
-                let y = b.y() as f64;
-                (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y))
-            },
-        );
+/// Collapsible panel with filters which are rarely needed.
+#[derive(Clone)]
+struct AdvancedSearch {
+    widget: gtk4::Expander,
+    source_checks: Vec<gtk4::CheckButton>,
+    /// First item is "any rarity", rest match `Rarity::ALL`.
+    rarity: gtk4::DropDown,
+}
 
-    let scene_width = max_x - min_x;
-    let scene_height = max_y - min_y;
-    let padding = 30.0;
-    let x_scale = (width - padding * 2.0) / scene_width;
-    let y_scale = (height - padding * 2.0) / scene_height;
-    let (scale, x_offset, y_offset) = if x_scale < y_scale {
-        (
-            x_scale,
-            padding - min_x,
-            (height - scene_height * x_scale) * 0.5 - min_y,
-        )
-    } else {
-        (
-            y_scale,
-            (width - scene_width * y_scale) * 0.5 - min_x,
-            padding - min_y,
-        )
-    };
+impl AdvancedSearch {
+    fn new(sources: &[String]) -> Self {
+        let source_list = gtk4::Box::builder()
+            .orientation(gtk4::Orientation::Vertical)
+            .build();
+        let source_checks = sources
+            .iter()
+            .map(|source| gtk4::CheckButton::builder().label(source).build())
+            .collect::<Vec<_>>();
+        for check in &source_checks {
+            source_list.append(check);
+        }
+
+        let rarity_names = std::iter::once("Any rarity")
+            .chain(Rarity::ALL.iter().map(|rarity| rarity.as_str()))
+            .collect::<Vec<_>>();
+        let rarity = gtk4::DropDown::from_strings(&rarity_names);
 
-    context.translate(x_offset, y_offset);
-    context.scale(scale, scale);
-    context.set_source_rgb(1.0, 1.0, 1.0);
-    context.rectangle(min_x, min_y, scene_width, scene_height);
-    context.fill().expect("Could not fill");
-    context.set_source_rgb(0.0, 0.0, 0.0);
-
-    context.set_line_width(0.5);
-    for poly in &scene.polygons {
-        context.move_to(poly.points[0].x() as f64, poly.points[0].y() as f64);
-        for point in &poly.points[1..] {
-            context.line_to(point.x() as f64, point.y() as f64);
+        let layout = gtk4::Box::builder()
+            .orientation(gtk4::Orientation::Vertical)
+            .build();
+        layout.append(&rarity);
+        layout.append(&gtk4::Label::new(Some("Sources")));
+        layout.append(
+            &gtk4::ScrolledWindow::builder()
+                .hscrollbar_policy(gtk4::PolicyType::Never)
+                .height_request(150)
+                .child(&source_list)
+                .build(),
+        );
+
+        let widget = gtk4::Expander::builder()
+            .label("Advanced")
+            .child(&layout)
+            .build();
+        Self {
+            widget,
+            source_checks,
+            rarity,
         }
-        context.stroke().expect("Cannot draw line");
     }
 
-    for text in &scene.parts {
-        context.set_font_size(text.font_size as f64 * 0.97);
-        context.set_font_face(&text.font.font_ref().font);
-        let pos = text.rect.lower_left();
-        context.move_to(pos.x() as f64, pos.y() as f64);
-        context.show_text(&text.text).expect("Cannot render text");
+    fn selected_sources(&self) -> Vec<String> {
+        self.source_checks
+            .iter()
+            .filter(|check| check.is_active())
+            .filter_map(|check| check.label())
+            .map(|label| label.to_string())
+            .collect()
     }
-}
 
-struct CairoFont {
-    font: cairo::FontFace,
+    fn selected_rarity(&self) -> Option<Rarity> {
+        let index = self.rarity.selected() as usize;
+        index
+            .checked_sub(1)
+            .and_then(|i| Rarity::ALL.get(i).copied())
+    }
 }
 
-impl FontProvider for CairoFont {
-    type Init = freetype::Library;
-
-    fn build_font(
-        provider_source: &mut Self::Init,
-        font: crate::rich_text::FontKind,
-    ) -> anyhow::Result<Self> {
-        let bytes = font.bytes();
-        let mut data = Vec::with_capacity(bytes.len());
-        data.extend_from_slice(bytes);
-        let data = Rc::new(data);
-        let font = provider_source.new_memory_face(data, 0)?;
-        Ok(CairoFont {
-            font: cairo::FontFace::create_from_ft(&font)?,
-        })
-    }
+/// Convinience function when working with gkt widgets.
+///
+/// Convert argument-less function reference into callback that takes appropriate widget.
+fn make_const_callback<T>(callback: &(impl Fn() + 'static + Clone)) -> impl Fn(&T) + 'static {
+    let cb = callback.clone();
+    move |_| cb()
 }
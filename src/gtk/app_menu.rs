@@ -0,0 +1,205 @@
+use crate::config;
+use crate::db::SimpleSpellDB;
+use crate::logging;
+use gtk4::{gio, prelude::*, ApplicationWindow};
+use std::path::PathBuf;
+
+/// Keyboard accelerators by action name. Shortcuts window lists the same actions.
+/// `<Primary>` is Command on macOS and Control elsewhere.
+const ACCELS: &[(&str, &[&str])] = &[
+    ("win.command-palette", &["<Primary>k"]),
+    ("win.export", &["<Primary>e"]),
+    ("win.print", &["<Primary>p"]),
+    ("win.preview-pages", &["<Primary><Shift>p"]),
+    ("win.reload-bundles", &["<Primary>r"]),
+    ("win.show-help-overlay", &["<Primary>question"]),
+    ("app.quit", &["<Primary>q"]),
+];
+
+/// Header bar with application menu. Menu items activate `win.*` actions, which are
+/// registered by the caller, and `app.quit`, registered here.
+pub fn build_header_bar(window: &ApplicationWindow) -> gtk4::HeaderBar {
+    let menu = gio::Menu::new();
+    let deck_section = gio::Menu::new();
+    deck_section.append(Some("Command palette…"), Some("win.command-palette"));
+    deck_section.append(Some("Save deck…"), Some("win.save-deck"));
+    deck_section.append(Some("Open deck…"), Some("win.open-deck"));
+    deck_section.append(Some("Deck properties"), Some("win.deck-properties"));
+    deck_section.append(Some("Level up…"), Some("win.level-up"));
+    menu.append_section(None, &deck_section);
+    let export_section = gio::Menu::new();
+    export_section.append(Some("Preview pages"), Some("win.preview-pages"));
+    export_section.append(Some("Print…"), Some("win.print"));
+    let exports = gio::Menu::new();
+    exports.append(Some("Export pdf…"), Some("win.export"));
+    exports.append(
+        Some("Export calibration page…"),
+        Some("win.export-calibration"),
+    );
+    exports.append(Some("Export Roll20 handouts…"), Some("win.export-roll20"));
+    exports.append(Some("Export Foundry journal…"), Some("win.export-foundry"));
+    exports.append(Some("Export Anki notes…"), Some("win.export-anki"));
+    exports.append(Some("Export CSV…"), Some("win.export-csv"));
+    export_section.append_submenu(Some("Export"), &exports);
+    let sharing = gio::Menu::new();
+    sharing.append(Some("Copy share code"), Some("win.copy-share-code"));
+    sharing.append(Some("Paste share code"), Some("win.paste-share-code"));
+    sharing.append(Some("Import CSV…"), Some("win.import-csv"));
+    export_section.append_submenu(Some("Share"), &sharing);
+    menu.append_section(None, &export_section);
+    let help_section = gio::Menu::new();
+    help_section.append(Some("Reload bundles"), Some("win.reload-bundles"));
+    help_section.append(Some("Spell data"), Some("win.data-info"));
+    help_section.append(Some("Sync data…"), Some("win.sync-data"));
+    help_section.append(Some("Open log"), Some("win.open-log"));
+    help_section.append(Some("Keyboard shortcuts"), Some("win.show-help-overlay"));
+    help_section.append(Some("About Spell Card generator"), Some("win.about"));
+    menu.append_section(None, &help_section);
+    let quit_section = gio::Menu::new();
+    quit_section.append(Some("Quit"), Some("app.quit"));
+    menu.append_section(None, &quit_section);
+
+    let menu_button = gtk4::MenuButton::builder()
+        .icon_name("open-menu-symbolic")
+        .menu_model(&menu)
+        .tooltip_text("Main menu")
+        .build();
+    let header_bar = gtk4::HeaderBar::new();
+    header_bar.pack_end(&menu_button);
+
+    let builder = gtk4::Builder::from_string(include_str!("../../static/shortcuts.ui"));
+    let shortcuts = builder.object::<gtk4::ShortcutsWindow>("shortcuts");
+    window.set_help_overlay(shortcuts.as_ref());
+
+    if let Some(app) = window.application() {
+        let quit = gio::SimpleAction::new("quit", None);
+        let app_moved = app.clone();
+        quit.connect_activate(move |_, _| app_moved.quit());
+        app.add_action(&quit);
+        for (action, accels) in ACCELS {
+            app.set_accels_for_action(action, accels);
+        }
+    }
+    header_bar
+}
+
+/// Add window action `name`, activated by menu items and shortcuts.
+pub fn add_window_action(window: &ApplicationWindow, name: &str, activate: impl Fn() + 'static) {
+    let action = gio::SimpleAction::new(name, None);
+    action.connect_activate(move |_, _| activate());
+    window.add_action(&action);
+}
+
+/// Enable or disable window action `name`, with menu items and shortcuts activating it.
+pub fn set_action_enabled(window: &ApplicationWindow, name: &str, enabled: bool) {
+    if let Some(action) = window
+        .lookup_action(name)
+        .and_downcast::<gio::SimpleAction>()
+    {
+        action.set_enabled(enabled);
+    }
+}
+
+/// About dialog with program and data versions, and licenses of program and game content.
+pub fn show_about(window: &ApplicationWindow, db: &SimpleSpellDB) {
+    let info = db.data_info();
+    let system = db.system();
+    let data_version = match &info.latest_release {
+        Some(release) => format!("{} data, released up to {release}", system.name()),
+        None => format!("{} data", system.name()),
+    };
+    let license = format!(
+        "This program is free software, distributed under the terms of \
+        GNU General Public License version 3.\n\n{}",
+        system.license_notice()
+    );
+    gtk4::AboutDialog::builder()
+        .transient_for(window)
+        .modal(true)
+        .program_name("Spell Card generator")
+        .version(env!("CARGO_PKG_VERSION"))
+        .comments(format!("Printable spell cards.\n{data_version}"))
+        .license_type(gtk4::License::Custom)
+        .license(license)
+        .wrap_license(true)
+        .build()
+        .present();
+}
+
+/// Open most recent log file in default application.
+pub fn open_log(window: &ApplicationWindow) {
+    let Some(path) = logging::latest_log_file() else {
+        gtk4::AlertDialog::builder()
+            .message("No log file")
+            .detail("Nothing was logged yet.")
+            .build()
+            .show(Some(window));
+        return;
+    };
+    open_file(window, path, "Unable to open log");
+}
+
+/// Offer to open crash report left by previous run.
+pub fn show_crash_report(window: &ApplicationWindow, report: PathBuf) {
+    let window_moved = window.clone();
+    gtk4::AlertDialog::builder()
+        .message("Spell Card generator crashed last time")
+        .detail(format!(
+            "Crash report was saved to {}. Attach it when reporting the problem.",
+            report.display()
+        ))
+        .buttons(["Close", "Open report"])
+        .cancel_button(0)
+        .default_button(1)
+        .build()
+        .choose(Some(window), gio::Cancellable::NONE, move |result| {
+            if matches!(result, Ok(1)) {
+                open_file(&window_moved, report, "Unable to open crash report");
+            }
+        });
+}
+
+/// Open `path` in default application, showing `error_message` if that fails.
+fn open_file(window: &ApplicationWindow, path: PathBuf, error_message: &'static str) {
+    let window_moved = window.clone();
+    gtk4::FileLauncher::new(Some(&gio::File::for_path(&path))).launch(
+        Some(window),
+        gio::Cancellable::NONE,
+        move |result| {
+            if let Err(error) = result {
+                gtk4::AlertDialog::builder()
+                    .message(error_message)
+                    .detail(format!("{}\n{error}", path.display()))
+                    .build()
+                    .show(Some(&window_moved));
+            }
+        },
+    );
+}
+
+/// Dialog listing loaded spell data: built-in spells and homebrew bundles.
+pub fn show_data_info(window: &ApplicationWindow, db: &SimpleSpellDB) {
+    let info = db.data_info();
+    let system = db.system();
+    let mut detail = format!("Game system: {}\n", system.name());
+    detail.push_str(&format!("Built-in spells: {}", info.builtin_spells));
+    if let Some(release) = &info.latest_release {
+        detail.push_str(&format!(", released up to {release}"));
+    }
+    detail.push('\n');
+    match config::bundles_dir(&*system) {
+        Some(dir) => detail.push_str(&format!("Bundles directory: {}\n", dir.display())),
+        None => detail.push_str("Bundles directory is unknown\n"),
+    }
+    if info.bundles.is_empty() {
+        detail.push_str("No bundles loaded");
+    }
+    for (label, count) in &info.bundles {
+        detail.push_str(&format!("Bundle `{label}`: {count} spells\n"));
+    }
+    gtk4::AlertDialog::builder()
+        .message("Spell data")
+        .detail(detail.trim_end())
+        .build()
+        .show(Some(window));
+}
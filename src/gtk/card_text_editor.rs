@@ -0,0 +1,120 @@
+use crate::spell::Spell;
+use gtk4::{prelude::*, Widget};
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Editor for description shown on a card.
+///
+/// Edits are stored as overrides by spell id, and never change spell database.
+#[derive(Clone)]
+pub struct CardTextEditor {
+    buffer: gtk4::TextBuffer,
+    /// Spell currently being edited.
+    spell: Rc<RefCell<Option<Rc<Spell>>>>,
+    overrides: Rc<RefCell<HashMap<usize, String>>>,
+    /// Set while buffer is filled programmatically, so it is not taken for user edit.
+    loading: Rc<Cell<bool>>,
+    on_changed: Rc<RefCell<Box<dyn Fn()>>>,
+}
+
+impl CardTextEditor {
+    pub fn new() -> (Self, impl IsA<Widget>) {
+        let buffer = gtk4::TextBuffer::new(None);
+        let text_view = gtk4::TextView::builder()
+            .buffer(&buffer)
+            .wrap_mode(gtk4::WrapMode::Word)
+            .build();
+        let reset_button = gtk4::Button::builder().label("Reset to original").build();
+
+        let result = Self {
+            buffer,
+            spell: Rc::new(RefCell::new(None)),
+            overrides: Rc::new(RefCell::new(HashMap::new())),
+            loading: Rc::new(Cell::new(false)),
+            on_changed: Rc::new(RefCell::new(Box::new(|| {}))),
+        };
+
+        let editor = result.clone();
+        result.buffer.connect_changed(move |_| editor.store_edit());
+        let editor = result.clone();
+        reset_button.connect_clicked(move |_| editor.reset());
+
+        let layout = gtk4::Box::builder()
+            .orientation(gtk4::Orientation::Vertical)
+            .build();
+        layout.append(
+            &gtk4::ScrolledWindow::builder()
+                .hscrollbar_policy(gtk4::PolicyType::Never)
+                .height_request(150)
+                .child(&text_view)
+                .build(),
+        );
+        layout.append(&reset_button);
+        let widget = gtk4::Expander::builder()
+            .label("Edit card text")
+            .child(&layout)
+            .build();
+        (result, widget)
+    }
+
+    /// Called after every edit of active spell text.
+    pub fn connect_changed(&self, changed: impl Fn() + 'static) {
+        let _ = self.on_changed.replace(Box::new(changed));
+    }
+
+    pub fn show_spell(&self, spell: Rc<Spell>) {
+        let text = self
+            .overrides
+            .borrow()
+            .get(&spell.id)
+            .cloned()
+            .unwrap_or_else(|| spell.description.clone());
+        self.spell.replace(Some(spell));
+        self.set_text(&text);
+    }
+
+    /// Spell with user edits applied.
+    pub fn apply<'a>(&self, spell: &'a Spell) -> Cow<'a, Spell> {
+        match self.overrides.borrow().get(&spell.id) {
+            Some(description) => Cow::Owned(Spell {
+                description: description.clone(),
+                ..spell.clone()
+            }),
+            None => Cow::Borrowed(spell),
+        }
+    }
+
+    fn set_text(&self, text: &str) {
+        self.loading.set(true);
+        self.buffer.set_text(text);
+        self.loading.set(false);
+    }
+
+    fn store_edit(&self) {
+        if self.loading.get() {
+            return;
+        }
+        let Some(spell) = self.spell.borrow().clone() else {
+            return;
+        };
+        let (start, end) = self.buffer.bounds();
+        let text = self.buffer.text(&start, &end, false).to_string();
+        if text == spell.description {
+            self.overrides.borrow_mut().remove(&spell.id);
+        } else {
+            self.overrides.borrow_mut().insert(spell.id, text);
+        }
+        self.on_changed.borrow()();
+    }
+
+    fn reset(&self) {
+        let Some(spell) = self.spell.borrow().clone() else {
+            return;
+        };
+        self.overrides.borrow_mut().remove(&spell.id);
+        self.set_text(&spell.description);
+        self.on_changed.borrow()();
+    }
+}
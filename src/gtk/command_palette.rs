@@ -0,0 +1,158 @@
+use crate::fuzzy::fuzzy_filter;
+use gtk4::{gdk, glib, prelude::*};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Most entries listed at once, typing more narrows the rest down.
+const MAX_ENTRIES: usize = 30;
+
+enum Entry {
+    /// Window action, without `win.` prefix.
+    Command(&'static str),
+    /// Index of spell name.
+    Spell(usize),
+}
+
+/// Show palette of `commands`, pairs of title and window action name without `win.`
+/// prefix, and of spells to add by name. Typing filters entries by fuzzy matching, `Enter`
+/// runs selected one and `Escape` closes the palette. Disabled actions are left out, and
+/// spells are listed only once something is typed.
+///
+/// `add_spell` is called with index of chosen name in `spells`.
+pub fn show_command_palette(
+    parent: &gtk4::ApplicationWindow,
+    commands: &[(&str, &'static str)],
+    spells: Vec<String>,
+    add_spell: impl Fn(usize) + 'static,
+) {
+    let mut titles = vec![];
+    let mut entries = vec![];
+    for &(title, action) in commands {
+        let enabled = parent
+            .lookup_action(action)
+            .is_some_and(|action| action.is_enabled());
+        if enabled {
+            titles.push(title.to_string());
+            entries.push(Entry::Command(action));
+        }
+    }
+    let commands_len = entries.len();
+    for (i, name) in spells.iter().enumerate() {
+        titles.push(format!("Add {name}"));
+        entries.push(Entry::Spell(i));
+    }
+
+    let entry = gtk4::SearchEntry::builder()
+        .placeholder_text("Type a command or spell name")
+        .build();
+    let list = gtk4::ListBox::builder()
+        .selection_mode(gtk4::SelectionMode::Browse)
+        .build();
+    let scrolled = gtk4::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk4::PolicyType::Never)
+        .min_content_height(300)
+        .vexpand(true)
+        .child(&list)
+        .build();
+    let layout = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .spacing(10)
+        .margin_top(10)
+        .margin_bottom(10)
+        .margin_start(10)
+        .margin_end(10)
+        .build();
+    layout.append(&entry);
+    layout.append(&scrolled);
+    let window = gtk4::Window::builder()
+        .title("Command palette")
+        .transient_for(parent)
+        .modal(true)
+        .default_width(400)
+        .child(&layout)
+        .build();
+
+    // Entries shown in the list, by row.
+    let shown = Rc::new(RefCell::new(vec![]));
+    let filter = {
+        let list = list.clone();
+        let shown = shown.clone();
+        move |query: &str| {
+            list.remove_all();
+            let matches = fuzzy_filter(query, titles.iter().map(String::as_str))
+                .into_iter()
+                .filter(|&i| !query.trim().is_empty() || i < commands_len)
+                .take(MAX_ENTRIES)
+                .collect::<Vec<_>>();
+            for &i in &matches {
+                list.append(&gtk4::Label::builder().label(&titles[i]).xalign(0.0).build());
+            }
+            list.select_row(list.row_at_index(0).as_ref());
+            shown.replace(matches);
+        }
+    };
+    filter("");
+    entry.connect_search_changed(move |entry| filter(&entry.text()));
+
+    let run = {
+        let window = window.clone();
+        let parent = parent.clone();
+        move |row: &gtk4::ListBoxRow| {
+            let Some(&i) = shown.borrow().get(row.index() as usize) else {
+                return;
+            };
+            window.close();
+            match entries[i] {
+                Entry::Command(action) => {
+                    let _ = parent.activate_action(&format!("win.{action}"), None);
+                }
+                Entry::Spell(spell) => add_spell(spell),
+            }
+        }
+    };
+    let run = Rc::new(run);
+    let run_moved = run.clone();
+    list.connect_row_activated(move |_, row| run_moved(row));
+    let list_moved = list.clone();
+    entry.connect_activate(move |_| {
+        if let Some(row) = list_moved.selected_row() {
+            run(&row);
+        }
+    });
+    let window_moved = window.clone();
+    entry.connect_stop_search(move |_| window_moved.close());
+
+    // Arrow keys move selection while focus stays in the search entry.
+    let keys = gtk4::EventControllerKey::new();
+    keys.set_propagation_phase(gtk4::PropagationPhase::Capture);
+    keys.connect_key_pressed(move |_, key, _, _| {
+        let step = match key {
+            gdk::Key::Down => 1,
+            gdk::Key::Up => -1,
+            _ => return glib::Propagation::Proceed,
+        };
+        let current = list.selected_row().map_or(-1, |row| row.index());
+        if let Some(row) = list.row_at_index(current + step) {
+            list.select_row(Some(&row));
+            scroll_to(&scrolled, &list, &row);
+        }
+        glib::Propagation::Stop
+    });
+    entry.add_controller(keys);
+    window.present();
+}
+
+/// Scroll `scrolled` just enough for `row` of `list` to be visible.
+fn scroll_to(scrolled: &gtk4::ScrolledWindow, list: &gtk4::ListBox, row: &gtk4::ListBoxRow) {
+    let Some(bounds) = row.compute_bounds(list) else {
+        return;
+    };
+    let adjustment = scrolled.vadjustment();
+    let top = bounds.y() as f64;
+    let bottom = (bounds.y() + bounds.height()) as f64;
+    if top < adjustment.value() {
+        adjustment.set_value(top);
+    } else if bottom > adjustment.value() + adjustment.page_size() {
+        adjustment.set_value(bottom - adjustment.page_size());
+    }
+}
@@ -0,0 +1,180 @@
+use crate::app_config::{self, AppConfig};
+use crate::config;
+use crate::db::SimpleSpellDB;
+use crate::network;
+use crate::sync::{self, PublisherKey, SyncProgress};
+use gtk4::{gio, glib, prelude::*};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How often progress bar is updated while downloading.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Show dialog downloading bundles listed in sync manifest into bundles directory.
+/// Downloads run on a background thread, and bundles are installed only after all of them
+/// are downloaded and verified. `win.reload-bundles` is activated after install. Manifest
+/// url and publisher key are saved to `config`.
+pub fn show_data_sync(
+    parent: &gtk4::ApplicationWindow,
+    db: Rc<RefCell<SimpleSpellDB>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let url = gtk4::Entry::builder()
+        .text(config.borrow().sync_url.as_deref().unwrap_or_default())
+        .placeholder_text("https://example.org/manifest.json")
+        .hexpand(true)
+        .build();
+    let key = gtk4::Entry::builder()
+        .text(config.borrow().sync_key.as_deref().unwrap_or_default())
+        .placeholder_text("64 hex digits")
+        .tooltip_text("Manifest must be signed with private key of this public key")
+        .hexpand(true)
+        .build();
+    let progress_bar = gtk4::ProgressBar::builder().show_text(true).build();
+    let status = gtk4::Label::builder().xalign(0.0).wrap(true).build();
+    let close = gtk4::Button::builder().label("Close").build();
+    let start = gtk4::Button::builder()
+        .label("Download")
+        .hexpand(true)
+        .build();
+    let buttons = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    buttons.append(&close);
+    buttons.append(&start);
+    let layout = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .spacing(10)
+        .margin_top(10)
+        .margin_bottom(10)
+        .margin_start(10)
+        .margin_end(10)
+        .build();
+    layout.append(
+        &gtk4::Label::builder()
+            .label("Manifest url")
+            .xalign(0.0)
+            .build(),
+    );
+    layout.append(&url);
+    layout.append(
+        &gtk4::Label::builder()
+            .label("Publisher key")
+            .xalign(0.0)
+            .build(),
+    );
+    layout.append(&key);
+    layout.append(&progress_bar);
+    layout.append(&status);
+    layout.append(&buttons);
+
+    let window = gtk4::Window::builder()
+        .title("Sync data")
+        .transient_for(parent)
+        .modal(true)
+        .default_width(450)
+        .child(&layout)
+        .build();
+    // Closing the dialog cancels running download.
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let window_moved = window.clone();
+    close.connect_clicked(move |_| window_moved.close());
+    let cancelled_moved = cancelled.clone();
+    window.connect_close_request(move |_| {
+        cancelled_moved.store(true, Ordering::SeqCst);
+        glib::Propagation::Proceed
+    });
+
+    let parent = parent.clone();
+    start.connect_clicked(move |start| {
+        let manifest_url = url.text().trim().to_string();
+        if manifest_url.is_empty() {
+            status.set_text("Enter manifest url first.");
+            return;
+        }
+        let publisher = match PublisherKey::parse(&key.text()) {
+            Ok(publisher) => publisher,
+            Err(error) => {
+                status.set_text(&format!("{error}."));
+                return;
+            }
+        };
+        let publisher_key = key.text().trim().to_string();
+        for (key, value, name) in [
+            ("url", &manifest_url, "sync url"),
+            ("key", &publisher_key, "publisher key"),
+        ] {
+            if let Err(error) = app_config::save_setting("sync", key, value.as_str()) {
+                tracing::warn!("Unable to save {name}. {error:#}");
+            }
+        }
+        let network = {
+            let mut config = config.borrow_mut();
+            config.sync_url = Some(manifest_url.clone());
+            config.sync_key = Some(publisher_key);
+            network::from_settings(&config)
+        };
+        start.set_sensitive(false);
+        status.set_text("Downloading manifest…");
+        let progress = Arc::new(Mutex::new(SyncProgress::default()));
+        let finished = Rc::new(Cell::new(false));
+
+        let progress_moved = progress.clone();
+        let progress_bar = progress_bar.clone();
+        let finished_moved = finished.clone();
+        glib::timeout_add_local(PROGRESS_INTERVAL, move || {
+            if finished_moved.get() {
+                return glib::ControlFlow::Break;
+            }
+            let progress = progress_moved.lock().map(|p| p.clone()).unwrap_or_default();
+            match progress.fraction() {
+                Some(fraction) => {
+                    progress_bar.set_fraction(fraction);
+                    progress_bar.set_text(Some(&format!(
+                        "Bundle {} of {}",
+                        progress.file, progress.files
+                    )));
+                }
+                None => progress_bar.pulse(),
+            }
+            glib::ControlFlow::Continue
+        });
+
+        let cancelled = cancelled.clone();
+        let db = db.clone();
+        let status = status.clone();
+        let start = start.clone();
+        let parent = parent.clone();
+        glib::spawn_future_local(async move {
+            let downloaded = gio::spawn_blocking(move || {
+                sync::download_bundles(&*network, &manifest_url, &publisher, &progress, &cancelled)
+            })
+            .await;
+            finished.set(true);
+            start.set_sensitive(true);
+            let installed = match downloaded {
+                Ok(Ok(downloads)) => {
+                    let db = db.borrow();
+                    match config::bundles_dir(&*db.system()) {
+                        Some(dir) => sync::install(&db, &downloads, &dir),
+                        None => Err(anyhow::anyhow!("Bundles directory is unknown")),
+                    }
+                }
+                Ok(Err(error)) => Err(error),
+                Err(_) => Err(anyhow::anyhow!("Download thread failed")),
+            };
+            match installed {
+                Ok(names) if names.is_empty() => status.set_text("Manifest lists no bundles."),
+                Ok(names) => {
+                    status.set_text(&format!("Installed: {}", names.join(", ")));
+                    let _ = parent.activate_action("win.reload-bundles", None);
+                }
+                Err(error) => status.set_text(&format!("Sync failed. {error:#}")),
+            }
+        });
+    });
+    window.present();
+}
@@ -0,0 +1,113 @@
+use crate::deck::DeckMetadata;
+use crate::spell::Tradition;
+use crate::system::GameSystem;
+use gtk4::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Show dialog editing name, character, spell list and notes of the deck. `metadata` is
+/// updated and `on_save` called then user presses `Save`.
+pub fn show_deck_properties(
+    parent: &gtk4::ApplicationWindow,
+    system: &dyn GameSystem,
+    metadata: Rc<RefCell<DeckMetadata>>,
+    on_save: impl Fn() + 'static,
+) {
+    let current = metadata.borrow().clone();
+    let name = gtk4::Entry::builder()
+        .text(current.name.as_str())
+        .placeholder_text("deck name")
+        .hexpand(true)
+        .build();
+    let character = gtk4::Entry::builder()
+        .text(current.character.as_str())
+        .placeholder_text("character name")
+        .build();
+    let notes = gtk4::TextView::builder()
+        .wrap_mode(gtk4::WrapMode::Word)
+        .build();
+    notes.buffer().set_text(&current.notes);
+    let mut spell_list_labels = vec!["Any"];
+    spell_list_labels.extend(system.tradition_labels());
+    let spell_list = gtk4::DropDown::from_strings(&spell_list_labels);
+    spell_list.set_tooltip_text(Some("Spells off the list are flagged in selection"));
+    let selected = current
+        .spell_list
+        .and_then(|list| Tradition::ALL.iter().position(|t| *t == list))
+        .map_or(0, |index| index + 1);
+    spell_list.set_selected(selected as u32);
+    let created = gtk4::Label::builder()
+        .label(current.created.as_str())
+        .xalign(0.0)
+        .build();
+
+    let grid = gtk4::Grid::builder()
+        .column_spacing(10)
+        .row_spacing(5)
+        .margin_top(10)
+        .margin_bottom(10)
+        .margin_start(10)
+        .margin_end(10)
+        .build();
+    let rows: [(&str, &gtk4::Widget); 4] = [
+        ("Name", name.upcast_ref()),
+        ("Character", character.upcast_ref()),
+        ("Spell list", spell_list.upcast_ref()),
+        ("Created", created.upcast_ref()),
+    ];
+    for (row, (title, widget)) in rows.into_iter().enumerate() {
+        let label = gtk4::Label::builder().label(title).xalign(0.0).build();
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(widget, 1, row as i32, 1, 1);
+    }
+    let notes_label = gtk4::Label::builder().label("Notes").xalign(0.0).build();
+    grid.attach(&notes_label, 0, 4, 2, 1);
+    grid.attach(
+        &gtk4::ScrolledWindow::builder()
+            .height_request(120)
+            .vexpand(true)
+            .child(&notes)
+            .build(),
+        0,
+        5,
+        2,
+        1,
+    );
+
+    let cancel = gtk4::Button::builder().label("Cancel").build();
+    let save = gtk4::Button::builder().label("Save").hexpand(true).build();
+    let buttons = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    buttons.append(&cancel);
+    buttons.append(&save);
+    grid.attach(&buttons, 0, 6, 2, 1);
+
+    let window = gtk4::Window::builder()
+        .title("Deck properties")
+        .transient_for(parent)
+        .modal(true)
+        .default_width(400)
+        .child(&grid)
+        .build();
+    let window_moved = window.clone();
+    cancel.connect_clicked(move |_| window_moved.close());
+    let window_moved = window.clone();
+    save.connect_clicked(move |_| {
+        let buffer = notes.buffer();
+        {
+            let mut metadata = metadata.borrow_mut();
+            metadata.name = name.text().trim().to_string();
+            metadata.character = character.text().trim().to_string();
+            metadata.notes = buffer
+                .text(&buffer.start_iter(), &buffer.end_iter(), false)
+                .to_string();
+            metadata.spell_list = (spell_list.selected() as usize)
+                .checked_sub(1)
+                .and_then(|index| Tradition::ALL.get(index).copied());
+        }
+        on_save();
+        window_moved.close();
+    });
+    window.present();
+}
@@ -0,0 +1,96 @@
+use crate::deck::DeckEntry;
+use crate::spell::Spell;
+use gtk4::prelude::*;
+use std::rc::Rc;
+
+/// Show dialog choosing which spell each of `entries` refers to, for imported names shared
+/// by spells of several sources. Every entry comes with its candidates. On `Add` chosen
+/// spells are passed to `on_add`, entries left at `Skip` are left out.
+pub fn show_disambiguation(
+    parent: &gtk4::ApplicationWindow,
+    entries: Vec<(DeckEntry, Vec<Rc<Spell>>)>,
+    on_add: impl Fn(Vec<(Rc<Spell>, DeckEntry)>) + 'static,
+) {
+    let grid = gtk4::Grid::builder()
+        .row_spacing(5)
+        .column_spacing(10)
+        .build();
+    let mut choices = vec![];
+    for (row, (entry, candidates)) in entries.into_iter().enumerate() {
+        let mut labels = vec!["Skip".to_string()];
+        labels.extend(
+            candidates
+                .iter()
+                .map(|spell| format!("{}, rank {}", spell.source, spell.level)),
+        );
+        let labels = labels.iter().map(String::as_str).collect::<Vec<_>>();
+        let choice = gtk4::DropDown::from_strings(&labels);
+        choice.set_selected(1);
+        choice.set_hexpand(true);
+        let name = match entry.count {
+            1 => entry.name.clone(),
+            count => format!("{} ×{count}", entry.name),
+        };
+        let label = gtk4::Label::builder().label(name).xalign(0.0).build();
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(&choice, 1, row as i32, 1, 1);
+        choices.push((choice, entry, candidates));
+    }
+    let scrolled = gtk4::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk4::PolicyType::Never)
+        .propagate_natural_height(true)
+        .max_content_height(400)
+        .child(&grid)
+        .build();
+
+    let cancel = gtk4::Button::builder().label("Cancel").build();
+    let add = gtk4::Button::builder().label("Add").hexpand(true).build();
+    let buttons = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    buttons.append(&cancel);
+    buttons.append(&add);
+    let layout = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .spacing(10)
+        .margin_top(10)
+        .margin_bottom(10)
+        .margin_start(10)
+        .margin_end(10)
+        .build();
+    layout.append(
+        &gtk4::Label::builder()
+            .label("Several books have spells of these names. Choose which ones to add.")
+            .wrap(true)
+            .xalign(0.0)
+            .build(),
+    );
+    layout.append(&scrolled);
+    layout.append(&buttons);
+
+    let window = gtk4::Window::builder()
+        .title("Choose spells")
+        .transient_for(parent)
+        .modal(true)
+        .default_width(450)
+        .child(&layout)
+        .build();
+    let window_moved = window.clone();
+    cancel.connect_clicked(move |_| window_moved.close());
+    let window_moved = window.clone();
+    add.connect_clicked(move |_| {
+        let chosen = choices
+            .iter()
+            .filter_map(|(choice, entry, candidates)| {
+                let index = (choice.selected() as usize).checked_sub(1)?;
+                let spell = candidates.get(index)?.clone();
+                let mut entry = entry.clone();
+                entry.source = Some(spell.source.clone());
+                Some((spell, entry))
+            })
+            .collect();
+        on_add(chosen);
+        window_moved.close();
+    });
+    window.present();
+}
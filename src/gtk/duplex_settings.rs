@@ -0,0 +1,69 @@
+use crate::render::Duplex;
+use gtk4::{prelude::*, Widget};
+
+/// Controls for printing card backs on the reverse side of pages.
+#[derive(Clone)]
+pub struct DuplexSettings {
+    card_backs: gtk4::CheckButton,
+    offset_x: gtk4::SpinButton,
+    offset_y: gtk4::SpinButton,
+}
+
+impl DuplexSettings {
+    pub fn new() -> (Self, impl IsA<Widget>) {
+        let card_backs = gtk4::CheckButton::builder()
+            .label("Print card backs")
+            .build();
+        let offset_x = offset_button();
+        let offset_y = offset_button();
+
+        let offsets = gtk4::Grid::builder().column_spacing(4).build();
+        offsets.attach(&gtk4::Label::new(Some("Back X offset, mm")), 0, 0, 1, 1);
+        offsets.attach(&offset_x, 1, 0, 1, 1);
+        offsets.attach(&gtk4::Label::new(Some("Back Y offset, mm")), 0, 1, 1, 1);
+        offsets.attach(&offset_y, 1, 1, 1, 1);
+        card_backs
+            .bind_property("active", &offsets, "sensitive")
+            .sync_create()
+            .build();
+
+        let layout = gtk4::Box::builder()
+            .orientation(gtk4::Orientation::Vertical)
+            .build();
+        layout.append(&card_backs);
+        layout.append(&offsets);
+        let widget = gtk4::Expander::builder()
+            .label("Duplex printing")
+            .child(&layout)
+            .build();
+        (
+            Self {
+                card_backs,
+                offset_x,
+                offset_y,
+            },
+            widget,
+        )
+    }
+
+    pub fn set_duplex(&self, duplex: Duplex) {
+        self.card_backs.set_active(duplex.card_backs);
+        self.offset_x.set_value(duplex.back_offset_x as f64);
+        self.offset_y.set_value(duplex.back_offset_y as f64);
+    }
+
+    pub fn duplex(&self) -> Duplex {
+        Duplex {
+            card_backs: self.card_backs.is_active(),
+            back_offset_x: self.offset_x.value() as f32,
+            back_offset_y: self.offset_y.value() as f32,
+        }
+    }
+}
+
+fn offset_button() -> gtk4::SpinButton {
+    let button = gtk4::SpinButton::with_range(-10.0, 10.0, 0.1);
+    button.set_digits(1);
+    button.set_value(0.0);
+    button
+}
@@ -0,0 +1,82 @@
+use crate::deck::DeckMetadata;
+use crate::heighten::changed_cards;
+use crate::spell::Spell;
+use gtk4::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Highest rank offered in casting rank selector.
+const MAX_RANK: u8 = 10;
+
+/// Show dialog choosing rank deck `spells` are cast at. On `Apply` casting rank of `metadata`
+/// is set, `on_apply` is called, and cards changed by the new rank are reported.
+pub fn show_level_up(
+    parent: &gtk4::ApplicationWindow,
+    metadata: Rc<RefCell<DeckMetadata>>,
+    spells: Vec<Spell>,
+    on_apply: impl Fn() + 'static,
+) {
+    let mut labels = vec!["Spell rank".to_string()];
+    labels.extend((1..=MAX_RANK).map(|rank| rank.to_string()));
+    let labels = labels.iter().map(String::as_str).collect::<Vec<_>>();
+    let rank = gtk4::DropDown::from_strings(&labels);
+    rank.set_tooltip_text(Some(
+        "Spells with heightened entries are printed heightened to this rank",
+    ));
+    let current = metadata.borrow().cast_rank;
+    rank.set_selected(current.map_or(0, u32::from));
+
+    let cancel = gtk4::Button::builder().label("Cancel").build();
+    let apply = gtk4::Button::builder().label("Apply").hexpand(true).build();
+    let buttons = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    buttons.append(&cancel);
+    buttons.append(&apply);
+    let layout = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .spacing(10)
+        .margin_top(10)
+        .margin_bottom(10)
+        .margin_start(10)
+        .margin_end(10)
+        .build();
+    layout.append(
+        &gtk4::Label::builder()
+            .label("Casting rank")
+            .xalign(0.0)
+            .build(),
+    );
+    layout.append(&rank);
+    layout.append(&buttons);
+
+    let window = gtk4::Window::builder()
+        .title("Level up")
+        .transient_for(parent)
+        .modal(true)
+        .default_width(300)
+        .child(&layout)
+        .build();
+    let window_moved = window.clone();
+    cancel.connect_clicked(move |_| window_moved.close());
+    let window_moved = window.clone();
+    let parent = parent.clone();
+    apply.connect_clicked(move |_| {
+        let new_rank = u8::try_from(rank.selected()).ok().filter(|rank| *rank > 0);
+        let changed = changed_cards(&spells, current, new_rank);
+        metadata.borrow_mut().cast_rank = new_rank;
+        on_apply();
+        window_moved.close();
+        let detail = if changed.is_empty() {
+            "No cards changed.".to_string()
+        } else {
+            format!("Changed cards:\n{}", changed.join("\n"))
+        };
+        gtk4::AlertDialog::builder()
+            .message("Level up")
+            .detail(detail)
+            .build()
+            .show(Some(&parent));
+    });
+    window.present();
+}
@@ -0,0 +1,95 @@
+use crate::lint::Finding;
+use gtk4::{glib, prelude::*};
+
+/// Show `findings` of selected spells before export, each with explanation and suggested
+/// options. `Export anyway` closes the dialog and calls `on_continue`, `Cancel` only closes
+/// it, so options can be changed first.
+pub fn show_lint_checklist(
+    parent: &gtk4::ApplicationWindow,
+    findings: Vec<Finding>,
+    on_continue: impl Fn() + 'static,
+) {
+    let list = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .spacing(8)
+        .build();
+    for finding in &findings {
+        let heading = gtk4::Label::builder()
+            .use_markup(true)
+            .label(format!(
+                "<b>{}</b>: {}",
+                glib::markup_escape_text(&finding.spell),
+                finding.rule.title()
+            ))
+            .xalign(0.0)
+            .build();
+        let explanation = gtk4::Label::builder()
+            .label(finding.explanation.as_str())
+            .wrap(true)
+            .xalign(0.0)
+            .build();
+        let suggestion = gtk4::Label::builder()
+            .label(finding.rule.suggestion())
+            .wrap(true)
+            .xalign(0.0)
+            .css_classes(["dim-label"])
+            .build();
+        let entry = gtk4::Box::builder()
+            .orientation(gtk4::Orientation::Vertical)
+            .build();
+        entry.append(&heading);
+        entry.append(&explanation);
+        entry.append(&suggestion);
+        list.append(&entry);
+    }
+    let scrolled = gtk4::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk4::PolicyType::Never)
+        .propagate_natural_height(true)
+        .max_content_height(400)
+        .child(&list)
+        .build();
+
+    let cancel = gtk4::Button::builder().label("Cancel").build();
+    let export = gtk4::Button::builder()
+        .label("Export anyway")
+        .hexpand(true)
+        .build();
+    let buttons = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    buttons.append(&cancel);
+    buttons.append(&export);
+    let layout = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .spacing(10)
+        .margin_top(10)
+        .margin_bottom(10)
+        .margin_start(10)
+        .margin_end(10)
+        .build();
+    layout.append(
+        &gtk4::Label::builder()
+            .label("Some cards may not print well. Check them before exporting.")
+            .wrap(true)
+            .xalign(0.0)
+            .build(),
+    );
+    layout.append(&scrolled);
+    layout.append(&buttons);
+
+    let window = gtk4::Window::builder()
+        .title("Check before export")
+        .transient_for(parent)
+        .modal(true)
+        .default_width(450)
+        .child(&layout)
+        .build();
+    let window_moved = window.clone();
+    cancel.connect_clicked(move |_| window_moved.close());
+    let window_moved = window.clone();
+    export.connect_clicked(move |_| {
+        window_moved.close();
+        on_continue();
+    });
+    window.present();
+}
@@ -0,0 +1,126 @@
+use super::print::{mm_to_pt, record_pages};
+use crate::render::{PageLayout, RenderOptions};
+use crate::spell::Spell;
+use gtk4::prelude::*;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Show window with pages of the deck exactly as they are exported, to check packing of
+/// double cards and cut guides before printing.
+pub fn show_page_preview(
+    parent: &gtk4::ApplicationWindow,
+    spells: Vec<Spell>,
+    options: RenderOptions,
+) -> anyhow::Result<()> {
+    let pages = Rc::new(record_pages(&spells, &options)?);
+    if pages.is_empty() {
+        anyhow::bail!("No cards to preview");
+    }
+    let layout = options.page;
+    // With card backs every second page is the back side.
+    let has_backs = options.duplex.card_backs;
+    let current = Rc::new(Cell::new(0));
+
+    let drawing_area = gtk4::DrawingArea::builder()
+        .hexpand(true)
+        .vexpand(true)
+        .build();
+    let pages_moved = pages.clone();
+    let current_moved = current.clone();
+    drawing_area.set_draw_func(move |_, context, width, height| {
+        if let Some(page) = pages_moved.get(current_moved.get()) {
+            draw_page(context, &layout, page, width as f64, height as f64);
+        }
+    });
+
+    let previous = gtk4::Button::builder()
+        .icon_name("go-previous-symbolic")
+        .build();
+    let next = gtk4::Button::builder()
+        .icon_name("go-next-symbolic")
+        .build();
+    let label = gtk4::Label::builder().hexpand(true).build();
+    let update = {
+        let previous = previous.clone();
+        let next = next.clone();
+        let label = label.clone();
+        let drawing_area = drawing_area.clone();
+        let current = current.clone();
+        let count = pages.len();
+        move || {
+            let index = current.get();
+            let side = match (has_backs, index % 2) {
+                (false, _) => "",
+                (true, 0) => ", front",
+                (true, _) => ", back",
+            };
+            label.set_text(&format!("Page {} of {count}{side}", index + 1));
+            previous.set_sensitive(index > 0);
+            next.set_sensitive(index + 1 < count);
+            drawing_area.queue_draw();
+        }
+    };
+    update();
+    let update = Rc::new(update);
+    let current_moved = current.clone();
+    let update_moved = update.clone();
+    previous.connect_clicked(move |_| {
+        current_moved.set(current_moved.get().saturating_sub(1));
+        update_moved();
+    });
+    let count = pages.len();
+    next.connect_clicked(move |_| {
+        current.set((current.get() + 1).min(count - 1));
+        update();
+    });
+
+    let navigation = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    navigation.append(&previous);
+    navigation.append(&label);
+    navigation.append(&next);
+    let content = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .build();
+    content.append(&drawing_area);
+    content.append(&navigation);
+    gtk4::Window::builder()
+        .title("Page preview")
+        .transient_for(parent)
+        .modal(true)
+        .default_width(500)
+        .default_height(700)
+        .child(&content)
+        .build()
+        .present();
+    Ok(())
+}
+
+/// Draw `page` on white sheet, scaled to fit the area.
+fn draw_page(
+    context: &cairo::Context,
+    layout: &PageLayout,
+    page: &cairo::RecordingSurface,
+    width: f64,
+    height: f64,
+) {
+    let padding = 10.0;
+    let page_width = mm_to_pt(layout.page_width());
+    let page_height = mm_to_pt(layout.page_height());
+    let scale = ((width - padding * 2.0) / page_width).min((height - padding * 2.0) / page_height);
+    context.translate(
+        (width - page_width * scale) * 0.5,
+        (height - page_height * scale) * 0.5,
+    );
+    context.scale(scale, scale);
+    context.rectangle(0.0, 0.0, page_width, page_height);
+    context.set_source_rgb(1.0, 1.0, 1.0);
+    let _ = context.fill_preserve();
+    context.set_source_rgb(0.6, 0.6, 0.6);
+    context.set_line_width(1.0 / scale);
+    let _ = context.stroke();
+    if context.set_source_surface(page, 0.0, 0.0).is_ok() {
+        let _ = context.paint();
+    }
+}
@@ -0,0 +1,139 @@
+use crate::cairo_render::{paint_scene, CairoFont};
+use crate::render::{
+    back_cards, fit_deck, lay_out_deck, prepare_deck, OwnedFontConfig, PageLayout, RenderOptions,
+};
+use crate::rich_text::Scene;
+use crate::spell::Spell;
+use freetype::Library;
+use gtk4::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Print cards with system print dialog. Pages are the same as in exported pdf,
+/// drawn with Cairo instead.
+pub fn print_spells(
+    window: &gtk4::ApplicationWindow,
+    spells: Vec<Spell>,
+    options: RenderOptions,
+) -> anyhow::Result<()> {
+    let layout = options.page;
+    let operation = gtk4::PrintOperation::new();
+    operation.set_default_page_setup(Some(&page_setup(&layout)));
+    // Card grid is positioned relative to paper edges, so printer margins are not applied.
+    operation.set_use_full_page(true);
+    operation.set_unit(gtk4::Unit::Points);
+
+    let pages = Rc::new(RefCell::new(vec![]));
+    let pages_moved = pages.clone();
+    operation.connect_begin_print(move |operation, _| match record_pages(&spells, &options) {
+        Ok(recorded) => {
+            operation.set_n_pages(recorded.len().max(1) as i32);
+            pages_moved.replace(recorded);
+        }
+        Err(error) => {
+            tracing::error!("Unable to lay out cards: {error:#}");
+            operation.cancel();
+        }
+    });
+    operation.connect_draw_page(move |_, context, page_index| {
+        if let Some(page) = pages.borrow().get(page_index as usize) {
+            let context = context.cairo_context();
+            if context.set_source_surface(page, 0.0, 0.0).is_ok() {
+                let _ = context.paint();
+            }
+        }
+    });
+    operation.run(gtk4::PrintOperationAction::PrintDialog, Some(window))?;
+    Ok(())
+}
+
+fn page_setup(layout: &PageLayout) -> gtk4::PageSetup {
+    let paper_size = gtk4::PaperSize::new_custom(
+        layout.page_size.as_str(),
+        layout.page_size.as_str(),
+        layout.page_width() as f64,
+        layout.page_height() as f64,
+        gtk4::Unit::Mm,
+    );
+    let setup = gtk4::PageSetup::new();
+    setup.set_paper_size(&paper_size);
+    setup.set_top_margin(0.0, gtk4::Unit::Mm);
+    setup.set_bottom_margin(0.0, gtk4::Unit::Mm);
+    setup.set_left_margin(0.0, gtk4::Unit::Mm);
+    setup.set_right_margin(0.0, gtk4::Unit::Mm);
+    setup
+}
+
+/// Draw every page of the deck, including card backs, into recordings replayed by print operation.
+pub fn record_pages(
+    spells: &[Spell],
+    options: &RenderOptions,
+) -> anyhow::Result<Vec<cairo::RecordingSurface>> {
+    let layout = &options.page;
+    let owned_font_config = OwnedFontConfig::<CairoFont>::with_fonts(
+        &mut Library::init()?,
+        &*options.system,
+        options.theme.fonts,
+    )?;
+    let font_config = owned_font_config.config();
+    let spells = fit_deck(&font_config, prepare_deck(spells, options), options);
+    let (columns, _) = lay_out_deck(&font_config, &spells, options);
+
+    let mut result = vec![];
+    for page in columns.chunks(layout.grid_width()) {
+        let (surface, context) = new_page(layout)?;
+        for (x, column) in page.iter().enumerate() {
+            for (y, cell) in column.iter().enumerate() {
+                if let Some(scene) = cell.scene() {
+                    draw_card(&context, layout, (x, y), (0.0, 0.0), scene)?;
+                }
+            }
+        }
+        result.push(surface);
+        if options.duplex.card_backs {
+            let (surface, context) = new_page(layout)?;
+            let shift = (options.duplex.back_offset_x, options.duplex.back_offset_y);
+            for (cell, back) in back_cards(&font_config, page, options) {
+                draw_card(&context, layout, cell, shift, &back)?;
+            }
+            result.push(surface);
+        }
+    }
+    Ok(result)
+}
+
+fn new_page(layout: &PageLayout) -> anyhow::Result<(cairo::RecordingSurface, cairo::Context)> {
+    let surface = cairo::RecordingSurface::create(cairo::Content::ColorAlpha, None)?;
+    let context = cairo::Context::new(&surface)?;
+    if layout.cut_guides {
+        let height = layout.page_height();
+        context.set_source_rgb(0.0, 0.0, 0.0);
+        context.set_line_width(0.25);
+        for [from, to] in layout.cut_guide_lines() {
+            context.move_to(mm_to_pt(from.0), mm_to_pt(height - from.1));
+            context.line_to(mm_to_pt(to.0), mm_to_pt(height - to.1));
+        }
+        context.stroke()?;
+    }
+    Ok((surface, context))
+}
+
+/// Draw `scene` into grid cell `(x, y)`, moved by `(shift_x, shift_y)` `Mm` right and down.
+fn draw_card(
+    context: &cairo::Context,
+    layout: &PageLayout,
+    (x, y): (usize, usize),
+    (shift_x, shift_y): (f32, f32),
+    scene: &Scene<'_, CairoFont>,
+) -> anyhow::Result<()> {
+    let (left, top) = layout.content_top_left(x, y);
+    context.save()?;
+    context.translate(mm_to_pt(left + shift_x), mm_to_pt(top + shift_y));
+    paint_scene(context, scene);
+    context.restore()?;
+    Ok(())
+}
+
+pub fn mm_to_pt(mm: f32) -> f64 {
+    mm as f64 * 72.0 / 25.4
+}
@@ -0,0 +1,544 @@
+use super::duplex_settings::DuplexSettings;
+use super::local_path;
+use crate::config;
+use crate::profiles::{load_profiles, save_profiles, Profile};
+use crate::render::{BorderStyle, DividerGrouping, PageSize, Placement, Truncation};
+use crate::templates::{installed_templates, TemplatePack};
+use gtk4::{gio, prelude::*, Widget};
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// Export settings, which can be saved as named profile and restored later.
+#[derive(Clone)]
+pub struct ProfileSettings {
+    /// Where profiles are saved. Saving is disabled if `None`.
+    path: Option<PathBuf>,
+    /// Built-in profiles followed by installed templates, see `Profile::presets` and
+    /// `templates` module.
+    presets: Rc<RefCell<Vec<Profile>>>,
+    profiles: Rc<RefCell<Vec<Profile>>>,
+    /// Profile settings not exposed in controls, like card size, are taken from.
+    current: Rc<RefCell<Profile>>,
+    /// First items match `presets`, rest match `profiles`.
+    profile_names: gtk4::StringList,
+    selector: gtk4::DropDown,
+    /// Items match `PageSize::ALL`.
+    page_size: gtk4::DropDown,
+    cut_guides: gtk4::CheckButton,
+    merge_duplicates: gtk4::CheckButton,
+    sleeve_fit: gtk4::CheckButton,
+    /// Items match `BorderStyle::ALL`.
+    border: gtk4::DropDown,
+    trait_pills: gtk4::CheckButton,
+    sigils: gtk4::CheckButton,
+    numbering: gtk4::CheckButton,
+    /// First item is no dividers, rest match `DividerGrouping::ALL`.
+    dividers: gtk4::DropDown,
+    attribution_page: gtk4::CheckButton,
+    cover_page: gtk4::CheckButton,
+    heightened_summary: gtk4::CheckButton,
+    caster_actions_card: gtk4::CheckButton,
+    /// Items match `Placement::ALL`.
+    placement: gtk4::DropDown,
+    card_per_page: gtk4::CheckButton,
+    print_ready: gtk4::CheckButton,
+    /// Items match `Truncation::ALL`.
+    truncation: gtk4::DropDown,
+    /// Deck default, spells can still leave them out one by one in the selection.
+    omit_heightened: gtk4::CheckButton,
+    omit_extras: gtk4::CheckButton,
+    duplex: DuplexSettings,
+    name: gtk4::Entry,
+    on_changed: Rc<RefCell<Box<dyn Fn()>>>,
+}
+
+impl ProfileSettings {
+    /// Create settings, selecting profile named `selected` if it exists.
+    pub fn new(
+        path: Option<PathBuf>,
+        duplex: DuplexSettings,
+        selected: Option<&str>,
+    ) -> (Self, impl IsA<Widget>) {
+        let profiles = path
+            .as_deref()
+            .map(load_profiles)
+            .transpose()
+            .unwrap_or_else(|error| {
+                tracing::warn!("{error:#}");
+                None
+            })
+            .unwrap_or_default();
+
+        let presets = load_presets();
+        let profile_names = gtk4::StringList::new(&[]);
+        let selector = gtk4::DropDown::builder().model(&profile_names).build();
+        let page_size_names = PageSize::ALL.map(PageSize::as_str);
+        let page_size = gtk4::DropDown::from_strings(&page_size_names);
+        let cut_guides = gtk4::CheckButton::builder().label("Cut guides").build();
+        let merge_duplicates = gtk4::CheckButton::builder()
+            .label("Single card per spell")
+            .tooltip_text("Ignore how many times spell is selected")
+            .build();
+        let sleeve_fit = gtk4::CheckButton::builder()
+            .label("Sleeve fit")
+            .tooltip_text("Shrink content and thicken border, so slightly off-size cuts look clean")
+            .build();
+        let border = gtk4::DropDown::from_strings(&[
+            "No card border",
+            "Thin card border",
+            "Thick card border",
+            "Rounded card border",
+            "Double card border",
+        ]);
+        border.set_tooltip_text(Some(
+            "Heavier borders are easier to follow then cutting by hand",
+        ));
+        let trait_pills = gtk4::CheckButton::builder()
+            .label("Trait pills")
+            .tooltip_text("Draw traits as filled pills with white text, like official statblocks")
+            .build();
+        let sigils = gtk4::CheckButton::builder()
+            .label("Sigils")
+            .tooltip_text(
+                "Fill empty space at the bottom of cards with a sigil drawn from spell name",
+            )
+            .build();
+        let numbering = gtk4::CheckButton::builder()
+            .label("Number cards")
+            .tooltip_text("Print card numbers and save manifest listing them next to exported file")
+            .build();
+        let dividers = gtk4::DropDown::from_strings(&[
+            "No divider cards",
+            "Dividers by rank",
+            "Dividers by tradition",
+        ]);
+        dividers.set_tooltip_text(Some("Add a labeled card in front of every group of spells"));
+        let attribution_page = gtk4::CheckButton::builder()
+            .label("License attribution page")
+            .tooltip_text("Add final page with license attribution of spell sources")
+            .build();
+        let cover_page = gtk4::CheckButton::builder()
+            .label("Cover page")
+            .tooltip_text("Start with a page showing deck name, character and card count by rank")
+            .build();
+        let heightened_summary = gtk4::CheckButton::builder()
+            .label("Heightened summary")
+            .tooltip_text("Add a card per rank listing heightened entries of selected spells")
+            .build();
+        let caster_actions_card = gtk4::CheckButton::builder()
+            .label("Caster actions card")
+            .tooltip_text("Add a card summarizing Cast a Spell, Sustain, Dismiss and Refocus")
+            .build();
+        let placement = gtk4::DropDown::from_strings(&[
+            "Pack cards tightly",
+            "Keep deck order",
+            "Balance columns",
+        ]);
+        placement.set_tooltip_text(Some(
+            "Packing fills gaps between double cards with single ones, saving pages. Balancing \
+            also spreads long and short cards evenly between page columns",
+        ));
+        let card_per_page = gtk4::CheckButton::builder()
+            .label("Card per page")
+            .tooltip_text(
+                "Put every card on its own page of card size, for virtual tabletops and phones",
+            )
+            .build();
+        let print_ready = gtk4::CheckButton::builder()
+            .label("Print shop ready")
+            .tooltip_text(
+                "Embed every font, add bleed around pages and leave out PDF/X metadata, \
+                for print services rejecting regular output",
+            )
+            .build();
+        let truncation = gtk4::DropDown::from_strings(&[
+            "Drop spells too long for a card",
+            "Cut long spells, note Nethys",
+            "Cut long spells, link Nethys page",
+        ]);
+        truncation.set_tooltip_text(Some(
+            "Spells not fitting even a double card can be cut after the last fitting sentence, \
+            with a note where to read the rest",
+        ));
+        let omit_heightened = gtk4::CheckButton::builder()
+            .label("Leave out heightened entries")
+            .tooltip_text(
+                "Print every card without heightened entries, for casters who cannot heighten",
+            )
+            .build();
+        let omit_extras = gtk4::CheckButton::builder()
+            .label("Leave out extras")
+            .tooltip_text(
+                "Print every card without sections after heightened entries, like psychic amps",
+            )
+            .build();
+        let name = gtk4::Entry::builder()
+            .placeholder_text("profile name")
+            .build();
+        let save_button = gtk4::Button::builder()
+            .label("Save profile")
+            .sensitive(path.is_some())
+            .build();
+        let import_button = gtk4::Button::builder()
+            .label("Import template")
+            .tooltip_text("Install template pack zip with card look and fonts")
+            .sensitive(config::templates_dir().is_some())
+            .build();
+
+        let result = Self {
+            path,
+            current: Rc::new(RefCell::new(presets[0].clone())),
+            presets: Rc::new(RefCell::new(presets)),
+            profiles: Rc::new(RefCell::new(profiles)),
+            profile_names,
+            selector,
+            page_size,
+            cut_guides,
+            merge_duplicates,
+            sleeve_fit,
+            border,
+            trait_pills,
+            sigils,
+            numbering,
+            dividers,
+            attribution_page,
+            cover_page,
+            heightened_summary,
+            caster_actions_card,
+            placement,
+            card_per_page,
+            print_ready,
+            truncation,
+            omit_heightened,
+            omit_extras,
+            duplex,
+            name,
+            on_changed: Rc::new(RefCell::new(Box::new(|| {}))),
+        };
+        result.refresh_names();
+        let selected_index = selected.and_then(|selected| {
+            let index = result.item_index(selected);
+            if index.is_none() {
+                tracing::warn!("Profile `{selected}` not found");
+            }
+            index
+        });
+        if let Some(index) = selected_index {
+            result.selector.set_selected(index as u32);
+            result.select(index);
+        }
+
+        let settings = result.clone();
+        result
+            .selector
+            .connect_selected_notify(move |selector| settings.select(selector.selected() as usize));
+        let settings = result.clone();
+        result
+            .sleeve_fit
+            .connect_toggled(move |_| settings.on_changed.borrow()());
+        let settings = result.clone();
+        result
+            .border
+            .connect_selected_notify(move |_| settings.on_changed.borrow()());
+        let settings = result.clone();
+        result
+            .trait_pills
+            .connect_toggled(move |_| settings.on_changed.borrow()());
+        let settings = result.clone();
+        result
+            .sigils
+            .connect_toggled(move |_| settings.on_changed.borrow()());
+        let settings = result.clone();
+        result
+            .truncation
+            .connect_selected_notify(move |_| settings.on_changed.borrow()());
+        let settings = result.clone();
+        result
+            .omit_heightened
+            .connect_toggled(move |_| settings.on_changed.borrow()());
+        let settings = result.clone();
+        result
+            .omit_extras
+            .connect_toggled(move |_| settings.on_changed.borrow()());
+        let settings = result.clone();
+        save_button.connect_clicked(move |button| {
+            if let Err(error) = settings.save() {
+                gtk4::AlertDialog::builder()
+                    .message("Unable to save profile")
+                    .detail(format!("{error:#}"))
+                    .build()
+                    .show(button.root().and_downcast_ref::<gtk4::Window>());
+            }
+        });
+        let settings = result.clone();
+        import_button.connect_clicked(move |button| settings.show_import_dialog(button));
+
+        let layout = gtk4::Box::builder()
+            .orientation(gtk4::Orientation::Vertical)
+            .build();
+        layout.append(&result.selector);
+        layout.append(&result.page_size);
+        layout.append(&result.cut_guides);
+        layout.append(&result.merge_duplicates);
+        layout.append(&result.sleeve_fit);
+        layout.append(&result.border);
+        layout.append(&result.trait_pills);
+        layout.append(&result.sigils);
+        layout.append(&result.numbering);
+        layout.append(&result.dividers);
+        layout.append(&result.attribution_page);
+        layout.append(&result.cover_page);
+        layout.append(&result.heightened_summary);
+        layout.append(&result.caster_actions_card);
+        layout.append(&result.placement);
+        layout.append(&result.card_per_page);
+        layout.append(&result.print_ready);
+        layout.append(&result.truncation);
+        layout.append(&result.omit_heightened);
+        layout.append(&result.omit_extras);
+        let save_bar = gtk4::Box::builder()
+            .orientation(gtk4::Orientation::Horizontal)
+            .build();
+        save_bar.append(&result.name);
+        save_bar.append(&save_button);
+        layout.append(&save_bar);
+        layout.append(&import_button);
+        let widget = gtk4::Expander::builder()
+            .label("Export profile")
+            .child(&layout)
+            .build();
+        (result, widget)
+    }
+
+    /// Called then selected profile changes.
+    pub fn connect_changed(&self, changed: impl Fn() + 'static) {
+        let _ = self.on_changed.replace(Box::new(changed));
+    }
+
+    /// Export settings currently shown, to be saved with a deck.
+    pub fn current_profile(&self) -> Profile {
+        let name = self.current.borrow().name.clone();
+        self.profile(&name)
+    }
+
+    /// Show settings saved with a deck. Profile selection is left as is.
+    pub fn show_profile(&self, profile: Profile) {
+        let page_size = PageSize::ALL
+            .iter()
+            .position(|size| *size == profile.page.page_size)
+            .unwrap_or(0);
+        self.page_size.set_selected(page_size as u32);
+        self.cut_guides.set_active(profile.page.cut_guides);
+        self.merge_duplicates.set_active(profile.merge_duplicates);
+        self.sleeve_fit.set_active(profile.sleeve_fit.is_some());
+        let border = BorderStyle::ALL
+            .iter()
+            .position(|style| *style == profile.theme.border)
+            .unwrap_or(0);
+        self.border.set_selected(border as u32);
+        self.trait_pills.set_active(profile.theme.trait_pills);
+        self.sigils.set_active(profile.theme.sigils);
+        self.numbering.set_active(profile.numbering);
+        let dividers = profile.dividers.map_or(0, |grouping| {
+            DividerGrouping::ALL
+                .iter()
+                .position(|item| *item == grouping)
+                .map_or(0, |index| index + 1)
+        });
+        self.dividers.set_selected(dividers as u32);
+        self.attribution_page.set_active(profile.attribution_page);
+        self.cover_page.set_active(profile.cover_page);
+        self.heightened_summary
+            .set_active(profile.heightened_summary);
+        self.caster_actions_card
+            .set_active(profile.caster_actions_card);
+        let placement = Placement::ALL
+            .iter()
+            .position(|placement| *placement == profile.placement)
+            .unwrap_or(0);
+        self.placement.set_selected(placement as u32);
+        self.card_per_page.set_active(profile.card_per_page);
+        self.print_ready.set_active(profile.print_ready.is_some());
+        let truncation = Truncation::ALL
+            .iter()
+            .position(|truncation| *truncation == profile.truncation)
+            .unwrap_or(0);
+        self.truncation.set_selected(truncation as u32);
+        self.omit_heightened
+            .set_active(profile.card.omit_heightened);
+        self.omit_extras.set_active(profile.card.omit_extras);
+        self.duplex.set_duplex(profile.duplex);
+        self.current.replace(profile);
+        self.on_changed.borrow()();
+    }
+
+    /// Profile matching current state of controls.
+    fn profile(&self, name: &str) -> Profile {
+        let mut profile = self.current.borrow().clone();
+        profile.name = name.to_string();
+        profile.page.page_size = PageSize::ALL
+            .get(self.page_size.selected() as usize)
+            .copied()
+            .unwrap_or(PageSize::A4);
+        profile.page.cut_guides = self.cut_guides.is_active();
+        profile.merge_duplicates = self.merge_duplicates.is_active();
+        profile.numbering = self.numbering.is_active();
+        profile.attribution_page = self.attribution_page.is_active();
+        profile.cover_page = self.cover_page.is_active();
+        profile.heightened_summary = self.heightened_summary.is_active();
+        profile.caster_actions_card = self.caster_actions_card.is_active();
+        profile.placement = Placement::ALL
+            .get(self.placement.selected() as usize)
+            .copied()
+            .unwrap_or(Placement::Packed);
+        profile.card_per_page = self.card_per_page.is_active();
+        profile.truncation = Truncation::ALL
+            .get(self.truncation.selected() as usize)
+            .copied()
+            .unwrap_or(Truncation::Drop);
+        profile.card.omit_heightened = self.omit_heightened.is_active();
+        profile.card.omit_extras = self.omit_extras.is_active();
+        profile.dividers = (self.dividers.selected() as usize)
+            .checked_sub(1)
+            .and_then(|index| DividerGrouping::ALL.get(index).copied());
+        profile.sleeve_fit = self
+            .sleeve_fit
+            .is_active()
+            .then(|| profile.sleeve_fit.unwrap_or_default());
+        profile.print_ready = self
+            .print_ready
+            .is_active()
+            .then(|| profile.print_ready.unwrap_or_default());
+        profile.theme.border = BorderStyle::ALL
+            .get(self.border.selected() as usize)
+            .copied()
+            .unwrap_or(BorderStyle::Thin);
+        profile.theme.trait_pills = self.trait_pills.is_active();
+        profile.theme.sigils = self.sigils.is_active();
+        profile.duplex = self.duplex.duplex();
+        profile
+    }
+
+    /// Select profile by dropdown item index, and update controls from it.
+    fn select(&self, index: usize) {
+        let preset_count = self.presets.borrow().len();
+        let profile = match index.checked_sub(preset_count) {
+            Some(index) => self.profiles.borrow().get(index).cloned(),
+            None => self.presets.borrow().get(index).cloned(),
+        };
+        let Some(profile) = profile else {
+            return;
+        };
+        if index >= preset_count {
+            self.name.set_text(&profile.name);
+        }
+        self.show_profile(profile);
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let name = self.name.text().trim().to_string();
+        if name.is_empty() {
+            anyhow::bail!("Profile name is empty");
+        }
+        let profile = self.profile(&name);
+        self.current.replace(profile.clone());
+        let index = match self.profile_index(&name) {
+            Some(index) => {
+                self.profiles.borrow_mut()[index] = profile;
+                index
+            }
+            None => {
+                self.profiles.borrow_mut().push(profile);
+                self.profiles.borrow().len() - 1
+            }
+        };
+        save_profiles(path, &self.profiles.borrow())?;
+        self.refresh_names();
+        self.selector
+            .set_selected((self.presets.borrow().len() + index) as u32);
+        Ok(())
+    }
+
+    /// Ask for template pack archive, install it and select it.
+    fn show_import_dialog(&self, button: &gtk4::Button) {
+        let window = button.root().and_downcast::<gtk4::Window>();
+        let parent = window.clone();
+        let settings = self.clone();
+        let cancelable: Option<&gio::Cancellable> = None;
+        gtk4::FileDialog::builder()
+            .title("Import template")
+            .filters(&super::file_filters("zip"))
+            .build()
+            .open(parent.as_ref(), cancelable, move |file| {
+                let Ok(file) = file else {
+                    return;
+                };
+                if let Err(error) = settings.import_template(&file) {
+                    gtk4::AlertDialog::builder()
+                        .message("Unable to import template")
+                        .detail(format!("{error:#}"))
+                        .build()
+                        .show(window.as_ref());
+                }
+            });
+    }
+
+    fn import_template(&self, file: &gio::File) -> anyhow::Result<()> {
+        let dir = config::templates_dir()
+            .ok_or_else(|| anyhow::anyhow!("Templates directory is unknown"))?;
+        let path = local_path(file)?;
+        let pack = TemplatePack::from_zip(&std::fs::read(path)?)?;
+        pack.install(&dir)?;
+        tracing::info!("Installed template `{}`", pack.profile.name);
+        self.presets.replace(load_presets());
+        self.refresh_names();
+        if let Some(index) = self.item_index(&pack.profile.name) {
+            self.selector.set_selected(index as u32);
+            self.select(index);
+        }
+        Ok(())
+    }
+
+    fn profile_index(&self, name: &str) -> Option<usize> {
+        self.profiles
+            .borrow()
+            .iter()
+            .position(|profile| profile.name == name)
+    }
+
+    /// Dropdown item index of saved profile or preset named `name`. Saved profiles take
+    /// precedence over presets of the same name.
+    fn item_index(&self, name: &str) -> Option<usize> {
+        let presets = self.presets.borrow();
+        match self.profile_index(name) {
+            Some(index) => Some(presets.len() + index),
+            None => presets.iter().position(|preset| preset.name == name),
+        }
+    }
+
+    fn refresh_names(&self) {
+        let names = self
+            .presets
+            .borrow()
+            .iter()
+            .chain(self.profiles.borrow().iter())
+            .map(|profile| profile.name.clone())
+            .collect::<Vec<_>>();
+        let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+        self.profile_names
+            .splice(0, self.profile_names.n_items(), &names);
+    }
+}
+
+/// Built-in presets followed by installed templates.
+fn load_presets() -> Vec<Profile> {
+    let mut result = Profile::presets();
+    if let Some(dir) = config::templates_dir() {
+        result.extend(installed_templates(&dir));
+    }
+    result
+}
@@ -0,0 +1,57 @@
+use crate::spell::Spell;
+use gtk4::{prelude::*, Widget};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+type SpellCallback = Box<dyn Fn(Rc<Spell>)>;
+
+/// Panel under preview suggesting cards related to previewed spell. Clicking a suggestion
+/// adds it to the deck.
+#[derive(Clone)]
+pub struct RelatedSpells {
+    container: gtk4::Box,
+    suggestions: gtk4::FlowBox,
+    spell_added: Rc<RefCell<SpellCallback>>,
+}
+
+impl RelatedSpells {
+    pub fn new() -> (Self, impl IsA<Widget>) {
+        let suggestions = gtk4::FlowBox::builder()
+            .selection_mode(gtk4::SelectionMode::None)
+            .column_spacing(5)
+            .row_spacing(5)
+            .build();
+        let container = gtk4::Box::builder()
+            .orientation(gtk4::Orientation::Horizontal)
+            .spacing(10)
+            .visible(false)
+            .build();
+        container.append(&gtk4::Label::new(Some("Related")));
+        container.append(&suggestions);
+        let result = Self {
+            container: container.clone(),
+            suggestions,
+            spell_added: Rc::new(RefCell::new(Box::new(|_| {}))),
+        };
+        (result, container)
+    }
+
+    pub fn connect_spell_added(&self, added: impl Fn(Rc<Spell>) + 'static) {
+        let _ = self.spell_added.as_ref().replace(Box::new(added));
+    }
+
+    /// Replace suggestions. Panel is hidden if there are none.
+    pub fn show_spells(&self, spells: Vec<Rc<Spell>>) {
+        self.suggestions.remove_all();
+        self.container.set_visible(!spells.is_empty());
+        for spell in spells {
+            let button = gtk4::Button::builder()
+                .label(format!("+ {}", spell.name))
+                .tooltip_text("Add to deck")
+                .build();
+            let spell_added = self.spell_added.clone();
+            button.connect_clicked(move |_| spell_added.borrow()(spell.clone()));
+            self.suggestions.append(&button);
+        }
+    }
+}
@@ -1,13 +1,16 @@
 use crate::spell::Spell;
 use gtk4::glib::Properties;
-use gtk4::{gio, glib, prelude::*, subclass::prelude::*, Widget};
+use gtk4::{gdk, gio, glib, prelude::*, subclass::prelude::*, Widget};
 use gtk4::{SignalListItemFactory, SingleSelection};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
 use std::rc::Rc;
 
 #[derive(Default)]
 struct SpellModelImpl {
     spell: RefCell<Option<Rc<Spell>>>,
+    /// Spell of another source has the same name, so source is shown with the name.
+    show_source: Cell<bool>,
 }
 
 #[glib::object_subclass]
@@ -29,9 +32,10 @@ glib::wrapper! {
 }
 
 impl SpellModel {
-    fn new(spell: Rc<Spell>) -> Self {
+    fn new(spell: Rc<Spell>, show_source: bool) -> Self {
         let result: SpellModel = glib::Object::builder().build();
         result.imp().spell.replace(Some(spell));
+        result.imp().show_source.set(show_source);
         result
     }
 }
@@ -80,6 +84,9 @@ impl SpellRow {
 
 type SpellCallback = Box<dyn Fn(Rc<Spell>)>;
 
+/// Results skipped by `PageUp` and `PageDown`.
+const PAGE_STEP: i64 = 10;
+
 #[derive(Clone)]
 pub struct SpellCollection {
     model: gio::ListStore,
@@ -100,10 +107,14 @@ impl SpellCollection {
         (result, widget)
     }
 
-    pub fn set_spells(&self, spells: &[Rc<Spell>]) {
+    /// Show `spells`, with source added to names in `shared_names`, see `db::shared_names`.
+    pub fn set_spells(&self, spells: &[Rc<Spell>], shared_names: &HashSet<String>) {
         let items = spells
             .iter()
-            .map(|spell| SpellModel::new(spell.clone()))
+            .map(|spell| {
+                let show_source = shared_names.contains(&spell.name.to_lowercase());
+                SpellModel::new(spell.clone(), show_source)
+            })
             .collect::<Vec<_>>();
         self.model.remove_all();
         self.model.extend_from_slice(&items);
@@ -118,10 +129,46 @@ impl SpellCollection {
     }
 
     fn build_widget(&self, factory: SignalListItemFactory) -> impl IsA<Widget> {
+        let selection = SingleSelection::new(Some(self.model.clone()));
         let list_view = gtk4::ListView::builder()
             .factory(&factory)
-            .model(&SingleSelection::new(Some(self.model.clone())))
+            .model(&selection)
             .build();
+        let spell_selected = self.spell_selected.clone();
+        selection.connect_selected_item_notify(move |selection| {
+            if let Some(model) = selection.selected_item().and_downcast::<SpellModel>() {
+                spell_selected.as_ref().borrow()(model.imp().spell());
+            }
+        });
+
+        // Arrow and page keys move selection itself rather than focus, so preview follows.
+        let keys = gtk4::EventControllerKey::new();
+        keys.set_propagation_phase(gtk4::PropagationPhase::Capture);
+        let list_view_moved = list_view.clone();
+        keys.connect_key_pressed(move |_, key, _, _| {
+            let step = match key {
+                gdk::Key::Down => 1,
+                gdk::Key::Up => -1,
+                gdk::Key::Page_Down => PAGE_STEP,
+                gdk::Key::Page_Up => -PAGE_STEP,
+                _ => return glib::Propagation::Proceed,
+            };
+            let count = selection.n_items() as i64;
+            if count == 0 {
+                return glib::Propagation::Stop;
+            }
+            let current = match selection.selected() {
+                gtk4::INVALID_LIST_POSITION => -1,
+                position => position as i64,
+            };
+            let next = (current + step).clamp(0, count - 1) as u32;
+            selection.set_selected(next);
+            let _ =
+                list_view_moved.activate_action("list.scroll-to-item", Some(&next.to_variant()));
+            glib::Propagation::Stop
+        });
+        list_view.add_controller(keys);
+
         gtk4::ScrolledWindow::builder()
             .hscrollbar_policy(gtk4::PolicyType::Never)
             .vexpand(true)
@@ -140,16 +187,6 @@ impl SpellCollection {
             list_item.set_child(Some(&row_widget));
 
             let collection_moved = collection.clone();
-            list_item.connect_selected_notify(move |item| {
-                if item.is_selected() {
-                    let model = item
-                        .item()
-                        .and_downcast::<SpellModel>()
-                        .expect("Must be SpellModel");
-                    collection_moved.spell_selected.as_ref().borrow()(model.imp().spell());
-                }
-            });
-
             let list_item = list_item.clone();
             row_widget.add_button().connect_clicked(move |_| {
                 let model = list_item
@@ -172,7 +209,12 @@ impl SpellCollection {
                 .and_downcast::<SpellRow>()
                 .expect("Must be SpellRow");
             let label = child.label();
-            label.set_text(&model.imp().spell().name);
+            let spell = model.imp().spell();
+            if model.imp().show_source.get() {
+                label.set_text(&format!("{} ({})", spell.name, spell.source));
+            } else {
+                label.set_text(&spell.name);
+            }
         });
         factory
     }
@@ -1,6 +1,10 @@
-use crate::spell::Spell;
+use crate::deck::{DeckEntry, MAX_CARD_COUNT};
+use crate::render::CardOverride;
+use crate::spell::{Spell, Tradition};
 use gtk4::{gio, glib, prelude::*, subclass::prelude::*, Widget};
 use gtk4::{SignalListItemFactory, SingleSelection};
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::rc::Rc;
 
 mod spell_model_impl {
@@ -18,6 +22,10 @@ mod spell_model_impl {
         pub spell: RefCell<Option<Rc<Spell>>>,
         #[property(get, set)]
         count: Cell<u32>,
+        #[property(get, set)]
+        omit_heightened: Cell<bool>,
+        #[property(get, set)]
+        notes: RefCell<String>,
     }
 
     #[glib::object_subclass]
@@ -62,11 +70,17 @@ mod selected_row_impl {
         #[property(get, set)]
         count_label: RefCell<gtk4::Label>,
         #[property(get, set)]
+        legality_icon: RefCell<gtk4::Image>,
+        #[property(get, set)]
         remove_button: RefCell<gtk4::Button>,
         #[property(get, set)]
         add_button: RefCell<gtk4::Button>,
         #[property(get, set)]
+        omit_heightened_button: RefCell<gtk4::ToggleButton>,
+        #[property(get, set)]
         binding: RefCell<Option<Binding>>,
+        #[property(get, set)]
+        omit_heightened_binding: RefCell<Option<Binding>>,
     }
 
     #[glib::object_subclass]
@@ -92,9 +106,11 @@ glib::wrapper! {
 impl SelectedSpellRow {
     pub fn new(
         label: gtk4::Label,
+        legality_icon: gtk4::Image,
         count: gtk4::Label,
         add_button: gtk4::Button,
         remove_button: gtk4::Button,
+        omit_heightened_button: gtk4::ToggleButton,
     ) -> Self {
         label.set_hexpand(true);
         count.set_width_request(40);
@@ -102,13 +118,17 @@ impl SelectedSpellRow {
         result.set_orientation(gtk4::Orientation::Horizontal);
         result.set_spacing(5);
         result.append(&label);
+        result.append(&legality_icon);
+        result.append(&omit_heightened_button);
         result.append(&remove_button);
         result.append(&count);
         result.append(&add_button);
         result.set_label(label);
+        result.set_legality_icon(legality_icon);
         result.set_count_label(count);
         result.set_add_button(add_button);
         result.set_remove_button(remove_button);
+        result.set_omit_heightened_button(omit_heightened_button);
         result
     }
 }
@@ -116,12 +136,17 @@ impl SelectedSpellRow {
 #[derive(Clone)]
 pub struct SelectedSpellCollection {
     model: gio::ListStore,
+    /// Spell list of the deck. Spells off it are flagged.
+    spell_list: Rc<Cell<Option<Tradition>>>,
 }
 
 impl SelectedSpellCollection {
     pub fn new() -> (SelectedSpellCollection, impl IsA<Widget>) {
         let model = gio::ListStore::new::<SelectedSpellModel>();
-        let result = Self { model };
+        let result = Self {
+            model,
+            spell_list: Rc::new(Cell::new(None)),
+        };
         let factory = result.setup_factory();
         let widget = result.build_widget(factory);
         (result, widget)
@@ -141,6 +166,100 @@ impl SelectedSpellCollection {
         result
     }
 
+    /// Card options set for individual selected spells, by spell id.
+    pub fn collect_overrides(&self) -> HashMap<usize, CardOverride> {
+        let mut result = HashMap::new();
+        let count = self.model.n_items();
+        for index in 0..count {
+            if let Some(spell_row) = self.model.item(index).and_downcast::<SelectedSpellModel>() {
+                let options = CardOverride {
+                    omit_heightened: spell_row.omit_heightened().then_some(true),
+                    ..CardOverride::default()
+                };
+                result.insert(spell_row.imp().spell().id, options);
+            }
+        }
+        result
+    }
+
+    /// Ids of selected spells with their counts.
+    pub fn spell_counts(&self) -> Vec<(usize, u32)> {
+        let mut result = vec![];
+        let count = self.model.n_items();
+        for index in 0..count {
+            if let Some(spell_row) = self.model.item(index).and_downcast::<SelectedSpellModel>() {
+                result.push((spell_row.imp().spell().id, spell_row.count()));
+            }
+        }
+        result
+    }
+
+    /// Selected spells with their counts and notes, for saving to interchange formats.
+    pub fn deck_entries(&self) -> Vec<DeckEntry> {
+        let mut result = vec![];
+        let count = self.model.n_items();
+        for index in 0..count {
+            if let Some(spell_row) = self.model.item(index).and_downcast::<SelectedSpellModel>() {
+                let spell = spell_row.imp().spell();
+                let mut entry = DeckEntry::new(&spell, spell_row.count(), spell_row.notes());
+                entry.omit_heightened = spell_row.omit_heightened();
+                result.push(entry);
+            }
+        }
+        result
+    }
+
+    /// Flag spells which are not on `spell_list`, or clear flags if `None`.
+    pub fn set_spell_list(&self, spell_list: Option<Tradition>) {
+        self.spell_list.set(spell_list);
+        // Rebind rows, so flags are updated.
+        let count = self.model.n_items();
+        self.model.items_changed(0, count, count);
+    }
+
+    /// Add spell `count` times, keeping from 1 to `MAX_CARD_COUNT` copies. Notes replace
+    /// existing ones, unless empty.
+    pub fn add_entry(&self, spell: Rc<Spell>, count: u32, notes: &str) {
+        let item = match self.spell_index(spell.as_ref()) {
+            Some(index) => {
+                let item = self
+                    .model
+                    .item(index)
+                    .and_downcast::<SelectedSpellModel>()
+                    .expect("Item must exist");
+                item.set_count(item.count().saturating_add(count).min(MAX_CARD_COUNT));
+                item
+            }
+            None => {
+                let item = SelectedSpellModel::new(spell);
+                item.set_count(count.clamp(1, MAX_CARD_COUNT));
+                self.model.append(&item);
+                item
+            }
+        };
+        if !notes.is_empty() {
+            item.set_notes(notes);
+        }
+    }
+
+    /// Replace selection with spells of opened deck.
+    pub fn replace_entries(&self, entries: Vec<(Rc<Spell>, DeckEntry)>) {
+        self.model.remove_all();
+        for (spell, entry) in entries {
+            self.add_deck_entry(spell, &entry);
+        }
+    }
+
+    /// Add spell of deck entry, with its count, notes and card options.
+    pub fn add_deck_entry(&self, spell: Rc<Spell>, entry: &DeckEntry) {
+        self.add_entry(spell.clone(), entry.count, &entry.notes);
+        if let Some(index) = self.spell_index(&spell) {
+            if let Some(item) = self.model.item(index).and_downcast::<SelectedSpellModel>() {
+                item.set_omit_heightened(entry.omit_heightened);
+            }
+        }
+    }
+
     pub fn add_spell(&self, spell: Rc<Spell>) {
         let index = self.spell_index(spell.as_ref());
         if let Some(index) = index {
@@ -149,7 +268,7 @@ impl SelectedSpellCollection {
                 .item(index)
                 .and_downcast::<SelectedSpellModel>()
                 .expect("Item must exist");
-            item.set_count(item.count() + 1);
+            item.set_count(item.count().saturating_add(1).min(MAX_CARD_COUNT));
         } else {
             self.model.append(&SelectedSpellModel::new(spell));
         }
@@ -226,6 +345,7 @@ impl SelectedSpellCollection {
                 collection_moved.add_spell(model.imp().spell());
             });
         });
+        let spell_list = self.spell_list.clone();
         factory.connect_bind(move |_, list_item| {
             let list_item = list_item
                 .downcast_ref::<gtk4::ListItem>()
@@ -240,13 +360,32 @@ impl SelectedSpellCollection {
                 .expect("Must be SelectedSpellRow");
             let label = child.label();
             let count_label = child.count_label();
+            let omit_heightened_button = child.omit_heightened_button();
 
-            label.set_text(&model.imp().spell().name);
+            let spell = model.imp().spell();
+            label.set_text(&spell.name);
+            let notes = model.notes();
+            label.set_tooltip_text((!notes.is_empty()).then_some(notes.as_str()));
+            let off_list = spell_list.get().filter(|list| !spell.is_on_list(*list));
+            let legality_icon = child.legality_icon();
+            legality_icon.set_visible(off_list.is_some());
+            legality_icon.set_tooltip_text(
+                off_list
+                    .map(|list| format!("Not on {} spell list", list.as_str()))
+                    .as_deref(),
+            );
+            omit_heightened_button.set_sensitive(spell.heightened.is_some());
             let binding = model
                 .bind_property("count", &count_label, "label")
                 .sync_create()
                 .build();
             child.set_binding(binding);
+            let binding = model
+                .bind_property("omit-heightened", &omit_heightened_button, "active")
+                .bidirectional()
+                .sync_create()
+                .build();
+            child.set_omit_heightened_binding(binding);
         });
         factory.connect_unbind(move |_, list_item| {
             let list_item = list_item
@@ -259,6 +398,9 @@ impl SelectedSpellCollection {
             if let Some(binding) = child.binding() {
                 binding.unbind();
             }
+            if let Some(binding) = child.omit_heightened_binding() {
+                binding.unbind();
+            }
         });
         factory
     }
@@ -266,13 +408,25 @@ impl SelectedSpellCollection {
     fn build_row_widget(&self) -> SelectedSpellRow {
         let label = gtk4::Label::new(None);
         let count_label = gtk4::Label::new(None);
+        let legality_icon = gtk4::Image::from_icon_name("dialog-warning-symbolic");
         let remove_button = gtk4::Button::builder()
             .icon_name("list-remove-symbolic")
             .build();
         let add_button = gtk4::Button::builder()
             .icon_name("list-add-symbolic")
             .build();
+        let omit_heightened_button = gtk4::ToggleButton::builder()
+            .label("H")
+            .tooltip_text("Omit heightened text")
+            .build();
 
-        SelectedSpellRow::new(label, count_label, add_button, remove_button)
+        SelectedSpellRow::new(
+            label,
+            legality_icon,
+            count_label,
+            add_button,
+            remove_button,
+            omit_heightened_button,
+        )
     }
 }
@@ -0,0 +1,30 @@
+use crate::spell::Spell;
+use gtk4::{prelude::*, Widget};
+
+/// Developer panel displaying parsed spell alongside json it was parsed from.
+#[derive(Clone)]
+pub struct SpellInspector {
+    buffer: gtk4::TextBuffer,
+}
+
+impl SpellInspector {
+    pub fn new() -> (Self, impl IsA<Widget>) {
+        let buffer = gtk4::TextBuffer::new(None);
+        let text_view = gtk4::TextView::builder()
+            .buffer(&buffer)
+            .editable(false)
+            .monospace(true)
+            .build();
+        let widget = gtk4::ScrolledWindow::builder()
+            .height_request(200)
+            .child(&text_view)
+            .build();
+        (Self { buffer }, widget)
+    }
+
+    pub fn show_spell(&self, spell: &Spell, raw_data: Option<String>) {
+        let raw_data = raw_data.unwrap_or_else(|| "<no source object>".to_string());
+        self.buffer
+            .set_text(&format!("{spell:#?}\n\n// Source object\n{raw_data}"));
+    }
+}
@@ -0,0 +1,22 @@
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use std::io::Read;
+use std::path::Path;
+
+/// Decompress gzip compressed utf-8 text.
+pub fn decompress(data: &[u8]) -> Result<String> {
+    let mut result = String::new();
+    GzDecoder::new(data)
+        .read_to_string(&mut result)
+        .context("Unable to decompress gzip data")?;
+    Ok(result)
+}
+
+/// Read text file, decompressing it if name ends with `.gz`.
+pub fn read_to_string(path: &Path) -> Result<String> {
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        decompress(&std::fs::read(path)?)
+    } else {
+        Ok(std::fs::read_to_string(path)?)
+    }
+}
@@ -0,0 +1,255 @@
+use crate::html::{actions_text, attribute, spell_type_name};
+use crate::spell::Spell;
+use anyhow::Result;
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use std::collections::HashSet;
+use std::io::Write;
+
+/// Output of `write_handout`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HandoutFormat {
+    /// Heading per spell, with bold labels and tables.
+    Markdown,
+    /// No markup, table cells separated by `|`.
+    Text,
+}
+
+/// Write `spells` as a document to read or print on plain paper: header with rank and action
+/// cost, traits, traditions, properties, text sections and source, built from spell fields.
+/// Nethys links keep only their text, and action icons are written out, like `Two Actions`.
+/// Spells selected several times are written once.
+pub fn write_handout<'a, T: Write>(
+    mut output: T,
+    format: HandoutFormat,
+    spells: impl IntoIterator<Item = &'a Spell>,
+) -> Result<()> {
+    let mut written = HashSet::new();
+    for spell in spells {
+        if !written.insert(spell.id) {
+            continue;
+        }
+        if written.len() > 1 {
+            match format {
+                HandoutFormat::Markdown => write!(output, "\n\n---\n\n")?,
+                HandoutFormat::Text => write!(output, "\n\n\n")?,
+            }
+        }
+        write!(output, "{}", spell_text(spell, format))?;
+    }
+    Ok(())
+}
+
+fn spell_text(spell: &Spell, format: HandoutFormat) -> String {
+    let mut header = vec![format!(
+        "{} {}",
+        spell_type_name(&spell.spell_type),
+        spell.level
+    )];
+    header.extend(actions_text(&spell.actions));
+    let header = header.join(", ");
+    let traditions = spell.traditions.names().join(", ");
+    let mut fields = vec![];
+    if !traditions.is_empty() {
+        fields.push(("Traditions", traditions));
+    }
+    fields.extend(spell.properties.iter().map(|property| {
+        let value = property.value.split_whitespace().collect::<Vec<_>>();
+        (property.name.as_str(), value.join(" "))
+    }));
+
+    let mut result = String::new();
+    match format {
+        HandoutFormat::Markdown => {
+            result += &format!("## {}\n\n**{header}**\n\n", spell.name);
+            if !spell.traits.is_empty() {
+                result += &format!("*{}*\n\n", spell.traits.join(", "));
+            }
+            let fields = fields
+                .iter()
+                .map(|(name, value)| format!("**{name}** {value}"))
+                .collect::<Vec<_>>();
+            if !fields.is_empty() {
+                // Trailing spaces break lines inside a paragraph.
+                result += &format!("{}\n\n", fields.join("  \n"));
+            }
+        }
+        HandoutFormat::Text => {
+            result += &format!("{}\n{header}\n", spell.name.to_uppercase());
+            if !spell.traits.is_empty() {
+                result += &format!("Traits: {}\n", spell.traits.join(", "));
+            }
+            for (name, value) in &fields {
+                result += &format!("{name}: {value}\n");
+            }
+            result += "\n";
+        }
+    }
+    let sections = std::iter::once(&spell.description)
+        .chain(&spell.heightened)
+        .chain(&spell.extras);
+    for section in sections {
+        result += &convert_markdown(section, format);
+        result += "\n\n";
+    }
+    if !spell.source.is_empty() {
+        match format {
+            HandoutFormat::Markdown => result += &format!("*Source: {}*\n", spell.source),
+            HandoutFormat::Text => result += &format!("Source: {}\n", spell.source),
+        }
+    }
+    result.trim_end().to_string()
+}
+
+/// Nethys markdown of a text section written again in `format`, without links and html.
+fn convert_markdown(markdown: &str, format: HandoutFormat) -> String {
+    let is_markdown = format == HandoutFormat::Markdown;
+    let markup = |text: &'static str| if is_markdown { text } else { "" };
+    // Nethys uses `<br />` to separate lines inside a paragraph.
+    let markdown = markdown.replace("<br />", "\n\n");
+    let mut result = String::new();
+    // Numbers of ordered lists, `None` for bullet ones, innermost last.
+    let mut lists = Vec::<Option<u64>>::new();
+    let mut head_cells = 0;
+    for event in Parser::new_ext(&markdown, Options::ENABLE_TABLES) {
+        match event {
+            Event::Text(text) if is_markdown => result += &escape_markdown(&text),
+            Event::Text(text) => result += &text,
+            Event::Code(text) if is_markdown => result += &format!("`{text}`"),
+            Event::Code(text) => result += &text,
+            Event::Html(html) | Event::InlineHtml(html) => result += &strip_html(&html, format),
+            Event::SoftBreak => result += " ",
+            Event::HardBreak => result += if is_markdown { "  \n" } else { "\n" },
+            Event::Rule => result += if is_markdown { "---\n\n" } else { "\n" },
+            Event::Start(Tag::Strong) | Event::End(TagEnd::Strong) => result += markup("**"),
+            Event::Start(Tag::Emphasis) | Event::End(TagEnd::Emphasis) => result += markup("*"),
+            Event::Start(Tag::Heading { .. }) => result += markup("#### "),
+            Event::End(TagEnd::Heading(_) | TagEnd::Paragraph) => result += "\n\n",
+            Event::Start(Tag::List(start)) => lists.push(start),
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+                result += "\n";
+            }
+            Event::Start(Tag::Item) => {
+                result += &"  ".repeat(lists.len().saturating_sub(1));
+                match lists.last_mut() {
+                    Some(Some(number)) => {
+                        result += &format!("{number}. ");
+                        *number += 1;
+                    }
+                    _ => result += "- ",
+                }
+            }
+            Event::End(TagEnd::Item) if !result.ends_with('\n') => result += "\n",
+            Event::Start(Tag::TableHead) => head_cells = 0,
+            Event::End(TagEnd::TableHead) => {
+                result += "\n";
+                if is_markdown {
+                    result += &format!("|{}\n", "---|".repeat(head_cells));
+                }
+            }
+            Event::Start(Tag::TableRow) | Event::End(TagEnd::TableRow)
+                if !result.ends_with('\n') =>
+            {
+                result += "\n";
+            }
+            Event::Start(Tag::TableCell) => {
+                head_cells += 1;
+                result += if is_markdown || !result.ends_with('\n') {
+                    "| "
+                } else {
+                    ""
+                };
+            }
+            Event::End(TagEnd::TableCell) => result += " ",
+            Event::End(TagEnd::Table) => {
+                result += if is_markdown { "|\n\n" } else { "\n\n" };
+            }
+            _ => {}
+        }
+    }
+    // Rows of markdown tables end with a cell border.
+    let result = if is_markdown {
+        result.replace(" \n|", " |\n|")
+    } else {
+        result
+    };
+    collapse_blank_lines(&result)
+}
+
+/// `text` with characters markdown would read as markup escaped by backslash, so literal
+/// asterisks, brackets and pipes stay text, and tables keep their cells.
+fn escape_markdown(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Text of html, with Nethys action icons written out and table cells separated by `|`.
+fn strip_html(html: &str, format: HandoutFormat) -> String {
+    let mut result = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        result += &rest[..start];
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        let closing = tag.starts_with('/');
+        match name.as_str() {
+            "actions" => {
+                if let Some(actions) = attribute(tag, "string") {
+                    match format {
+                        HandoutFormat::Markdown => result += &format!("**{actions}**"),
+                        HandoutFormat::Text => result += &format!("[{actions}]"),
+                    }
+                }
+            }
+            "td" | "th" if closing => result += " | ",
+            "tr" | "p" | "li" if closing => result += "\n",
+            "br" => result += "\n",
+            _ => {}
+        }
+        rest = &rest[start + end + 1..];
+    }
+    result += rest;
+    result
+}
+
+/// Text with trailing spaces of lines, except markdown line breaks, and runs of blank lines
+/// removed.
+fn collapse_blank_lines(text: &str) -> String {
+    let mut result = String::new();
+    let mut blank = false;
+    for line in text.trim().lines() {
+        let line = if line.ends_with("  ") && !line.trim().is_empty() {
+            line
+        } else {
+            line.trim_end()
+        };
+        if line.is_empty() {
+            blank = true;
+            continue;
+        }
+        if blank && !result.is_empty() {
+            result += "\n";
+        }
+        blank = false;
+        result += line;
+        result += "\n";
+    }
+    result.trim_end_matches('\n').to_string()
+}
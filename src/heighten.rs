@@ -0,0 +1,153 @@
+use crate::spell::Spell;
+
+const ENTRY_PREFIX: &str = "**Heightened (";
+
+/// Rank condition of a heightened entry.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Step {
+    /// `(+2)`: applied once for every 2 ranks above spell rank.
+    Every(u8),
+    /// `(6th)`: applied at rank 6 and above.
+    At(u8),
+}
+
+/// Heightened entry of a spell, as listed on heightened summary cards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeightenedEntry {
+    pub spell: String,
+    /// Rank condition as printed, like `+1` or `6th`.
+    pub label: String,
+    /// Markdown text after the label, like `The damage increases by 2d6.`
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    step: Step,
+    /// Text after the label, like ` The damage increases by 2d6.`
+    text: String,
+}
+
+/// Copy of `spell` cast at `rank`: header shows `rank`, and heightened section keeps only
+/// entries in effect at it, with `+N` entries marked with number of times they apply.
+///
+/// `None` if no heightened entry is in effect at `rank`, so the card is the same.
+pub fn heighten(spell: &Spell, rank: u8) -> Option<Spell> {
+    let heightened = spell.heightened.as_deref()?;
+    let (before, entries) = parse_entries(heightened);
+    let above = rank.checked_sub(spell.level).filter(|above| *above > 0)?;
+    let mut applied = vec![];
+    for entry in entries {
+        let label = match entry.step {
+            Step::Every(step) if step > 0 && above >= step => {
+                format!("Heightened (+{step}) ×{}", above / step)
+            }
+            Step::At(at) if at <= rank => format!("Heightened ({})", ordinal(at)),
+            _ => continue,
+        };
+        applied.push(format!("**{label}**{}", entry.text));
+    }
+    if applied.is_empty() {
+        return None;
+    }
+    let mut result = spell.clone();
+    result.level = rank;
+    let text = applied.join("\n\n");
+    result.heightened = Some(match before.trim() {
+        "" => text,
+        before => format!("{before}\n\n{text}"),
+    });
+    Some(result)
+}
+
+/// Heightened entries of `spell`, empty if it has none. Text before the first entry is
+/// left out.
+pub fn heightened_entries(spell: &Spell) -> Vec<HeightenedEntry> {
+    let Some(heightened) = spell.heightened.as_deref() else {
+        return vec![];
+    };
+    parse_entries(heightened)
+        .1
+        .into_iter()
+        .map(|entry| HeightenedEntry {
+            spell: spell.name.clone(),
+            label: match entry.step {
+                Step::Every(step) => format!("+{step}"),
+                Step::At(at) => ordinal(at),
+            },
+            text: entry.text.trim().to_string(),
+        })
+        .collect()
+}
+
+/// Names of spells, which cards differ between casting at rank `from` and `to`, like
+/// `Fireball: rank 3 → 5`. Spells are listed once, in given order.
+pub fn changed_cards<'a>(
+    spells: impl IntoIterator<Item = &'a Spell>,
+    from: Option<u8>,
+    to: Option<u8>,
+) -> Vec<String> {
+    let cast = |spell: &Spell, rank: Option<u8>| {
+        rank.and_then(|rank| heighten(spell, rank))
+            .map(|spell| (spell.level, spell.heightened))
+            .unwrap_or_else(|| (spell.level, spell.heightened.clone()))
+    };
+    let mut result: Vec<String> = vec![];
+    for spell in spells {
+        let (old_rank, old_text) = cast(spell, from);
+        let (new_rank, new_text) = cast(spell, to);
+        let line = if old_rank != new_rank {
+            format!("{}: rank {old_rank} → {new_rank}", spell.name)
+        } else if old_text != new_text {
+            format!("{}: heightened text", spell.name)
+        } else {
+            continue;
+        };
+        if !result.contains(&line) {
+            result.push(line);
+        }
+    }
+    result
+}
+
+/// Split heightened section into text before first entry and entries.
+fn parse_entries(heightened: &str) -> (&str, Vec<Entry>) {
+    let mut parts = heightened.split(ENTRY_PREFIX);
+    let before = parts.next().unwrap_or_default();
+    let mut entries = vec![];
+    for part in parts {
+        let Some((label, text)) = part.split_once(")**") else {
+            continue;
+        };
+        let Some(step) = parse_step(label.trim()) else {
+            continue;
+        };
+        entries.push(Entry {
+            step,
+            text: text.trim_end().to_string(),
+        });
+    }
+    (before, entries)
+}
+
+/// Parse `+1` or `6th` label.
+fn parse_step(label: &str) -> Option<Step> {
+    match label.strip_prefix('+') {
+        Some(step) => step.parse().ok().map(Step::Every),
+        None => label
+            .trim_end_matches(char::is_alphabetic)
+            .parse()
+            .ok()
+            .map(Step::At),
+    }
+}
+
+fn ordinal(rank: u8) -> String {
+    let suffix = match rank {
+        1 => "st",
+        2 => "nd",
+        3 => "rd",
+        _ => "th",
+    };
+    format!("{rank}{suffix}")
+}
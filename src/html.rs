@@ -0,0 +1,138 @@
+use crate::spell::{Actions, Spell, SpellType};
+use pulldown_cmark::{html, Event, Parser, Tag, TagEnd};
+use std::fmt::Write as _;
+
+/// Tags kept in output. Rest of markup is dropped, leaving only text inside it.
+const ALLOWED_TAGS: &[&str] = &[
+    "b", "br", "em", "h3", "h4", "hr", "i", "li", "ol", "p", "strong", "sup", "table", "td", "th",
+    "tr", "ul",
+];
+
+/// Spell as html document with the same content as a card: header, traits, properties and
+/// text sections. Markup is limited to tags virtual tabletops keep in their notes.
+pub fn spell_html(spell: &Spell) -> String {
+    let mut result = String::new();
+    let _ = writeln!(result, "<h3>{}</h3>", escape(&spell.name));
+    let mut header = vec![format!(
+        "{} {}",
+        spell_type_name(&spell.spell_type),
+        spell.level
+    )];
+    if let Some(actions) = actions_text(&spell.actions) {
+        header.push(actions);
+    }
+    let _ = writeln!(result, "<p><b>{}</b></p>", escape(&header.join(", ")));
+    if !spell.traits.is_empty() {
+        let _ = writeln!(result, "<p><i>{}</i></p>", escape(&spell.traits.join(", ")));
+    }
+    let traditions = spell.traditions.names();
+    if !traditions.is_empty() {
+        let _ = writeln!(
+            result,
+            "<p><b>Traditions</b> {}</p>",
+            escape(&traditions.join(", "))
+        );
+    }
+    for property in &spell.properties {
+        let _ = writeln!(
+            result,
+            "<p><b>{}</b> {}</p>",
+            escape(&property.name),
+            escape(&property.value)
+        );
+    }
+    let sections = std::iter::once(&spell.description)
+        .chain(&spell.heightened)
+        .chain(&spell.extras);
+    for section in sections {
+        result.push_str("<hr>\n");
+        push_markdown(&mut result, section);
+    }
+    if !spell.source.is_empty() {
+        let _ = writeln!(result, "<p><i>Source: {}</i></p>", escape(&spell.source));
+    }
+    result
+}
+
+fn push_markdown(output: &mut String, markdown: &str) {
+    // Nethys uses `<br />` to separate lines inside a paragraph.
+    let markdown = markdown.replace("<br />", "\n\n");
+    let events = Parser::new(&markdown).map(|event| match event {
+        // Links point to nethys pages, so only their text is kept, like on cards.
+        Event::Start(Tag::Link { .. }) => Event::Start(Tag::Emphasis),
+        Event::End(TagEnd::Link) => Event::End(TagEnd::Emphasis),
+        Event::Html(html) => Event::Html(sanitize_html(&html).into()),
+        Event::InlineHtml(html) => Event::InlineHtml(sanitize_html(&html).into()),
+        event => event,
+    });
+    html::push_html(output, events);
+}
+
+/// Remove tags missing from `ALLOWED_TAGS`, along with their attributes.
+/// Nethys `<actions string="Two Actions" />` is replaced by its text in bold.
+fn sanitize_html(html: &str) -> String {
+    let mut result = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        result.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        if name == "actions" {
+            if let Some(actions) = attribute(tag, "string") {
+                let _ = write!(result, "<b>{}</b>", escape(actions));
+            }
+        } else if ALLOWED_TAGS.contains(&name.as_str()) {
+            let _ = write!(result, "<{}{name}>", if closing { "/" } else { "" });
+        }
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Value of attribute `name` in tag source, like `actions string="Reaction" /`.
+pub fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let start = tag.find(&format!("{name}=\""))? + name.len() + 2;
+    let length = tag[start..].find('"')?;
+    Some(&tag[start..start + length])
+}
+
+/// Spell type as written in headers like `Cantrip 1`.
+pub fn spell_type_name(spell_type: &SpellType) -> &'static str {
+    match spell_type {
+        SpellType::Spell => "Spell",
+        SpellType::Focus => "Focus",
+        SpellType::Cantrip => "Cantrip",
+    }
+}
+
+/// Action cost in words, like `2 actions`. `None` if spell lists no cost.
+pub fn actions_text(actions: &Actions) -> Option<String> {
+    let plural = |count: u8| if count == 1 { "action" } else { "actions" };
+    let text = match actions {
+        Actions::Number(count) => format!("{count} {}", plural(*count)),
+        Actions::Range(from, to) => format!("{from} to {to} {}", plural(*to)),
+        Actions::Reaction => "reaction".to_string(),
+        Actions::FreeAction => "free action".to_string(),
+        Actions::Other(text) if text.trim().is_empty() => return None,
+        Actions::Other(text) => text.clone(),
+    };
+    Some(text)
+}
+
+/// Text with characters having special meaning in html replaced by entities.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
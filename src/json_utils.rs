@@ -13,6 +13,30 @@ impl TypedParse for u8 {
     }
 }
 
+impl TypedParse for u32 {
+    fn parse(object: &JsonValue) -> Result<Self> {
+        object
+            .as_u32()
+            .ok_or_else(|| anyhow!("Wrong type: expected `u32`"))
+    }
+}
+
+impl TypedParse for f32 {
+    fn parse(object: &JsonValue) -> Result<Self> {
+        object
+            .as_f32()
+            .ok_or_else(|| anyhow!("Wrong type: expected `number`"))
+    }
+}
+
+impl TypedParse for bool {
+    fn parse(object: &JsonValue) -> Result<Self> {
+        object
+            .as_bool()
+            .ok_or_else(|| anyhow!("Wrong type: expected `bool`"))
+    }
+}
+
 impl TypedParse for String {
     fn parse(object: &JsonValue) -> Result<Self> {
         object
@@ -0,0 +1,76 @@
+//! Spell database, card layout and export. The GTK application in `main.rs` is built on
+//! top of it, and benchmarks use it directly.
+//!
+//! Main parts for other tools:
+//!
+//! - `db`: `SimpleSpellDB` with built-in spells of a game system and loaded bundles,
+//!   searched through `SpellDB` trait.
+//! - `spell`: `Spell` as parsed from Archives of Nethys data.
+//! - `render`: `build_spell_scene` lays out a single card, `write_to_pdf` exports a deck,
+//!   and `Renderer` does it reporting progress, all configured by `RenderOptions`.
+//! - `error`: `ParseError`, `LayoutError` and `ExportError` returned by the above, naming
+//!   spell and field a problem is about.
+//! - `rich_text`: `SceneBuilder` placing styled text into boxes, and the resulting `Scene`.
+//!
+//! ```no_run
+//! use spellcard_generator::db::{SimpleSpellDB, SpellDB};
+//! use spellcard_generator::render::{write_to_pdf, RenderOptions};
+//! use spellcard_generator::system::Pathfinder2e;
+//! use std::rc::Rc;
+//!
+//! let db = SimpleSpellDB::new(Rc::new(Pathfinder2e))?;
+//! let spells = db.find_by_name("Fireball");
+//! let output = std::fs::File::create("fireball.pdf")?;
+//! write_to_pdf(output, spells.iter().map(|spell| &**spell), &RenderOptions::default())?;
+//! # anyhow::Ok(())
+//! ```
+//!
+//! Features: `gtk` (default) builds the application window, `cairo` adds `cairo_render`
+//! drawing scenes with cairo, and `serve` adds local http api. Without them neither GTK nor
+//! cairo libraries are needed.
+
+pub mod abbreviations;
+pub mod anki;
+pub mod app_config;
+pub mod attribution;
+pub mod batch;
+#[cfg(feature = "cairo")]
+pub mod cairo_render;
+pub mod caster_actions;
+pub mod config;
+pub mod crash;
+pub mod csv;
+pub mod db;
+pub mod deck;
+pub mod discord;
+pub mod error;
+pub mod foundry;
+pub mod fuzzy;
+pub mod glossary;
+pub mod gzip;
+pub mod handout;
+pub mod heighten;
+pub mod html;
+pub mod json_utils;
+pub mod lint;
+pub mod logging;
+pub mod markdown;
+pub mod network;
+pub mod options_chain;
+pub mod output;
+pub mod profiles;
+pub mod references;
+pub mod render;
+pub mod rich_text;
+pub mod roll20;
+pub mod scene_diff;
+pub mod search_syntax;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod spell;
+pub mod subset;
+pub mod sync;
+pub mod system;
+pub mod templates;
+pub mod validate;
+pub mod watch;
@@ -0,0 +1,207 @@
+use crate::spell::Spell;
+use std::collections::{BTreeSet, HashSet};
+
+/// Characters listed in a finding about unusual ones, further ones are counted.
+const LISTED_CHARACTERS: usize = 5;
+
+/// Likely problem of a card, found before export.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Rule {
+    /// More traits than `LintLimits::max_traits`, which wrap to another line.
+    ManyTraits,
+    /// Description longer than `LintLimits::max_description_words`, which needs a double
+    /// card or gets cut.
+    LongDescription,
+    /// Tables are printed as lines of cells separated by `|`.
+    Table,
+    /// Characters missing from card fonts, printed as empty boxes, or invisible ones.
+    UnusualCharacters,
+}
+
+impl Rule {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Rule::ManyTraits => "Many traits",
+            Rule::LongDescription => "Long description",
+            Rule::Table => "Table",
+            Rule::UnusualCharacters => "Unusual characters",
+        }
+    }
+
+    /// Options of the app which help, or how to change the text.
+    pub fn suggestion(&self) -> &'static str {
+        match self {
+            Rule::ManyTraits => {
+                "Turn off `Trait pills` under `Export profile`, plain traits take less room"
+            }
+            Rule::LongDescription => {
+                "Turn on `Abbreviate text`, `Leave out heightened entries` or `Leave out extras`, \
+                or let long spells be cut with a note to Nethys under `Export profile`"
+            }
+            Rule::Table => "Rewrite the table as a list in card text editor under preview",
+            Rule::UnusualCharacters => {
+                "Replace them in card text editor under preview, or in the bundle file"
+            }
+        }
+    }
+}
+
+/// Thresholds of rules counting things on a card.
+#[derive(Copy, Clone, Debug)]
+pub struct LintLimits {
+    pub max_traits: usize,
+    pub max_description_words: usize,
+}
+
+impl Default for LintLimits {
+    fn default() -> Self {
+        Self {
+            max_traits: 7,
+            max_description_words: 250,
+        }
+    }
+}
+
+/// Rule broken by a spell, with details of what was found.
+#[derive(Clone, Debug)]
+pub struct Finding {
+    pub spell: String,
+    pub rule: Rule,
+    pub explanation: String,
+}
+
+/// Findings for `spells`, in their order. Spells selected several times are checked once.
+pub fn lint_spells<'a>(
+    spells: impl IntoIterator<Item = &'a Spell>,
+    limits: &LintLimits,
+) -> Vec<Finding> {
+    let mut checked = HashSet::new();
+    spells
+        .into_iter()
+        .filter(|spell| checked.insert(spell.id))
+        .flat_map(|spell| lint_spell(spell, limits))
+        .collect()
+}
+
+pub fn lint_spell(spell: &Spell, limits: &LintLimits) -> Vec<Finding> {
+    let mut findings = vec![];
+    let mut found = |rule, explanation| {
+        findings.push(Finding {
+            spell: spell.name.clone(),
+            rule,
+            explanation,
+        })
+    };
+    if spell.traits.len() > limits.max_traits {
+        found(
+            Rule::ManyTraits,
+            format!(
+                "{} traits, more than {} fit on one line",
+                spell.traits.len(),
+                limits.max_traits
+            ),
+        );
+    }
+    let words = word_count(&spell.description);
+    if words > limits.max_description_words {
+        found(
+            Rule::LongDescription,
+            format!(
+                "Description has {words} words, cards fit about {}",
+                limits.max_description_words
+            ),
+        );
+    }
+    let sections = || {
+        [&spell.description, &spell.summary]
+            .into_iter()
+            .chain(&spell.heightened)
+            .chain(&spell.extras)
+    };
+    if sections().any(|text| has_table(text)) {
+        found(
+            Rule::Table,
+            "Table is printed as lines of cells separated by `|`, without columns".to_string(),
+        );
+    }
+    let unusual = sections()
+        .chain([&spell.name])
+        .chain(&spell.traits)
+        .flat_map(|text| text.chars())
+        .filter(|c| is_unusual(*c))
+        .collect::<BTreeSet<_>>();
+    if !unusual.is_empty() {
+        let mut listed = unusual
+            .iter()
+            .take(LISTED_CHARACTERS)
+            .map(|&c| {
+                if is_invisible(c) {
+                    format!("invisible U+{:04X}", c as u32)
+                } else {
+                    format!("`{c}` (U+{:04X})", c as u32)
+                }
+            })
+            .collect::<Vec<_>>();
+        if unusual.len() > LISTED_CHARACTERS {
+            listed.push(format!("{} more", unusual.len() - LISTED_CHARACTERS));
+        }
+        found(
+            Rule::UnusualCharacters,
+            format!("{} print as empty boxes or not at all", listed.join(", ")),
+        );
+    }
+    findings
+}
+
+/// Words of spell `text`, without html tags.
+fn word_count(text: &str) -> usize {
+    let mut in_tag = false;
+    let plain = text
+        .chars()
+        .map(|c| match c {
+            '<' => {
+                in_tag = true;
+                ' '
+            }
+            '>' if in_tag => {
+                in_tag = false;
+                ' '
+            }
+            _ if in_tag => ' ',
+            _ => c,
+        })
+        .collect::<String>();
+    plain
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+/// Html table, as in built-in spell data, or markdown one, as in bundles.
+fn has_table(text: &str) -> bool {
+    text.contains("<table") || text.lines().any(|line| line.trim_start().starts_with('|'))
+}
+
+/// Character missing from card fonts, approximately, or invisible one.
+fn is_unusual(c: char) -> bool {
+    if is_invisible(c) {
+        return true;
+    }
+    match c {
+        '\n' | '\r' | '\t' | ' '..='~' => false,
+        // Latin, Greek and Cyrillic letters with punctuation.
+        '\u{a0}'..='\u{24f}' | '\u{370}'..='\u{4ff}' => false,
+        // Punctuation, super- and subscripts, currencies and letterlike symbols.
+        '\u{2000}'..='\u{20bf}' | '\u{2100}'..='\u{218f}' => false,
+        '\u{2212}' | '\u{221e}' | '\u{2248}' | '\u{2260}' | '\u{2264}' | '\u{2265}' => false,
+        '\u{fb01}' | '\u{fb02}' => false,
+        _ => true,
+    }
+}
+
+/// Control characters, other than line breaks and tabs, and zero width and direction marks.
+fn is_invisible(c: char) -> bool {
+    let mark =
+        matches!(c, '\u{200b}'..='\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2060}'..='\u{206f}');
+    mark || (c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+}
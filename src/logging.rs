@@ -0,0 +1,48 @@
+use crate::config;
+use std::path::PathBuf;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+
+/// Log files are named like `spellcards.log.2024-05-01`.
+const LOG_FILE_PREFIX: &str = "spellcards.log";
+/// Number of daily log files kept, older ones are removed on rotation.
+const MAX_LOG_FILES: usize = 7;
+
+/// Log to stderr and to a daily rotated file in `config::logs_dir`. Level is `info`, or
+/// `debug` if `debug` is set, and can be overridden by `RUST_LOG` environment variable.
+///
+/// If log file cannot be created, logging goes to stderr only.
+pub fn init(debug: bool) {
+    let default_level = if debug { "debug" } else { "info" };
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_level));
+    let file = config::logs_dir().and_then(|dir| {
+        std::fs::create_dir_all(&dir).ok()?;
+        RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(LOG_FILE_PREFIX)
+            .max_log_files(MAX_LOG_FILES)
+            .build(dir)
+            .ok()
+    });
+    let file_layer = file.map(|file| fmt::layer().with_ansi(false).with_writer(file));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(file_layer)
+        .init();
+}
+
+/// Most recent log file, if any was written.
+pub fn latest_log_file() -> Option<PathBuf> {
+    std::fs::read_dir(config::logs_dir()?)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(LOG_FILE_PREFIX))
+        })
+        .max()
+}
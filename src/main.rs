@@ -1,19 +1,147 @@
 #![windows_subsystem = "windows"]
 
-mod db;
+mod cli;
+#[cfg(feature = "gtk")]
 mod gtk;
-mod json_utils;
-mod markdown;
-mod render;
-mod rich_text;
-mod spell;
 
+// Non-GTK modules live in the library, imported here so `crate::` paths keep working.
+use spellcard_generator::{
+    app_config, batch, config, crash, csv, db, deck, handout, logging, output, profiles, render,
+    search_syntax, spell, system, validate, watch,
+};
+
+#[cfg(feature = "gtk")]
+use spellcard_generator::{
+    abbreviations, anki, cairo_render, discord, foundry, fuzzy, glossary, heighten, lint, network,
+    options_chain, rich_text, roll20, sync, templates,
+};
+
+#[cfg(feature = "serve")]
+use spellcard_generator::serve;
+
+use crate::app_config::AppConfig;
+use crate::cli::{Cli, Command};
 use crate::db::SimpleSpellDB;
+#[cfg(feature = "gtk")]
 use crate::gtk::run_gtk_app;
+use crate::system::{all_systems, find_system, GameSystem};
+use clap::Parser;
+use std::rc::Rc;
 
 fn main() -> anyhow::Result<()> {
-    run_gtk_app(SimpleSpellDB::new(include_str!(
-        "../nethys_data/spells.json"
-    ))?);
-    Ok(())
+    #[cfg(windows)]
+    if std::env::args_os().len() > 1 {
+        attach_parent_console();
+    }
+    let cli = Cli::parse();
+    logging::init(cli.debug);
+    crash::install_hook();
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), "Starting");
+    let app_config = config::app_config_path()
+        .map(|path| AppConfig::load(&path))
+        .unwrap_or_else(|| Ok(AppConfig::default()))
+        .unwrap_or_else(|error| {
+            tracing::warn!("{error:#}. Using default settings.");
+            AppConfig::default()
+        });
+    let system = select_system(cli.system, app_config.system.clone());
+    if let Some(Command::Validate { file }) = &cli.command {
+        return cli::validate(&*system, file);
+    }
+    let mut db = SimpleSpellDB::new(system.clone())?;
+    db.set_extra_bundles(app_config.bundle.iter().cloned().collect());
+    match db.load_bundles(config::bundles_dir(&*system).as_deref()) {
+        Ok(reports) => reports
+            .iter()
+            .for_each(|report| tracing::info!("Loading bundle {report}")),
+        Err(error) => tracing::warn!("Unable to read bundles directory: {error}"),
+    }
+    match cli.command {
+        Some(Command::Render {
+            input,
+            output,
+            dry_run,
+        }) => {
+            let profile = cli.profile.as_deref();
+            return match output {
+                Some(output) if !dry_run => cli::render(&db, &app_config, &input, &output, profile),
+                _ => cli::dry_run(&db, &app_config, &input, profile),
+            };
+        }
+        Some(Command::Search {
+            name,
+            rank,
+            tradition,
+            format,
+        }) => {
+            return cli::search(&db, name.as_deref(), rank, &tradition, format);
+        }
+        Some(Command::Export {
+            input,
+            output,
+            format,
+        }) => {
+            return cli::export(&db, &input, output.as_deref(), format);
+        }
+        Some(Command::Batch { manifest, combined }) => {
+            let profile = cli.profile.as_deref();
+            return cli::batch(&db, &app_config, &manifest, combined.as_deref(), profile);
+        }
+        Some(Command::Watch { deck, output }) => {
+            let output = output.unwrap_or_else(|| deck.with_extension("pdf"));
+            let bundles_dir = config::bundles_dir(&*system);
+            return watch::watch(&mut db, &app_config, bundles_dir.as_deref(), &deck, &output);
+        }
+        Some(Command::Validate { .. }) | None => {}
+    }
+    #[cfg(feature = "serve")]
+    if let Some(address) = cli.serve {
+        return serve::serve(
+            &db,
+            &app_config,
+            address.as_deref().unwrap_or(serve::DEFAULT_ADDRESS),
+            cli.serve_origin.as_deref(),
+        );
+    }
+    #[cfg(not(feature = "serve"))]
+    if cli.serve.is_some() {
+        anyhow::bail!("Http api is only available in builds with `serve` feature");
+    }
+    #[cfg(not(feature = "gtk"))]
+    anyhow::bail!("Application window is only available in builds with `gtk` feature, see `--help` for subcommands");
+    #[cfg(feature = "gtk")]
+    {
+        run_gtk_app(db, app_config, cli.debug, cli.profile);
+        Ok(())
+    }
+}
+
+/// System given in command line, or `saved` in settings, or the default one.
+fn select_system(requested: Option<String>, saved: Option<String>) -> Rc<dyn GameSystem> {
+    requested
+        .or(saved)
+        .and_then(|name| {
+            let system = find_system(&name);
+            if system.is_none() {
+                tracing::warn!("Unknown game system `{name}`");
+            }
+            system
+        })
+        .unwrap_or_else(|| all_systems().remove(0))
+}
+
+/// Print to console of the shell program is started from. Windows builds are gui programs,
+/// which get no console of their own, so output of subcommands, `--help` and `--dry-run` would
+/// be lost. Does nothing if started without one, like from file explorer.
+#[cfg(windows)]
+fn attach_parent_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // Failure only means there is no console to print to.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
 }
@@ -1,4 +1,5 @@
-use crate::rich_text::{Font, SceneBuilder};
+use crate::references::link_kind;
+use crate::rich_text::{BoxStyle, Font, SceneBuilder};
 use pulldown_cmark::{Event, Parser, Tag, TagEnd};
 use xml::reader::{EventReader, XmlEvent};
 
@@ -7,23 +8,155 @@ pub struct MdConfig<'a, T> {
     pub text_font: &'a Font<T>,
     pub bold_font: &'a Font<T>,
     pub italic_font: &'a Font<T>,
+    pub degree_style: DegreeStyle,
+    /// Indent of wrapped lines in degree of success entries.
+    pub hanging_indent: f32,
+}
+
+/// How `**Critical Success** ...` like paragraphs are laid out.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DegreeStyle {
+    /// Same as any other paragraph.
+    Plain,
+    /// Bold label with hanging indent for wrapped lines.
+    Compact,
+    /// Same as `Compact`, but labels are abbreviated (`Crit Succ`).
+    Abbreviated,
+    /// Same as `Compact`, with consecutive entries framed in a box, like statblock sidebars.
+    Boxed,
+}
+
+impl DegreeStyle {
+    pub const ALL: [DegreeStyle; 4] = [
+        DegreeStyle::Plain,
+        DegreeStyle::Compact,
+        DegreeStyle::Abbreviated,
+        DegreeStyle::Boxed,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DegreeStyle::Plain => "plain",
+            DegreeStyle::Compact => "compact",
+            DegreeStyle::Abbreviated => "abbreviated",
+            DegreeStyle::Boxed => "boxed",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|style| style.as_str() == name)
+    }
+}
+
+/// Space between frame of `DegreeStyle::Boxed` and entries in it, in `Pt`.
+const DEGREE_BOX_PADDING: f32 = 2.0;
+
+#[derive(Copy, Clone)]
+enum Degree {
+    CriticalSuccess,
+    Success,
+    Failure,
+    CriticalFailure,
+}
+
+impl Degree {
+    const ALL: [Degree; 4] = [
+        Degree::CriticalSuccess,
+        Degree::Success,
+        Degree::Failure,
+        Degree::CriticalFailure,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Degree::CriticalSuccess => "Critical Success",
+            Degree::Success => "Success",
+            Degree::Failure => "Failure",
+            Degree::CriticalFailure => "Critical Failure",
+        }
+    }
+
+    fn abbreviation(self) -> &'static str {
+        match self {
+            Degree::CriticalSuccess => "Crit Succ",
+            Degree::Success => "Succ",
+            Degree::Failure => "Fail",
+            Degree::CriticalFailure => "Crit Fail",
+        }
+    }
+
+    /// Split line like `**Success** The target is unaffected.` into degree and rest of the line.
+    fn split_label(line: &str) -> Option<(Self, &str)> {
+        let line = line.trim_start();
+        Self::ALL.into_iter().find_map(|degree| {
+            line.strip_prefix("**")?
+                .strip_prefix(degree.label())?
+                .strip_prefix("**")
+                .map(|rest| (degree, rest))
+        })
+    }
 }
 
 impl<'a, T> SceneBuilder<'a, T> {
-    pub fn add_markdown(&mut self, config: &MdConfig<'a, T>, markdown: &'a str) -> &mut Self {
+    pub fn add_markdown(&mut self, config: &MdConfig<'a, T>, markdown: &str) -> &mut Self {
         let mut tag_stack = vec![];
 
-        let mut iter = markdown.split("\n\n").flat_map(|s| s.split("<br />"));
-        let mut update_fn = |event| self.add_event(config, &mut tag_stack, event);
-        traverse_markdown(iter.next().unwrap(), &mut update_fn);
-        for line in iter {
-            self.finish_line();
-            let mut update_fn = |event| self.add_event(config, &mut tag_stack, event);
-            traverse_markdown(line, &mut update_fn);
+        let mut lines = markdown
+            .split("\n\n")
+            .flat_map(|s| s.split("<br />"))
+            .peekable();
+        let mut first = true;
+        while let Some(line) = lines.next() {
+            if !first {
+                self.finish_line();
+            }
+            first = false;
+            match Degree::split_label(line) {
+                Some(entry) if config.degree_style == DegreeStyle::Boxed => {
+                    let mut entries = vec![entry];
+                    while let Some(entry) = lines.peek().copied().and_then(Degree::split_label) {
+                        entries.push(entry);
+                        lines.next();
+                    }
+                    self.add_sub_box(None, DEGREE_BOX_PADDING, BoxStyle::default(), |builder| {
+                        for (degree, rest) in entries {
+                            builder.add_degree_of_success(config, &mut tag_stack, degree, rest);
+                        }
+                    });
+                }
+                Some((degree, rest)) if config.degree_style != DegreeStyle::Plain => {
+                    self.add_degree_of_success(config, &mut tag_stack, degree, rest);
+                }
+                _ => {
+                    let mut update_fn = |event| self.add_event(config, &mut tag_stack, event);
+                    traverse_markdown(line, &mut update_fn);
+                }
+            }
         }
         self
     }
 
+    fn add_degree_of_success(
+        &mut self,
+        config: &MdConfig<'a, T>,
+        font_stack: &mut Vec<&'a Font<T>>,
+        degree: Degree,
+        rest: &str,
+    ) {
+        let label = match config.degree_style {
+            DegreeStyle::Abbreviated => degree.abbreviation(),
+            _ => degree.label(),
+        };
+        let font = self.get_font();
+        self.set_font(config.bold_font)
+            .add_text(label)
+            .set_font(font)
+            .set_indent(config.hanging_indent);
+        let mut update_fn = |event| self.add_event(config, font_stack, event);
+        traverse_markdown(rest, &mut update_fn);
+        self.finish_line().set_indent(0.0);
+    }
+
     fn add_event(
         &mut self,
         config: &MdConfig<'a, T>,
@@ -66,6 +199,8 @@ enum EmpasisTag {
 }
 
 fn traverse_markdown(markdown: &str, event_listener: &mut impl FnMut(MixedEvent)) {
+    // Whether each open link is a cross-reference, shown in italic.
+    let mut links = vec![];
     for event in Parser::new(markdown) {
         match event {
             Event::HardBreak | Event::SoftBreak => {
@@ -74,9 +209,14 @@ fn traverse_markdown(markdown: &str, event_listener: &mut impl FnMut(MixedEvent)
             Event::Text(text) => {
                 event_listener(MixedEvent::Text(text.into_string()));
             }
-            Event::Start(Tag::Link { title, .. }) => {
-                event_listener(MixedEvent::StartStyle(EmpasisTag::Italic));
-                event_listener(MixedEvent::Text(title.into_string()));
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                let is_reference = link_kind(&dest_url).is_some();
+                if is_reference {
+                    event_listener(MixedEvent::StartStyle(EmpasisTag::Italic));
+                }
+                links.push(is_reference);
+            }
+            Event::End(TagEnd::Link) if links.pop() == Some(true) => {
                 event_listener(MixedEvent::EndStyle);
             }
             Event::Start(Tag::Strong) => {
@@ -97,7 +237,12 @@ fn traverse_markdown(markdown: &str, event_listener: &mut impl FnMut(MixedEvent)
 }
 
 fn traverse_html(html: &[u8], event_listener: &mut impl FnMut(MixedEvent)) {
-    for event in EventReader::new(html).into_iter().filter_map(|x| x.ok()) {
+    let events = EventReader::new(html).into_iter().filter_map(|event| {
+        event
+            .map_err(|error| tracing::debug!("Skipping malformed html in spell text: {error}"))
+            .ok()
+    });
+    for event in events {
         match &event {
             XmlEvent::Characters(characters) => {
                 traverse_markdown(characters, event_listener);
@@ -0,0 +1,51 @@
+use crate::app_config::AppConfig;
+use anyhow::{bail, Result};
+use std::io::Read;
+
+/// Body of a successful request.
+pub struct Response {
+    /// Body size, if server reports it.
+    pub length: Option<u64>,
+    pub body: Box<dyn Read + Send>,
+}
+
+/// Every networked feature goes through this, so network access is only possible then
+/// user allowed it in settings, see `from_settings`.
+pub trait Network: Send + Sync {
+    fn get(&self, url: &str) -> Result<Response>;
+}
+
+/// Network over http(s).
+pub struct HttpNetwork;
+
+/// Network refusing every request. Used then network access is not allowed, and by code
+/// which must not touch network.
+pub struct OfflineNetwork;
+
+impl Network for HttpNetwork {
+    fn get(&self, url: &str) -> Result<Response> {
+        let response = ureq::get(url).call()?;
+        let length = response
+            .header("Content-Length")
+            .and_then(|length| length.parse().ok());
+        Ok(Response {
+            length,
+            body: Box::new(response.into_reader()),
+        })
+    }
+}
+
+impl Network for OfflineNetwork {
+    fn get(&self, url: &str) -> Result<Response> {
+        bail!("Network access is disabled in settings, `{url}` was not requested")
+    }
+}
+
+/// Network according to settings: `OfflineNetwork` unless user allowed network access.
+pub fn from_settings(config: &AppConfig) -> Box<dyn Network> {
+    if config.network {
+        Box::new(HttpNetwork)
+    } else {
+        Box::new(OfflineNetwork)
+    }
+}
@@ -0,0 +1,58 @@
+use crate::app_config::AppConfig;
+use crate::deck::DeckMetadata;
+use crate::profiles::Profile;
+use crate::render::{CardOverride, RenderOptions};
+use std::collections::HashMap;
+
+/// Layers render options are resolved from, each one taking precedence over the ones
+/// before it:
+///
+/// 1. app defaults: game system, abbreviations, settings of `config.toml` and built-in
+///    values of everything else,
+/// 2. deck: its export profile, with theme and options of every card, and metadata, like
+///    rank spells are heightened to,
+/// 3. spells: card options set for a single spell, like leaving out heightened entries.
+///
+/// Spell options override deck ones field by field, so a spell only setting
+/// `omit_heightened` still follows `omit_extras` of the deck. Config settings only replace
+/// built-in values, the ones a profile keeps, see `AppConfig::apply`. App window, command line
+/// and http api all resolve options through it, so the same deck prints the same cards.
+#[derive(Clone, Default)]
+pub struct OptionsChain {
+    /// Deck metadata and spell overrides of it are replaced by the later layers.
+    pub defaults: RenderOptions,
+    /// Settings of `config.toml`, part of app defaults.
+    pub config: AppConfig,
+    /// Export settings of the deck. Missing ones keep app defaults.
+    pub profile: Option<Profile>,
+    pub deck: DeckMetadata,
+    /// Card options by spell id.
+    pub spells: HashMap<usize, CardOverride>,
+}
+
+impl OptionsChain {
+    pub fn new(defaults: RenderOptions, config: &AppConfig) -> Self {
+        Self {
+            defaults,
+            config: config.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// Options with every layer applied.
+    pub fn resolve(&self) -> RenderOptions {
+        let mut options = self.defaults.clone();
+        if let Some(profile) = &self.profile {
+            profile.apply(&mut options);
+        }
+        self.config.apply(&mut options);
+        options.deck = self.deck.clone();
+        options.overrides = self
+            .spells
+            .iter()
+            .filter(|(_, card_override)| **card_override != CardOverride::default())
+            .map(|(id, card_override)| (*id, *card_override))
+            .collect();
+        options
+    }
+}
@@ -0,0 +1,23 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// Write file at `path` with `write`. Contents go to `path` followed by `.part` first, and
+/// are moved over `path` only once `write` succeeds, so a failed export keeps the previous
+/// file, and viewers reloading it never read a partial one.
+pub fn write_replacing<T>(path: &Path, write: impl FnOnce(File) -> Result<T>) -> Result<T> {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+    let file = File::create(&partial)
+        .with_context(|| format!("Unable to create `{}`", partial.display()))?;
+    let result = write(file).and_then(|result| {
+        std::fs::rename(&partial, path)
+            .with_context(|| format!("Unable to replace `{}`", path.display()))?;
+        Ok(result)
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+    result
+}
@@ -0,0 +1,428 @@
+use crate::config;
+use crate::json_utils::{JsonValueExt, ObjectExt};
+use crate::markdown::DegreeStyle;
+use crate::render::{
+    BorderStyle, CardFormat, CardOptions, DividerGrouping, Duplex, PageLayout, PageSize, Placement,
+    PrintReady, RenderOptions, SleeveFit, Theme, Truncation,
+};
+use crate::rich_text::Color;
+use crate::templates;
+use anyhow::{anyhow, Result};
+use json::{object::Object, JsonValue};
+use std::path::Path;
+
+/// Named set of export settings, so they don't have to be configured for every print run.
+///
+/// Profiles are stored as json array of objects. Every field except `name` is optional:
+///
+/// ```json
+/// [{
+///     "name": "Home printer",
+///     "page_size": "Letter",
+///     "card_width": 63, "card_height": 88,
+///     "cut_guides": true,
+///     "merge_duplicates": false,
+///     "sleeve_fit": true,
+///     "numbering": true,
+///     "dividers": "rank",
+///     "attribution_page": true,
+///     "cover_page": false,
+///     "heightened_summary": false,
+///     "caster_actions_card": false,
+///     "placement": "packed",
+///     "card_per_page": false,
+///     "print_ready": false, "bleed": 3,
+///     "truncation": "drop",
+///     "card_format": "standard",
+///     "omit_heightened": false, "omit_extras": false,
+///     "font_scale": 1.0,
+///     "min_font_size": 6.0,
+///     "degree_style": "abbreviated",
+///     "template": "Parchment",
+///     "card_backs": true, "back_offset_x": 0.5, "back_offset_y": -0.3,
+///     "theme": {
+///         "uncommon": "#fad4ad", "rare": "#b8ccf2", "unique": "#dbbff2",
+///         "border": "thin",
+///         "trait_pills": false, "trait_pill": "#5d0000", "trait_pill_text": "#ffffff",
+///         "sigils": false
+///     }
+/// }]
+/// ```
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub page: PageLayout,
+    pub theme: Theme,
+    pub merge_duplicates: bool,
+    pub duplex: Duplex,
+    pub sleeve_fit: Option<SleeveFit>,
+    pub numbering: bool,
+    pub dividers: Option<DividerGrouping>,
+    pub attribution_page: bool,
+    pub cover_page: bool,
+    pub heightened_summary: bool,
+    pub caster_actions_card: bool,
+    pub placement: Placement,
+    pub card_per_page: bool,
+    pub print_ready: Option<PrintReady>,
+    pub truncation: Truncation,
+    pub card_format: CardFormat,
+    /// Options of every card, unless overridden for a spell, see `options_chain` module.
+    pub card: CardOptions,
+    /// Font sizes and spacing are multiplied by it.
+    pub font_scale: f32,
+    /// Text smaller than it, in `Pt`, is reported after export.
+    pub min_font_size: f32,
+    pub degree_style: DegreeStyle,
+    /// Installed template pack fonts are taken from, see `templates` module.
+    pub template: Option<String>,
+}
+
+impl Profile {
+    pub fn new(name: impl Into<String>) -> Self {
+        let options = RenderOptions::default();
+        Self {
+            name: name.into(),
+            page: options.page,
+            theme: options.theme,
+            merge_duplicates: options.merge_duplicates,
+            duplex: options.duplex,
+            sleeve_fit: options.sleeve_fit,
+            numbering: options.numbering,
+            dividers: options.dividers,
+            attribution_page: options.attribution_page,
+            cover_page: options.cover_page,
+            heightened_summary: options.heightened_summary,
+            caster_actions_card: options.caster_actions_card,
+            placement: options.placement,
+            card_per_page: options.card_per_page,
+            print_ready: options.print_ready,
+            truncation: options.truncation,
+            card_format: options.card_format,
+            card: options.card,
+            font_scale: 1.0,
+            min_font_size: options.min_font_size,
+            degree_style: options.typography.degree_style,
+            template: None,
+        }
+    }
+
+    /// Built-in profiles, offered before saved ones. First one is the default.
+    pub fn presets() -> Vec<Profile> {
+        vec![Self::new("Default"), Self::phone(), Self::mini()]
+    }
+
+    /// One narrow card per page with large type and no borders, for reading on a phone.
+    pub fn phone() -> Self {
+        let mut result = Self::new("Phone");
+        result.page.card_width = 75.0;
+        result.page.card_height = 160.0;
+        result.card_per_page = true;
+        result.theme.border = BorderStyle::None;
+        result.font_scale = 1.4;
+        result
+    }
+
+    /// Standard cards cut in half across, 16 on an A4 page, reminding of spells in short.
+    pub fn mini() -> Self {
+        let mut result = Self::new("Mini cards");
+        result.page.card_width = 44.0;
+        result.page.card_height = 63.0;
+        result.card_format = CardFormat::Mini;
+        result
+    }
+
+    /// Override export settings in `options` with ones from profile.
+    pub fn apply(&self, options: &mut RenderOptions) {
+        options.page = self.page;
+        options.theme = self.theme.clone();
+        options.merge_duplicates = self.merge_duplicates;
+        options.duplex = self.duplex;
+        options.sleeve_fit = self.sleeve_fit;
+        options.numbering = self.numbering;
+        options.dividers = self.dividers;
+        options.attribution_page = self.attribution_page;
+        options.cover_page = self.cover_page;
+        options.heightened_summary = self.heightened_summary;
+        options.caster_actions_card = self.caster_actions_card;
+        options.placement = self.placement;
+        options.card_per_page = self.card_per_page;
+        options.print_ready = self.print_ready;
+        options.truncation = self.truncation;
+        options.card_format = self.card_format;
+        options.card = self.card;
+        options.typography = options.typography.scaled(self.font_scale);
+        options.min_font_size = self.min_font_size;
+        options.typography.degree_style = self.degree_style;
+        if let (Some(name), Some(dir)) = (&self.template, config::templates_dir()) {
+            options.theme.fonts = templates::template_fonts(&dir, name);
+        }
+    }
+
+    pub fn parse(object: &Object) -> Result<Self> {
+        let name: String = object.get_typed("name")?;
+        let mut result = Self::new(name);
+        let page = &mut result.page;
+        if let Some(page_size) = object.get_typed_maybe::<String>("page_size")? {
+            page.page_size = PageSize::parse(&page_size)
+                .ok_or_else(|| anyhow!("Unknown page size `{page_size}`"))?;
+        }
+        set_maybe(&mut page.card_width, object.get_typed_maybe("card_width")?);
+        set_maybe(
+            &mut page.card_height,
+            object.get_typed_maybe("card_height")?,
+        );
+        set_maybe(&mut page.cut_guides, object.get_typed_maybe("cut_guides")?);
+        set_maybe(
+            &mut result.merge_duplicates,
+            object.get_typed_maybe("merge_duplicates")?,
+        );
+        if let Some(sleeve_fit) = object.get_typed_maybe::<bool>("sleeve_fit")? {
+            result.sleeve_fit = sleeve_fit.then(SleeveFit::default);
+        }
+        set_maybe(&mut result.numbering, object.get_typed_maybe("numbering")?);
+        if let Some(dividers) = object.get_typed_maybe::<String>("dividers")? {
+            result.dividers = match dividers.as_str() {
+                "none" => None,
+                name => Some(
+                    DividerGrouping::parse(name)
+                        .ok_or_else(|| anyhow!("Unknown divider grouping `{name}`"))?,
+                ),
+            };
+        }
+        set_maybe(
+            &mut result.attribution_page,
+            object.get_typed_maybe("attribution_page")?,
+        );
+        set_maybe(
+            &mut result.cover_page,
+            object.get_typed_maybe("cover_page")?,
+        );
+        set_maybe(
+            &mut result.heightened_summary,
+            object.get_typed_maybe("heightened_summary")?,
+        );
+        set_maybe(
+            &mut result.caster_actions_card,
+            object.get_typed_maybe("caster_actions_card")?,
+        );
+        // Older profiles only had a flag for deck order.
+        if object.get_typed_maybe::<bool>("keep_order")? == Some(true) {
+            result.placement = Placement::DeckOrder;
+        }
+        if let Some(placement) = object.get_typed_maybe::<String>("placement")? {
+            result.placement = Placement::parse(&placement)
+                .ok_or_else(|| anyhow!("Unknown placement `{placement}`"))?;
+        }
+        set_maybe(
+            &mut result.card_per_page,
+            object.get_typed_maybe("card_per_page")?,
+        );
+        if let Some(print_ready) = object.get_typed_maybe::<bool>("print_ready")? {
+            result.print_ready = print_ready.then(PrintReady::default);
+        }
+        if let Some(print_ready) = &mut result.print_ready {
+            set_maybe(&mut print_ready.bleed, object.get_typed_maybe("bleed")?);
+            if print_ready.bleed < 0.0 {
+                return Err(anyhow!("Field `bleed` must not be negative"));
+            }
+        }
+        if let Some(truncation) = object.get_typed_maybe::<String>("truncation")? {
+            result.truncation = Truncation::parse(&truncation)
+                .ok_or_else(|| anyhow!("Unknown truncation `{truncation}`"))?;
+        }
+        if let Some(format) = object.get_typed_maybe::<String>("card_format")? {
+            result.card_format = CardFormat::parse(&format)
+                .ok_or_else(|| anyhow!("Unknown card format `{format}`"))?;
+        }
+        set_maybe(
+            &mut result.card.omit_heightened,
+            object.get_typed_maybe("omit_heightened")?,
+        );
+        set_maybe(
+            &mut result.card.omit_extras,
+            object.get_typed_maybe("omit_extras")?,
+        );
+        set_maybe(
+            &mut result.font_scale,
+            object.get_typed_maybe("font_scale")?,
+        );
+        if result.font_scale <= 0.0 {
+            return Err(anyhow!("Field `font_scale` must be positive"));
+        }
+        set_maybe(
+            &mut result.min_font_size,
+            object.get_typed_maybe("min_font_size")?,
+        );
+        if let Some(style) = object.get_typed_maybe::<String>("degree_style")? {
+            result.degree_style = DegreeStyle::parse(&style)
+                .ok_or_else(|| anyhow!("Unknown degree of success style `{style}`"))?;
+        }
+        result.template = object.get_typed_maybe("template")?;
+        let duplex = &mut result.duplex;
+        set_maybe(
+            &mut duplex.card_backs,
+            object.get_typed_maybe("card_backs")?,
+        );
+        set_maybe(
+            &mut duplex.back_offset_x,
+            object.get_typed_maybe("back_offset_x")?,
+        );
+        set_maybe(
+            &mut duplex.back_offset_y,
+            object.get_typed_maybe("back_offset_y")?,
+        );
+        if let Some(theme) = object.get("theme") {
+            let theme = theme
+                .as_object()
+                .map_err(|e| e.context("Then parsing field `theme`"))?;
+            let colors = [
+                ("uncommon", &mut result.theme.uncommon_fill),
+                ("rare", &mut result.theme.rare_fill),
+                ("unique", &mut result.theme.unique_fill),
+                ("trait_pill", &mut result.theme.trait_pill_fill),
+                ("trait_pill_text", &mut result.theme.trait_pill_text),
+            ];
+            for (key, color) in colors {
+                if let Some(hex) = theme.get_typed_maybe::<String>(key)? {
+                    *color = parse_color(&hex)
+                        .ok_or_else(|| anyhow!("Field `{key}`: expected `#rrggbb` color"))?;
+                }
+            }
+            set_maybe(
+                &mut result.theme.trait_pills,
+                theme.get_typed_maybe("trait_pills")?,
+            );
+            set_maybe(&mut result.theme.sigils, theme.get_typed_maybe("sigils")?);
+            if let Some(border) = theme.get_typed_maybe::<String>("border")? {
+                result.theme.border = BorderStyle::parse(&border)
+                    .ok_or_else(|| anyhow!("Unknown border style `{border}`"))?;
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        let mut theme = JsonValue::new_object();
+        theme["uncommon"] = format_color(self.theme.uncommon_fill).into();
+        theme["rare"] = format_color(self.theme.rare_fill).into();
+        theme["unique"] = format_color(self.theme.unique_fill).into();
+        theme["border"] = self.theme.border.as_str().into();
+        theme["trait_pills"] = self.theme.trait_pills.into();
+        theme["trait_pill"] = format_color(self.theme.trait_pill_fill).into();
+        theme["trait_pill_text"] = format_color(self.theme.trait_pill_text).into();
+        theme["sigils"] = self.theme.sigils.into();
+
+        let mut result = JsonValue::new_object();
+        result["name"] = self.name.as_str().into();
+        result["page_size"] = self.page.page_size.as_str().into();
+        result["card_width"] = self.page.card_width.into();
+        result["card_height"] = self.page.card_height.into();
+        result["cut_guides"] = self.page.cut_guides.into();
+        result["merge_duplicates"] = self.merge_duplicates.into();
+        result["sleeve_fit"] = self.sleeve_fit.is_some().into();
+        result["numbering"] = self.numbering.into();
+        result["dividers"] = self
+            .dividers
+            .map_or("none", |grouping| grouping.as_str())
+            .into();
+        result["attribution_page"] = self.attribution_page.into();
+        result["cover_page"] = self.cover_page.into();
+        result["heightened_summary"] = self.heightened_summary.into();
+        result["caster_actions_card"] = self.caster_actions_card.into();
+        result["placement"] = self.placement.as_str().into();
+        result["card_per_page"] = self.card_per_page.into();
+        result["print_ready"] = self.print_ready.is_some().into();
+        if let Some(print_ready) = &self.print_ready {
+            result["bleed"] = print_ready.bleed.into();
+        }
+        result["truncation"] = self.truncation.as_str().into();
+        result["card_format"] = self.card_format.as_str().into();
+        result["omit_heightened"] = self.card.omit_heightened.into();
+        result["omit_extras"] = self.card.omit_extras.into();
+        result["font_scale"] = self.font_scale.into();
+        result["min_font_size"] = self.min_font_size.into();
+        result["degree_style"] = self.degree_style.as_str().into();
+        if let Some(template) = &self.template {
+            result["template"] = template.as_str().into();
+        }
+        result["card_backs"] = self.duplex.card_backs.into();
+        result["back_offset_x"] = self.duplex.back_offset_x.into();
+        result["back_offset_y"] = self.duplex.back_offset_y.into();
+        result["theme"] = theme;
+        result
+    }
+}
+
+/// Saved profile named `name`, or built-in preset or installed template if none is saved
+/// under the name, like profile selector of the app finds them.
+pub fn find_profile(name: &str) -> Result<Option<Profile>> {
+    let mut profiles = match config::profiles_path() {
+        Some(path) => load_profiles(&path)?,
+        None => vec![],
+    };
+    profiles.extend(Profile::presets());
+    if let Some(dir) = config::templates_dir() {
+        profiles.extend(templates::installed_templates(&dir));
+    }
+    Ok(profiles.into_iter().find(|profile| profile.name == name))
+}
+
+/// Load profiles from file. Missing file means no profiles were saved yet.
+pub fn load_profiles(path: &Path) -> Result<Vec<Profile>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let parse = || -> Result<Vec<Profile>> {
+        json::parse(&std::fs::read_to_string(path)?)?
+            .as_array()?
+            .iter()
+            .enumerate()
+            .map(|(i, profile)| {
+                profile
+                    .as_object()
+                    .and_then(Profile::parse)
+                    .map_err(|e| e.context(format!("While parsing profile at index `{i}`")))
+            })
+            .collect()
+    };
+    parse().map_err(|e| e.context(format!("Unable to load `{}`", path.display())))
+}
+
+pub fn save_profiles(path: &Path, profiles: &[Profile]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let data = JsonValue::Array(profiles.iter().map(Profile::to_json).collect());
+    std::fs::write(path, json::stringify_pretty(data, 2))
+        .map_err(|e| anyhow::Error::from(e).context(format!("Unable to save `{}`", path.display())))
+}
+
+fn set_maybe<T>(field: &mut T, value: Option<T>) {
+    if let Some(value) = value {
+        *field = value;
+    }
+}
+
+fn parse_color(hex: &str) -> Option<Color> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| {
+        u8::from_str_radix(hex.get(i..i + 2)?, 16)
+            .ok()
+            .map(|x| x as f32 / 255.0)
+    };
+    Some(Color::new(channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn format_color(color: Color) -> String {
+    let channel = |x: f32| (x.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color.r),
+        channel(color.g),
+        channel(color.b)
+    )
+}
@@ -0,0 +1,96 @@
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use std::borrow::Cow;
+
+/// What a cross-reference in spell text points to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReferenceKind {
+    Spell,
+    Condition,
+}
+
+/// Mention of another spell or condition in spell text, like `frightened 1` or `heal`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossReference {
+    pub kind: ReferenceKind,
+    /// Referenced name, lowercase, with condition value dropped: `frightened`.
+    pub name: String,
+}
+
+/// Kind of reference link points to. Nethys links look like `/Spells.aspx?ID=565`,
+/// wikilinks expanded by `expand_wikilinks` like `spell:Fireball`.
+pub fn link_kind(url: &str) -> Option<ReferenceKind> {
+    let url = url.to_lowercase();
+    if url.starts_with("/spells.aspx") || url.starts_with("spell:") {
+        Some(ReferenceKind::Spell)
+    } else if url.starts_with("/conditions.aspx") || url.starts_with("condition:") {
+        Some(ReferenceKind::Condition)
+    } else {
+        None
+    }
+}
+
+/// Replace wikilinks, used in homebrew bundles, with markdown links. `[[Fireball]]` refers to
+/// spell, `[[condition:Frightened 1]]` to condition.
+pub fn expand_wikilinks(markdown: &str) -> Cow<'_, str> {
+    if !markdown.contains("[[") {
+        return Cow::Borrowed(markdown);
+    }
+    let mut result = String::new();
+    let mut rest = markdown;
+    while let Some(start) = rest.find("[[") {
+        let Some(end) = rest[start..].find("]]") else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        let target = rest[start + 2..start + end].trim();
+        let (scheme, name) = match target.split_once(':') {
+            Some((kind, name)) if kind.trim().eq_ignore_ascii_case("condition") => {
+                ("condition", name.trim())
+            }
+            _ => ("spell", target),
+        };
+        result.push_str(&format!("[{name}](<{scheme}:{name}>)"));
+        rest = &rest[start + end + 2..];
+    }
+    result.push_str(rest);
+    Cow::Owned(result)
+}
+
+/// Spells and conditions linked from `markdown`, in order of first mention.
+pub fn find_references(markdown: &str, references: &mut Vec<CrossReference>) {
+    let mut current: Option<(ReferenceKind, String)> = None;
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                current = link_kind(&dest_url).map(|kind| (kind, String::new()));
+            }
+            Event::Text(text) => {
+                if let Some((_, name)) = &mut current {
+                    name.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Link) => {
+                let Some((kind, text)) = current.take() else {
+                    continue;
+                };
+                let name = reference_name(kind, &text);
+                let reference = CrossReference { kind, name };
+                if !reference.name.is_empty() && !references.contains(&reference) {
+                    references.push(reference);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn reference_name(kind: ReferenceKind, text: &str) -> String {
+    let name = text.trim().to_lowercase();
+    match kind {
+        ReferenceKind::Spell => name,
+        ReferenceKind::Condition => name
+            .trim_end_matches(|c: char| c.is_ascii_digit())
+            .trim_end()
+            .to_string(),
+    }
+}
@@ -1,43 +1,66 @@
+mod calibration;
+mod options;
+mod packing;
+mod page;
+mod renderer;
+mod sigil;
+
+use crate::attribution::{deck_attribution, Attribution};
+use crate::caster_actions::{caster_actions, CasterAction};
+use crate::csv;
+use crate::db::DEFENSE_PROPERTIES;
+use crate::error::{ExportError, LayoutError};
+use crate::heighten::{heightened_entries, HeightenedEntry};
 use crate::markdown::MdConfig;
 use crate::rich_text::{
-    AlignStrategy, Font, FontKind, FontProvider, Scene, SceneBuilder, TextChunk,
+    AlignStrategy, BandHeight, Color, Font, FontKind, FontProvider, Primitive, RasterImage, Scene,
+    SceneBuilder, TextChunk,
 };
-use crate::spell::{Actions, Spell};
-use anyhow::{anyhow, Result};
+use crate::spell::{Actions, Property, Spell};
+use crate::subset::{subset_font, UsedChars};
+use crate::system::{CardSection, GameSystem};
+use anyhow::Result;
 use pathfinder_geometry::rect::RectF;
 use pathfinder_geometry::vector::Vector2F;
 use printpdf::{
     path::{PaintMode, WindingOrder},
-    Color, Mm, PdfDocument, PdfLayerReference, Point, Polygon, Pt, Rgb,
+    ColorBits, ColorSpace, Image, ImageTransform, ImageXObject, Line, Mm, PdfDocument,
+    PdfLayerReference, Point, Polygon, Pt, Px, Rgb,
+};
+use printpdf::{
+    BuiltinFont, CustomPdfConformance, IndirectFontRef, PdfConformance, PdfDocumentReference,
 };
-use printpdf::{BuiltinFont, IndirectFontRef, PdfDocumentReference};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashSet};
 use std::io::{BufWriter, Write};
 
-// Everything is measured in Mm
-const A4_WIDTH: f32 = 210.0;
-const A4_HEIGHT: f32 = 297.0;
-const CARD_WIDTH: f32 = 63.0;
-const CARD_HEIGHT: f32 = 88.0;
-
-const GRID_WIDTH: usize = 3;
-const GRID_HEIGHT: usize = 3;
-
-const X_PADDING: f32 = 2.0;
-const Y_PADDING: f32 = 2.0;
-const X_PADDING_PAGE: f32 =
-    (A4_WIDTH - CARD_WIDTH * (GRID_WIDTH as f32) - X_PADDING * (GRID_WIDTH as f32 - 1.0)) * 0.5;
-const Y_PADDING_PAGE: f32 =
-    (A4_HEIGHT - CARD_HEIGHT * (GRID_HEIGHT as f32) - Y_PADDING * (GRID_HEIGHT as f32 - 1.0)) * 0.5;
-const MARGIN: f32 = 1.0;
-const CARD_WIDTH_INNER: f32 = CARD_WIDTH - 2.0 * MARGIN;
-const CARD_HEIGHT_INNER: f32 = CARD_HEIGHT - 2.0 * MARGIN;
-
-const HEADER_LINE_SPACE: f32 = 1.0;
-const LINE_SPACE: f32 = 0.5;
-const TRAIT_PADDING: f32 = 0.8;
-const TRAIT_CHUNK_SPACE: f32 = 0.3;
+pub use calibration::write_calibration_page;
+pub use options::{
+    BorderStyle, CardFormat, CardOptions, CardOverride, DividerGrouping, Duplex, Placement,
+    PrintReady, RenderOptions, SleeveFit, Theme, ThemeFonts, Truncation, Typography,
+};
+pub use packing::{pack, CardSize};
+pub use page::{PageLayout, PageSize};
+use renderer::Progress;
+pub use renderer::Renderer;
+pub use sigil::spell_sigil;
 
-const GENERAL_TEXT_FONT_SIZE: f32 = 7.7;
+const NUMBER_FONT_SIZE: f32 = 5.0;
+/// Printed on cards of spells missing from localized bundles, see `Spell::untranslated`.
+const UNTRANSLATED_LABEL: &str = "EN";
+const DIVIDER_STRIP_FILL: Color = Color::new(0.85, 0.85, 0.85);
+/// Stroke width of `BorderStyle::Thick` in `Pt`.
+const THICK_BORDER: f32 = 2.0;
+/// Corner radius of `BorderStyle::Rounded` in `Mm`, that of common corner cutters.
+const ROUNDED_BORDER_RADIUS: f32 = 3.0;
+/// Space between lines of `BorderStyle::Double` in `Pt`.
+const DOUBLE_BORDER_GAP: f32 = 1.5;
+/// Distance from page edges to text of cover and attribution pages, in `Mm`.
+const PAGE_MARGIN: f32 = 15.0;
+/// Title of caster reference card.
+const CASTER_ACTIONS_TITLE: &str = "Caster actions";
+/// Ends description of spells cut to fit, see `Truncation::Note`.
+const TRUNCATION_NOTE: &str = "(cont. — see Archives of Nethys)";
 
 #[derive(Copy, Clone)]
 pub struct FontConfig<'a, T> {
@@ -52,13 +75,31 @@ pub struct OwnedFontConfig<T> {
     action_count: Font<T>,
 }
 
+/// Pdf document fonts are added to.
+pub struct PdfFonts {
+    pub doc: PdfDocumentReference,
+    /// Characters drawn with each font. Embedded fonts are subset to them if given.
+    pub used_chars: Option<UsedChars>,
+}
+
 impl FontProvider for IndirectFontRef {
-    type Init = PdfDocumentReference;
+    type Init = PdfFonts;
 
     fn build_font(provider: &mut Self::Init, font: FontKind) -> Result<IndirectFontRef> {
         let font = match font {
-            FontKind::ActionCount => {
-                return Ok(provider.add_external_font(font.bytes())?);
+            FontKind::Glyphs(_) | FontKind::External(_) => {
+                let data = font.bytes();
+                let chars = provider.used_chars.as_ref().map(|used| used.get(data));
+                let subset = match chars {
+                    Some(chars) => subset_font(data, chars.unwrap_or(&BTreeSet::new()))
+                        .map_err(|error| tracing::warn!("Embedding whole font: {error:#}"))
+                        .ok(),
+                    None => None,
+                };
+                return Ok(match subset {
+                    Some(subset) => provider.doc.add_external_font(&subset[..])?,
+                    None => provider.doc.add_external_font(data)?,
+                });
             }
             FontKind::Text => BuiltinFont::Helvetica,
             FontKind::Bold => BuiltinFont::HelveticaBold,
@@ -66,6 +107,7 @@ impl FontProvider for IndirectFontRef {
         };
 
         let result = provider
+            .doc
             .add_builtin_font(font)
             .map_err(|e| anyhow::Error::from(e).context("Unable to load font ref"))?;
         Ok(result)
@@ -73,18 +115,36 @@ impl FontProvider for IndirectFontRef {
 }
 
 impl<T: FontProvider> OwnedFontConfig<T> {
-    pub fn new(doc: &mut T::Init) -> Result<Self> {
-        let text = Font::<T>::build(doc, FontKind::Text)
-            .map_err(|e| e.context("Unable to load Helvetica"))?;
+    /// Load fonts, with action glyphs of `system`.
+    pub fn new(doc: &mut T::Init, system: &dyn GameSystem) -> Result<Self, LayoutError> {
+        Self::with_fonts(doc, system, ThemeFonts::default())
+    }
+
+    /// Load fonts, replacing built-in ones with `fonts` set by theme.
+    pub fn with_fonts(
+        doc: &mut T::Init,
+        system: &dyn GameSystem,
+        fonts: ThemeFonts,
+    ) -> Result<Self, LayoutError> {
+        let kind = |font: Option<&'static [u8]>, default| font.map_or(default, FontKind::External);
+        let failed = |font: &str| {
+            let font = font.to_string();
+            move |error: anyhow::Error| LayoutError::Font {
+                font,
+                message: format!("{error:#}"),
+            }
+        };
+        let text =
+            Font::<T>::build(doc, kind(fonts.text, FontKind::Text)).map_err(failed("text"))?;
 
-        let bold = Font::<T>::build(doc, FontKind::Bold)
-            .map_err(|e| e.context("Unable to load Helvetica Bold"))?;
+        let bold =
+            Font::<T>::build(doc, kind(fonts.bold, FontKind::Bold)).map_err(failed("bold"))?;
 
-        let italic = Font::<T>::build(doc, FontKind::Italic)
-            .map_err(|e| e.context("Unable to load Helvetica Italic"))?;
+        let italic = Font::<T>::build(doc, kind(fonts.italic, FontKind::Italic))
+            .map_err(failed("italic"))?;
 
-        let action_count = Font::<T>::build(doc, FontKind::ActionCount)
-            .map_err(|e| e.context("Unable to load Pathfinder Icons font"))?;
+        let action_count = Font::<T>::build(doc, FontKind::Glyphs(system.glyph_font()))
+            .map_err(failed(&format!("{} glyph", system.name())))?;
         Ok(Self {
             text,
             bold,
@@ -94,11 +154,14 @@ impl<T: FontProvider> OwnedFontConfig<T> {
     }
 
     pub fn config(&self) -> FontConfig<'_, T> {
+        let typography = Typography::default();
         FontConfig {
             md_config: MdConfig {
                 text_font: &self.text,
                 bold_font: &self.bold,
                 italic_font: &self.italic,
+                degree_style: typography.degree_style,
+                hanging_indent: mm_to_pt(typography.hanging_indent),
             },
             action_count_font: &self.action_count,
         }
@@ -106,213 +169,1692 @@ impl<T: FontProvider> OwnedFontConfig<T> {
 }
 
 /// Write document containing all spells into `output`
+///
+/// Returns manifest listing cards in order they were placed. See `Renderer` for progress
+/// reports.
 pub fn write_to_pdf<'a, T: Write>(
     output: T,
     spells: impl IntoIterator<Item = &'a Spell>,
-) -> Result<()> {
-    let (mut doc, page1, layer1) =
-        PdfDocument::new("Spells", Mm(A4_WIDTH), Mm(A4_HEIGHT), "Layer1");
-
-    let owned_font_config = OwnedFontConfig::<IndirectFontRef>::new(&mut doc)?;
-    let font_config = owned_font_config.config();
-    let mut layer = doc.get_page(page1).get_layer(layer1);
-
-    init_page(&mut layer);
-    let pages = build_pages(&font_config, spells);
-    if pages.len() >= GRID_WIDTH {
-        draw_page(&mut layer, &pages[..GRID_WIDTH]);
-        for page in pages[GRID_WIDTH..].chunks(GRID_WIDTH) {
-            let (page_index, layer_index) = doc.add_page(Mm(A4_WIDTH), Mm(A4_HEIGHT), "Layer");
-            layer = doc.get_page(page_index).get_layer(layer_index);
-            init_page(&mut layer);
-            draw_page(&mut layer, page);
+    options: &RenderOptions,
+) -> Result<Manifest, ExportError> {
+    Renderer::new(options).render(output, spells)
+}
+
+/// Deck of a combined pdf, see `write_groups_to_pdf`.
+pub struct DeckGroup<'a> {
+    pub spells: Vec<&'a Spell>,
+    /// Options of the deck. Its name, in `deck` field, titles the divider page.
+    pub options: &'a RenderOptions,
+}
+
+/// Write several decks into a single pdf titled `title`, every deck starting with a divider
+/// page showing its name, as on cover page. Decks keep their own options, while ones of the
+/// whole pdf, like `print_ready`, come from the first deck.
+///
+/// Returns manifest of every deck, with pages counted from the start of the deck.
+pub fn write_groups_to_pdf<T: Write>(
+    output: T,
+    title: &str,
+    groups: &[DeckGroup<'_>],
+) -> Result<Vec<Manifest>, ExportError> {
+    write_pdf(output, title, groups, true, &mut Progress::default())
+}
+
+/// Write `groups` one after another, with divider pages if `dividers` is set, reporting
+/// drawn cards and pages of every deck to `progress`.
+fn write_pdf<T: Write>(
+    mut output: T,
+    title: &str,
+    groups: &[DeckGroup<'_>],
+    dividers: bool,
+    progress: &mut Progress<'_>,
+) -> Result<Vec<Manifest>, ExportError> {
+    let Some(first) = groups.first() else {
+        return Err(ExportError::Pdf("No decks to write".to_string()));
+    };
+    let mut doc = PdfDocument::empty(title);
+    let print_ready = first.options.print_ready;
+    if print_ready.is_some() {
+        doc = doc.with_conformance(PdfConformance::Custom(CustomPdfConformance {
+            requires_xmp_metadata: false,
+            requires_icc_profile: false,
+            ..Default::default()
+        }));
+    }
+    let deck_fonts = |options: &RenderOptions| match print_ready {
+        Some(_) => options.theme.fonts.embedded(),
+        None => options.theme.fonts,
+    };
+    // Every deck is fit before fonts are added, so embedded fonts are subset to characters
+    // of the whole pdf.
+    let mut used_chars = UsedChars::default();
+    let mut decks = vec![];
+    for group in groups {
+        let options = group.options;
+        // Fonts without pdf objects measure text for truncation and font subsetting.
+        let metrics =
+            OwnedFontConfig::<()>::with_fonts(&mut (), &*options.system, deck_fonts(options))?;
+        let spells = fit_deck(
+            &metrics.config(),
+            prepare_deck(group.spells.iter().copied(), options),
+            options,
+        );
+        add_used_chars(&mut used_chars, &metrics.config(), &spells, options);
+        decks.push(spells);
+    }
+    let mut fonts = PdfFonts {
+        doc,
+        used_chars: Some(used_chars),
+    };
+    // Fonts are added once for all decks using the same files and game system.
+    let mut font_configs: Vec<(ThemeFonts, &[u8], OwnedFontConfig<IndirectFontRef>)> = vec![];
+    let mut deck_configs = vec![];
+    for group in groups {
+        let options = group.options;
+        let theme_fonts = deck_fonts(options);
+        let glyphs = options.system.glyph_font();
+        let found = font_configs.iter().position(|(files, other_glyphs, _)| {
+            files.same_files(&theme_fonts) && std::ptr::eq(*other_glyphs, glyphs)
+        });
+        let index = match found {
+            Some(index) => index,
+            None => {
+                let config =
+                    OwnedFontConfig::with_fonts(&mut fonts, &*options.system, theme_fonts)?;
+                font_configs.push((theme_fonts, glyphs, config));
+                font_configs.len() - 1
+            }
+        };
+        deck_configs.push(index);
+    }
+    let mut manifests = vec![];
+    let mut page_count = 0;
+    for ((group, spells), index) in groups.iter().zip(&decks).zip(deck_configs) {
+        let font_config = font_configs[index].2.config();
+        let (manifest, pages) =
+            add_deck_pages(&fonts.doc, &font_config, group, spells, dividers, progress)?;
+        manifests.push(manifest);
+        page_count += pages;
+    }
+    let doc = fonts.doc;
+    if page_count == 0 {
+        // Pdf must have at least one page.
+        add_page(&doc, &first.options.page);
+    }
+
+    match print_ready {
+        Some(print_ready) => {
+            let pdf = add_bleed(&doc.save_to_bytes()?, print_ready.bleed)?;
+            output.write_all(&pdf)?;
+            output.flush()?;
+        }
+        None => {
+            let mut output = BufWriter::new(output);
+            doc.save(&mut output)?;
+            output.flush()?;
+        }
+    }
+    Ok(manifests)
+}
+
+/// Add pages of `spells` of a single deck, fit to cards, to `doc`, starting with divider page
+/// if `divider` is set, or cover page if enabled. Returns manifest of the deck and number of
+/// added pages.
+fn add_deck_pages(
+    doc: &PdfDocumentReference,
+    font_config: &FontConfig<'_, IndirectFontRef>,
+    group: &DeckGroup<'_>,
+    spells: &[Cow<'_, Spell>],
+    divider: bool,
+    progress: &mut Progress<'_>,
+) -> Result<(Manifest, usize), ExportError> {
+    let options = group.options;
+    let layout = &options.page;
+    let (pages, manifest) = lay_out_deck(font_config, spells, options);
+    tracing::info!(
+        spells = spells.len(),
+        pages = pages.len(),
+        "Writing deck `{}` to pdf",
+        options.deck.name
+    );
+    let attribution_page = options.attribution_page && !spells.is_empty();
+    let cover_page = options.cover_page || divider;
+    let total_pages = deck_page_count(&pages, options, cover_page, attribution_page);
+    let mut page_count = 0;
+    let mut finish_page = |progress: &mut Progress<'_>| {
+        page_count += 1;
+        progress.page_finished(page_count, total_pages)
+    };
+    // Manifest lists cards in order they are drawn.
+    let mut cards = manifest.cards.iter().peekable();
+    let total_cards = manifest.cards.len();
+    // Card backs are only printed on grid pages, which are cut out.
+    let card_backs = options.duplex.card_backs && !options.card_per_page;
+    if cover_page {
+        let mut layer = add_blank_page(doc, layout.page_width(), layout.page_height());
+        let scene = build_cover_scene(font_config, options, spells);
+        let top_left = (PAGE_MARGIN, PAGE_MARGIN);
+        render_scene_at(&mut layer, layout.page_height(), top_left, &scene);
+        finish_page(progress)?;
+        if card_backs {
+            // Blank back of the cover, so card fronts and backs stay on the same sheets.
+            add_blank_page(doc, layout.page_width(), layout.page_height());
+            finish_page(progress)?;
+        }
+    }
+    if options.card_per_page {
+        for cell in pages.iter().flatten() {
+            let Some(scene) = cell.scene() else {
+                continue;
+            };
+            add_card_page(doc, layout, scene);
+            if matches!(cell, PageCell::Filled(..)) {
+                if let Some(card) = cards.next() {
+                    progress.card_rendered(card, total_cards)?;
+                }
+            }
+            finish_page(progress)?;
         }
     } else {
-        draw_page(&mut layer, &pages);
+        for (index, page) in pages.chunks(layout.grid_width()).enumerate() {
+            let mut layer = add_page(doc, layout);
+            draw_page(&mut layer, layout, page);
+            while let Some(card) = cards.next_if(|card| card.page == index + 1) {
+                progress.card_rendered(card, total_cards)?;
+            }
+            finish_page(progress)?;
+            if card_backs {
+                let mut layer = add_page(doc, layout);
+                draw_back_page(&mut layer, font_config, page, options);
+                finish_page(progress)?;
+            }
+        }
+    }
+    if attribution_page {
+        let attribution = deck_attribution(spells.iter().map(|s| s.as_ref()), &*options.system);
+        let mut layer = add_blank_page(doc, layout.page_width(), layout.page_height());
+        let scene = build_attribution_scene(font_config, layout, &attribution);
+        let top_left = (PAGE_MARGIN, PAGE_MARGIN);
+        render_scene_at(&mut layer, layout.page_height(), top_left, &scene);
+        finish_page(progress)?;
     }
+    Ok((manifest, total_pages))
+}
 
-    doc.save(&mut BufWriter::new(output))?;
-    Ok(())
+/// Pages `add_deck_pages` adds for laid out `pages`: cards with their backs, and cover and
+/// attribution pages if `cover` and `attribution` are set.
+fn deck_page_count<T>(
+    pages: &[Vec<PageCell<'_, T>>],
+    options: &RenderOptions,
+    cover: bool,
+    attribution: bool,
+) -> usize {
+    let card_backs = options.duplex.card_backs && !options.card_per_page;
+    let sides = if card_backs { 2 } else { 1 };
+    let mut count = if options.card_per_page {
+        pages.iter().flatten().filter_map(PageCell::scene).count()
+    } else {
+        pages.chunks(options.page.grid_width()).count() * sides
+    };
+    if cover {
+        count += sides;
+    }
+    if attribution {
+        count += 1;
+    }
+    count
 }
 
-fn draw_page(layer: &mut PdfLayerReference, page: &[[PageCell; GRID_HEIGHT]]) {
+/// Deck measured by `dry_run`, without writing pdf.
+#[derive(Debug, Clone)]
+pub struct DryRun {
+    /// Spells taking a double card, in deck order.
+    pub double: Vec<String>,
+    /// Spells exceeding even a double card, with overflow in `Pt`. They are cut or left out,
+    /// as set by `RenderOptions::truncation`, and not listed in `double`.
+    pub too_long: Vec<(String, f32)>,
+    /// Pages `write_to_pdf` would write, with cover, card backs and attribution.
+    pub page_count: usize,
+}
+
+/// Lay out deck like `write_to_pdf` does, reporting cards which do not fit a single card and
+/// the number of pages. Spells in the deck several times are reported once.
+pub fn dry_run<'a>(
+    spells: impl IntoIterator<Item = &'a Spell>,
+    options: &RenderOptions,
+) -> Result<DryRun, LayoutError> {
+    let metrics =
+        OwnedFontConfig::<()>::with_fonts(&mut (), &*options.system, options.theme.fonts)?;
+    let config = metrics.config();
+    let prepared = prepare_deck(spells, options);
+    let mut double = vec![];
+    let mut too_long = vec![];
+    let mut reported = HashSet::new();
+    for spell in prepared.iter().filter(|spell| reported.insert(spell.id)) {
+        let report = layout_report(&config, spell, options);
+        if report.overflow_pt > 0.0 {
+            too_long.push((spell.name.clone(), report.overflow_pt));
+        } else if report.is_double {
+            double.push(spell.name.clone());
+        }
+    }
+    let spells = fit_deck(&config, prepared, options);
+    let (pages, _) = lay_out_deck(&config, &spells, options);
+    // Same pages as added by `write_to_pdf`.
+    let attribution = options.attribution_page && !spells.is_empty();
+    let page_count = deck_page_count(&pages, options, options.cover_page, attribution);
+    Ok(DryRun {
+        double,
+        too_long,
+        page_count: page_count.max(1),
+    })
+}
+
+/// Extend every page of `pdf` by `bleed` in `Mm` on all sides, with trim box marking the
+/// original page, and drop document info fields which are empty or claim PDF/X conformance.
+fn add_bleed(pdf: &[u8], bleed: f32) -> Result<Vec<u8>, ExportError> {
+    use printpdf::lopdf::{Dictionary, Document, Object, Stream};
+
+    if bleed < 0.0 {
+        return Err(ExportError::Pdf(format!(
+            "Bleed must not be negative, not {bleed} mm"
+        )));
+    }
+    let mut doc = Document::load_mem(pdf)?;
+    let bleed = mm_to_pt(bleed);
+    for page_id in doc.get_pages().into_values() {
+        let page = doc.get_object(page_id)?.as_dict()?;
+        let media_box = page
+            .get(b"MediaBox")?
+            .as_array()?
+            .iter()
+            .map(Object::as_float)
+            .collect::<Result<Vec<_>, _>>()?;
+        let [.., width, height] = media_box[..] else {
+            return Err(ExportError::Pdf("Invalid page media box".to_string()));
+        };
+        let contents = page.get(b"Contents")?.clone();
+        // Content is shifted by bleed, and graphics state restored after it.
+        let mut stream = |content: String| {
+            let stream = Stream::new(Dictionary::new(), content.into_bytes());
+            Object::Reference(doc.add_object(stream))
+        };
+        let begin = stream(format!("q 1 0 0 1 {bleed} {bleed} cm\n"));
+        let end = stream("\nQ".to_string());
+        let contents = match contents {
+            Object::Array(parts) => [vec![begin], parts, vec![end]].concat(),
+            part => vec![begin, part, end],
+        };
+
+        let page = doc.get_object_mut(page_id)?.as_dict_mut()?;
+        let rect = |left: f32, bottom: f32, right: f32, top: f32| {
+            Object::Array(vec![left.into(), bottom.into(), right.into(), top.into()])
+        };
+        let full = rect(0.0, 0.0, width + bleed * 2.0, height + bleed * 2.0);
+        page.set("Contents", contents);
+        page.set("MediaBox", full.clone());
+        page.set("CropBox", full.clone());
+        page.set("BleedBox", full);
+        page.set("TrimBox", rect(bleed, bleed, width + bleed, height + bleed));
+    }
+
+    if let Ok(info) = doc.trailer.get(b"Info").and_then(Object::as_reference) {
+        let info = doc.get_object_mut(info)?.as_dict_mut()?;
+        let dropped = info
+            .iter()
+            .filter(|(key, value)| {
+                key.starts_with(b"GTS_") || value.as_str().is_ok_and(|text| text.is_empty())
+            })
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        for key in dropped {
+            info.remove(&key);
+        }
+    }
+    let mut result = vec![];
+    doc.save_to(&mut result)?;
+    Ok(result)
+}
+
+/// Add characters `write_to_pdf` draws with each font for a deck of `spells` to `result`,
+/// found by laying out the deck without pdf fonts. Cover, card backs and attribution page are
+/// included whether or not they are printed, they only add a few characters.
+fn add_used_chars(
+    result: &mut UsedChars,
+    font_config: &FontConfig<'_, ()>,
+    spells: &[Cow<'_, Spell>],
+    options: &RenderOptions,
+) {
+    let (pages, _) = lay_out_deck(font_config, spells, options);
+    for page in pages.chunks(options.page.grid_width()) {
+        for scene in page.iter().flatten().filter_map(PageCell::scene) {
+            result.add_scene(scene);
+        }
+        for (_, back) in back_cards(font_config, page, options) {
+            result.add_scene(&back);
+        }
+    }
+    result.add_scene(&build_cover_scene(font_config, options, spells));
+    let attribution = deck_attribution(spells.iter().map(|s| s.as_ref()), &*options.system);
+    result.add_scene(&build_attribution_scene(
+        font_config,
+        &options.page,
+        &attribution,
+    ));
+}
+
+/// Spells as they go into deck: duplicates merged if requested, and text transformations applied.
+pub fn prepare_deck<'a>(
+    spells: impl IntoIterator<Item = &'a Spell>,
+    options: &RenderOptions,
+) -> Vec<Cow<'a, Spell>> {
+    let mut spells = spells.into_iter().collect::<Vec<_>>();
+    if options.merge_duplicates {
+        let mut seen = HashSet::new();
+        spells.retain(|spell| seen.insert(spell.id));
+    }
+    spells
+        .into_iter()
+        .map(|spell| options.prepare_spell(spell))
+        .collect()
+}
+
+/// Arrange cards of prepared deck into columns, `grid_width` columns per page, and number them.
+pub fn lay_out_deck<'a, T>(
+    font_config: &'a FontConfig<'a, T>,
+    spells: &'a [Cow<'_, Spell>],
+    options: &RenderOptions,
+) -> (Vec<Vec<PageCell<'a, T>>>, Manifest) {
+    let mut pages = build_pages(font_config, spells.iter().map(|s| s.as_ref()), options);
+    let manifest = number_cards(font_config, &mut pages, options);
+    (pages, manifest)
+}
+
+/// Page of the card grid, with cut guides if enabled.
+fn add_page(doc: &PdfDocumentReference, layout: &PageLayout) -> PdfLayerReference {
+    let mut layer = add_blank_page(doc, layout.page_width(), layout.page_height());
+    init_page(&mut layer, layout);
+    layer
+}
+
+/// Page of given size in `Mm`, without cut guides.
+fn add_blank_page(doc: &PdfDocumentReference, width: f32, height: f32) -> PdfLayerReference {
+    let (page_index, layer_index) = doc.add_page(Mm(width), Mm(height), "Layer");
+    doc.get_page(page_index).get_layer(layer_index)
+}
+
+/// Page sized exactly to the card of `scene`, twice as tall for double cards.
+fn add_card_page(
+    doc: &PdfDocumentReference,
+    layout: &PageLayout,
+    scene: &Scene<'_, IndirectFontRef>,
+) {
+    let is_double = scene.bounding_box.height() > mm_to_pt(layout.card_height_inner());
+    let height = layout.card_height * if is_double { 2.0 } else { 1.0 };
+    let mut layer = add_blank_page(doc, layout.card_width, height);
+    layer.set_outline_color(pdf_color(Color::new(0.0, 0.0, 0.0)));
+    layer.set_outline_thickness(0.0);
+    let margin = layout.margin;
+    render_scene_at(&mut layer, height, (margin, margin), scene);
+}
+
+fn draw_page(
+    layer: &mut PdfLayerReference,
+    layout: &PageLayout,
+    page: &[Vec<PageCell<IndirectFontRef>>],
+) {
     for (x, row) in page.iter().enumerate() {
-        for (y, scene) in row.iter().enumerate() {
-            if let PageCell::Filled(scene) = scene {
-                render_scene(layer, (x, y), scene);
+        for (y, cell) in row.iter().enumerate() {
+            if let Some(scene) = cell.scene() {
+                render_scene(layer, layout, (x, y), (0.0, 0.0), scene);
             }
         }
     }
 }
 
-pub enum PageCell<'a> {
-    Filled(Scene<'a, IndirectFontRef>),
-    Empty,
+fn draw_back_page<'a>(
+    layer: &mut PdfLayerReference,
+    font_config: &'a FontConfig<'a, IndirectFontRef>,
+    page: &[Vec<PageCell<'a, IndirectFontRef>>],
+    options: &RenderOptions,
+) {
+    let shift = (options.duplex.back_offset_x, options.duplex.back_offset_y);
+    for (cell, back) in back_cards(font_config, page, options) {
+        render_scene(layer, &options.page, cell, shift, &back);
+    }
 }
 
-fn build_pages<'a, 'b: 'a>(
-    font_config: &'a FontConfig<'a, IndirectFontRef>,
-    spells: impl IntoIterator<Item = &'b Spell>,
-) -> Vec<[PageCell<'a>; GRID_HEIGHT]> {
-    let mut doubles = vec![];
-    let mut normal = vec![];
-    for spell in spells {
-        match build_spell_scene(font_config, spell) {
-            Ok((scene, true)) => doubles.push(scene),
-            Ok((scene, false)) => normal.push(scene),
-            Err(error) => {
-                eprintln!("Failed to render spell: {}. {}", spell.name, error);
+/// Backs of cards on `page`, with grid cells they go into. Columns are mirrored, so backs end
+/// up behind their fronts then page is flipped along the long edge.
+pub fn back_cards<'a, T>(
+    font_config: &'a FontConfig<'a, T>,
+    page: &[Vec<PageCell<'a, T>>],
+    options: &RenderOptions,
+) -> Vec<((usize, usize), Scene<'a, T>)> {
+    let layout = &options.page;
+    let mut result = vec![];
+    for (x, row) in page.iter().enumerate() {
+        for (y, cell) in row.iter().enumerate() {
+            if let Some(scene) = cell.scene() {
+                let is_double = scene.bounding_box.height() > mm_to_pt(layout.card_height_inner());
+                let back = build_back_scene(font_config, options, is_double);
+                let mirrored_x = layout.grid_width() - 1 - x;
+                result.push(((mirrored_x, y), back));
             }
         }
     }
+    result
+}
 
-    let mut pad: [PageCell; GRID_HEIGHT] = std::array::from_fn(|_| PageCell::Empty);
-    let mut pad_index = 0;
+fn build_back_scene<'a, T>(
+    config: &'a FontConfig<'a, T>,
+    options: &RenderOptions,
+    is_double: bool,
+) -> Scene<'a, T> {
+    let rect = card_rect(&options.page);
+    let mut builder = SceneBuilder::new(config.md_config.bold_font, rect);
+    if is_double {
+        builder.double_box();
+    }
+    let title_size = 20.0;
+    builder
+        .add_vertical_space((builder.get_bounding_box().height() - title_size) * 0.5)
+        .set_alignment(AlignStrategy::AlignCenter)
+        .set_font_size(title_size)
+        .add_text("Spell")
+        .finish_line();
+    let border = builder.get_bounding_box();
+    let mut scene = builder.scene();
+    add_border(&mut scene, border, options);
+    scene
+}
+
+/// Divider card with `label` on a wide strip at the top, so it stands out in a card box.
+fn build_divider_scene<'a, T>(
+    config: &'a FontConfig<'a, T>,
+    options: &RenderOptions,
+    label: &str,
+) -> Scene<'a, T> {
+    let rect = card_rect(&options.page);
+    let mut builder = SceneBuilder::new(config.md_config.bold_font, rect);
+    let label_size = 16.0;
+    let strip_height = label_size * 2.5;
+    builder
+        .add_vertical_space((strip_height - label_size) * 0.5)
+        .set_alignment(AlignStrategy::AlignCenter)
+        .set_font_size(label_size)
+        .add_text(label.to_string())
+        .finish_line();
+    let border = builder.get_bounding_box();
+    let mut scene = builder.scene();
+    // Strip reaches the middle of border line.
+    let strip = RectF::new(border.origin(), Vector2F::new(border.width(), strip_height))
+        .dilate(mm_to_pt(options.page.margin));
+    scene.push_under(Primitive::Rect(strip, DIVIDER_STRIP_FILL));
+    add_border(&mut scene, border, options);
+    scene
+}
+
+/// Titles and heightened entries of summary cards, one per spell rank of `spells` with
+/// heightened entries. Spells are listed once, in given order.
+fn heightened_summary(
+    spells: &[&Spell],
+    system: &dyn GameSystem,
+) -> Vec<(String, Vec<HeightenedEntry>)> {
+    let mut seen = HashSet::new();
+    let mut groups = std::collections::BTreeMap::new();
+    for spell in spells {
+        if !seen.insert(spell.id) {
+            continue;
+        }
+        let entries = heightened_entries(spell);
+        if entries.is_empty() {
+            continue;
+        }
+        let (rank, label) = DividerGrouping::Rank.group(spell, system);
+        groups
+            .entry(rank)
+            .or_insert_with(|| (format!("{label} heightened"), vec![]))
+            .1
+            .extend(entries);
+    }
+    groups.into_values().collect()
+}
+
+/// Summary cards titled `title` listing `entries`, with whether each is double. Entries not
+/// fitting a double card continue on the next one, every card takes as many as fit.
+fn build_heightened_scenes<'a, T>(
+    config: &'a FontConfig<'a, T>,
+    options: &RenderOptions,
+    title: &str,
+    mut entries: &[HeightenedEntry],
+) -> Vec<(Scene<'a, T>, bool)> {
     let mut result = vec![];
+    while !entries.is_empty() {
+        if let Some(card) = build_heightened_scene(config, options, title, entries) {
+            result.push(card);
+            break;
+        }
+        // Binary search of the longest fitting prefix, `fitting` fits and `too_many` does not.
+        let (mut fitting, mut too_many) = (0, entries.len());
+        let mut card = None;
+        while too_many - fitting > 1 {
+            let count = (fitting + too_many) / 2;
+            match build_heightened_scene(config, options, title, &entries[..count]) {
+                Some(scene) => {
+                    card = Some(scene);
+                    fitting = count;
+                }
+                None => too_many = count,
+            }
+        }
+        match card {
+            Some(card) => result.push(card),
+            None => tracing::error!(
+                "Heightened entry of `{}` does not fit a double card.",
+                entries[0].spell
+            ),
+        }
+        entries = &entries[fitting.max(1)..];
+    }
+    result
+}
 
-    while !(doubles.is_empty() && normal.is_empty()) {
-        if pad_index + 2 <= GRID_HEIGHT && !doubles.is_empty() {
-            pad[pad_index] = PageCell::Filled(doubles.pop().unwrap());
-            pad_index += 2;
-        } else {
-            pad[pad_index] = PageCell::Filled(normal.pop().unwrap());
-            pad_index += 1;
+/// Summary card titled `title` listing `entries`, like `**Fireball (+1)** The damage
+/// increases by 2d6.`, and whether it is double. `None` if it does not fit a double card.
+fn build_heightened_scene<'a, T>(
+    config: &'a FontConfig<'a, T>,
+    options: &RenderOptions,
+    title: &str,
+    entries: &[HeightenedEntry],
+) -> Option<(Scene<'a, T>, bool)> {
+    let typography = &options.typography;
+    let md_config = MdConfig {
+        degree_style: typography.degree_style,
+        hanging_indent: mm_to_pt(typography.hanging_indent),
+        ..config.md_config
+    };
+    let mut builder = SceneBuilder::new(md_config.text_font, card_rect(&options.page));
+    builder.add_band("header", BandHeight::Auto, |builder| {
+        builder
+            .set_line_space(mm_to_pt(typography.header_line_space))
+            .set_alignment(AlignStrategy::AlignCenter)
+            .set_font(md_config.bold_font)
+            .set_font_size(typography.title_font_size)
+            .add_text(title.to_string())
+            .set_font(md_config.text_font);
+    });
+    builder.add_separator_line();
+    let markdown = entries
+        .iter()
+        .map(|entry| format!("**{} ({})** {}", entry.spell, entry.label, entry.text))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    builder.add_band("heightened", BandHeight::Auto, |builder| {
+        builder
+            .set_line_space(mm_to_pt(typography.line_space))
+            .set_alignment(AlignStrategy::AlignLeft)
+            .set_font_size(typography.text_font_size)
+            .add_markdown(&md_config, &markdown);
+    });
+    finish_reference_card(builder, options)
+}
+
+/// Reference card summarizing common caster actions, like `Sustain`, with their action cost
+/// glyphs and traits. `None` if they do not fit a double card.
+fn build_caster_actions_scene<'a, T>(
+    config: &'a FontConfig<'a, T>,
+    options: &RenderOptions,
+    actions: &[CasterAction],
+) -> Option<(Scene<'a, T>, bool)> {
+    let typography = &options.typography;
+    let md_config = MdConfig {
+        degree_style: typography.degree_style,
+        hanging_indent: mm_to_pt(typography.hanging_indent),
+        ..config.md_config
+    };
+    let mut builder = SceneBuilder::new(md_config.text_font, card_rect(&options.page));
+    builder.add_band("header", BandHeight::Auto, |builder| {
+        builder
+            .set_line_space(mm_to_pt(typography.header_line_space))
+            .set_alignment(AlignStrategy::AlignCenter)
+            .set_font(md_config.bold_font)
+            .set_font_size(typography.title_font_size)
+            .add_text(CASTER_ACTIONS_TITLE)
+            .set_font(md_config.text_font);
+    });
+    let trait_padding = mm_to_pt(typography.trait_padding);
+    for action in actions {
+        builder.add_separator_line();
+        builder.add_band("action", BandHeight::Auto, |builder| {
+            builder
+                .set_line_space(mm_to_pt(typography.line_space))
+                .set_alignment(AlignStrategy::AlignLeft)
+                .set_font(md_config.bold_font)
+                .set_font_size(typography.title_font_size)
+                .add_text(action.name.clone())
+                .set_font(md_config.text_font);
+            match &action.actions {
+                Actions::Range(from, to) => {
+                    builder
+                        .set_font(config.action_count_font)
+                        .set_font_size(typography.action_font_size)
+                        .add_text(Actions::number_as_str(*from).unwrap_or(""))
+                        .set_font(md_config.text_font)
+                        .set_font_size(typography.title_font_size)
+                        .add_text("to")
+                        .set_font(config.action_count_font)
+                        .set_font_size(typography.action_font_size)
+                        .add_text(Actions::number_as_str(*to).unwrap_or(""))
+                        .set_font(md_config.text_font);
+                }
+                Actions::Other(cost) => {
+                    builder
+                        .set_font_size(typography.text_font_size)
+                        .add_text(cost.clone());
+                }
+                actions => {
+                    if let Some(glyph) = actions.as_str() {
+                        builder
+                            .set_font(config.action_count_font)
+                            .set_font_size(typography.action_font_size)
+                            .add_text(glyph)
+                            .set_font(md_config.text_font);
+                    }
+                }
+            }
+            builder
+                .finish_line()
+                .set_font_size(typography.text_font_size);
+            for trait_ in &action.traits {
+                let style = options.theme.trait_box(trait_);
+                builder.add_styled_boxed_text(trait_.clone(), trait_padding, style);
+            }
+            builder.finish_line().add_markdown(&md_config, &action.text);
+        });
+    }
+    finish_reference_card(builder, options)
+}
+
+/// Check that reference card content fits a card, doubling it if needed, and add border.
+/// `None` if it does not fit a double card.
+fn finish_reference_card<'a, T>(
+    mut builder: SceneBuilder<'a, T>,
+    options: &RenderOptions,
+) -> Option<(Scene<'a, T>, bool)> {
+    builder.finish_line();
+    if !builder.failed_words().is_empty() {
+        return None;
+    }
+    let is_double = builder.is_out_of_bounds();
+    if is_double {
+        builder.double_box();
+        if builder.is_out_of_bounds() {
+            return None;
+        }
+    }
+    let border = builder.get_bounding_box();
+    let mut scene = builder.scene();
+    add_border(&mut scene, border, options);
+    Some((scene, is_double))
+}
+
+/// First page of the deck with its name, character, date and card count by rank.
+fn build_cover_scene<'a, T>(
+    config: &'a FontConfig<'a, T>,
+    options: &RenderOptions,
+    spells: &[Cow<'_, Spell>],
+) -> Scene<'a, T> {
+    let deck = &options.deck;
+    let md_config = &config.md_config;
+    let mut builder = SceneBuilder::new(md_config.bold_font, page_text_rect(&options.page));
+    let title = match deck.name.trim() {
+        "" => "Spells",
+        name => name,
+    };
+    builder
+        .add_vertical_space(mm_to_pt(40.0))
+        .set_alignment(AlignStrategy::AlignCenter)
+        .set_font_size(28.0)
+        .add_text(title.to_string())
+        .finish_line()
+        .add_vertical_space(10.0)
+        .set_font(md_config.text_font)
+        .set_font_size(14.0);
+    if !deck.character.trim().is_empty() {
+        builder
+            .add_text(deck.character.trim().to_string())
+            .finish_line();
+    }
+    let date = if deck.created.is_empty() {
+        crate::deck::today()
+    } else {
+        deck.created.clone()
+    };
+    builder
+        .add_text(date)
+        .finish_line()
+        .add_vertical_space(mm_to_pt(20.0));
+
+    let mut counts = std::collections::BTreeMap::new();
+    for spell in spells {
+        let (rank, label) = DividerGrouping::Rank.group(spell, &*options.system);
+        counts.entry(rank).or_insert((label, 0)).1 += 1;
+    }
+    builder.set_font_size(12.0);
+    for (label, count) in counts.into_values() {
+        builder.add_text(format!("{label}: {count}")).finish_line();
+    }
+    builder
+        .add_vertical_space(6.0)
+        .set_font(md_config.bold_font)
+        .add_text(format!("Total: {} cards", spells.len()))
+        .finish_line();
+    builder.scene()
+}
+
+/// Text area of cover and attribution pages.
+fn page_text_rect(layout: &PageLayout) -> RectF {
+    RectF::new(
+        Vector2F::zero(),
+        Vector2F::new(
+            mm_to_pt(layout.page_width() - 2.0 * PAGE_MARGIN),
+            mm_to_pt(layout.page_height() - 2.0 * PAGE_MARGIN),
+        ),
+    )
+}
+
+/// Page listing license attribution of deck content, with a heading per paragraph.
+fn build_attribution_scene<'a, T>(
+    config: &'a FontConfig<'a, T>,
+    layout: &PageLayout,
+    attribution: &[Attribution],
+) -> Scene<'a, T> {
+    let md_config = &config.md_config;
+    let mut builder = SceneBuilder::new(md_config.bold_font, page_text_rect(layout));
+    builder
+        .set_font_size(14.0)
+        .add_text("License attribution")
+        .finish_line()
+        .add_vertical_space(8.0);
+    for paragraph in attribution {
+        builder
+            .set_font(md_config.bold_font)
+            .set_font_size(10.0)
+            .add_text(paragraph.heading.clone())
+            .finish_line()
+            .add_vertical_space(3.0)
+            .set_font(md_config.text_font)
+            .set_font_size(9.0);
+        for line in paragraph.text.lines() {
+            if line.trim().is_empty() {
+                builder.add_vertical_space(4.0);
+            } else {
+                builder.add_text(line.to_string()).finish_line();
+            }
         }
-        if pad_index == GRID_HEIGHT {
-            pad_index = 0;
-            let mut tmp = std::array::from_fn(|_| PageCell::Empty);
-            std::mem::swap(&mut pad, &mut tmp);
-            result.push(tmp);
+        builder.add_vertical_space(10.0);
+    }
+    builder.scene()
+}
+
+/// Draw card border around `content` box in theme style, applying sleeve fit if enabled.
+/// Sleeve fit overrides border thickness.
+fn add_border<T>(scene: &mut Scene<'_, T>, content: RectF, options: &RenderOptions) {
+    use crate::rich_text::Polygon;
+
+    let border = content.dilate(mm_to_pt(options.page.margin) + 1.0);
+    let style = options.theme.border;
+    let default_thickness = match style {
+        BorderStyle::Thick => THICK_BORDER,
+        _ => 0.0,
+    };
+    let thickness = match &options.sleeve_fit {
+        Some(sleeve_fit) => {
+            scene.inset(mm_to_pt(sleeve_fit.inset));
+            sleeve_fit.border_thickness
+        }
+        None => default_thickness,
+    };
+    let mut add = |polygon| scene.primitives.push(Primitive::Path(polygon));
+    match style {
+        BorderStyle::None => {}
+        BorderStyle::Thin | BorderStyle::Thick => add(Polygon::rect(border, None, thickness)),
+        BorderStyle::Rounded => {
+            let radius = mm_to_pt(ROUNDED_BORDER_RADIUS);
+            add(Polygon::rounded_rect(border, radius, None, thickness));
+        }
+        BorderStyle::Double => {
+            add(Polygon::rect(border, None, thickness));
+            let inner = border.contract(DOUBLE_BORDER_GAP + thickness);
+            add(Polygon::rect(inner, None, 0.0));
         }
     }
-    if pad_index > 0 {
-        result.push(pad);
+}
+
+pub enum PageCell<'a, T> {
+    Filled(Scene<'a, T>, &'a Spell),
+    /// Divider card in front of a group of spells, with group label.
+    Divider(Scene<'a, T>, String),
+    /// Generated reference card, like heightened summary, with its title.
+    Reference(Scene<'a, T>, String),
+    Empty,
+}
+
+impl<'a, T> PageCell<'a, T> {
+    pub fn scene(&self) -> Option<&Scene<'a, T>> {
+        match self {
+            PageCell::Filled(scene, _)
+            | PageCell::Divider(scene, _)
+            | PageCell::Reference(scene, _) => Some(scene),
+            PageCell::Empty => None,
+        }
     }
+}
 
-    result
+/// Card numbers of exported deck, so users can verify nothing was lost after cutting.
+#[derive(Debug, Clone, Default)]
+pub struct Manifest {
+    pub cards: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ManifestEntry {
+    /// Starting from 1, in order cards are placed on pages.
+    pub number: usize,
+    /// Starting from 1.
+    pub page: usize,
+    pub name: String,
+    pub rank: u8,
 }
 
-fn init_page(layer: &mut PdfLayerReference) {
-    layer.set_outline_color(Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)));
+impl Manifest {
+    pub fn write_csv<T: Write>(&self, mut output: T) -> std::io::Result<()> {
+        csv::write_row(&mut output, &["number", "page", "spell", "rank"])?;
+        for card in &self.cards {
+            csv::write_row(
+                &mut output,
+                &[
+                    card.number.to_string(),
+                    card.page.to_string(),
+                    card.name.clone(),
+                    card.rank.to_string(),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn build_pages<'a, 'b: 'a, T>(
+    font_config: &'a FontConfig<'a, T>,
+    spells: impl IntoIterator<Item = &'b Spell>,
+    options: &RenderOptions,
+) -> Vec<Vec<PageCell<'a, T>>> {
+    let mut spells = spells.into_iter().collect::<Vec<_>>();
+    let summary = match options.heightened_summary {
+        true => heightened_summary(&spells, &*options.system),
+        false => vec![],
+    };
+    let groups = match options.dividers {
+        Some(grouping) => {
+            let system = &*options.system;
+            spells.sort_by_cached_key(|spell| grouping.group(spell, system).0);
+            spells
+                .chunk_by(|a, b| grouping.group(a, system).0 == grouping.group(b, system).0)
+                .map(|group| (Some(grouping.group(group[0], system).1), group.to_vec()))
+                .collect()
+        }
+        None => vec![(None, spells)],
+    };
+
+    let grid_height = options.page.grid_height();
+    // Cells of all columns one after another, see `packing::pack`.
+    let mut cells = vec![];
+    let mut next_cell = 0;
+
+    // Groups are placed one after another, so every divider is followed by its spells.
+    for (label, group) in groups {
+        let mut cards = vec![];
+        let mut sizes = vec![];
+        for spell in group {
+            match build_spell_scene(font_config, spell, options) {
+                Ok((scene, is_double)) => {
+                    sizes.push(packing::CardSize {
+                        span: if is_double { 2 } else { 1 },
+                        fill: (scene.content_height / scene.bounding_box.height()).clamp(0.0, 1.0),
+                    });
+                    cards.push((spell, PageCell::Filled(scene, spell)));
+                }
+                Err(error) => {
+                    tracing::error!(spell = %spell.name, "Failed to render spell: {error:#}");
+                }
+            }
+        }
+        if let Some(label) = label {
+            let scene = build_divider_scene(font_config, options, &label);
+            place_cell(&mut cells, next_cell, PageCell::Divider(scene, label));
+            next_cell += 1;
+        }
+
+        let (positions, end) = packing::pack(&sizes, next_cell, grid_height, options.placement);
+        for ((spell, card), position) in cards.into_iter().zip(positions) {
+            match position {
+                Some(position) => place_cell(&mut cells, position, card),
+                None => tracing::error!(
+                    spell = %spell.name,
+                    "Failed to render spell: page is too small for a double card."
+                ),
+            }
+        }
+        next_cell = end;
+    }
+
+    // Reference cards follow the deck.
+    let mut references = vec![];
+    if options.caster_actions_card {
+        match caster_actions() {
+            Ok(actions) => match build_caster_actions_scene(font_config, options, &actions) {
+                Some(card) => references.push((CASTER_ACTIONS_TITLE.to_string(), card)),
+                None => tracing::error!("Caster actions do not fit a double card."),
+            },
+            Err(error) => tracing::error!("Unable to load caster actions: {error:#}"),
+        }
+    }
+    for (title, entries) in summary {
+        for card in build_heightened_scenes(font_config, options, &title, &entries) {
+            references.push((title.clone(), card));
+        }
+    }
+    let mut cards = vec![];
+    let mut sizes = vec![];
+    for (title, (scene, is_double)) in references {
+        sizes.push(packing::CardSize {
+            span: if is_double { 2 } else { 1 },
+            fill: (scene.content_height / scene.bounding_box.height()).clamp(0.0, 1.0),
+        });
+        cards.push(PageCell::Reference(scene, title));
+    }
+    let (positions, _) = packing::pack(&sizes, next_cell, grid_height, options.placement);
+    for (card, position) in cards.into_iter().zip(positions) {
+        match position {
+            Some(position) => place_cell(&mut cells, position, card),
+            None => tracing::error!("Failed to render reference card: page is too small."),
+        }
+    }
+
+    let columns = cells.len().div_ceil(grid_height);
+    cells.resize_with(columns * grid_height, || PageCell::Empty);
+    let mut cells = cells.into_iter();
+    (0..columns)
+        .map(|_| cells.by_ref().take(grid_height).collect())
+        .collect()
+}
+
+fn place_cell<'a, T>(cells: &mut Vec<PageCell<'a, T>>, index: usize, cell: PageCell<'a, T>) {
+    if cells.len() <= index {
+        cells.resize_with(index + 1, || PageCell::Empty);
+    }
+    cells[index] = cell;
+}
+
+/// Assign numbers to cards in order they are placed on pages.
+/// If numbering is enabled, numbers are printed in the right corner of card footer bands.
+fn number_cards<'a, T>(
+    font_config: &'a FontConfig<'a, T>,
+    columns: &mut [Vec<PageCell<'a, T>>],
+    options: &RenderOptions,
+) -> Manifest {
+    let total = columns
+        .iter()
+        .flatten()
+        .filter(|cell| matches!(cell, PageCell::Filled(..)))
+        .count();
+    let mut manifest = Manifest::default();
+    for (column_index, column) in columns.iter_mut().enumerate() {
+        for cell in column {
+            if let PageCell::Filled(scene, spell) = cell {
+                let number = manifest.cards.len() + 1;
+                if options.numbering {
+                    scene.fill_band("footer", font_config.md_config.text_font, |builder| {
+                        builder
+                            .set_font_size(NUMBER_FONT_SIZE)
+                            .set_alignment(AlignStrategy::AlignRight)
+                            .add_text(format!("{number}/{total}"));
+                    });
+                }
+                manifest.cards.push(ManifestEntry {
+                    number,
+                    page: column_index / options.page.grid_width() + 1,
+                    name: spell.name.clone(),
+                    rank: spell.level,
+                });
+            }
+        }
+    }
+    manifest
+}
+
+fn init_page(layer: &mut PdfLayerReference, layout: &PageLayout) {
+    layer.set_outline_color(pdf_color(Color::new(0.0, 0.0, 0.0)));
     layer.set_outline_thickness(0.0);
+    if layout.cut_guides {
+        for [from, to] in layout.cut_guide_lines() {
+            layer.add_line(Line {
+                points: vec![
+                    (Point::new(Mm(from.0), Mm(from.1)), false),
+                    (Point::new(Mm(to.0), Mm(to.1)), false),
+                ],
+                is_closed: false,
+            });
+        }
+    }
+}
+
+fn card_rect(layout: &PageLayout) -> RectF {
+    RectF::new(
+        Vector2F::zero(),
+        Vector2F::new(
+            mm_to_pt(layout.card_width_inner()),
+            mm_to_pt(layout.card_height_inner()),
+        ),
+    )
+}
+
+/// How well spell content fits a card.
+#[derive(Debug, Clone)]
+pub struct FitReport {
+    /// Height of card content in `Pt`.
+    pub height_used: f32,
+    /// How much content exceeds the card in `Pt`. Zero if it fits.
+    pub overflow_pt: f32,
+    /// Whether spell takes double card.
+    pub is_double: bool,
+    /// Words which are too wide to fit on a line.
+    pub failed_words: Vec<String>,
+    /// Size of the smallest font on the card in `Pt`, `None` if card has no text.
+    pub smallest_font_size: Option<f32>,
+}
+
+impl FitReport {
+    pub fn fits(&self) -> bool {
+        self.overflow_pt <= 0.0 && self.failed_words.is_empty()
+    }
+}
+
+/// Measure spell content without producing a card.
+pub fn layout_report<T>(
+    config: &FontConfig<'_, T>,
+    spell: &Spell,
+    options: &RenderOptions,
+) -> FitReport {
+    let builder = lay_out_spell(config, spell, options);
+    let height_used = builder.content_height();
+    let single_height = builder.get_bounding_box().height();
+    let is_double = height_used >= single_height;
+    let capacity = if is_double {
+        single_height * 2.0
+    } else {
+        single_height
+    };
+    FitReport {
+        height_used,
+        overflow_pt: (height_used - capacity).max(0.0),
+        is_double,
+        failed_words: builder.failed_words().to_vec(),
+        smallest_font_size: builder.smallest_font_size(),
+    }
+}
+
+/// Card with text smaller than `RenderOptions::min_font_size`.
+#[derive(Debug, Clone)]
+pub struct LegibilityWarning {
+    pub spell: String,
+    /// Size of the smallest font on the card in `Pt`.
+    pub font_size: f32,
+    /// Font scale increase bringing the smallest font to the minimum size.
+    pub scale: f32,
+    /// Whether the card still fits with fonts scaled by `scale`.
+    pub fits_enlarged: bool,
+    /// Whether the card takes a double card with fonts scaled by `scale`.
+    pub double_enlarged: bool,
+}
+
+impl std::fmt::Display for LegibilityWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` has {:.1}Pt text", self.spell, self.font_size)?;
+        let card = if self.double_enlarged {
+            "a double card"
+        } else {
+            "a single card"
+        };
+        if self.fits_enlarged {
+            write!(
+                f,
+                ", it fits {card} with font scale raised {:.0}%.",
+                (self.scale - 1.0) * 100.0
+            )
+        } else {
+            write!(
+                f,
+                ", omit its heightened entries or abbreviate text to enlarge it."
+            )
+        }
+    }
+}
+
+/// Spells of the deck with text smaller than `options.min_font_size`, once each in deck
+/// order. Every one is laid out again with fonts enlarged to the minimum, to tell whether
+/// it would take a double card instead.
+pub fn legibility_report<'b, T>(
+    config: &FontConfig<'_, T>,
+    spells: impl IntoIterator<Item = &'b Spell>,
+    options: &RenderOptions,
+) -> Vec<LegibilityWarning> {
+    let mut seen = HashSet::new();
+    let mut result = vec![];
+    for spell in fit_deck(config, prepare_deck(spells, options), options) {
+        if !seen.insert(spell.id) {
+            continue;
+        }
+        let report = layout_report(config, &spell, options);
+        let Some(font_size) = report
+            .smallest_font_size
+            .filter(|size| *size < options.min_font_size)
+        else {
+            continue;
+        };
+        let scale = options.min_font_size / font_size;
+        let enlarged_options = RenderOptions {
+            typography: options.typography.scaled(scale),
+            ..options.clone()
+        };
+        let enlarged = layout_report(config, &spell, &enlarged_options);
+        result.push(LegibilityWarning {
+            spell: spell.name.clone(),
+            font_size,
+            scale,
+            fits_enlarged: enlarged.fits(),
+            double_enlarged: enlarged.is_double,
+        });
+    }
+    result
 }
 
 /// Write spell
 pub fn build_spell_scene<'a, T>(
     config: &'a FontConfig<'a, T>,
     spell: &'a Spell,
-) -> Result<(Scene<'a, T>, bool)> {
-    let rect = RectF::new(
-        Vector2F::zero(),
-        Vector2F::new(mm_to_pt(CARD_WIDTH_INNER), mm_to_pt(CARD_HEIGHT_INNER)),
-    );
-    let mut builder = SceneBuilder::<'a, T>::new(config.md_config.text_font, rect);
+    options: &RenderOptions,
+) -> Result<(Scene<'a, T>, bool), LayoutError> {
+    let mut builder = lay_out_spell(config, spell, options);
+    if let Some(word) = builder.failed_words().first() {
+        return Err(LayoutError::WordTooWide {
+            spell_id: spell.id,
+            spell: spell.name.clone(),
+            word: word.clone(),
+        });
+    }
+
+    let is_double = if builder.is_out_of_bounds() {
+        builder.double_box();
+        true
+    } else {
+        false
+    };
+
+    if builder.is_out_of_bounds() {
+        Err(LayoutError::DoesNotFit {
+            spell_id: spell.id,
+            spell: spell.name.clone(),
+        })
+    } else {
+        let border = builder.get_bounding_box();
+        let free_space = builder.free_space();
+        let mut scene = builder.scene();
+        if options.theme.sigils {
+            let sigil = spell_sigil(spell, free_space.contract(mm_to_pt(1.0)));
+            scene
+                .primitives
+                .extend(sigil.into_iter().map(Primitive::Path));
+        }
+        add_border(&mut scene, border, options);
+        Ok((scene, is_double))
+    }
+}
+
+/// Spells of the deck passed through `fit_spell`.
+pub fn fit_deck<'a, T>(
+    config: &FontConfig<'_, T>,
+    spells: Vec<Cow<'a, Spell>>,
+    options: &RenderOptions,
+) -> Vec<Cow<'a, Spell>> {
+    spells
+        .into_iter()
+        .map(|spell| fit_spell(config, spell, options))
+        .collect()
+}
+
+/// Spell shortened to fit a double card, as set by `options.truncation`. Heightened and extra
+/// sections are left out, and description is cut after the last sentence which fits, followed
+/// by a note where to read the rest. Spells which fit already, or do not fit even cut after
+/// the first sentence, are returned as they are.
+pub fn fit_spell<'a, T>(
+    config: &FontConfig<'_, T>,
+    spell: Cow<'a, Spell>,
+    options: &RenderOptions,
+) -> Cow<'a, Spell> {
+    if options.truncation == Truncation::Drop || fits_double(config, &spell, options) {
+        return spell;
+    }
+    let note = match (options.truncation, &spell.url) {
+        (Truncation::Link, Some(url)) => {
+            format!("(cont. — {}{url})", options.system.reference_site())
+        }
+        _ => TRUNCATION_NOTE.to_string(),
+    };
+    let cut = |end: usize| Spell {
+        description: format!("{} {note}", spell.description[..end].trim_end()),
+        heightened: None,
+        extras: vec![],
+        ..spell.as_ref().clone()
+    };
+    let ends = sentence_ends(&spell.description);
+    // Binary search of the most sentences kept, `fitting` fit and `too_many` do not.
+    let (mut fitting, mut too_many) = (0, ends.len() + 1);
+    while too_many - fitting > 1 {
+        let count = (fitting + too_many) / 2;
+        if fits_double(config, &cut(ends[count - 1]), options) {
+            fitting = count;
+        } else {
+            too_many = count;
+        }
+    }
+    if fitting == 0 {
+        return spell;
+    }
+    tracing::warn!(spell = %spell.name, "Description is cut to fit a double card");
+    Cow::Owned(cut(ends[fitting - 1]))
+}
 
+/// Whether content of `spell` fits a double card.
+fn fits_double<T>(config: &FontConfig<'_, T>, spell: &Spell, options: &RenderOptions) -> bool {
+    let mut builder = lay_out_spell(config, spell, options);
+    if builder.is_out_of_bounds() {
+        builder.double_box();
+    }
+    !builder.is_out_of_bounds()
+}
+
+/// Byte offsets where sentences of markdown `text` end: after `.`, `!` or `?` followed by
+/// whitespace, and at line breaks, so list items and table rows count as sentences too.
+fn sentence_ends(text: &str) -> Vec<usize> {
+    let mut result = vec![];
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, next)| next);
+        if matches!(c, '.' | '!' | '?') && next.is_none_or(char::is_whitespace) {
+            result.push(i + c.len_utf8());
+        } else if c == '\n' {
+            result.push(i);
+        }
+    }
+    result.push(text.len());
+    result.dedup();
+    result.retain(|&end| !text[..end].trim().is_empty());
+    result
+}
+
+/// Lay out spell content inside single card, without checking whether it fits.
+fn lay_out_spell<'a, T>(
+    config: &'a FontConfig<'a, T>,
+    spell: &'a Spell,
+    options: &RenderOptions,
+) -> SceneBuilder<'a, T> {
+    if options.card_format == CardFormat::Mini {
+        return lay_out_mini_spell(config, spell, options);
+    }
+    let typography = &options.typography;
+    let card_options = options.card_options(spell);
+    let md_config = MdConfig {
+        degree_style: typography.degree_style,
+        hanging_indent: mm_to_pt(typography.hanging_indent),
+        ..config.md_config
+    };
+    let rect = card_rect(&options.page);
+    let mut builder = SceneBuilder::<'a, T>::new(md_config.text_font, rect);
+    add_card_footer(&mut builder, config, spell, options);
+
+    for section in options.system.card_sections() {
+        match section {
+            CardSection::Header => {
+                builder.add_band("header", BandHeight::Auto, |builder| {
+                    builder
+                        .set_line_space(mm_to_pt(typography.header_line_space))
+                        // Draw header
+                        .set_alignment(AlignStrategy::JustifyEven)
+                        .set_font_size(typography.title_font_size) // Name
+                        .set_source(Some("name"))
+                        .add_text(&spell.name);
+                    add_action_cost(builder, config, typography, &spell.actions);
+                    let traditions = spell.traditions.initials();
+                    if !traditions.is_empty() {
+                        builder
+                            .set_source(Some("traditions"))
+                            .set_font_size(typography.text_font_size)
+                            .set_font(md_config.bold_font)
+                            .add_text(traditions)
+                            .set_font(md_config.text_font);
+                    }
+                    builder
+                        .set_source(Some("rank"))
+                        .set_font_size(typography.title_font_size) // Spell level
+                        .add_text(format!("{}", spell.level))
+                        .set_source(None);
+                });
+            }
+            CardSection::Traits => {
+                let trait_padding = mm_to_pt(typography.trait_padding);
+                builder.add_band("traits", BandHeight::Auto, |builder| {
+                    builder
+                        .set_line_space(mm_to_pt(typography.line_space))
+                        .set_font_size(typography.text_font_size)
+                        .set_chunk_space(mm_to_pt(typography.trait_chunk_space))
+                        .set_alignment(AlignStrategy::AlignLeft);
+                    for trait_ in &spell.traits {
+                        let style = options.theme.trait_box(trait_);
+                        builder
+                            .set_source(Some(&format!("trait:{}", trait_.to_lowercase())))
+                            .add_styled_boxed_text(trait_, trait_padding, style);
+                    }
+                    builder.set_source(None).set_default_chunk_space();
+                });
+            }
+            CardSection::Properties => {
+                builder.add_band("properties", BandHeight::Auto, |builder| {
+                    add_properties(builder, &md_config, &spell.properties);
+                });
+            }
+            CardSection::Description => {
+                builder.add_separator_line();
+                builder.add_band("description", BandHeight::Auto, |builder| {
+                    builder.add_markdown(&md_config, &spell.description);
+                });
+            }
+            CardSection::Heightened => {
+                let heightened = spell
+                    .heightened
+                    .as_ref()
+                    .filter(|_| !card_options.omit_heightened);
+                if let Some(heighened) = heightened {
+                    builder.add_separator_line();
+                    builder.add_band("heightened", BandHeight::Auto, |builder| {
+                        builder.add_markdown(&md_config, heighened.as_str());
+                    });
+                }
+            }
+            CardSection::Extras => {
+                let extras = if card_options.omit_extras {
+                    &[][..]
+                } else {
+                    &spell.extras[..]
+                };
+                for extra in extras {
+                    builder.add_separator_line();
+                    builder.add_band("extra", BandHeight::Auto, |builder| {
+                        builder.add_markdown(&md_config, extra.as_str());
+                    });
+                }
+            }
+        }
+    }
+    builder.finish_line();
+    builder
+}
+
+/// Reserve footer band at the bottom of spell card: label of untranslated spells in the left
+/// corner, and space for card number in the right one, filled by `number_cards` once cards
+/// are placed on pages. Cards without either have no footer.
+fn add_card_footer<'a, T>(
+    builder: &mut SceneBuilder<'a, T>,
+    config: &'a FontConfig<'a, T>,
+    spell: &Spell,
+    options: &RenderOptions,
+) {
+    if !options.numbering && !spell.untranslated {
+        return;
+    }
+    builder.add_footer_band("footer", BandHeight::Fixed(NUMBER_FONT_SIZE), |builder| {
+        builder
+            .set_font(config.md_config.text_font)
+            .set_font_size(NUMBER_FONT_SIZE);
+        if spell.untranslated {
+            builder.add_text(UNTRANSLATED_LABEL);
+        }
+    });
+}
+
+/// Lay out reminder of spell for `CardFormat::Mini`: name and action cost, range and defense,
+/// and one sentence summary. It is the `summary` of spell data, or the first sentence of
+/// description for spells without one, like ones of some bundles.
+fn lay_out_mini_spell<'a, T>(
+    config: &'a FontConfig<'a, T>,
+    spell: &'a Spell,
+    options: &RenderOptions,
+) -> SceneBuilder<'a, T> {
+    let typography = &options.typography;
+    let md_config = MdConfig {
+        degree_style: typography.degree_style,
+        hanging_indent: mm_to_pt(typography.hanging_indent),
+        ..config.md_config
+    };
+    let rect = card_rect(&options.page);
+    let mut builder = SceneBuilder::<'a, T>::new(md_config.text_font, rect);
+    add_card_footer(&mut builder, config, spell, options);
+    builder.add_band("header", BandHeight::Auto, |builder| {
+        builder
+            .set_line_space(mm_to_pt(typography.header_line_space))
+            .set_alignment(AlignStrategy::JustifyEven)
+            .set_font_size(typography.title_font_size)
+            .set_source(Some("name"))
+            .add_text(&spell.name);
+        add_action_cost(builder, config, typography, &spell.actions);
+        builder.set_source(None);
+    });
+    let properties = spell
+        .properties
+        .iter()
+        .filter(|property| {
+            property.name == "Range" || DEFENSE_PROPERTIES.contains(&property.name.as_str())
+        })
+        .collect::<Vec<_>>();
+    if !properties.is_empty() {
+        builder.add_band("properties", BandHeight::Auto, |builder| {
+            builder
+                .set_line_space(mm_to_pt(typography.line_space))
+                .set_font_size(typography.text_font_size);
+            add_properties(builder, &md_config, properties);
+        });
+    }
+    let summary = match spell.summary.trim() {
+        "" => sentence_ends(&spell.description)
+            .first()
+            .map_or("", |&end| spell.description[..end].trim()),
+        summary => summary,
+    };
+    if !summary.is_empty() {
+        builder.add_separator_line();
+        builder.add_band("summary", BandHeight::Auto, |builder| {
+            builder.add_markdown(&md_config, summary);
+        });
+    }
+    builder.finish_line();
     builder
-        .set_line_space(mm_to_pt(HEADER_LINE_SPACE))
-        // Draw header
-        .set_alignment(AlignStrategy::JustifyEven)
-        .set_font_size(11.0) // Name
-        .add_text(&spell.name);
+}
 
-    if let Actions::Range(from, to) = &spell.actions {
+/// Action cost glyphs of card header, following spell name.
+fn add_action_cost<'a, T>(
+    builder: &mut SceneBuilder<'a, T>,
+    config: &'a FontConfig<'a, T>,
+    typography: &Typography,
+    actions: &Actions,
+) {
+    let text_font = config.md_config.text_font;
+    builder.set_source(Some("actions"));
+    if let Actions::Range(from, to) = actions {
         builder
-            .set_font_size(14.0)
+            .begin_no_break_group()
+            .set_font_size(typography.action_font_size)
             .set_font(config.action_count_font) // Action count;
             .add_text(Actions::number_as_str(*from).unwrap_or(""))
-            .set_font(config.md_config.text_font)
-            .set_font_size(11.0)
+            .set_font(text_font)
+            .set_font_size(typography.title_font_size)
             .add_text("to")
             .set_font(config.action_count_font) // Action count;
-            .set_font_size(14.0)
+            .set_font_size(typography.action_font_size)
             .add_text(Actions::number_as_str(*to).unwrap_or(""))
-            .set_font(config.md_config.text_font);
-    } else if let Some(action) = spell.actions.as_str() {
+            .end_no_break_group()
+            .set_font(text_font);
+    } else if let Some(action) = actions.as_str() {
         builder
-            .set_font_size(14.0)
+            .set_font_size(typography.action_font_size)
             .set_font(config.action_count_font) // Action count;
             .add_text(action)
-            .set_font(config.md_config.text_font);
+            .set_font(text_font);
     }
-    builder
-        .set_font_size(11.0) // Spell level
-        .add_text(format!("{}", spell.level))
-        .finish_line();
+}
 
-    // Draw traits
-    builder
-        .set_line_space(mm_to_pt(LINE_SPACE))
-        .set_font_size(GENERAL_TEXT_FONT_SIZE)
-        .set_chunk_space(mm_to_pt(TRAIT_CHUNK_SPACE))
-        .set_alignment(AlignStrategy::AlignLeft);
-    for trait_ in &spell.traits {
-        builder.add_boxed_text(trait_.as_str(), mm_to_pt(TRAIT_PADDING));
-    }
-    builder.set_default_chunk_space().finish_line();
-    // Draw properties
-    for property in &spell.properties {
+/// Property per line, name in bold and values aligned.
+fn add_properties<'a, T>(
+    builder: &mut SceneBuilder<'a, T>,
+    md_config: &MdConfig<'a, T>,
+    properties: impl IntoIterator<Item = &'a Property>,
+) {
+    for property in properties {
+        // Name is never left at the end of a line without its value.
+        let value = property.value.trim();
+        let (first_word, rest) = value.split_once(' ').unwrap_or((value, ""));
+        let source = format!("property:{}", property.name.to_lowercase());
         builder
-            .set_font(config.md_config.bold_font)
+            .set_source(Some(&source))
+            .begin_no_break_group()
+            .set_font(md_config.bold_font)
             .add_text(property.name.as_str())
-            .set_font(config.md_config.text_font)
-            .add_text(property.value.as_str())
+            .add_tab()
+            .set_font(md_config.text_font)
+            .add_text(first_word)
+            .end_no_break_group()
+            .add_text(rest)
             .finish_line();
     }
-    builder.add_separator_line();
-    builder.add_markdown(&config.md_config, &spell.description);
-    if let Some(heighened) = &spell.heightened {
-        builder.add_separator_line();
-        builder
-            .add_markdown(&config.md_config, heighened.as_str())
-            .finish_line();
-    }
-    builder.finish_line();
-
-    let is_double = if builder.is_out_of_bounds() {
-        builder.double_box();
-        true
-    } else {
-        false
-    };
-    builder.add_rect(builder.get_bounding_box().dilate(mm_to_pt(MARGIN) + 1.0));
-
-    if builder.is_out_of_bounds() {
-        Err(anyhow!(
-            "Spell `{spell_name}` does not fit card format!",
-            spell_name = spell.name
-        ))
-    } else {
-        Ok((builder.scene(), is_double))
-    }
+    // Values start at the same column, so they are easy to scan.
+    builder.set_source(None).align_tabs();
 }
 
+/// Draw `scene` into grid cell `(x, y)`, moved by `(shift_x, shift_y)` `Mm` right and down.
 fn render_scene(
     layer: &mut PdfLayerReference,
+    layout: &PageLayout,
     (x, y): (usize, usize),
+    (shift_x, shift_y): (f32, f32),
     scene: &Scene<'_, IndirectFontRef>,
 ) {
-    let offset = Point::new(
-        Mm(X_PADDING_PAGE + (CARD_WIDTH + X_PADDING) * x as f32),
-        Mm(Y_PADDING_PAGE + (CARD_HEIGHT + Y_PADDING) * (GRID_HEIGHT - 1 - y) as f32),
-    );
-    for chunk in &scene.parts {
-        draw_text(layer, offset, chunk);
+    // Top left corner of card content.
+    let (left, top) = layout.content_top_left(x, y);
+    let top_left = (left + shift_x, top + shift_y);
+    render_scene_at(layer, layout.page_height(), top_left, scene);
+}
+
+/// Draw `scene` with its top left corner at `(left, top)` `Mm` from the top left page corner.
+fn render_scene_at(
+    layer: &mut PdfLayerReference,
+    page_height: f32,
+    (left, top): (f32, f32),
+    scene: &Scene<'_, IndirectFontRef>,
+) {
+    let offset = Point::new(Mm(left), Mm(page_height - top));
+    let to_ring = |points: &[Vector2F]| {
+        points
+            .iter()
+            .map(|x| (text_coords_to_render(offset, *x), false))
+            .collect::<Vec<_>>()
+    };
+    for primitive in &scene.primitives {
+        match primitive {
+            Primitive::Text(chunk) => {
+                layer.set_fill_color(pdf_color(chunk.color));
+                draw_text(layer, offset, chunk);
+            }
+            Primitive::Path(poly) => {
+                let mode = match (poly.fill, poly.stroke) {
+                    (None, false) => continue,
+                    (None, true) => PaintMode::Stroke,
+                    (Some(_), false) => PaintMode::Fill,
+                    (Some(_), true) => PaintMode::FillStroke,
+                };
+                if let Some(fill) = poly.fill {
+                    layer.set_fill_color(pdf_color(fill));
+                }
+                layer.set_outline_thickness(poly.thickness);
+                layer.add_polygon(Polygon {
+                    rings: vec![to_ring(&poly.points)],
+                    mode,
+                    winding_order: WindingOrder::NonZero,
+                });
+            }
+            Primitive::Rect(rect, fill) => {
+                let corners = [
+                    rect.origin(),
+                    rect.upper_right(),
+                    rect.lower_right(),
+                    rect.lower_left(),
+                ];
+                layer.set_fill_color(pdf_color(*fill));
+                layer.add_polygon(Polygon {
+                    rings: vec![to_ring(&corners)],
+                    mode: PaintMode::Fill,
+                    winding_order: WindingOrder::NonZero,
+                });
+            }
+            Primitive::Image(rect, image) => draw_image(layer, offset, *rect, image),
+        }
     }
-    let polygons = scene
-        .polygons
-        .iter()
-        .map(|poly| {
-            poly.points
-                .iter()
-                .map(|x| (text_coords_to_render(offset, *x), false))
-                .collect::<Vec<_>>()
-        })
-        .collect::<Vec<_>>();
-    layer.add_polygon(Polygon {
-        rings: polygons,
-        mode: PaintMode::Stroke,
-        winding_order: WindingOrder::NonZero,
-    });
+    layer.set_outline_thickness(0.0);
+}
+
+/// Draw `image` stretched over `rect` of scene with top left corner at `offset`.
+fn draw_image(layer: &PdfLayerReference, offset: Point, rect: RectF, image: &RasterImage) {
+    if image.width == 0 || image.pixels.len() != image.width * image.height * 3 {
+        return;
+    }
+    let image_x_object = ImageXObject {
+        width: Px(image.width),
+        height: Px(image.height),
+        color_space: ColorSpace::Rgb,
+        bits_per_component: ColorBits::Bit8,
+        interpolate: true,
+        image_data: image.pixels.clone(),
+        image_filter: None,
+        smask: None,
+        clipping_bbox: None,
+    };
+    let origin = text_coords_to_render(offset, rect.lower_left());
+    // At 72 dpi image pixel takes one `Pt`.
+    Image::from(image_x_object).add_to_layer(
+        layer.clone(),
+        ImageTransform {
+            translate_x: Some(origin.x.into()),
+            translate_y: Some(origin.y.into()),
+            scale_x: Some(rect.width() / image.width as f32),
+            scale_y: Some(rect.height() / image.height as f32),
+            dpi: Some(72.0),
+            ..Default::default()
+        },
+    );
+}
+
+fn pdf_color(color: Color) -> printpdf::Color {
+    printpdf::Color::Rgb(Rgb::new(color.r, color.g, color.b, None))
 }
 
 fn draw_text(
@@ -331,9 +1873,8 @@ fn draw_text(
 }
 
 fn text_coords_to_render(offset: Point, text_pos: Vector2F) -> Point {
-    let height_in_pt = Pt::from(Mm(CARD_HEIGHT_INNER)).0;
-    let x = offset.x.0 + text_pos.x() + mm_to_pt(MARGIN);
-    let y = offset.y.0 + height_in_pt - text_pos.y() + mm_to_pt(MARGIN);
+    let x = offset.x.0 + text_pos.x();
+    let y = offset.y.0 - text_pos.y();
     Point::new(Mm::from(Pt(x)), Mm::from(Pt(y)))
 }
 
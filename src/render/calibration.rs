@@ -0,0 +1,185 @@
+use super::RenderOptions;
+use crate::error::ExportError;
+use printpdf::{BuiltinFont, Line, Mm, PdfDocument, PdfLayerReference, Point};
+use std::io::{BufWriter, Write};
+
+// Everything is measured in Mm
+const RULER_MARGIN: f32 = 15.0;
+const RULER_LENGTH_X: f32 = 170.0;
+const RULER_LENGTH_Y: f32 = 150.0;
+const RULER_Y: f32 = 270.0;
+const CARD_TOP: f32 = 255.0;
+const TEXT_TOP: f32 = 100.0;
+const TEXT_LINE_HEIGHT: f32 = 6.0;
+const CROSS_SIZE: f32 = 10.0;
+
+const INSTRUCTIONS: &[&str] = &[
+    "Check that the card outline above measures exactly as its label says,",
+    "and that every 10 mm on both rulers matches a real ruler.",
+    "",
+    "Recommended printer settings:",
+    "  - Scale: 100% / Actual size. Disable \"Fit to page\" and \"Shrink oversized pages\".",
+    "  - Paper size: same as in export profile.",
+    "  - Borderless printing: off.",
+    "  - Duplex: off, unless printing card backs.",
+];
+
+const DUPLEX_INSTRUCTIONS: &[&str] = &[
+    "Print both pages double-sided, flipping on the long edge.",
+    "Hold the sheet against light and compare the cross of the front page",
+    "with the scale on this page. Each tick is 1 mm.",
+    "Adjust back offsets by the distance between cross centers and print",
+    "this page again, until crosses match.",
+    "",
+    "Offsets applied to this page:",
+];
+
+/// Write page with rulers and card outline, used to check printer scaling.
+///
+/// If card backs are enabled, second page helps to measure duplex misalignment.
+pub fn write_calibration_page<T: Write>(
+    output: T,
+    options: &RenderOptions,
+) -> Result<(), ExportError> {
+    let layout = &options.page;
+    let duplex = &options.duplex;
+    let (card_width, card_height) = (layout.card_width, layout.card_height);
+    let page_size = (Mm(layout.page_width()), Mm(layout.page_height()));
+    let (doc, page, layer) = PdfDocument::new("Calibration", page_size.0, page_size.1, "Layer1");
+    let font = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+    let layer = doc.get_page(page).get_layer(layer);
+    layer.set_outline_thickness(0.0);
+
+    // Horizontal ruler along the top, vertical one along the left side.
+    for mm in 0..=RULER_LENGTH_X as usize {
+        let x = RULER_MARGIN + mm as f32;
+        let length = tick_length(mm);
+        draw_line(&layer, &[(x, RULER_Y), (x, RULER_Y - length)]);
+        if mm.is_multiple_of(10) {
+            layer.use_text(
+                format!("{}", mm / 10),
+                6.0,
+                Mm(x + 0.5),
+                Mm(RULER_Y - length),
+                &font,
+            );
+        }
+    }
+    for mm in 0..=RULER_LENGTH_Y as usize {
+        let y = RULER_Y - mm as f32;
+        let length = tick_length(mm);
+        draw_line(&layer, &[(RULER_MARGIN, y), (RULER_MARGIN + length, y)]);
+        if mm.is_multiple_of(10) && mm > 0 {
+            layer.use_text(
+                format!("{}", mm / 10),
+                6.0,
+                Mm(RULER_MARGIN + length + 0.5),
+                Mm(y - 1.0),
+                &font,
+            );
+        }
+    }
+
+    let left = (layout.page_width() - card_width) * 0.5;
+    let bottom = CARD_TOP - card_height;
+    let right = left + card_width;
+    draw_line(
+        &layer,
+        &[
+            (left, bottom),
+            (right, bottom),
+            (right, CARD_TOP),
+            (left, CARD_TOP),
+            (left, bottom),
+        ],
+    );
+    let (center_x, center_y) = (left + card_width * 0.5, bottom + card_height * 0.5);
+    draw_cross(&layer, center_x, center_y);
+    layer.use_text(
+        format!("{card_width} x {card_height} mm"),
+        10.0,
+        Mm(left),
+        Mm(bottom - 6.0),
+        &font,
+    );
+
+    for (i, line) in INSTRUCTIONS.iter().enumerate() {
+        layer.use_text(
+            *line,
+            10.0,
+            Mm(RULER_MARGIN + 10.0),
+            Mm(TEXT_TOP - TEXT_LINE_HEIGHT * i as f32),
+            &font,
+        );
+    }
+
+    if duplex.card_backs {
+        let (page, layer) = doc.add_page(page_size.0, page_size.1, "Layer1");
+        let layer = doc.get_page(page).get_layer(layer);
+        layer.set_outline_thickness(0.0);
+        // Card is centered horizontally, so it stays in place then page is mirrored.
+        let center_x = center_x + duplex.back_offset_x;
+        let center_y = center_y - duplex.back_offset_y;
+        draw_cross(&layer, center_x, center_y);
+        for mm in 1..=CROSS_SIZE as usize {
+            let length = tick_length(mm).min(3.0);
+            for sign in [-1.0, 1.0] {
+                let d = sign * mm as f32;
+                draw_line(
+                    &layer,
+                    &[(center_x + d, center_y), (center_x + d, center_y - length)],
+                );
+                draw_line(
+                    &layer,
+                    &[(center_x, center_y + d), (center_x + length, center_y + d)],
+                );
+            }
+        }
+        let offsets = format!(
+            "X: {} mm, Y: {} mm",
+            duplex.back_offset_x, duplex.back_offset_y
+        );
+        let lines = DUPLEX_INSTRUCTIONS
+            .iter()
+            .copied()
+            .chain([offsets.as_str()]);
+        for (i, line) in lines.enumerate() {
+            layer.use_text(
+                line,
+                10.0,
+                Mm(RULER_MARGIN + 10.0),
+                Mm(TEXT_TOP - TEXT_LINE_HEIGHT * i as f32),
+                &font,
+            );
+        }
+    }
+
+    doc.save(&mut BufWriter::new(output))?;
+    Ok(())
+}
+
+fn draw_cross(layer: &PdfLayerReference, x: f32, y: f32) {
+    draw_line(layer, &[(x - CROSS_SIZE, y), (x + CROSS_SIZE, y)]);
+    draw_line(layer, &[(x, y - CROSS_SIZE), (x, y + CROSS_SIZE)]);
+}
+
+/// Centimeter ticks are the longest, half centimeter ticks are longer than millimeter ones.
+fn tick_length(mm: usize) -> f32 {
+    if mm.is_multiple_of(10) {
+        5.0
+    } else if mm.is_multiple_of(5) {
+        3.0
+    } else {
+        1.5
+    }
+}
+
+fn draw_line(layer: &PdfLayerReference, points: &[(f32, f32)]) {
+    layer.add_line(Line {
+        points: points
+            .iter()
+            .map(|(x, y)| (Point::new(Mm(*x), Mm(*y)), false))
+            .collect(),
+        is_closed: false,
+    });
+}
@@ -0,0 +1,522 @@
+use super::page::PageLayout;
+use crate::abbreviations::Abbreviations;
+use crate::deck::DeckMetadata;
+use crate::heighten::heighten;
+use crate::markdown::DegreeStyle;
+use crate::rich_text::{BoxStyle, Color, FontKind};
+use crate::spell::{Rarity, Spell, SpellType};
+use crate::system::{GameSystem, Pathfinder2e};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Everything affecting how cards are laid out and drawn.
+#[derive(Clone)]
+pub struct RenderOptions {
+    /// Provides card sections and glyph font.
+    pub system: Rc<dyn GameSystem>,
+    pub page: PageLayout,
+    pub theme: Theme,
+    pub typography: Typography,
+    /// Options for cards without override.
+    pub card: CardOptions,
+    /// Per spell card options by spell id, overriding `card` field by field.
+    pub overrides: HashMap<usize, CardOverride>,
+    /// Applied to spell text before layout, if present.
+    pub abbreviations: Option<Rc<Abbreviations>>,
+    pub duplex: Duplex,
+    /// Print a single card for spells selected several times.
+    pub merge_duplicates: bool,
+    pub sleeve_fit: Option<SleeveFit>,
+    /// Print card numbers, like `12/48`, in card corners.
+    pub numbering: bool,
+    /// Put a divider card in front of every group of spells.
+    pub dividers: Option<DividerGrouping>,
+    /// Add final page with license attribution of spells in the deck.
+    pub attribution_page: bool,
+    /// Deck name is used as pdf title and on cover page.
+    pub deck: DeckMetadata,
+    /// Start pdf with a page showing deck name, character and card count by rank.
+    pub cover_page: bool,
+    /// How cards are arranged in page columns.
+    pub placement: Placement,
+    /// Put every card on its own page sized to the card, for viewing on screen. Cover and
+    /// attribution pages keep paper size, and card backs are not printed.
+    pub card_per_page: bool,
+    /// Font size in `Pt` below which text is reported as hard to read, see
+    /// `render::legibility_report`.
+    pub min_font_size: f32,
+    /// Add a card per spell rank after the deck, listing heightened entries of its spells.
+    pub heightened_summary: bool,
+    /// Add a reference card of common caster actions, like `Sustain`, after the deck.
+    pub caster_actions_card: bool,
+    /// Write pdf for print shops, see `PrintReady`.
+    pub print_ready: Option<PrintReady>,
+    /// What happens to spells which do not fit even a double card.
+    pub truncation: Truncation,
+    /// Content of spell cards, whole spell or a reminder, see `CardFormat`.
+    pub card_format: CardFormat,
+}
+
+/// Options of a single card.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CardOptions {
+    /// Skip heightened section. Useful then caster cannot heighten spell anyway.
+    pub omit_heightened: bool,
+    /// Skip sections after heightened, like psychic amps.
+    pub omit_extras: bool,
+}
+
+/// Card options set for a single spell. Options left `None` are taken from options of
+/// the deck.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CardOverride {
+    pub omit_heightened: Option<bool>,
+    pub omit_extras: Option<bool>,
+}
+
+impl CardOverride {
+    /// Options of `base` with ones set by override replaced.
+    pub fn over(&self, base: CardOptions) -> CardOptions {
+        CardOptions {
+            omit_heightened: self.omit_heightened.unwrap_or(base.omit_heightened),
+            omit_extras: self.omit_extras.unwrap_or(base.omit_extras),
+        }
+    }
+}
+
+/// Card backs printed on the reverse side of every page.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Duplex {
+    /// Add a page of card backs after every page of cards.
+    pub card_backs: bool,
+    /// Shift of back pages to the right in `Mm`, compensating printer misalignment.
+    pub back_offset_x: f32,
+    /// Shift of back pages downwards in `Mm`.
+    pub back_offset_y: f32,
+}
+
+/// How spells are grouped behind divider cards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DividerGrouping {
+    Rank,
+    /// By first tradition of a spell.
+    Tradition,
+}
+
+impl DividerGrouping {
+    pub const ALL: [DividerGrouping; 2] = [DividerGrouping::Rank, DividerGrouping::Tradition];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DividerGrouping::Rank => "rank",
+            DividerGrouping::Tradition => "tradition",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|grouping| grouping.as_str() == name)
+    }
+
+    /// Group of `spell` as sorting key and divider label.
+    pub fn group(&self, spell: &Spell, system: &dyn GameSystem) -> (u8, String) {
+        match self {
+            DividerGrouping::Rank => match spell.spell_type {
+                SpellType::Cantrip => (0, "Cantrips".to_string()),
+                _ => (spell.level, format!("Rank {}", spell.level)),
+            },
+            DividerGrouping::Tradition => {
+                let traditions = &spell.traditions;
+                let flags = [
+                    traditions.is_arcane,
+                    traditions.is_primal,
+                    traditions.is_divine,
+                    traditions.is_occult,
+                ];
+                match flags.iter().position(|flag| *flag) {
+                    Some(index) => (index as u8, system.tradition_labels()[index].to_string()),
+                    None => (flags.len() as u8, "Other".to_string()),
+                }
+            }
+        }
+    }
+}
+
+/// How cards are arranged in page columns, see `packing::pack`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// Double cards are placed first and single cards fill gaps between them, which takes
+    /// fewest pages.
+    Packed,
+    /// Cards are placed in deck order, which may leave gaps.
+    DeckOrder,
+    /// Takes as many pages as `Packed`, but single cards are spread so columns get similar
+    /// amounts of text.
+    Balanced,
+}
+
+impl Placement {
+    pub const ALL: [Placement; 3] = [Placement::Packed, Placement::DeckOrder, Placement::Balanced];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Placement::Packed => "packed",
+            Placement::DeckOrder => "deck_order",
+            Placement::Balanced => "balanced",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|placement| placement.as_str() == name)
+    }
+}
+
+/// What happens to spells which do not fit even a double card, see `render::fit_spell`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Truncation {
+    /// Card is left out of the deck, with an error logged.
+    Drop,
+    /// Description is cut after the last fitting sentence, followed by a note pointing
+    /// to Archives of Nethys.
+    Note,
+    /// Like `Note`, but with address of the spell page, where spell data has one.
+    Link,
+}
+
+impl Truncation {
+    pub const ALL: [Truncation; 3] = [Truncation::Drop, Truncation::Note, Truncation::Link];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Truncation::Drop => "drop",
+            Truncation::Note => "note",
+            Truncation::Link => "link",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|truncation| truncation.as_str() == name)
+    }
+}
+
+/// Content of spell cards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardFormat {
+    /// Whole spell, with sections listed by the game system.
+    Standard,
+    /// Reminder for small cards: name, action cost, range and defense, and the first sentence
+    /// of description as summary.
+    Mini,
+}
+
+impl CardFormat {
+    pub const ALL: [CardFormat; 2] = [CardFormat::Standard, CardFormat::Mini];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CardFormat::Standard => "standard",
+            CardFormat::Mini => "mini",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.as_str() == name)
+    }
+}
+
+/// Shrinks card content and thickens its border, so cards cut slightly
+/// off-size still look clean in sleeves.
+#[derive(Debug, Clone, Copy)]
+pub struct SleeveFit {
+    /// Free space added between border and content, in `Mm`.
+    pub inset: f32,
+    /// Border width in `Pt`.
+    pub border_thickness: f32,
+}
+
+/// Pdf accepted by print shops: every font embedded, including built-in Helvetica, document
+/// info without PDF/X claims or XMP packet, and pages extended by bleed with trim box marking
+/// the original page.
+#[derive(Debug, Clone, Copy)]
+pub struct PrintReady {
+    /// Margin added around every page, in `Mm`.
+    pub bleed: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub uncommon_fill: Color,
+    pub rare_fill: Color,
+    pub unique_fill: Color,
+    /// Border drawn around every card.
+    pub border: BorderStyle,
+    /// Draw traits as filled pills with `trait_pill_text` colored text, like official
+    /// statblocks do, instead of outlined boxes.
+    pub trait_pills: bool,
+    /// Pill color of traits other than rarity.
+    pub trait_pill_fill: Color,
+    pub trait_pill_text: Color,
+    /// Fill empty space at the bottom of cards with a sigil drawn from spell name, see
+    /// `spell_sigil`, so decks without art look finished.
+    pub sigils: bool,
+    pub fonts: ThemeFonts,
+}
+
+/// Font files replacing built-in Helvetica, like ones of a template pack. Files are kept for
+/// the rest of the run, since fonts borrow them.
+#[derive(Clone, Copy, Default)]
+pub struct ThemeFonts {
+    pub text: Option<&'static [u8]>,
+    pub bold: Option<&'static [u8]>,
+    pub italic: Option<&'static [u8]>,
+}
+
+impl ThemeFonts {
+    /// Whether both use the same files. Cheaper than comparing file contents.
+    pub fn same_files(&self, other: &Self) -> bool {
+        let same = |a: Option<&[u8]>, b: Option<&[u8]>| match (a, b) {
+            (Some(a), Some(b)) => std::ptr::eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        same(self.text, other.text)
+            && same(self.bold, other.bold)
+            && same(self.italic, other.italic)
+    }
+
+    /// Fonts with built-in Helvetica files in place of missing ones, so every font is
+    /// embedded.
+    pub fn embedded(self) -> Self {
+        Self {
+            text: self.text.or(Some(FontKind::Text.bytes())),
+            bold: self.bold.or(Some(FontKind::Bold.bytes())),
+            italic: self.italic.or(Some(FontKind::Italic.bytes())),
+        }
+    }
+}
+
+impl std::fmt::Debug for ThemeFonts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let describe = |font: Option<&[u8]>| match font {
+            Some(bytes) => format!("{} bytes", bytes.len()),
+            None => "built-in".to_string(),
+        };
+        f.debug_struct("ThemeFonts")
+            .field("text", &describe(self.text))
+            .field("bold", &describe(self.bold))
+            .field("italic", &describe(self.italic))
+            .finish()
+    }
+}
+
+/// Card border. Borders guide cutting, heavier ones are easier to follow by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorderStyle {
+    /// No border, for decks read on screen.
+    None,
+    /// Thinnest line output device can draw.
+    Thin,
+    Thick,
+    /// Thin line with rounded corners, matching rounded card corner cutters.
+    Rounded,
+    /// Two thin lines.
+    Double,
+}
+
+impl BorderStyle {
+    pub const ALL: [BorderStyle; 5] = [
+        BorderStyle::None,
+        BorderStyle::Thin,
+        BorderStyle::Thick,
+        BorderStyle::Rounded,
+        BorderStyle::Double,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BorderStyle::None => "none",
+            BorderStyle::Thin => "thin",
+            BorderStyle::Thick => "thick",
+            BorderStyle::Rounded => "rounded",
+            BorderStyle::Double => "double",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|style| style.as_str() == name)
+    }
+}
+
+/// Font sizes are measured in `Pt`, everything else in `Mm`.
+#[derive(Debug, Clone)]
+pub struct Typography {
+    pub title_font_size: f32,
+    pub action_font_size: f32,
+    pub text_font_size: f32,
+    pub header_line_space: f32,
+    pub line_space: f32,
+    pub trait_padding: f32,
+    pub trait_chunk_space: f32,
+    /// Indent of wrapped lines in degree of success entries.
+    pub hanging_indent: f32,
+    pub degree_style: DegreeStyle,
+}
+
+impl Default for RenderOptions {
+    /// Built-in values. Settings of `config.toml` replace them in `OptionsChain`.
+    fn default() -> Self {
+        Self {
+            system: Rc::new(Pathfinder2e),
+            page: PageLayout::default(),
+            theme: Theme::default(),
+            typography: Typography::default(),
+            card: CardOptions::default(),
+            overrides: HashMap::new(),
+            abbreviations: None,
+            duplex: Duplex::default(),
+            merge_duplicates: false,
+            sleeve_fit: None,
+            numbering: false,
+            dividers: None,
+            attribution_page: true,
+            deck: DeckMetadata::default(),
+            cover_page: false,
+            placement: Placement::Packed,
+            card_per_page: false,
+            min_font_size: 6.0,
+            heightened_summary: false,
+            caster_actions_card: false,
+            print_ready: None,
+            truncation: Truncation::Drop,
+            card_format: CardFormat::Standard,
+        }
+    }
+}
+
+impl RenderOptions {
+    pub fn card_options(&self, spell: &Spell) -> CardOptions {
+        match self.overrides.get(&spell.id) {
+            Some(card_override) => card_override.over(self.card),
+            None => self.card,
+        }
+    }
+
+    /// Spell with text transformations, like heightening to deck casting rank and
+    /// abbreviations, applied.
+    pub fn prepare_spell<'a>(&self, spell: &'a Spell) -> Cow<'a, Spell> {
+        let heightened = self.deck.cast_rank.and_then(|rank| heighten(spell, rank));
+        let spell = match heightened {
+            Some(heightened) => Cow::Owned(heightened),
+            None => Cow::Borrowed(spell),
+        };
+        match &self.abbreviations {
+            Some(abbreviations) => Cow::Owned(abbreviations.apply(&spell)),
+            None => spell,
+        }
+    }
+}
+
+impl Default for SleeveFit {
+    fn default() -> Self {
+        Self {
+            inset: 1.5,
+            border_thickness: 2.5,
+        }
+    }
+}
+
+impl Default for PrintReady {
+    fn default() -> Self {
+        Self { bleed: 3.0 }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            uncommon_fill: Color::new(0.98, 0.83, 0.68),
+            rare_fill: Color::new(0.72, 0.8, 0.95),
+            unique_fill: Color::new(0.86, 0.75, 0.95),
+            border: BorderStyle::Thin,
+            trait_pills: false,
+            trait_pill_fill: Color::new(0.36, 0.0, 0.0),
+            trait_pill_text: Color::WHITE,
+            sigils: false,
+            fonts: ThemeFonts::default(),
+        }
+    }
+}
+
+impl Theme {
+    /// Non-common rarity traits are highlighted, so they are easy to spot.
+    pub fn rarity_fill(&self, trait_: &str) -> Option<Color> {
+        match Rarity::parse(trait_)? {
+            Rarity::Common => None,
+            Rarity::Uncommon => Some(self.uncommon_fill),
+            Rarity::Rare => Some(self.rare_fill),
+            Rarity::Unique => Some(self.unique_fill),
+        }
+    }
+
+    /// Box style of `trait_`. Pills of rarity traits use darker shades of rarity colors,
+    /// so light text stays readable.
+    pub fn trait_box(&self, trait_: &str) -> BoxStyle {
+        if !self.trait_pills {
+            return BoxStyle {
+                fill: self.rarity_fill(trait_),
+                ..BoxStyle::default()
+            };
+        }
+        let fill = match Rarity::parse(trait_) {
+            Some(Rarity::Uncommon) => Color::new(0.6, 0.32, 0.24),
+            Some(Rarity::Rare) => Color::new(0.0, 0.15, 0.39),
+            Some(Rarity::Unique) => Color::new(0.33, 0.09, 0.43),
+            Some(Rarity::Common) | None => self.trait_pill_fill,
+        };
+        BoxStyle {
+            fill: Some(fill),
+            border: false,
+            // Small enough to keep clear of text, which is only padded by a millimeter.
+            corner_radius: 2.0,
+            text_color: self.trait_pill_text,
+        }
+    }
+}
+
+impl Typography {
+    /// Typography with font sizes and spacing multiplied by `factor`.
+    pub fn scaled(&self, factor: f32) -> Self {
+        Self {
+            title_font_size: self.title_font_size * factor,
+            action_font_size: self.action_font_size * factor,
+            text_font_size: self.text_font_size * factor,
+            header_line_space: self.header_line_space * factor,
+            line_space: self.line_space * factor,
+            trait_padding: self.trait_padding * factor,
+            trait_chunk_space: self.trait_chunk_space * factor,
+            hanging_indent: self.hanging_indent * factor,
+            degree_style: self.degree_style,
+        }
+    }
+}
+
+impl Default for Typography {
+    fn default() -> Self {
+        Self {
+            title_font_size: 11.0,
+            action_font_size: 14.0,
+            text_font_size: 7.7,
+            header_line_space: 1.0,
+            line_space: 0.5,
+            trait_padding: 0.8,
+            trait_chunk_space: 0.3,
+            hanging_indent: 2.0,
+            degree_style: DegreeStyle::Abbreviated,
+        }
+    }
+}
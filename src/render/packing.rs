@@ -0,0 +1,115 @@
+use super::options::Placement;
+use std::cmp::Reverse;
+
+/// Card to place.
+#[derive(Debug, Clone, Copy)]
+pub struct CardSize {
+    /// Number of cells card takes.
+    pub span: usize,
+    /// Share of card height taken by content, from 0 to 1.
+    pub fill: f32,
+}
+
+/// Place `cards` into page columns of `height` cells, starting at cell `start`. Cells are
+/// numbered column by column: cell `i` is row `i % height` of column `i / height`.
+///
+/// Returns first cell of every card, `None` for cards taller than a column, and the cell
+/// following the last used one.
+///
+/// With `Placement::DeckOrder` cards are placed in given order, leaving a gap then a double
+/// card does not fit into the rest of a column. Otherwise cards are placed first fit
+/// decreasing: double cards go first, each into the first column with room, and single
+/// cards fill remaining gaps. This takes the fewest columns possible, and cards of the same
+/// span keep their order. `Placement::Balanced` then moves single cards between columns,
+/// see `balance`.
+pub fn pack(
+    cards: &[CardSize],
+    start: usize,
+    height: usize,
+    placement: Placement,
+) -> (Vec<Option<usize>>, usize) {
+    let keep_order = placement == Placement::DeckOrder;
+    let first_column = start / height;
+    // Number of filled cells of every column, starting with `first_column`.
+    let mut levels = vec![start % height];
+    let mut order = (0..cards.len()).collect::<Vec<_>>();
+    if !keep_order {
+        order.sort_by_key(|&i| Reverse(cards[i].span));
+    }
+
+    let mut result = vec![None; cards.len()];
+    for i in order {
+        let span = cards[i].span;
+        if span > height {
+            continue;
+        }
+        let fits = |level: &usize| level + span <= height;
+        let column = if keep_order {
+            Some(levels.len() - 1).filter(|&last| fits(&levels[last]))
+        } else {
+            levels.iter().position(fits)
+        };
+        let column = column.unwrap_or_else(|| {
+            levels.push(0);
+            levels.len() - 1
+        });
+        result[i] = Some((first_column + column) * height + levels[column]);
+        levels[column] += span;
+    }
+
+    if placement == Placement::Balanced {
+        balance(cards, &mut result, height);
+    }
+    let last = levels.len() - 1;
+    (result, (first_column + last) * height + levels[last])
+}
+
+/// Reassign cells taken by single cards, so every column gets a similar amount of content.
+/// Cards go longest first into the column with least content and a free cell, which pairs
+/// long cards with short ones. Inside a column cards keep given order, so related spells
+/// stay adjacent then they land in the same column.
+fn balance(cards: &[CardSize], positions: &mut [Option<usize>], height: usize) {
+    let Some(first_column) = positions.iter().flatten().map(|cell| cell / height).min() else {
+        return;
+    };
+    let columns = positions
+        .iter()
+        .flatten()
+        .map(|cell| cell / height - first_column + 1)
+        .max()
+        .unwrap_or(0);
+    // Content of double cards, which stay in place, and cells of single cards by column.
+    let mut totals = vec![0.0; columns];
+    let mut cells = vec![vec![]; columns];
+    let mut singles = vec![];
+    for (i, (card, position)) in cards.iter().zip(&*positions).enumerate() {
+        let Some(cell) = *position else {
+            continue;
+        };
+        let column = cell / height - first_column;
+        if card.span == 1 {
+            cells[column].push(cell);
+            singles.push(i);
+        } else {
+            totals[column] += card.fill * card.span as f32;
+        }
+    }
+
+    singles.sort_by(|&a, &b| cards[b].fill.total_cmp(&cards[a].fill));
+    let mut assigned = vec![vec![]; columns];
+    for i in singles {
+        let column = (0..columns)
+            .filter(|&column| assigned[column].len() < cells[column].len())
+            .min_by(|&a, &b| totals[a].total_cmp(&totals[b]))
+            .expect("every single card has a cell");
+        totals[column] += cards[i].fill;
+        assigned[column].push(i);
+    }
+    for (mut column_cells, mut column_cards) in cells.into_iter().zip(assigned) {
+        column_cells.sort_unstable();
+        column_cards.sort_unstable();
+        for (cell, i) in column_cells.into_iter().zip(column_cards) {
+            positions[i] = Some(cell);
+        }
+    }
+}
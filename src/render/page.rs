@@ -0,0 +1,146 @@
+// Everything is measured in Mm
+const GUIDE_LENGTH: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageSize {
+    A4,
+    Letter,
+}
+
+impl PageSize {
+    pub const ALL: [PageSize; 2] = [PageSize::A4, PageSize::Letter];
+
+    /// Width and height of paper.
+    pub fn dimensions(self) -> (f32, f32) {
+        match self {
+            PageSize::A4 => (210.0, 297.0),
+            PageSize::Letter => (215.9, 279.4),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PageSize::A4 => "A4",
+            PageSize::Letter => "Letter",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|size| size.as_str().eq_ignore_ascii_case(value))
+    }
+}
+
+/// Paper and card sizes. Cards are arranged in a centered grid, as many as fit on a page.
+#[derive(Debug, Clone, Copy)]
+pub struct PageLayout {
+    pub page_size: PageSize,
+    pub card_width: f32,
+    pub card_height: f32,
+    /// Space between card border and its content.
+    pub margin: f32,
+    /// Space between neighbouring cards on a page.
+    pub card_spacing: f32,
+    /// Draw cut guides on page margins, along card edges.
+    pub cut_guides: bool,
+}
+
+impl Default for PageLayout {
+    fn default() -> Self {
+        Self {
+            page_size: PageSize::A4,
+            card_width: 63.0,
+            card_height: 88.0,
+            margin: 1.0,
+            card_spacing: 2.0,
+            cut_guides: false,
+        }
+    }
+}
+
+impl PageLayout {
+    pub fn page_width(&self) -> f32 {
+        self.page_size.dimensions().0
+    }
+
+    pub fn page_height(&self) -> f32 {
+        self.page_size.dimensions().1
+    }
+
+    /// Number of card columns on a page.
+    pub fn grid_width(&self) -> usize {
+        self.fit_count(self.page_width(), self.card_width)
+    }
+
+    /// Number of card rows on a page.
+    pub fn grid_height(&self) -> usize {
+        self.fit_count(self.page_height(), self.card_height)
+    }
+
+    pub fn card_width_inner(&self) -> f32 {
+        self.card_width - 2.0 * self.margin
+    }
+
+    pub fn card_height_inner(&self) -> f32 {
+        self.card_height - 2.0 * self.margin
+    }
+
+    /// Lower left corner of card in grid cell `(x, y)`, counting rows from the top.
+    pub fn card_origin(&self, x: usize, y: usize) -> (f32, f32) {
+        let (padding_x, padding_y) = self.page_padding();
+        (
+            padding_x + (self.card_width + self.card_spacing) * x as f32,
+            padding_y
+                + (self.card_height + self.card_spacing) * (self.grid_height() - 1 - y) as f32,
+        )
+    }
+
+    /// Top left corner of card content in grid cell `(x, y)`, measured from the top left page corner.
+    pub fn content_top_left(&self, x: usize, y: usize) -> (f32, f32) {
+        let (left, bottom) = self.card_origin(x, y);
+        (
+            left + self.margin,
+            self.page_height() - bottom - self.card_height + self.margin,
+        )
+    }
+
+    /// Line segments of cut guides: short lines on page margins continuing card edges.
+    pub fn cut_guide_lines(&self) -> Vec<[(f32, f32); 2]> {
+        let (padding_x, padding_y) = self.page_padding();
+        let (page_width, page_height) = (self.page_width(), self.page_height());
+        let guide_x = GUIDE_LENGTH.min(padding_x);
+        let guide_y = GUIDE_LENGTH.min(padding_y);
+        let mut result = vec![];
+        for column in 0..self.grid_width() {
+            let left = padding_x + (self.card_width + self.card_spacing) * column as f32;
+            for x in [left, left + self.card_width] {
+                result.push([(x, 0.0), (x, guide_y)]);
+                result.push([(x, page_height - guide_y), (x, page_height)]);
+            }
+        }
+        for row in 0..self.grid_height() {
+            let bottom = padding_y + (self.card_height + self.card_spacing) * row as f32;
+            for y in [bottom, bottom + self.card_height] {
+                result.push([(0.0, y), (guide_x, y)]);
+                result.push([(page_width - guide_x, y), (page_width, y)]);
+            }
+        }
+        result
+    }
+
+    /// Distance from page edges to the card grid.
+    fn page_padding(&self) -> (f32, f32) {
+        let columns = self.grid_width() as f32;
+        let rows = self.grid_height() as f32;
+        let spacing = self.card_spacing;
+        (
+            (self.page_width() - self.card_width * columns - spacing * (columns - 1.0)) * 0.5,
+            (self.page_height() - self.card_height * rows - spacing * (rows - 1.0)) * 0.5,
+        )
+    }
+
+    fn fit_count(&self, space: f32, size: f32) -> usize {
+        (((space + self.card_spacing) / (size + self.card_spacing)) as usize).max(1)
+    }
+}
@@ -0,0 +1,118 @@
+use super::{write_pdf, DeckGroup, Manifest, ManifestEntry, RenderOptions};
+use crate::error::ExportError;
+use crate::spell::Spell;
+use std::io::Write;
+use std::ops::ControlFlow;
+
+type CardCallback<'h> = Box<dyn FnMut(&ManifestEntry, usize) -> ControlFlow<()> + 'h>;
+type PageCallback<'h> = Box<dyn FnMut(usize, usize) -> ControlFlow<()> + 'h>;
+
+/// Pdf export of a deck, like `write_to_pdf`, reporting progress to callbacks. Any of them
+/// can stop the export by returning `ControlFlow::Break`, which fails it with
+/// `ExportError::Cancelled`.
+///
+/// Cards are laid out first, and then drawn page by page, so callbacks run as pages are
+/// written, not while cards are fit.
+///
+/// ```no_run
+/// use spellcard_generator::render::{RenderOptions, Renderer};
+/// use std::ops::ControlFlow;
+/// # let spells: Vec<spellcard_generator::spell::Spell> = vec![];
+///
+/// let options = RenderOptions::default();
+/// let output = std::fs::File::create("deck.pdf")?;
+/// Renderer::new(&options)
+///     .on_page_finished(|page, pages| {
+///         eprintln!("Page {page} of {pages}");
+///         ControlFlow::Continue(())
+///     })
+///     .render(output, &spells)?;
+/// # anyhow::Ok(())
+/// ```
+pub struct Renderer<'a> {
+    options: &'a RenderOptions,
+    progress: Progress<'a>,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(options: &'a RenderOptions) -> Self {
+        Self {
+            options,
+            progress: Progress::default(),
+        }
+    }
+
+    /// Call `callback` with every spell card, and the number of spell cards in the deck,
+    /// once the page it is on is drawn. Cards come in order of `Manifest`.
+    pub fn on_card_rendered(
+        mut self,
+        callback: impl FnMut(&ManifestEntry, usize) -> ControlFlow<()> + 'a,
+    ) -> Self {
+        self.progress.on_card_rendered = Some(Box::new(callback));
+        self
+    }
+
+    /// Call `callback` with the number of every page once it is written, starting from 1,
+    /// and the number of pages in the deck. Cover, card backs and attribution are counted.
+    pub fn on_page_finished(
+        mut self,
+        callback: impl FnMut(usize, usize) -> ControlFlow<()> + 'a,
+    ) -> Self {
+        self.progress.on_page_finished = Some(Box::new(callback));
+        self
+    }
+
+    /// Write document containing all spells into `output`.
+    ///
+    /// Returns manifest listing cards in order they were placed.
+    pub fn render<'s, T: Write>(
+        mut self,
+        output: T,
+        spells: impl IntoIterator<Item = &'s Spell>,
+    ) -> Result<Manifest, ExportError> {
+        let title = match self.options.deck.name.trim() {
+            "" => "Spells",
+            name => name,
+        };
+        let group = DeckGroup {
+            spells: spells.into_iter().collect(),
+            options: self.options,
+        };
+        let mut manifests = write_pdf(output, title, &[group], false, &mut self.progress)?;
+        Ok(manifests.remove(0))
+    }
+}
+
+/// Callbacks of `Renderer`, none for plain exports.
+#[derive(Default)]
+pub(super) struct Progress<'h> {
+    on_card_rendered: Option<CardCallback<'h>>,
+    on_page_finished: Option<PageCallback<'h>>,
+}
+
+impl Progress<'_> {
+    pub(super) fn card_rendered(
+        &mut self,
+        card: &ManifestEntry,
+        total: usize,
+    ) -> Result<(), ExportError> {
+        match &mut self.on_card_rendered {
+            Some(callback) => proceed(callback(card, total)),
+            None => Ok(()),
+        }
+    }
+
+    pub(super) fn page_finished(&mut self, page: usize, total: usize) -> Result<(), ExportError> {
+        match &mut self.on_page_finished {
+            Some(callback) => proceed(callback(page, total)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn proceed(flow: ControlFlow<()>) -> Result<(), ExportError> {
+    match flow {
+        ControlFlow::Continue(()) => Ok(()),
+        ControlFlow::Break(()) => Err(ExportError::Cancelled),
+    }
+}
@@ -0,0 +1,188 @@
+use crate::rich_text::{Color, Polygon};
+use crate::spell::Spell;
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::vector::Vector2F;
+use std::f32::consts::TAU;
+
+/// Smallest side of free space, in `Pt`, worth drawing a sigil in. About 15 `Mm`.
+const MIN_SIGIL_SIZE: f32 = 42.0;
+/// Largest sigil diameter, in `Pt`, so big empty spaces of short spells are not dominated
+/// by it. About 30 `Mm`.
+const MAX_SIGIL_SIZE: f32 = 85.0;
+const CIRCLE_SEGMENTS: usize = 48;
+/// Width of sigil lines in `Pt`.
+const LINE_WIDTH: f32 = 0.7;
+
+/// Decoration for `spell` in `area`, centered in it: rings around a star polygon with marks
+/// on its points, all as filled paths. Shape is picked by spell name, so every spell gets its
+/// own sigil and it is the same on every export. Color is the one of its school trait, for
+/// spells of legacy data, or of its first tradition.
+///
+/// Empty if `area` is too small to hold a readable one.
+pub fn spell_sigil(spell: &Spell, area: RectF) -> Vec<Polygon> {
+    let size = area.width().min(area.height()).min(MAX_SIGIL_SIZE);
+    if size < MIN_SIGIL_SIZE {
+        return vec![];
+    }
+    let mut random = Xorshift::new(crc32fast::hash(spell.name.as_bytes()));
+    let color = sigil_color(spell);
+    let center = area.center();
+    let radius = size * 0.45;
+    let points = 5 + random.below(4);
+    // Star polygon `{points/step}`, regular polygon then step is 1. Edges join every point to
+    // the one `step` further, so stars of several loops, like a hexagram, are drawn whole.
+    let step = 1 + random.below((points - 1) / 2);
+    let rotation = random.unit() * TAU / points as f32;
+    let inner_ring = 0.3 + 0.25 * random.unit();
+    let vertex = |i: usize, radius: f32| {
+        let angle = rotation + TAU * i as f32 / points as f32;
+        center + Vector2F::new(angle.sin(), -angle.cos()) * radius
+    };
+
+    let mut result = vec![
+        ring(center, radius, LINE_WIDTH * 1.5, color),
+        ring(center, radius * 0.9, LINE_WIDTH, color),
+        ring(center, radius * inner_ring, LINE_WIDTH, color),
+    ];
+    let star_radius = radius * 0.9;
+    for i in 0..points {
+        let from = vertex(i, star_radius);
+        let to = vertex(i + step, star_radius);
+        result.push(line(from, to, LINE_WIDTH, color));
+    }
+    let mark = radius * 0.07;
+    for i in 0..points {
+        let tip = vertex(i, star_radius);
+        result.push(if random.below(2) == 0 {
+            disc(tip, mark, color)
+        } else {
+            diamond(tip, mark * 1.4, color)
+        });
+    }
+    result
+}
+
+/// Color of the sigil, light enough not to draw attention from text.
+fn sigil_color(spell: &Spell) -> Color {
+    let school = spell
+        .traits
+        .iter()
+        .find_map(|name| match name.to_lowercase().as_str() {
+            "abjuration" => Some(Color::new(0.25, 0.45, 0.75)),
+            "conjuration" => Some(Color::new(0.55, 0.4, 0.2)),
+            "divination" => Some(Color::new(0.2, 0.6, 0.65)),
+            "enchantment" => Some(Color::new(0.75, 0.3, 0.55)),
+            "evocation" => Some(Color::new(0.8, 0.3, 0.15)),
+            "illusion" => Some(Color::new(0.5, 0.35, 0.75)),
+            "necromancy" => Some(Color::new(0.3, 0.3, 0.3)),
+            "transmutation" => Some(Color::new(0.3, 0.6, 0.3)),
+            _ => None,
+        });
+    let traditions = &spell.traditions;
+    let tradition = if traditions.is_arcane {
+        Color::new(0.25, 0.4, 0.7)
+    } else if traditions.is_divine {
+        Color::new(0.75, 0.6, 0.2)
+    } else if traditions.is_occult {
+        Color::new(0.5, 0.3, 0.65)
+    } else if traditions.is_primal {
+        Color::new(0.3, 0.55, 0.25)
+    } else {
+        Color::new(0.45, 0.45, 0.45)
+    };
+    let Color { r, g, b } = school.unwrap_or(tradition);
+    let lighten = |c: f32| c + (1.0 - c) * 0.35;
+    Color::new(lighten(r), lighten(g), lighten(b))
+}
+
+/// Points of a circle approximated by a polygon, going clockwise in page coordinates, with
+/// the first point repeated at the end.
+fn circle_points(center: Vector2F, radius: f32) -> Vec<Vector2F> {
+    (0..=CIRCLE_SEGMENTS)
+        .map(|i| {
+            let angle = TAU * i as f32 / CIRCLE_SEGMENTS as f32;
+            center + Vector2F::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+/// Filled polygon, without outline, since outlines are always black.
+fn filled(points: Vec<Vector2F>, color: Color) -> Polygon {
+    Polygon {
+        points,
+        fill: Some(color),
+        thickness: 0.0,
+        stroke: false,
+    }
+}
+
+/// Circle line `width` wide, as outer circle joined with inner one going back, which
+/// leaves the inside empty with nonzero fill rule.
+fn ring(center: Vector2F, radius: f32, width: f32, color: Color) -> Polygon {
+    let mut points = circle_points(center, radius + width * 0.5);
+    points.extend(
+        circle_points(center, radius - width * 0.5)
+            .into_iter()
+            .rev(),
+    );
+    filled(points, color)
+}
+
+/// Line from `from` to `to`, `width` wide.
+fn line(from: Vector2F, to: Vector2F, width: f32, color: Color) -> Polygon {
+    let direction = to - from;
+    let length = direction.length().max(f32::EPSILON);
+    let side = Vector2F::new(-direction.y(), direction.x()) * (width * 0.5 / length);
+    filled(
+        vec![from + side, to + side, to - side, from - side, from + side],
+        color,
+    )
+}
+
+fn disc(center: Vector2F, radius: f32, color: Color) -> Polygon {
+    filled(circle_points(center, radius), color)
+}
+
+fn diamond(center: Vector2F, radius: f32, color: Color) -> Polygon {
+    let points = [
+        (0.0, -1.0),
+        (1.0, 0.0),
+        (0.0, 1.0),
+        (-1.0, 0.0),
+        (0.0, -1.0),
+    ]
+    .into_iter()
+    .map(|(x, y)| center + Vector2F::new(x, y) * radius)
+    .collect();
+    filled(points, color)
+}
+
+/// Small deterministic random generator, so sigils do not change between runs or versions
+/// of a random crate.
+struct Xorshift(u32);
+
+impl Xorshift {
+    fn new(seed: u32) -> Self {
+        // Zero state stays zero forever.
+        Self(seed.max(1))
+    }
+
+    fn next(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+
+    /// Number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        self.next() as usize % n.max(1)
+    }
+
+    /// Number in `0.0..1.0`.
+    fn unit(&mut self) -> f32 {
+        (self.next() >> 8) as f32 / (1 << 24) as f32
+    }
+}
@@ -5,10 +5,19 @@ use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
 
 const LINE_THICKNESS: f32 = 1.0;
+/// Invisible unless line breaks at it, then shown as hyphen.
+const SOFT_HYPHEN: char = '\u{ad}';
+/// Shown as space, but line never breaks at it.
+const NO_BREAK_SPACE: char = '\u{a0}';
+const HELVETICA: &[u8] = include_bytes!("../static/Helvetica.ttf");
 
 pub struct Font<T> {
+    /// Font file, also embedded into pdf for external fonts.
+    data: &'static [u8],
     font: Face<&'static [u8]>,
     font_ref: T,
     size_cache: RefCell<HashMap<char, Option<f32>>>,
@@ -20,15 +29,21 @@ pub enum FontKind {
     Text,
     Bold,
     Italic,
-    ActionCount,
+    /// Action cost glyphs, provided by game system.
+    Glyphs(&'static [u8]),
+    /// Font file replacing a built-in font, see `ThemeFonts`.
+    External(&'static [u8]),
 }
 
 impl FontKind {
     pub fn bytes(self) -> &'static [u8] {
         match self {
-            FontKind::Text | FontKind::Italic => include_bytes!("../static/Helvetica.ttf"),
+            FontKind::Text => HELVETICA,
+            // Regular face slanted by 12 degrees, with the same advances, so text lays out
+            // the same with either.
+            FontKind::Italic => include_bytes!("../static/Helvetica-Oblique.ttf"),
             FontKind::Bold => include_bytes!("../static/Helvetica-Bold.ttf"),
-            FontKind::ActionCount => include_bytes!("../static/Pathfinder2eActions.ttf"),
+            FontKind::Glyphs(bytes) | FontKind::External(bytes) => bytes,
         }
     }
 }
@@ -43,9 +58,11 @@ impl<T: FontProvider> Font<T> {
     pub fn build(provider_source: &mut T::Init, font: FontKind) -> Result<Self> {
         let font_ref = T::build_font(provider_source, font)?;
 
-        let font = Library::init()?.new_memory_face2(font.bytes(), 0)?;
+        let data = font.bytes();
+        let font = Library::init()?.new_memory_face2(data, 0)?;
         let units_per_em = font.em_size() as f32;
         Ok(Font {
+            data,
             font,
             font_ref,
             size_cache: RefCell::new(HashMap::new()),
@@ -67,6 +84,11 @@ impl<T> Font<T> {
         &self.font_ref
     }
 
+    /// Font file it was built from.
+    pub fn data(&self) -> &'static [u8] {
+        self.data
+    }
+
     fn char_width(&self, c: char) -> Option<f32> {
         let mut map = self.size_cache.borrow_mut();
         if let Some(result) = map.get(&c) {
@@ -86,15 +108,304 @@ impl<T> Font<T> {
     }
 }
 
+/// Color with components in range `0.0..=1.0`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::new(0.0, 0.0, 0.0);
+    pub const WHITE: Color = Color::new(1.0, 1.0, 1.0);
+
+    pub const fn new(r: f32, g: f32, b: f32) -> Self {
+        Self { r, g, b }
+    }
+}
+
+/// Look of a box around text, see `SceneBuilder::add_styled_boxed_text`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoxStyle {
+    pub fill: Option<Color>,
+    /// Draw box outline.
+    pub border: bool,
+    /// Corner radius in `Pt`, zero for square corners.
+    pub corner_radius: f32,
+    pub text_color: Color,
+}
+
+impl Default for BoxStyle {
+    /// Outlined box with square corners and black text.
+    fn default() -> Self {
+        Self {
+            fill: None,
+            border: true,
+            corner_radius: 0.0,
+            text_color: Color::BLACK,
+        }
+    }
+}
+
+/// Height of a band of content, see `SceneBuilder::add_band`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BandHeight {
+    /// As tall as band content.
+    Auto,
+    /// Given height in `Pt`. Band grows if its content is taller, rather than overlapping
+    /// the next one.
+    Fixed(f32),
+}
+
+impl BandHeight {
+    fn apply(self, content_height: f32) -> f32 {
+        match self {
+            Self::Auto => content_height,
+            Self::Fixed(height) => content_height.max(height),
+        }
+    }
+}
+
 /// Polygon to draw boxes
 pub struct Polygon {
     pub points: Vec<Vector2F>,
+    pub fill: Option<Color>,
+    /// Stroke width in `Pt`. Zero means the thinnest line output device can draw.
+    pub thickness: f32,
+    /// Draw outline. Only filled polygons go without one.
+    pub stroke: bool,
+}
+
+impl Polygon {
+    /// Outline of `rect`, drawn inside of it.
+    pub fn rect(rect: RectF, fill: Option<Color>, thickness: f32) -> Self {
+        let rect = rect.contract(LINE_THICKNESS.max(thickness * 0.5));
+        Self {
+            points: vec![
+                rect.origin(),
+                rect.upper_right(),
+                rect.lower_right(),
+                rect.lower_left(),
+                rect.origin(),
+            ],
+            fill,
+            thickness,
+            stroke: true,
+        }
+    }
+
+    /// Outline of `rect` with corners rounded by `radius`, drawn inside of it.
+    pub fn rounded_rect(rect: RectF, radius: f32, fill: Option<Color>, thickness: f32) -> Self {
+        const CORNER_SEGMENTS: usize = 6;
+        let rect = rect.contract(LINE_THICKNESS.max(thickness * 0.5));
+        let radius = radius
+            .min(rect.width() * 0.5)
+            .min(rect.height() * 0.5)
+            .max(0.0);
+        let inner = rect.contract(radius);
+        // Corner centers with angle arcs start at, clockwise in page coordinates.
+        let corners = [
+            (inner.upper_right(), -0.5),
+            (inner.lower_right(), 0.0),
+            (inner.lower_left(), 0.5),
+            (inner.origin(), 1.0),
+        ];
+        let mut points = vec![];
+        for (center, start) in corners {
+            for i in 0..=CORNER_SEGMENTS {
+                let angle =
+                    (start + 0.5 * i as f32 / CORNER_SEGMENTS as f32) * std::f32::consts::PI;
+                points.push(center + Vector2F::new(angle.cos(), angle.sin()) * radius);
+            }
+        }
+        points.push(points[0]);
+        Self {
+            points,
+            fill,
+            thickness,
+            stroke: true,
+        }
+    }
+}
+
+/// RGB picture with 8 bits per channel, rows going top to bottom.
+#[derive(Debug, Clone)]
+pub struct RasterImage {
+    pub width: usize,
+    pub height: usize,
+    /// `width * height * 3` bytes.
+    pub pixels: Vec<u8>,
+}
+
+/// Drawing operation of a scene.
+pub enum Primitive<'a, T> {
+    Text(TextChunk<'a, 'a, T>),
+    Path(Polygon),
+    /// Rectangle filled with color, without outline.
+    Rect(RectF, Color),
+    /// Picture stretched over rectangle.
+    Image(RectF, Rc<RasterImage>),
+}
+
+impl<'a, T> Primitive<'a, T> {
+    /// Smallest rectangle containing primitive.
+    pub fn bounds(&self) -> RectF {
+        match self {
+            Self::Text(chunk) => chunk.rect,
+            Self::Path(polygon) => {
+                let Some(&first) = polygon.points.first() else {
+                    return RectF::default();
+                };
+                let (min, max) = polygon
+                    .points
+                    .iter()
+                    .fold((first, first), |(min, max), &point| {
+                        (min.min(point), max.max(point))
+                    });
+                RectF::from_points(min, max)
+            }
+            Self::Rect(rect, _) | Self::Image(rect, _) => *rect,
+        }
+    }
+
+    pub fn as_text(&self) -> Option<&TextChunk<'a, 'a, T>> {
+        match self {
+            Self::Text(chunk) => Some(chunk),
+            _ => None,
+        }
+    }
+
+    fn translate(&mut self, offset: Vector2F) {
+        self.transform(|point| point + offset, 1.0);
+    }
+
+    /// Move every point through `transform`, which scales sizes by `scale`.
+    fn transform(&mut self, transform: impl Fn(Vector2F) -> Vector2F, scale: f32) {
+        let transform_rect = |rect: &mut RectF| {
+            *rect = RectF::new(transform(rect.origin()), rect.size() * scale);
+        };
+        match self {
+            Self::Text(chunk) => {
+                transform_rect(&mut chunk.rect);
+                chunk.font_size *= scale;
+            }
+            Self::Path(polygon) => {
+                for point in &mut polygon.points {
+                    *point = transform(*point);
+                }
+            }
+            Self::Rect(rect, _) | Self::Image(rect, _) => transform_rect(rect),
+        }
+    }
 }
 
 /// Scene to display
 pub struct Scene<'a, T> {
-    pub polygons: Vec<Polygon>,
-    pub parts: Vec<TextChunk<'a, 'a, T>>,
+    /// Content in drawing order, later primitives cover earlier ones.
+    pub primitives: Vec<Primitive<'a, T>>,
+    /// Vertical extents of named parts of content. Used for layout debugging.
+    pub sections: Vec<Section>,
+    /// Height of laid out content. Exceeds bounding box height if content does not fit.
+    pub content_height: f32,
+    pub bounding_box: RectF,
+}
+
+impl<'a, T> Scene<'a, T> {
+    /// Text chunks in drawing order.
+    pub fn texts(&self) -> impl Iterator<Item = &TextChunk<'a, 'a, T>> {
+        self.primitives.iter().filter_map(Primitive::as_text)
+    }
+
+    /// Primitive under `point`, with what it shows. Text is found before shapes drawn under
+    /// it, like trait boxes, and otherwise primitives drawn later before earlier ones.
+    pub fn hit_test(&self, point: Vector2F) -> Option<ElementRef<'_>> {
+        let is_hit = |index: &usize| self.primitives[*index].bounds().contains_point(point);
+        let mut indices = (0..self.primitives.len()).rev();
+        let index = indices
+            .clone()
+            .filter(|&index| self.primitives[index].as_text().is_some())
+            .find(is_hit)
+            .or_else(|| indices.find(is_hit))?;
+        let chunk = self.primitives[index].as_text();
+        Some(ElementRef {
+            index,
+            source: chunk.and_then(|chunk| chunk.source.as_deref()),
+            section: self
+                .sections
+                .iter()
+                .find(|section| (section.top..=section.bottom).contains(&point.y()))
+                .map(|section| section.name),
+        })
+    }
+
+    /// Add `primitive` under all other content, like a background.
+    pub fn push_under(&mut self, primitive: Primitive<'a, T>) {
+        self.primitives.insert(0, primitive);
+    }
+
+    /// Shrink content towards the center of bounding box, leaving at least `inset` `Pt`
+    /// of free space along each side. Proportions are kept.
+    pub fn inset(&mut self, inset: f32) {
+        let size = self.bounding_box.size();
+        let scale = ((size.x() - 2.0 * inset) / size.x())
+            .min((size.y() - 2.0 * inset) / size.y())
+            .max(0.0);
+        let center = self.bounding_box.center();
+        let transform = |point: Vector2F| center + (point - center) * scale;
+        let transform_y = |y: f32| center.y() + (y - center.y()) * scale;
+        for primitive in &mut self.primitives {
+            primitive.transform(transform, scale);
+        }
+        for section in &mut self.sections {
+            section.top = transform_y(section.top);
+            section.bottom = transform_y(section.bottom);
+        }
+        self.content_height = transform_y(self.content_height);
+    }
+
+    /// Lay out more content in band `name` of finished scene, like card number known only
+    /// once cards are placed on pages. `lay_out` fills the band through a builder of its own,
+    /// as with `SceneBuilder::add_footer_band`. Does nothing if scene has no such band.
+    pub fn fill_band(
+        &mut self,
+        name: &str,
+        default_font: &'a Font<T>,
+        lay_out: impl FnOnce(&mut SceneBuilder<'a, T>),
+    ) {
+        let Some(section) = self.sections.iter().find(|section| section.name == name) else {
+            return;
+        };
+        let size = Vector2F::new(self.bounding_box.width(), section.bottom - section.top);
+        let offset = Vector2F::new(0.0, section.top);
+        let mut builder = SceneBuilder::new(default_font, RectF::new(Vector2F::zero(), size));
+        lay_out(&mut builder);
+        builder.finish_line();
+        for mut primitive in builder.primitives {
+            primitive.translate(offset);
+            self.primitives.push(primitive);
+        }
+    }
+}
+
+/// Primitive of a scene found by `Scene::hit_test`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElementRef<'s> {
+    /// Index in `Scene::primitives`.
+    pub index: usize,
+    /// What text shows, like `trait:fire`, set by `SceneBuilder::set_source`. `None` for
+    /// shapes and unmarked text.
+    pub source: Option<&'s str>,
+    /// Section the point is in, like `description`.
+    pub section: Option<&'static str>,
+}
+
+/// Named horizontal band of scene, like `description`.
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub name: &'static str,
+    pub top: f32,
+    pub bottom: f32,
 }
 
 /// Builder for rich text rendering.
@@ -103,8 +414,10 @@ pub struct Scene<'a, T> {
 /// Coordinates are
 pub struct SceneBuilder<'a, T> {
     /// Prepared content.
-    chunks: Vec<TextChunk<'a, 'a, T>>,
-    polygons: Vec<Polygon>,
+    primitives: Vec<Primitive<'a, T>>,
+    sections: Vec<Section>,
+    /// Words too wide to fit even on an empty line. They are skipped.
+    failed_words: Vec<String>,
     /// Content which is still being laid out. Positions will change
     /// once line will be finilized.
     current_line: Vec<Block<'a, T>>,
@@ -116,37 +429,109 @@ pub struct SceneBuilder<'a, T> {
     x_offset: f32,
     /// y position of current line from top line of bounding box.
     y_offset: f32,
+    /// x position new lines start at.
+    indent: f32,
+    /// x position current line started at. Differs from `indent` if it was changed
+    /// in the middle of the line.
+    line_start: f32,
 
     align: AlignStrategy,
     font_size: f32,
 
     line_space: f32,
     chunk_space: f32,
+
+    /// Number of finished lines.
+    line_count: usize,
+    /// Tabs added since last `align_tabs`.
+    tab_marks: Vec<TabMark>,
+    /// Index in `current_line` of the first block of open no-break group.
+    no_break_start: Option<usize>,
+    /// Bands pinned to the bottom of bounding box, top to bottom.
+    footers: Vec<Footer<'a, T>>,
+    /// Source of text added from now on, see `set_source`.
+    source: Option<Rc<str>>,
+}
+
+/// Band added by `SceneBuilder::add_footer_band`, laid out with its top at zero.
+struct Footer<'a, T> {
+    name: &'static str,
+    primitives: Vec<Primitive<'a, T>>,
+    height: f32,
+}
+
+/// Place of `SceneBuilder::add_tab` call.
+struct TabMark {
+    /// Index in `current_line` of the first block after tab.
+    block: usize,
+    /// Primitives of blocks from tab to the end of line, known once line is finished.
+    primitives: Range<usize>,
+    line: usize,
 }
 
 impl<'a, T> SceneBuilder<'a, T> {
     pub fn new(default_font: &'a Font<T>, bounding_box: RectF) -> Self {
         let mut result = Self {
-            chunks: vec![],
-            polygons: vec![],
+            primitives: vec![],
+            sections: vec![],
+            failed_words: vec![],
             current_line: vec![],
             bounding_box,
             current_font: default_font,
             x_offset: 0.0,
             y_offset: 0.0,
+            indent: 0.0,
+            line_start: 0.0,
             align: AlignStrategy::AlignLeft,
             font_size: 10.0,
             line_space: 0.0,
             chunk_space: 0.0,
+            line_count: 0,
+            tab_marks: vec![],
+            no_break_start: None,
+            footers: vec![],
+            source: None,
         };
         result.set_default_chunk_space();
         result
     }
 
-    pub fn scene(self) -> Scene<'a, T> {
+    pub fn scene(mut self) -> Scene<'a, T> {
+        self.close_section();
+        let content_height = self.content_height();
+        let mut top = self.bounding_box.height() - self.footer_height();
+        for footer in std::mem::take(&mut self.footers) {
+            self.sections.push(Section {
+                name: footer.name,
+                top,
+                bottom: top + footer.height,
+            });
+            self.append_content(footer.primitives, Vector2F::new(0.0, top));
+            top += footer.height;
+        }
         Scene {
-            polygons: self.polygons,
-            parts: self.chunks,
+            primitives: self.primitives,
+            sections: self.sections,
+            content_height,
+            bounding_box: self.bounding_box,
+        }
+    }
+
+    /// Start new named section at the next line, ending previous one.
+    pub fn start_section(&mut self, name: &'static str) -> &mut Self {
+        self.finish_line();
+        self.close_section();
+        self.sections.push(Section {
+            name,
+            top: self.y_offset,
+            bottom: self.y_offset,
+        });
+        self
+    }
+
+    fn close_section(&mut self) {
+        if let Some(section) = self.sections.last_mut() {
+            section.bottom = section.bottom.max(self.y_offset);
         }
     }
 
@@ -162,7 +547,119 @@ impl<'a, T> SceneBuilder<'a, T> {
     }
 
     pub fn is_out_of_bounds(&self) -> bool {
-        self.y_offset >= self.bounding_box.height()
+        self.content_height() >= self.bounding_box.height()
+    }
+
+    /// Finish current line and leave empty space below it.
+    pub fn add_vertical_space(&mut self, space: f32) -> &mut Self {
+        self.finish_line();
+        self.y_offset += space;
+        self
+    }
+
+    /// Height of content laid out so far, footer bands included.
+    pub fn content_height(&self) -> f32 {
+        self.y_offset + self.footer_height()
+    }
+
+    /// Space left between content laid out so far and footer bands.
+    pub fn free_space(&self) -> RectF {
+        let height = (self.bounding_box.height() - self.content_height()).max(0.0);
+        RectF::new(
+            self.bounding_box.origin() + Vector2F::new(0.0, self.y_offset),
+            Vector2F::new(self.bounding_box.width(), height),
+        )
+    }
+
+    fn footer_height(&self) -> f32 {
+        self.footers.iter().map(|footer| footer.height).sum()
+    }
+
+    /// Lay out content of a named band starting at the next line, like card header or
+    /// description. Band is a section, and content following it starts below its `height`.
+    pub fn add_band(
+        &mut self,
+        name: &'static str,
+        height: BandHeight,
+        lay_out: impl FnOnce(&mut Self),
+    ) -> &mut Self {
+        self.start_section(name);
+        let top = self.y_offset;
+        lay_out(self);
+        self.finish_line();
+        self.y_offset = top + height.apply(self.y_offset - top);
+        self.close_section();
+        self
+    }
+
+    /// Lay out content of a named band pinned to the bottom of bounding box, below all other
+    /// content, like a source line. Space for it is reserved right away, and it stays at the
+    /// bottom if box is doubled. Footer bands added later go below earlier ones.
+    ///
+    /// `lay_out` fills the band through a builder of its own, as with `add_sub_box`.
+    pub fn add_footer_band(
+        &mut self,
+        name: &'static str,
+        height: BandHeight,
+        lay_out: impl FnOnce(&mut SceneBuilder<'a, T>),
+    ) -> &mut Self {
+        let mut inner = self.sub_builder(Vector2F::new(
+            self.bounding_box.width(),
+            self.bounding_box.height() - self.content_height(),
+        ));
+        lay_out(&mut inner);
+        inner.finish_line();
+        self.failed_words.append(&mut inner.failed_words);
+        self.footers.push(Footer {
+            name,
+            primitives: inner.primitives,
+            height: height.apply(inner.y_offset),
+        });
+        self
+    }
+
+    /// Empty builder of `size` starting with font, font size and spacing of this one.
+    fn sub_builder(&self, size: Vector2F) -> SceneBuilder<'a, T> {
+        let mut result = SceneBuilder::new(
+            self.current_font,
+            RectF::new(Vector2F::zero(), size.max(Vector2F::zero())),
+        );
+        result
+            .set_font_size(self.font_size)
+            .set_line_space(self.line_space)
+            .set_chunk_space(self.chunk_space);
+        result.source = self.source.clone();
+        result
+    }
+
+    /// Add content laid out by a sub builder, moved by `offset`.
+    fn append_content(&mut self, primitives: Vec<Primitive<'a, T>>, offset: Vector2F) {
+        for mut primitive in primitives {
+            primitive.translate(offset);
+            self.primitives.push(primitive);
+        }
+    }
+
+    /// Size of the smallest font of laid out text, `None` if there is no text.
+    pub fn smallest_font_size(&self) -> Option<f32> {
+        let footers = self.footers.iter().flat_map(|footer| &footer.primitives);
+        self.primitives
+            .iter()
+            .chain(footers)
+            .filter_map(Primitive::as_text)
+            .map(|chunk| chunk.font_size)
+            .min_by(f32::total_cmp)
+    }
+
+    pub fn failed_words(&self) -> &[String] {
+        &self.failed_words
+    }
+
+    /// Mark text added from now on as showing `source`, like `trait:fire` or `name`, found by
+    /// `Scene::hit_test`. `None` stops marking.
+    pub fn set_source(&mut self, source: Option<&str>) -> &mut Self {
+        self.source = source.map(Rc::from);
+        self
     }
 
     pub fn set_font(&mut self, font: &'a Font<T>) -> &mut Self {
@@ -184,6 +681,16 @@ impl<'a, T> SceneBuilder<'a, T> {
         self
     }
 
+    /// Set indent for lines started after this call. Used for hanging indents.
+    pub fn set_indent(&mut self, indent: f32) -> &mut Self {
+        self.indent = indent;
+        if self.current_line.is_empty() {
+            self.x_offset = indent;
+            self.line_start = indent;
+        }
+        self
+    }
+
     pub fn set_alignment(&mut self, align: AlignStrategy) -> &mut Self {
         self.align = align;
         self
@@ -192,41 +699,63 @@ impl<'a, T> SceneBuilder<'a, T> {
     pub fn add_separator_line(&mut self) -> &mut Self {
         self.finish_line();
         self.y_offset += self.line_space * 2.0;
-        self.polygons.push(Polygon {
+        self.primitives.push(Primitive::Path(Polygon {
             points: vec![
                 self.bounding_box.origin() + Vector2F::new(0.0, self.y_offset),
                 self.bounding_box.upper_right() + Vector2F::new(0.0, self.y_offset),
             ],
-        });
+            fill: None,
+            thickness: 0.0,
+            stroke: true,
+        }));
         self.y_offset += self.line_space;
         self
     }
 
     pub fn add_rect(&mut self, rect: RectF) -> &mut Self {
-        let rect = rect.contract(LINE_THICKNESS);
-        self.polygons.push(Polygon {
-            points: vec![
-                rect.origin(),
-                rect.upper_right(),
-                rect.lower_right(),
-                rect.lower_left(),
-                rect.origin(),
-            ],
-        });
+        self.add_box(rect, BoxStyle::default())
+    }
+
+    fn add_box(&mut self, rect: RectF, style: BoxStyle) -> &mut Self {
+        let mut polygon = match style.corner_radius > 0.0 {
+            true => Polygon::rounded_rect(rect, style.corner_radius, style.fill, 0.0),
+            false => Polygon::rect(rect, style.fill, 0.0),
+        };
+        polygon.stroke = style.border;
+        self.primitives.push(Primitive::Path(polygon));
         self
     }
 
     pub fn add_boxed_text(&mut self, text: &'a str, padding: f32) -> &mut Self {
-        let text_width = self.get_text_width(text);
+        self.add_styled_boxed_text(text, padding, BoxStyle::default())
+    }
+
+    /// Same as `add_boxed_text`, but box background is filled with `fill` color.
+    pub fn add_filled_boxed_text(&mut self, text: &'a str, padding: f32, fill: Color) -> &mut Self {
+        let style = BoxStyle {
+            fill: Some(fill),
+            ..BoxStyle::default()
+        };
+        self.add_styled_boxed_text(text, padding, style)
+    }
+
+    /// Text in a box of given `style`, like a filled pill with white text.
+    pub fn add_styled_boxed_text(
+        &mut self,
+        text: impl Into<Cow<'a, str>>,
+        padding: f32,
+        style: BoxStyle,
+    ) -> &mut Self {
+        let text = text.into();
+        let text_width = self.get_text_width(&text);
         let width = text_width + 2.0 * padding;
-        if width > self.bounding_box.width() {
-            panic!(
-                "Cannot fit `{text}`. Text required {width}Pt, but only {max_width}Pt available.",
-                max_width = self.bounding_box.width()
-            );
+        if width + self.x_offset > self.bounding_box.width() && !self.break_before_group() {
+            self.finish_line();
         }
         if width + self.x_offset > self.bounding_box.width() {
-            self.finish_line();
+            // Too wide even for an empty line, reported like words of plain text.
+            self.failed_words.push(text.to_string());
+            return self;
         }
 
         let rect = RectF::new(
@@ -235,19 +764,161 @@ impl<'a, T> SceneBuilder<'a, T> {
         );
         let block = Block::PaddedText {
             chunk: TextChunk {
-                text: Cow::from(text),
+                text: match text {
+                    Cow::Borrowed(text) => display_text(text),
+                    Cow::Owned(text) => Cow::Owned(display_text(&text).into_owned()),
+                },
                 rect,
                 font: self.current_font,
                 font_size: self.font_size,
+                color: style.text_color,
+                source: self.source.clone(),
             },
             padding,
-            border: true,
+            style,
         };
         self.x_offset += width + self.chunk_space;
         self.current_line.push(block);
         self
     }
 
+    /// Lay out content in a nested box starting on a new line, and continue below the box.
+    /// Box spans from current indent to the right edge, or is `width` wide if that is less.
+    /// Its frame is drawn in `style`, with `padding` between frame and content.
+    ///
+    /// `lay_out` fills the box through a builder of its own, which starts with font, font
+    /// size and spacing of this one. Sections started in it are dropped, box content belongs
+    /// to the section box is added to. Returns height taken by the box, frame included.
+    pub fn add_sub_box(
+        &mut self,
+        width: Option<f32>,
+        padding: f32,
+        style: BoxStyle,
+        lay_out: impl FnOnce(&mut SceneBuilder<'a, T>),
+    ) -> f32 {
+        self.finish_line();
+        let available = self.bounding_box.width() - self.indent;
+        let width = width.map_or(available, |width| width.min(available));
+        let top = self.y_offset;
+        let mut inner = self.sub_builder(Vector2F::new(
+            width - 2.0 * padding,
+            self.bounding_box.height() - self.content_height() - 2.0 * padding,
+        ));
+        lay_out(&mut inner);
+        inner.finish_line();
+
+        let height = inner.y_offset + 2.0 * padding;
+        let frame = RectF::new(
+            Vector2F::new(self.indent, top),
+            Vector2F::new(width, height),
+        );
+        if style.border || style.fill.is_some() {
+            self.add_box(frame, style);
+        }
+        let offset = frame.origin() + Vector2F::splat(padding);
+        self.append_content(inner.primitives, offset);
+        self.failed_words.append(&mut inner.failed_words);
+        self.y_offset = top + height + self.line_space;
+        height
+    }
+
+    /// Start group of content kept on one line: if a line break would fall inside the group,
+    /// whole group moves to the next line instead. Groups wider than a line break as usual.
+    pub fn begin_no_break_group(&mut self) -> &mut Self {
+        self.no_break_start = Some(self.current_line.len());
+        self
+    }
+
+    pub fn end_no_break_group(&mut self) -> &mut Self {
+        self.no_break_start = None;
+        self
+    }
+
+    /// Move open no-break group to the next line, if there is content before it. Returns
+    /// whether it was moved.
+    fn break_before_group(&mut self) -> bool {
+        let Some(start) = self.no_break_start.filter(|&start| start > 0) else {
+            return false;
+        };
+        if let Some(first) = self.current_line.get(start) {
+            // New line may start further right, if indent was changed in the middle of this one.
+            if self.indent + self.x_offset - first.left() > self.bounding_box.width() {
+                return false;
+            }
+        }
+        let group = self.current_line.split_off(start);
+        for mark in &mut self.tab_marks {
+            if mark.line == self.line_count && mark.block >= start {
+                mark.line += 1;
+                mark.block -= start;
+            }
+        }
+        self.finish_line();
+        // Gaps between blocks are kept.
+        let shift = group
+            .first()
+            .map_or(0.0, |first| self.x_offset - first.left());
+        for mut block in group {
+            block.align_to_left_line(block.left() + shift);
+            self.x_offset = block.left() + block.width() + self.chunk_space;
+            self.current_line.push(block);
+        }
+        true
+    }
+
+    /// Mark start of a column: text added after tab on this line is moved right by
+    /// `align_tabs`, so it starts at the same position on every line with a tab. Only one tab
+    /// per line is supported.
+    pub fn add_tab(&mut self) -> &mut Self {
+        self.tab_marks.push(TabMark {
+            block: self.current_line.len(),
+            primitives: 0..0,
+            line: self.line_count,
+        });
+        self
+    }
+
+    /// Finish current line and align text after tabs added since last call into a column.
+    /// Column starts after the longest text before a tab, but is moved left if needed, so
+    /// no line is pushed past right edge; lines which still do not reach it keep their
+    /// position. Lines where text after tab wraps are not moved either, since moved text
+    /// would not fit.
+    pub fn align_tabs(&mut self) -> &mut Self {
+        self.finish_line();
+        let marks = std::mem::take(&mut self.tab_marks);
+        let width = self.bounding_box.width();
+        // Start of text after tab, free space to the right of it and whether it wraps.
+        let lines = marks
+            .iter()
+            .enumerate()
+            .filter(|(_, mark)| !mark.primitives.is_empty())
+            .map(|(i, mark)| {
+                let next_line = marks.get(i + 1).map_or(self.line_count, |next| next.line);
+                let primitives = &self.primitives[mark.primitives.clone()];
+                let start = primitives[0].bounds().origin_x();
+                let end = primitives
+                    .iter()
+                    .map(|primitive| primitive.bounds().max_x())
+                    .fold(0.0, f32::max);
+                (mark, start, width - end, next_line > mark.line + 1)
+            })
+            .collect::<Vec<_>>();
+        let widest = lines.iter().map(|line| line.1).fold(0.0, f32::max);
+        let stop = lines
+            .iter()
+            .filter(|(_, _, _, wraps)| !wraps)
+            .map(|(_, start, room, _)| start + room)
+            .fold(widest, f32::min);
+        for (mark, start, _, wraps) in lines {
+            if !wraps && start < stop {
+                for primitive in &mut self.primitives[mark.primitives.clone()] {
+                    primitive.translate(Vector2F::new(stop - start, 0.0));
+                }
+            }
+        }
+        self
+    }
+
     pub fn add_text<'b: 'a>(&mut self, text: impl Into<Cow<'b, str>>) -> &mut Self {
         match text.into() {
             Cow::Borrowed(text) => self.add_text_str(text),
@@ -256,14 +927,19 @@ impl<'a, T> SceneBuilder<'a, T> {
     }
 
     fn add_text_owned(&mut self, text: String) -> &mut Self {
-        let mut text = text.trim();
+        let mut text = text.trim_matches(is_break_space);
         while !text.is_empty() {
             let (chunk, remaining) = self.split_chunk(text);
+            if !remaining.is_empty() && self.break_before_group() {
+                continue;
+            }
             if let Some(TextChunk {
                 text: chunk_text,
                 rect,
                 font,
                 font_size,
+                color,
+                source,
             }) = chunk
             {
                 let chunk_text: String = chunk_text.as_ref().to_string();
@@ -273,12 +949,14 @@ impl<'a, T> SceneBuilder<'a, T> {
                     rect,
                     font,
                     font_size,
+                    color,
+                    source,
                 }));
                 text = remaining;
             } else if self.current_line.is_empty() {
-                let text = &text[0..Self::next_word(text, 0)];
-                let width = self.get_text_width(text);
-                panic!("Cannot fit `{text}`. Text required {width}Pt, but only {max_width}Pt available.", max_width=self.bounding_box.width());
+                let word_end = Self::next_word(text, 0);
+                self.failed_words.push(text[..word_end].to_string());
+                text = text[word_end..].trim_start_matches(is_break_space);
             } else {
                 self.finish_line();
             }
@@ -287,17 +965,20 @@ impl<'a, T> SceneBuilder<'a, T> {
     }
 
     fn add_text_str(&mut self, text: &'a str) -> &mut Self {
-        let mut text = text.trim();
+        let mut text = text.trim_matches(is_break_space);
         while !text.is_empty() {
             let (chunk, remaining) = self.split_chunk(text);
+            if !remaining.is_empty() && self.break_before_group() {
+                continue;
+            }
             if let Some(chunk) = chunk {
                 self.x_offset += chunk.rect.width() + self.chunk_space;
                 self.current_line.push(Block::Text(chunk));
                 text = remaining;
             } else if self.current_line.is_empty() {
-                let text = &text[0..Self::next_word(text, 0)];
-                let width = self.get_text_width(text);
-                panic!("Cannot fit `{text}`. Text required {width}Pt, but only {max_width}Pt available.", max_width=self.bounding_box.width());
+                let word_end = Self::next_word(text, 0);
+                self.failed_words.push(text[..word_end].to_string());
+                text = text[word_end..].trim_start_matches(is_break_space);
             } else {
                 self.finish_line();
             }
@@ -316,11 +997,16 @@ impl<'a, T> SceneBuilder<'a, T> {
     }
 
     fn get_char_width(&self, c: char) -> f32 {
+        let c = match c {
+            SOFT_HYPHEN => return 0.0,
+            NO_BREAK_SPACE => ' ',
+            c => c,
+        };
         self.current_font.char_width(c).unwrap_or(0.0) * self.current_font.scale(self.font_size)
     }
 
     fn split_chunk<'b>(&mut self, text: &'b str) -> (Option<TextChunk<'a, 'b, T>>, &'b str) {
-        let text = text.trim();
+        let text = text.trim_matches(is_break_space);
         let mut offset = 0;
         let mut last_part = None;
         while offset < text.len() {
@@ -330,14 +1016,45 @@ impl<'a, T> SceneBuilder<'a, T> {
                 last_part = chunk;
                 offset = new_offset;
             } else {
-                return (last_part, &text[offset..]);
+                return match self.try_fit_hyphenated(text, offset, new_offset) {
+                    Some((chunk, remaining)) => (Some(chunk), remaining),
+                    None => (last_part, &text[offset..]),
+                };
             }
         }
 
         (last_part, &text[offset..])
     }
 
-    fn get_text_width(&self, text: &'a str) -> f32 {
+    /// Fit `text` up to the last soft hyphen of word `text[word_start..word_end]` that fits,
+    /// followed by hyphen. Returns chunk and text after the soft hyphen.
+    fn try_fit_hyphenated<'b>(
+        &self,
+        text: &'b str,
+        word_start: usize,
+        word_end: usize,
+    ) -> Option<(TextChunk<'a, 'b, T>, &'b str)> {
+        let word = &text[word_start..word_end];
+        let word_offset = word_start + (word.len() - word.trim_start_matches(is_break_space).len());
+        text[word_offset..word_end]
+            .rmatch_indices(SOFT_HYPHEN)
+            .filter(|(i, _)| *i > 0)
+            .find_map(|(i, _)| {
+                let hyphenated = format!("{}-", &text[..word_offset + i]);
+                let chunk = self.try_fit_chunk(&hyphenated)?;
+                let chunk = TextChunk {
+                    text: Cow::Owned(chunk.text.into_owned()),
+                    rect: chunk.rect,
+                    font: chunk.font,
+                    font_size: chunk.font_size,
+                    color: chunk.color,
+                    source: chunk.source,
+                };
+                Some((chunk, &text[word_offset + i + SOFT_HYPHEN.len_utf8()..]))
+            })
+    }
+
+    fn get_text_width(&self, text: &str) -> f32 {
         text.chars().map(|c| self.get_char_width(c)).sum::<f32>()
     }
 
@@ -353,19 +1070,21 @@ impl<'a, T> SceneBuilder<'a, T> {
             Vector2F::new(width, height),
         );
         let result = TextChunk {
-            text: Cow::from(text),
+            text: display_text(text),
             rect,
             font: self.current_font,
             font_size: self.font_size,
+            color: Color::BLACK,
+            source: self.source.clone(),
         };
         Some(result)
     }
 
     fn next_word(text: &str, offset: usize) -> usize {
         let slice = &text[offset..];
-        let stripped = slice.trim_start();
+        let stripped = slice.trim_start_matches(is_break_space);
         let spaces_skipped = slice.len() - stripped.len();
-        let first_whitespace = stripped.char_indices().find(|(_, c)| c.is_whitespace());
+        let first_whitespace = stripped.char_indices().find(|(_, c)| is_break_space(*c));
         if let Some((loc, _)) = first_whitespace {
             offset + spaces_skipped + loc
         } else {
@@ -385,30 +1104,47 @@ impl<'a, T> SceneBuilder<'a, T> {
             AlignStrategy::AlignRight => {
                 self.align_line_right(&mut line);
             }
+            AlignStrategy::AlignCenter => {
+                self.align_line_center(&mut line);
+            }
             AlignStrategy::JustifyEven => {
                 self.justify_line_even(&mut line);
             }
         }
+        let mut block_starts = vec![];
         for block in line {
+            block_starts.push(self.primitives.len());
             self.add_block(block);
         }
-        self.x_offset = 0.0;
+        let end = self.primitives.len();
+        for mark in &mut self.tab_marks {
+            if mark.line == self.line_count {
+                mark.primitives = block_starts.get(mark.block).map_or(end, |&start| start)..end;
+            }
+        }
+        self.line_count += 1;
+        if self.no_break_start.is_some() {
+            // Group did not fit on a line of its own, rest of it starts the new line.
+            self.no_break_start = Some(0);
+        }
+        self.x_offset = self.indent;
+        self.line_start = self.indent;
         self.y_offset += max_height + self.line_space;
         self
     }
 
     fn add_block(&mut self, block: Block<'a, T>) {
         match block {
-            Block::Text(chunk) => self.chunks.push(chunk),
+            Block::Text(chunk) => self.primitives.push(Primitive::Text(chunk)),
             Block::PaddedText {
                 chunk,
                 padding,
-                border,
+                style,
             } => {
-                if border {
-                    self.add_rect(chunk.rect.dilate(padding));
+                if style.border || style.fill.is_some() {
+                    self.add_box(chunk.rect.dilate(padding), style);
                 }
-                self.chunks.push(chunk);
+                self.primitives.push(Primitive::Text(chunk));
             }
         }
     }
@@ -437,14 +1173,25 @@ impl<'a, T> SceneBuilder<'a, T> {
         }
     }
 
+    fn align_line_center(&self, line: &mut [Block<'a, T>]) {
+        let width = line.iter().map(|chunk| chunk.width()).sum::<f32>()
+            + self.chunk_space * line.len().saturating_sub(1) as f32;
+        let mut x = self.line_start + (self.bounding_box.width() - self.line_start - width) * 0.5;
+        for chunk in line {
+            chunk.align_to_left_line(x);
+            x += chunk.width() + self.chunk_space;
+        }
+    }
+
     fn justify_line_even(&self, line: &mut [Block<'a, T>]) {
         if line.len() < 2 {
             return;
         }
-        let total_spacing =
-            self.bounding_box.width() - line.iter().map(|chunk| chunk.width()).sum::<f32>();
+        let total_spacing = self.bounding_box.width()
+            - self.line_start
+            - line.iter().map(|chunk| chunk.width()).sum::<f32>();
         let spacing = total_spacing / (line.len() - 1) as f32;
-        let mut x = 0.0;
+        let mut x = self.line_start;
         for chunk in line {
             chunk.align_to_left_line(x);
             x += chunk.width() + spacing;
@@ -459,6 +1206,9 @@ pub struct TextChunk<'a, 'b, T> {
     pub rect: RectF,
     pub font: &'a Font<T>,
     pub font_size: f32,
+    pub color: Color,
+    /// What the text shows, like `trait:fire`, see `SceneBuilder::set_source`.
+    pub source: Option<Rc<str>>,
 }
 
 #[derive(Debug)]
@@ -467,7 +1217,7 @@ pub enum Block<'a, T> {
     PaddedText {
         chunk: TextChunk<'a, 'a, T>,
         padding: f32,
-        border: bool,
+        style: BoxStyle,
     },
 }
 
@@ -486,6 +1236,14 @@ impl<'a, T> Block<'a, T> {
         }
     }
 
+    /// Left edge, including padding.
+    fn left(&self) -> f32 {
+        match self {
+            Self::Text(chunk) => chunk.rect.origin_x(),
+            Self::PaddedText { chunk, padding, .. } => chunk.rect.origin_x() - padding,
+        }
+    }
+
     fn align_to_left_line(&mut self, x_offset: f32) {
         match self {
             Self::Text(chunk) => {
@@ -524,11 +1282,26 @@ impl<'a, 'b, T> fmt::Debug for TextChunk<'a, 'b, T> {
 
 pub enum AlignStrategy {
     AlignLeft,
-    #[allow(dead_code)]
     AlignRight,
+    AlignCenter,
     JustifyEven,
 }
 
+/// Whitespace line can break at.
+fn is_break_space(c: char) -> bool {
+    c.is_whitespace() && c != NO_BREAK_SPACE
+}
+
+/// `text` as drawn: soft hyphens not used as break points are dropped, and no-break spaces
+/// become usual ones, which all fonts have.
+fn display_text(text: &str) -> Cow<'_, str> {
+    if text.contains([SOFT_HYPHEN, NO_BREAK_SPACE]) {
+        Cow::Owned(text.replace(SOFT_HYPHEN, "").replace(NO_BREAK_SPACE, " "))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
 fn set_origin_x(rect: &mut RectF, x: f32) {
     *rect = RectF::new(Vector2F::new(x, rect.origin_y()), rect.size());
 }
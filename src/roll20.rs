@@ -0,0 +1,38 @@
+use crate::html::spell_html;
+use crate::spell::Spell;
+use anyhow::Result;
+use std::collections::HashSet;
+use std::io::{Seek, Write};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Write zip archive with one Roll20 handout per spell. Handout is html, to be pasted
+/// into handout notes. Spells selected several times get single handout.
+pub fn write_handouts<'a, T: Write + Seek>(
+    output: T,
+    spells: impl IntoIterator<Item = &'a Spell>,
+) -> Result<()> {
+    let mut zip = ZipWriter::new(output);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut written = HashSet::new();
+    for spell in spells {
+        if !written.insert(spell.id) {
+            continue;
+        }
+        let name = format!("{:03} {}.html", written.len(), file_name(&spell.name));
+        zip.start_file(name, options)?;
+        zip.write_all(spell_html(spell).as_bytes())?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// Spell name, with characters not allowed in file names on some systems replaced.
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect()
+}
@@ -0,0 +1,281 @@
+use crate::json_utils::{JsonValueExt, ObjectExt};
+use crate::rich_text::Scene;
+use anyhow::{bail, Result};
+use json::JsonValue;
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::vector::Vector2F;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Distance in `Pt` below which positions and sizes count as equal. Snapshots are rounded
+/// to hundredths.
+const EPSILON: f32 = 0.011;
+
+/// Text chunk of a `SceneSnapshot`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkSnapshot {
+    pub text: String,
+    /// See `SceneBuilder::set_source`.
+    pub source: Option<String>,
+    pub rect: RectF,
+    pub font_size: f32,
+}
+
+/// Text layout of a `Scene`, which can be saved and compared with `diff` after layout
+/// changes. Shapes and fonts are left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneSnapshot {
+    pub bounding_box: RectF,
+    pub content_height: f32,
+    pub chunks: Vec<ChunkSnapshot>,
+}
+
+impl SceneSnapshot {
+    pub fn of<T>(scene: &Scene<'_, T>) -> Self {
+        Self {
+            bounding_box: round_rect(scene.bounding_box),
+            content_height: round(scene.content_height),
+            chunks: scene
+                .texts()
+                .map(|chunk| ChunkSnapshot {
+                    text: chunk.text.to_string(),
+                    source: chunk.source.as_deref().map(str::to_string),
+                    rect: round_rect(chunk.rect),
+                    font_size: round(chunk.font_size),
+                })
+                .collect(),
+        }
+    }
+
+    /// Json object with a chunk per line, so snapshots can be reviewed with usual diff too.
+    pub fn to_json(&self) -> String {
+        let chunks = self
+            .chunks
+            .iter()
+            .map(|chunk| {
+                let mut object = JsonValue::new_object();
+                object["text"] = chunk.text.as_str().into();
+                if let Some(source) = &chunk.source {
+                    object["source"] = source.as_str().into();
+                }
+                object["rect"] = rect_json(chunk.rect);
+                object["font_size"] = number_json(chunk.font_size);
+                format!("    {}", json::stringify(object))
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "{{\n  \"bounding_box\": {},\n  \"content_height\": {},\n  \"chunks\": [\n{}\n  ]\n}}\n",
+            json::stringify(rect_json(self.bounding_box)),
+            self.content_height,
+            chunks.join(",\n")
+        )
+    }
+
+    pub fn parse(source: &str) -> Result<Self> {
+        let snapshot = json::parse(source)?;
+        let snapshot = snapshot.as_object()?;
+        let chunks = snapshot
+            .get("chunks")
+            .map_or(Ok(&[][..]), JsonValue::as_array)?
+            .iter()
+            .map(|chunk| {
+                let chunk = chunk.as_object()?;
+                Ok(ChunkSnapshot {
+                    text: chunk.get_typed("text")?,
+                    source: chunk.get_typed_maybe("source")?,
+                    rect: parse_rect(chunk.get_typed("rect")?)?,
+                    font_size: chunk.get_typed("font_size")?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            bounding_box: parse_rect(snapshot.get_typed("bounding_box")?)?,
+            content_height: snapshot.get_typed("content_height")?,
+            chunks,
+        })
+    }
+}
+
+/// Change of a text chunk between two snapshots.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChunkChange {
+    /// Same size at another position.
+    Moved {
+        text: String,
+        from: RectF,
+        to: RectF,
+    },
+    /// Another size, or font size, and maybe position.
+    Resized {
+        text: String,
+        from: RectF,
+        to: RectF,
+    },
+    Added {
+        text: String,
+        rect: RectF,
+    },
+    Removed {
+        text: String,
+        rect: RectF,
+    },
+}
+
+impl fmt::Display for ChunkChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkChange::Moved { text, from, to } => {
+                let shift = to.origin() - from.origin();
+                write!(
+                    f,
+                    "moved {text:?} by ({:.2}, {:.2}) to {}",
+                    shift.x(),
+                    shift.y(),
+                    format_rect(*to)
+                )
+            }
+            ChunkChange::Resized { text, from, to } => {
+                write!(
+                    f,
+                    "resized {text:?} from {} to {}",
+                    format_rect(*from),
+                    format_rect(*to)
+                )
+            }
+            ChunkChange::Added { text, rect } => {
+                write!(f, "added {text:?} at {}", format_rect(*rect))
+            }
+            ChunkChange::Removed { text, rect } => {
+                write!(f, "removed {text:?} from {}", format_rect(*rect))
+            }
+        }
+    }
+}
+
+/// Differences of two snapshots of a scene, see `diff`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SceneDiff {
+    /// Content height before and after, if it changed.
+    pub content_height: Option<(f32, f32)>,
+    /// Changed chunks in order of the new scene, followed by removed ones.
+    pub changes: Vec<ChunkChange>,
+}
+
+impl SceneDiff {
+    pub fn is_empty(&self) -> bool {
+        self.content_height.is_none() && self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for SceneDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "layout is the same");
+        }
+        let mut lines = vec![];
+        if let Some((before, after)) = self.content_height {
+            lines.push(format!("content height {before:.2} -> {after:.2}"));
+        }
+        lines.extend(self.changes.iter().map(ChunkChange::to_string));
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Changes of text chunks from `before` to `after`. Chunks are matched by text and source,
+/// in order: second chunk reading `the` before a change is the second one after it.
+pub fn diff(before: &SceneSnapshot, after: &SceneSnapshot) -> SceneDiff {
+    let key = |chunk: &ChunkSnapshot| (chunk.text.clone(), chunk.source.clone());
+    let mut unmatched = HashMap::<_, Vec<&ChunkSnapshot>>::new();
+    for chunk in before.chunks.iter().rev() {
+        unmatched.entry(key(chunk)).or_default().push(chunk);
+    }
+    let mut changes = vec![];
+    for chunk in &after.chunks {
+        let text = chunk.text.clone();
+        let Some(old) = unmatched.get_mut(&key(chunk)).and_then(Vec::pop) else {
+            changes.push(ChunkChange::Added {
+                text,
+                rect: chunk.rect,
+            });
+            continue;
+        };
+        let (from, to) = (old.rect, chunk.rect);
+        if !same(from.size(), to.size()) || (old.font_size - chunk.font_size).abs() > EPSILON {
+            changes.push(ChunkChange::Resized { text, from, to });
+        } else if !same(from.origin(), to.origin()) {
+            changes.push(ChunkChange::Moved { text, from, to });
+        }
+    }
+    // Removed chunks in order of the old scene.
+    let mut removed = unmatched.into_values().flatten().collect::<Vec<_>>();
+    removed.sort_by_key(|chunk| {
+        before
+            .chunks
+            .iter()
+            .position(|other| std::ptr::eq(other, *chunk))
+    });
+    changes.extend(removed.into_iter().map(|chunk| ChunkChange::Removed {
+        text: chunk.text.clone(),
+        rect: chunk.rect,
+    }));
+    let content_height = (before.content_height - after.content_height).abs() > EPSILON;
+    SceneDiff {
+        content_height: content_height.then_some((before.content_height, after.content_height)),
+        changes,
+    }
+}
+
+fn same(a: Vector2F, b: Vector2F) -> bool {
+    (a - b).length() <= EPSILON
+}
+
+fn round(value: f32) -> f32 {
+    (value * 100.0).round() / 100.0
+}
+
+fn round_rect(rect: RectF) -> RectF {
+    RectF::new(
+        Vector2F::new(round(rect.origin_x()), round(rect.origin_y())),
+        Vector2F::new(round(rect.width()), round(rect.height())),
+    )
+}
+
+/// `[x, y, width, height]`.
+fn rect_json(rect: RectF) -> JsonValue {
+    JsonValue::Array(
+        [
+            rect.origin_x(),
+            rect.origin_y(),
+            rect.width(),
+            rect.height(),
+        ]
+        .into_iter()
+        .map(number_json)
+        .collect(),
+    )
+}
+
+/// Rounded in `f64`, so snapshots read `172.91` rather than `172.91000366210938`.
+fn number_json(value: f32) -> JsonValue {
+    ((value as f64 * 100.0).round() / 100.0).into()
+}
+
+fn parse_rect(values: Vec<f32>) -> Result<RectF> {
+    let [x, y, width, height] = values[..] else {
+        bail!("Expected rect as `[x, y, width, height]`");
+    };
+    Ok(RectF::new(
+        Vector2F::new(x, y),
+        Vector2F::new(width, height),
+    ))
+}
+
+fn format_rect(rect: RectF) -> String {
+    format!(
+        "({:.2}, {:.2}) {:.2}x{:.2}",
+        rect.origin_x(),
+        rect.origin_y(),
+        rect.width(),
+        rect.height()
+    )
+}
@@ -0,0 +1,156 @@
+use crate::db::Query;
+use crate::spell::Rarity;
+
+/// Piece of search text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// Word or quoted phrase, lowercased. `negated` if prefixed with `-`.
+    Phrase { text: String, negated: bool },
+    /// `OR` or `|` between alternatives.
+    Or,
+    /// `name:value` with known field name. `negated` if prefixed with `-`.
+    Field {
+        name: String,
+        value: String,
+        negated: bool,
+    },
+}
+
+const FIELDS: &[&str] = &["rank", "tr", "tradition", "trait", "save", "rarity"];
+
+/// Short description of the syntax, shown next to search entry.
+pub const SYNTAX_HINT: &str = "\
+fire cold\tboth words in name or traits
+\"cone of\"\texact phrase
+fire OR cold\teither word, also `fire | cold`
+-cantrip\texclude word
+rank:3\tspell rank
+tr:primal\ttradition, also `tradition:`
+trait:fire\tspell has trait
+-trait:fire\tspell lacks trait
+save:reflex\tdefense mentions text
+rarity:rare\tspell rarity";
+
+/// Fill text filters of `query` from search entry text.
+///
+/// Words and `"quoted phrases"` must all be found in spell name or traits, `-word` must not.
+/// `OR` (or `|`) between words makes either of them enough. Field prefixes, like `rank:3` or
+/// `tr:primal`, see `SYNTAX_HINT`, set filters of `query`, `-trait:fire` excludes trait.
+/// For example `fire OR cold -cantrip rank:3`.
+///
+/// Every filter prefixes can set is reset first, so filters of text previously applied to
+/// `query` don't stick once removed from search entry. Callers add their own filters after.
+pub fn apply(text: &str, query: &mut Query) {
+    query.phrases.clear();
+    query.excluded.clear();
+    query.traits.clear();
+    query.excluded_traits.clear();
+    query.defense = None;
+    query.spell_rank = None;
+    query.is_arcane = false;
+    query.is_primal = false;
+    query.is_divine = false;
+    query.is_occult = false;
+    query.rarity = None;
+    let mut alternative = false;
+    for token in tokenize(text) {
+        match token {
+            Token::Phrase {
+                text,
+                negated: true,
+            } => query.excluded.push(text),
+            Token::Phrase {
+                text,
+                negated: false,
+            } => match query.phrases.last_mut() {
+                Some(group) if alternative => group.push(text),
+                _ => query.phrases.push(vec![text]),
+            },
+            Token::Or => {
+                alternative = true;
+                continue;
+            }
+            Token::Field {
+                name,
+                value,
+                negated,
+            } => apply_field(&name, &value, negated, query),
+        }
+        alternative = false;
+    }
+}
+
+/// Apply `name:value` prefix. Invalid values are ignored, as they are likely being typed,
+/// and so are negated fields other than `trait`.
+fn apply_field(name: &str, value: &str, negated: bool, query: &mut Query) {
+    let value = value.to_lowercase();
+    if negated {
+        if name == "trait" && !value.is_empty() {
+            query.excluded_traits.push(value);
+        }
+        return;
+    }
+    match name {
+        "rank" => query.spell_rank = value.parse().ok().or(query.spell_rank),
+        "tr" | "tradition" => match value.as_str() {
+            "arcane" => query.is_arcane = true,
+            "primal" => query.is_primal = true,
+            "divine" => query.is_divine = true,
+            "occult" => query.is_occult = true,
+            _ => {}
+        },
+        "trait" if !value.is_empty() => query.traits.push(value),
+        "save" if !value.is_empty() => query.defense = Some(value),
+        "rarity" => query.rarity = Rarity::parse(&value).or(query.rarity),
+        _ => {}
+    }
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        let (negated, body) = match rest.strip_prefix('-') {
+            Some(body) if !body.is_empty() && !body.starts_with(char::is_whitespace) => {
+                (true, body)
+            }
+            _ => (false, rest),
+        };
+        let (word, quoted, remaining) = match body.strip_prefix('"') {
+            Some(quoted) => match quoted.find('"') {
+                Some(end) => (&quoted[..end], true, &quoted[end + 1..]),
+                // Unterminated quote takes the rest of the text.
+                None => (quoted, true, ""),
+            },
+            None => {
+                let end = body.find(char::is_whitespace).unwrap_or(body.len());
+                (&body[..end], false, &body[end..])
+            }
+        };
+        rest = remaining.trim_start();
+
+        if !quoted && !negated && (word == "OR" || word == "|") {
+            tokens.push(Token::Or);
+            continue;
+        }
+        if !quoted {
+            if let Some((name, value)) = word.split_once(':') {
+                let name = name.to_lowercase();
+                if FIELDS.contains(&name.as_str()) {
+                    tokens.push(Token::Field {
+                        name,
+                        value: value.to_string(),
+                        negated,
+                    });
+                    continue;
+                }
+            }
+        }
+        let text = word.trim().to_lowercase();
+        // Lone `-` is most likely exclusion being typed.
+        if !text.is_empty() && text != "-" {
+            tokens.push(Token::Phrase { text, negated });
+        }
+    }
+    tokens
+}
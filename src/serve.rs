@@ -0,0 +1,379 @@
+use crate::app_config::AppConfig;
+use crate::db::{Query, SimpleSpellDB, SpellDB};
+use crate::deck::{self, DeckFile};
+use crate::search_syntax;
+use anyhow::{anyhow, bail, Context, Result};
+use json::JsonValue;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Listens on loopback only, so the engine is not exposed to the network unless asked to.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:8787";
+/// Largest accepted request body, a deck file is a few kilobytes.
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+/// Largest accepted request line or header.
+const MAX_LINE_SIZE: usize = 8 * 1024;
+/// Search results returned unless request gives `limit`.
+const DEFAULT_LIMIT: usize = 50;
+/// Time a client has to send its whole request, or to take the whole response, before the
+/// connection is dropped, however slowly data keeps coming.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Connections read at once. Further ones are dropped until some finish.
+const MAX_CONNECTIONS: usize = 16;
+
+/// Serve spell search and deck rendering over http on `address`, until the process is killed.
+/// Connections are read and written on threads of their own, so a slow client does not hold
+/// up others, and complete requests are answered one at a time:
+///
+/// - `GET /search?q=fire%20rank:3&limit=20`: spells matching query in search entry syntax,
+///   see `search_syntax`, as json array of `{"id", "name", "rank", "traits", "source", "summary"}`.
+/// - `POST /render`: deck file in request body, see `DeckFile`, rendered to pdf. Export
+///   settings saved with the deck are applied over `config`. Spells which are not found are
+///   skipped.
+///
+/// Browsers may call it only from `origin`, like `http://localhost:30000` of a Foundry module.
+/// Requests sent by pages of other origins are refused, and without `origin` all requests
+/// made by browsers are, so a visited web page cannot use the api.
+pub fn serve(
+    db: &SimpleSpellDB,
+    config: &AppConfig,
+    address: &str,
+    origin: Option<&str>,
+) -> Result<()> {
+    let listener =
+        TcpListener::bind(address).with_context(|| format!("Unable to listen on `{address}`"))?;
+    tracing::info!("Serving spell card api on http://{address}");
+    serve_on(db, config, listener, origin)
+}
+
+/// Serve api like `serve`, on connections of bound `listener`.
+pub fn serve_on(
+    db: &SimpleSpellDB,
+    config: &AppConfig,
+    listener: TcpListener,
+    origin: Option<&str>,
+) -> Result<()> {
+    let (sender, requests) = mpsc::channel();
+    let origin = origin.map(str::to_string);
+    std::thread::spawn(move || accept_connections(listener, sender, origin));
+    for (request, reply) in requests {
+        tracing::info!("{} {}", request.method, request.path);
+        let response = handle_request(db, config, &request)
+            .unwrap_or_else(|error| Response::text("400 Bad Request", format!("{error:#}")));
+        // Client may have given up waiting.
+        let _ = reply.send(response);
+    }
+    Ok(())
+}
+
+/// Request read from a connection, with the channel its response goes to.
+type Pending = (Request, Sender<Response>);
+
+/// Read requests of `listener` connections, each on a thread of its own, and pass them to
+/// `requests`.
+fn accept_connections(listener: TcpListener, requests: Sender<Pending>, origin: Option<String>) {
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                tracing::warn!("Unable to accept connection: {error:#}");
+                continue;
+            }
+        };
+        if active.load(Ordering::SeqCst) >= MAX_CONNECTIONS {
+            tracing::warn!("Dropped connection, {MAX_CONNECTIONS} are already open");
+            continue;
+        }
+        active.fetch_add(1, Ordering::SeqCst);
+        let (requests, origin, active) = (requests.clone(), origin.clone(), active.clone());
+        std::thread::spawn(move || {
+            if let Err(error) = handle_connection(stream, &requests, origin.as_deref()) {
+                tracing::warn!("Unable to answer request: {error:#}");
+            }
+            active.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    /// Decoded query string parameters.
+    params: Vec<(String, String)>,
+    /// `Origin` header, sent by browsers with requests of web pages.
+    origin: Option<String>,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl Response {
+    fn text(status: &'static str, text: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: text.into().into_bytes(),
+        }
+    }
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    requests: &Sender<Pending>,
+    origin: Option<&str>,
+) -> Result<()> {
+    let mut reader = BufReader::new(Deadline::new(stream.try_clone()?, READ_TIMEOUT));
+    let response = match read_request(&mut reader) {
+        Ok(request) if request.origin.is_some() && request.origin.as_deref() != origin => {
+            tracing::warn!(
+                "Refused {} {} from other origin",
+                request.method,
+                request.path
+            );
+            Response::text("403 Forbidden", "Origin is not allowed")
+        }
+        Ok(request) => {
+            let (reply, response) = mpsc::channel();
+            requests
+                .send((request, reply))
+                .map_err(|_| anyhow!("Server is stopped"))?;
+            response.recv().context("Server is stopped")?
+        }
+        Err(error) => Response::text("400 Bad Request", format!("{error:#}")),
+    };
+    write_response(Deadline::new(stream, WRITE_TIMEOUT), &response, origin)
+}
+
+/// Stream which fails reads and writes once `timeout` given on creation has passed, rather
+/// than only those waiting that long for data.
+struct Deadline {
+    stream: TcpStream,
+    deadline: Instant,
+}
+
+impl Deadline {
+    fn new(stream: TcpStream, timeout: Duration) -> Self {
+        Self {
+            stream,
+            deadline: Instant::now() + timeout,
+        }
+    }
+
+    /// Time left, error if there is none.
+    fn left(&self) -> std::io::Result<Duration> {
+        let left = self.deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "Connection took too long",
+            ));
+        }
+        Ok(left)
+    }
+}
+
+impl Read for Deadline {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.stream.set_read_timeout(Some(self.left()?))?;
+        self.stream.read(buf)
+    }
+}
+
+impl Write for Deadline {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.stream.set_write_timeout(Some(self.left()?))?;
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+fn handle_request(db: &SimpleSpellDB, config: &AppConfig, request: &Request) -> Result<Response> {
+    match (request.method.as_str(), request.path.as_str()) {
+        // Preflight of cross origin requests, allowed headers are sent with every response.
+        ("OPTIONS", _) => Ok(Response::text("204 No Content", "")),
+        ("GET", "/search") => search(db, request),
+        ("POST", "/render") => render(db, config, request),
+        (_, "/search" | "/render") => Ok(Response::text("405 Method Not Allowed", "")),
+        _ => Ok(Response::text("404 Not Found", "Unknown endpoint")),
+    }
+}
+
+fn search(db: &SimpleSpellDB, request: &Request) -> Result<Response> {
+    let mut query = Query::default();
+    search_syntax::apply(request.param("q").unwrap_or(""), &mut query);
+    let limit = match request.param("limit") {
+        Some(limit) => limit
+            .parse()
+            .context("Parameter `limit` must be a number")?,
+        None => DEFAULT_LIMIT,
+    };
+    let spells = db
+        .search(&query)
+        .into_iter()
+        .take(limit)
+        .map(|spell| {
+            let mut result = JsonValue::new_object();
+            result["id"] = spell.id.into();
+            result["name"] = spell.name.as_str().into();
+            result["rank"] = spell.level.into();
+            result["traits"] = spell.traits.clone().into();
+            result["source"] = spell.source.as_str().into();
+            result["summary"] = spell.summary.as_str().into();
+            result
+        })
+        .collect::<Vec<_>>();
+    Ok(Response {
+        status: "200 OK",
+        content_type: "application/json",
+        body: json::stringify(spells).into_bytes(),
+    })
+}
+
+fn render(db: &SimpleSpellDB, config: &AppConfig, request: &Request) -> Result<Response> {
+    let source = std::str::from_utf8(&request.body).context("Deck is not valid utf-8")?;
+    let deck_file = DeckFile::parse(source)?;
+    let mut pdf = vec![];
+    for mismatch in deck::write_deck_pdf(&mut pdf, db, config, deck_file)? {
+        tracing::warn!("{mismatch}");
+    }
+    Ok(Response {
+        status: "200 OK",
+        content_type: "application/pdf",
+        body: pdf,
+    })
+}
+
+fn read_request(reader: &mut impl BufRead) -> Result<Request> {
+    let request_line = read_line(reader)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        bail!("Malformed request line");
+    };
+    let mut content_length = 0;
+    let mut origin = None;
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let name = name.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().context("Invalid `Content-Length`")?;
+            } else if name.eq_ignore_ascii_case("origin") {
+                origin = Some(value.trim().to_string());
+            }
+        }
+    }
+    if content_length > MAX_BODY_SIZE {
+        bail!("Request body is too large");
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let params = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Ok((percent_decode(key)?, percent_decode(value)?))
+        })
+        .collect::<Result<_>>()?;
+    Ok(Request {
+        method: method.to_string(),
+        path: percent_decode(path)?,
+        params,
+        origin,
+        body,
+    })
+}
+
+/// Line without `\r\n`, which must fit `MAX_LINE_SIZE`.
+fn read_line(reader: &mut impl BufRead) -> Result<String> {
+    let mut line = vec![];
+    reader
+        .take(MAX_LINE_SIZE as u64 + 1)
+        .read_until(b'\n', &mut line)?;
+    if line.len() > MAX_LINE_SIZE {
+        bail!("Request header is too long");
+    }
+    if line.last() != Some(&b'\n') {
+        bail!("Connection closed before request was complete");
+    }
+    let line = String::from_utf8(line).context("Request header is not valid utf-8")?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Decode `%xx` escapes of url component, and `+` as space.
+fn percent_decode(text: &str) -> Result<String> {
+    let mut result = vec![];
+    let mut bytes = text.bytes();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'+' => result.push(b' '),
+            b'%' => {
+                let hex = [bytes.next(), bytes.next()];
+                let [Some(high), Some(low)] = hex else {
+                    bail!("Truncated escape in `{text}`");
+                };
+                let hex = std::str::from_utf8(&[high, low])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| anyhow!("Invalid escape in `{text}`"))?;
+                result.push(hex);
+            }
+            byte => result.push(byte),
+        }
+    }
+    String::from_utf8(result).with_context(|| format!("`{text}` is not valid utf-8"))
+}
+
+/// Write `response`, allowing browsers to read it from pages of `origin`.
+fn write_response(mut stream: impl Write, response: &Response, origin: Option<&str>) -> Result<()> {
+    let cors = match origin {
+        Some(origin) => format!(
+            "Access-Control-Allow-Origin: {origin}\r\n\
+            Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+            Access-Control-Allow-Headers: Content-Type\r\n\
+            Vary: Origin\r\n"
+        ),
+        None => String::new(),
+    };
+    let header = format!(
+        "HTTP/1.1 {status}\r\n\
+        Content-Type: {content_type}\r\n\
+        Content-Length: {length}\r\n\
+        {cors}\
+        Connection: close\r\n\r\n",
+        status = response.status,
+        content_type = response.content_type,
+        length = response.body.len(),
+    );
+    stream.write_all(header.as_bytes())?;
+    stream.write_all(&response.body)?;
+    stream.flush()?;
+    Ok(())
+}
@@ -1,7 +1,9 @@
-use crate::json_utils::ObjectExt;
-use anyhow::{anyhow, bail, Result};
+use crate::error::ParseError;
+use crate::json_utils::{ObjectExt, TypedParse};
+use crate::references::{expand_wikilinks, find_references, CrossReference};
 use json::object::Object;
 use std::borrow::Cow;
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub struct Spell {
@@ -10,6 +12,7 @@ pub struct Spell {
     pub level: u8,
     pub spell_type: SpellType,
     pub traits: Vec<String>,
+    pub rarity: Rarity,
     pub actions: Actions,
     pub properties: Vec<Property>,
     pub description: String,
@@ -17,9 +20,23 @@ pub struct Spell {
     pub heightened: Option<String>,
     pub extras: Vec<String>,
     pub traditions: Traditions,
+    /// Book spell was published in, like `Player Core`.
+    pub source: String,
+    /// Path of spell page on Archives of Nethys, like `/Spells.aspx?ID=565`. `None` for
+    /// spells without a page, like homebrew ones.
+    pub url: Option<String>,
+    /// Name of homebrew bundle spell was loaded from. `None` for built-in data.
+    pub bundle: Option<String>,
+    /// License attribution given in the bundle, see `SimpleSpellDB::merge_bundle`.
+    pub license: Option<Arc<str>>,
+    /// Built-in spell standing in for one missing from localized bundles, see
+    /// `SimpleSpellDB::merge_bundle`. Its card is marked with a language label.
+    pub untranslated: bool,
+    /// Spells and conditions linked from description, heightened and extra sections.
+    pub references: Vec<CrossReference>,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Default)]
 pub struct Traditions {
     pub is_arcane: bool,
     pub is_primal: bool,
@@ -27,6 +44,23 @@ pub struct Traditions {
     pub is_occult: bool,
 }
 
+/// Single tradition, like spell list of a class.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Tradition {
+    Arcane,
+    Primal,
+    Divine,
+    Occult,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Unique,
+}
+
 /// Various properties like area, target or distance
 #[derive(Debug, Clone)]
 pub struct Property {
@@ -41,7 +75,7 @@ pub enum SpellType {
     Cantrip,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Actions {
     Number(u8),
     Range(u8, u8),
@@ -51,83 +85,105 @@ pub enum Actions {
 }
 
 impl Spell {
-    pub fn parse(object: &Object) -> Result<Spell> {
-        Self::parse_(object).map_err(|err| {
-            let name = object
-                .get_typed("name")
-                .unwrap_or_else(|_| "no-name".to_string());
-            err.context(format!("Unable to parse spell `{name}`."))
+    /// Parse spell in Archives of Nethys format. `properties` lists json fields shown as
+    /// properties, with their names, like `("range_raw", "Range")`.
+    pub fn parse(object: &Object, properties: &[(&str, &str)]) -> Result<Spell, ParseError> {
+        Self::parse_(object, properties).map_err(|err| ParseError {
+            spell: object.get_typed("name").ok(),
+            spell_id: Self::parse_id(object).ok(),
+            ..err
         })
     }
 
-    fn parse_(object: &Object) -> Result<Spell> {
-        let name = object
-            .get_typed("name")
-            .map_err(|err| err.context("Unable to parse Spell."))?;
-        let (description, heightened, extras) =
-            Self::parse_markdown(&object.get_typed::<String>("markdown")?)?;
-        let traditions = Traditions::parse(
-            object
-                .get_typed_maybe::<Vec<String>>("tradition")?
-                .unwrap_or(vec![]),
-        );
+    fn parse_(object: &Object, properties: &[(&str, &str)]) -> Result<Spell, ParseError> {
+        let name = required(object, "name")?;
+        let markdown = required::<String>(object, "markdown")?;
+        let (description, heightened, extras) = Self::parse_markdown(&expand_wikilinks(&markdown))
+            .ok_or_else(|| {
+                ParseError::field("markdown", "Unable to extract description and heightened.")
+            })?;
+        let mut references = vec![];
+        for section in std::iter::once(&description)
+            .chain(&heightened)
+            .chain(&extras)
+        {
+            find_references(section, &mut references);
+        }
+        let traditions =
+            Traditions::parse(optional::<Vec<String>>(object, "tradition")?.unwrap_or(vec![]));
+        let source = optional::<Vec<String>>(object, "source")?
+            .and_then(|sources| sources.into_iter().next())
+            .unwrap_or_default();
+
+        let traits = Self::parse_traits(object)?;
 
         Ok(Spell {
             id: Self::parse_id(object)?,
             name,
-            level: object.get_typed("level")?,
-            spell_type: SpellType::parse(&object.get_typed::<String>("category")?)?,
-            traits: Self::parse_traits(object)?,
-            actions: Actions::parse(object.get_typed::<String>("actions")?)?,
-            properties: Self::parse_properties(object)?,
+            level: required(object, "level")?,
+            spell_type: SpellType::parse(&required::<String>(object, "category")?)?,
+            rarity: Rarity::from_traits(&traits),
+            traits,
+            actions: Actions::parse(required(object, "actions")?),
+            properties: Self::parse_properties(object, properties)?,
             description,
-            summary: object.get_typed::<String>("summary")?,
+            summary: required(object, "summary")?,
             heightened,
             extras,
             traditions,
+            source,
+            url: optional(object, "url")?,
+            bundle: None,
+            license: None,
+            untranslated: false,
+            references,
         })
     }
 
-    fn parse_id(object: &Object) -> Result<usize> {
-        let id = object.get_typed::<String>("id")?;
-        if !id.starts_with("spell-") {
-            bail!("Invalid Id format!");
-        }
-        Ok(id[6..].parse()?)
+    fn parse_id(object: &Object) -> Result<usize, ParseError> {
+        let id = required::<String>(object, "id")?;
+        id.strip_prefix("spell-")
+            .and_then(|number| number.parse().ok())
+            .ok_or_else(|| {
+                ParseError::field("id", format!("Must look like `spell-123`, not `{id}`"))
+            })
     }
 
-    fn parse_markdown(markdown: &str) -> Result<(String, Option<String>, Vec<String>)> {
+    /// Description, heightened and extra sections, `None` without `---` separators.
+    fn parse_markdown(markdown: &str) -> Option<(String, Option<String>, Vec<String>)> {
         match markdown.split("---").collect::<Vec<_>>().as_slice() {
-            [_, description, heightened, ref extras @ ..] => Ok((
+            [_, description, heightened, ref extras @ ..] => Some((
                 description.trim().to_string(),
                 Some(heightened.trim().to_string()),
-                extras.iter().map(|s| s.to_string()).collect(),
+                extras
+                    .iter()
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect(),
             )),
-            [_, description] => Ok((description.to_string(), None, vec![])),
-            _ => Err(anyhow!("Unable to extract description and heightened.")),
+            [_, description] => Some((description.to_string(), None, vec![])),
+            _ => None,
         }
     }
 
-    fn parse_properties(object: &Object) -> Result<Vec<Property>> {
-        let str_properties = &[
-            ("area", "Area"),
-            ("duration_raw", "Duration"),
-            ("target", "Target"),
-            ("saving_throw", "Defence"),
-            ("range_raw", "Range"),
-            ("trigger", "Trigger"),
-        ];
-
-        let result = str_properties
+    fn parse_properties(
+        object: &Object,
+        properties: &[(&str, &str)],
+    ) -> Result<Vec<Property>, ParseError> {
+        let result = properties
             .iter()
             .filter_map(|(key, name)| Self::construct_propertry(object, key, name))
-            .collect::<Result<Vec<Property>>>()?;
+            .collect::<Result<Vec<Property>, _>>()?;
 
         Ok(result)
     }
 
-    fn construct_propertry(object: &Object, key: &str, key_name: &str) -> Option<Result<Property>> {
-        let value = object.get_typed_maybe::<String>(key).transpose()?;
+    fn construct_propertry(
+        object: &Object,
+        key: &str,
+        key_name: &str,
+    ) -> Option<Result<Property, ParseError>> {
+        let value = optional::<String>(object, key).transpose()?;
         let value = match value {
             Ok(value) => value,
             Err(error) => {
@@ -140,9 +196,9 @@ impl Spell {
         }))
     }
 
-    fn parse_traits(object: &Object) -> Result<Vec<String>> {
-        let mut traits: Vec<String> = object.get_typed("trait")?;
-        let components: Option<Vec<String>> = object.get_typed_maybe("component")?;
+    fn parse_traits(object: &Object) -> Result<Vec<String>, ParseError> {
+        let mut traits: Vec<String> = required(object, "trait")?;
+        let components: Option<Vec<String>> = optional(object, "component")?;
         if let Some(components) = components {
             if components.contains(&"somatic".to_string()) {
                 traits.push("Manipulate".to_string());
@@ -155,26 +211,65 @@ impl Spell {
     }
 }
 
+impl Spell {
+    /// Whether spell can be taken from `tradition` spell list. Spells without traditions,
+    /// like focus spells, are granted by class features, so they are never excluded.
+    pub fn is_on_list(&self, tradition: Tradition) -> bool {
+        let traditions = &self.traditions;
+        let has_any = Tradition::ALL.into_iter().any(|t| traditions.contains(t));
+        !has_any || traditions.contains(tradition)
+    }
+}
+
+impl Tradition {
+    /// In order of `GameSystem::tradition_labels`.
+    pub const ALL: [Tradition; 4] = [
+        Tradition::Arcane,
+        Tradition::Primal,
+        Tradition::Divine,
+        Tradition::Occult,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|tradition| tradition.as_str().eq_ignore_ascii_case(name))
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Tradition::Arcane => "Arcane",
+            Tradition::Primal => "Primal",
+            Tradition::Divine => "Divine",
+            Tradition::Occult => "Occult",
+        }
+    }
+}
+
 impl Traditions {
+    pub fn contains(&self, tradition: Tradition) -> bool {
+        match tradition {
+            Tradition::Arcane => self.is_arcane,
+            Tradition::Primal => self.is_primal,
+            Tradition::Divine => self.is_divine,
+            Tradition::Occult => self.is_occult,
+        }
+    }
+
     fn parse(traditions: Vec<String>) -> Self {
-        let mut result = Self {
-            is_arcane: false,
-            is_primal: false,
-            is_divine: false,
-            is_occult: false,
-        };
+        let mut result = Self::default();
         for tradition in &traditions {
-            match tradition.as_str() {
-                "Arcane" => {
+            match tradition.to_lowercase().as_str() {
+                "arcane" => {
                     result.is_arcane = true;
                 }
-                "Primal" => {
+                "primal" => {
                     result.is_primal = true;
                 }
-                "Occult" => {
+                "occult" => {
                     result.is_occult = true;
                 }
-                "Divine" => {
+                "divine" => {
                     result.is_divine = true;
                 }
                 _ => {}
@@ -182,48 +277,121 @@ impl Traditions {
         }
         result
     }
+
+    /// Names of traditions, like `["Arcane", "Primal"]`.
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            (self.is_arcane, "Arcane"),
+            (self.is_divine, "Divine"),
+            (self.is_occult, "Occult"),
+            (self.is_primal, "Primal"),
+        ]
+        .iter()
+        .filter(|(is_present, _)| *is_present)
+        .map(|(_, name)| *name)
+        .collect()
+    }
+
+    /// Compact form to display on card, like `ADP`. Empty for spells without tradition.
+    pub fn initials(&self) -> String {
+        [
+            (self.is_arcane, 'A'),
+            (self.is_divine, 'D'),
+            (self.is_occult, 'O'),
+            (self.is_primal, 'P'),
+        ]
+        .iter()
+        .filter(|(is_present, _)| *is_present)
+        .map(|(_, initial)| initial)
+        .collect()
+    }
+}
+
+impl Rarity {
+    pub const ALL: [Rarity; 4] = [
+        Rarity::Common,
+        Rarity::Uncommon,
+        Rarity::Rare,
+        Rarity::Unique,
+    ];
+
+    /// Rarity is listed among traits. Spell without one is common.
+    fn from_traits(traits: &[String]) -> Self {
+        traits
+            .iter()
+            .find_map(|trait_| Self::parse(trait_))
+            .unwrap_or(Self::Common)
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|rarity| rarity.as_str().eq_ignore_ascii_case(name))
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Rarity::Common => "Common",
+            Rarity::Uncommon => "Uncommon",
+            Rarity::Rare => "Rare",
+            Rarity::Unique => "Unique",
+        }
+    }
 }
 
 impl SpellType {
-    fn parse(name: &str) -> Result<Self> {
+    fn parse(name: &str) -> Result<Self, ParseError> {
         match name {
             "spell" => Ok(Self::Spell),
             "focus" => Ok(Self::Focus),
             "cantrip" => Ok(Self::Cantrip),
-            _ => Err(anyhow!("Field `category` contains invalid value.")),
+            _ => Err(ParseError::field(
+                "category",
+                format!("Contains invalid value `{name}`."),
+            )),
         }
     }
 }
 
 impl Actions {
-    fn parse(source: String) -> Result<Self> {
-        let result = Self::parse_range(&source)
+    /// Parse action cost, like `Two Actions` or `One to Three Actions`. Costs which are not
+    /// a number of actions, like `10 minutes`, are kept as `Other`.
+    pub fn parse(source: String) -> Self {
+        Self::parse_range(&source)
             .or_else(|| Self::numeric_parse(&source))
-            .unwrap_or(Self::Other(source));
-        Ok(result)
+            .unwrap_or(Self::Other(source))
     }
 
+    /// Parse single action cost, like `Two Actions` or `Reaction`.
+    ///
+    /// Parsing is case-insensitive and tolerant to different spellings of count
+    /// (`Single Action`, `One Action`, `1 Action`, and legacy `Singe Action`).
     fn numeric_parse(source: &str) -> Option<Self> {
-        match source {
-            "Reaction" => Some(Self::Reaction),
-            "Single Action" => Some(Self::Number(1)),
-            "Two Actions" => Some(Self::Number(2)),
-            "Three Actions" => Some(Self::Number(3)),
-            "Free Action" => Some(Self::FreeAction),
+        let source = source.to_lowercase();
+        match source.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["reaction"] => Some(Self::Reaction),
+            ["free", "action"] => Some(Self::FreeAction),
+            [count] | [count, "action" | "actions"] => Self::parse_count(count).map(Self::Number),
             _ => None,
         }
     }
 
-    fn parse_range(source: &str) -> Option<Self> {
-        let mut parts: Vec<_> = source.split("to").collect();
-        if parts.len() != 2 {
-            parts = source.split("or").collect();
-        }
-        if parts.len() != 2 {
-            return None;
+    fn parse_count(word: &str) -> Option<u8> {
+        match word {
+            "1" | "one" | "single" | "singe" => Some(1),
+            "2" | "two" => Some(2),
+            "3" | "three" => Some(3),
+            _ => None,
         }
-        let left = Self::numeric_parse(parts[0].trim())?;
-        let right = Self::numeric_parse(parts[1].trim())?;
+    }
+
+    fn parse_range(source: &str) -> Option<Self> {
+        let source = source.to_lowercase();
+        let (left, right) = source
+            .split_once(" to ")
+            .or_else(|| source.split_once(" or "))?;
+        let left = Self::numeric_parse(left)?;
+        let right = Self::numeric_parse(right)?;
         if let (Self::Number(from), Self::Number(to)) = (left, right) {
             Some(Self::Range(from, to))
         } else {
@@ -249,3 +417,16 @@ impl Actions {
         }
     }
 }
+
+/// Field `key` of spell object, which must be present.
+fn required<T: TypedParse>(object: &Object, key: &str) -> Result<T, ParseError> {
+    optional(object, key)?.ok_or_else(|| ParseError::field(key, "Missing"))
+}
+
+/// Field `key` of spell object, `None` if it is not present.
+fn optional<T: TypedParse>(object: &Object, key: &str) -> Result<Option<T>, ParseError> {
+    object
+        .get(key)
+        .map(|value| T::parse(value).map_err(|error| ParseError::field(key, format!("{error:#}"))))
+        .transpose()
+}
@@ -0,0 +1,52 @@
+use crate::rich_text::Scene;
+use anyhow::{bail, Result};
+use std::collections::{BTreeSet, HashMap};
+use ttf_parser::Face;
+
+/// Subset of TrueType font `data`, keeping only outlines of `chars`, of glyphs they are
+/// composed of and of `.notdef` glyph. Other glyphs are left empty.
+///
+/// Glyph ids and character map stay as they are, so the subset draws the kept characters
+/// exactly like the full font. Tables not needed for pdf embedding, like kerning, are dropped,
+/// as text is laid out with the full font. Fonts with CFF outlines are rejected.
+pub fn subset_font(data: &[u8], chars: &BTreeSet<char>) -> Result<Vec<u8>> {
+    let face = Face::parse(data, 0)?;
+    if face.tables().glyf.is_none() {
+        bail!("Only fonts with TrueType outlines can be subset");
+    }
+    let mut glyphs = vec![0];
+    glyphs.extend(
+        chars
+            .iter()
+            .filter_map(|&c| face.glyph_index(c))
+            .map(|glyph| glyph.0),
+    );
+    Ok(subsetter::subset(
+        data,
+        0,
+        subsetter::Profile::pdf(&glyphs),
+    )?)
+}
+
+/// Characters drawn with every font of a document, so embedded fonts can be subset.
+///
+/// Fonts are told apart by address of their data, which lives for the whole run.
+#[derive(Debug, Default)]
+pub struct UsedChars(HashMap<usize, BTreeSet<char>>);
+
+impl UsedChars {
+    /// Add characters of all text in `scene`.
+    pub fn add_scene<T>(&mut self, scene: &Scene<'_, T>) {
+        for text in scene.texts() {
+            self.0
+                .entry(text.font.data().as_ptr() as usize)
+                .or_default()
+                .extend(text.text.chars());
+        }
+    }
+
+    /// Characters drawn with font of `data`, `None` if font is not used at all.
+    pub fn get(&self, data: &[u8]) -> Option<&BTreeSet<char>> {
+        self.0.get(&(data.as_ptr() as usize))
+    }
+}
@@ -0,0 +1,310 @@
+use crate::db::SimpleSpellDB;
+use crate::json_utils::{JsonValueExt, ObjectExt};
+use crate::network::Network;
+use anyhow::{anyhow, bail, Context, Result};
+use ring::signature::{UnparsedPublicKey, ED25519};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Size of chunks downloads are read in, progress is updated after every chunk.
+const CHUNK_SIZE: usize = 16 * 1024;
+/// Directory inside bundles directory downloads are written to before they replace old
+/// bundles. Bundle files are only read from bundles directory itself.
+const STAGING_DIR: &str = ".sync";
+/// Directory inside staging one old bundles are moved to while new ones replace them.
+const BACKUP_DIR: &str = "previous";
+
+/// Bundle listed in sync manifest.
+#[derive(Debug, Clone)]
+pub struct ManifestEntry {
+    /// File stem of installed bundle, also its label.
+    pub name: String,
+    pub url: String,
+    /// Lowercase hex SHA-256 of bundle file.
+    pub sha256: String,
+}
+
+/// List of bundles to download, published next to them:
+///
+/// ```json
+/// {
+///     "bundles": [
+///         { "name": "homebrew", "url": "https://example.org/homebrew.json", "sha256": "9f86d0..." }
+///     ]
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    pub bundles: Vec<ManifestEntry>,
+}
+
+/// Ed25519 public key of manifest publisher. Manifests are only trusted then signed with
+/// its private key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublisherKey(pub [u8; 32]);
+
+impl PublisherKey {
+    /// Parse key written as 64 hex digits.
+    pub fn parse(hex: &str) -> Result<Self> {
+        decode_hex(hex.trim())
+            .and_then(|key| key.try_into().ok())
+            .map(Self)
+            .ok_or_else(|| anyhow!("Publisher key must be 64 hex digits"))
+    }
+
+    /// Check `signature` of `message`, made with private key of publisher.
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<()> {
+        UnparsedPublicKey::new(&ED25519, &self.0)
+            .verify(message, signature)
+            .map_err(|_| anyhow!("Signature does not match publisher key"))
+    }
+}
+
+/// Downloaded bundle with verified checksum.
+pub struct Download {
+    pub name: String,
+    pub data: String,
+}
+
+/// Progress of running sync, shared with UI thread.
+#[derive(Debug, Clone, Default)]
+pub struct SyncProgress {
+    /// Index of file being downloaded, manifest first.
+    pub file: usize,
+    /// Number of files, known after manifest is downloaded.
+    pub files: usize,
+    pub downloaded: u64,
+    /// Size of current file, if server reports it.
+    pub total: Option<u64>,
+}
+
+impl SyncProgress {
+    /// Fraction of sync done, from 0 to 1. `None` while manifest is downloaded.
+    pub fn fraction(&self) -> Option<f64> {
+        if self.files == 0 {
+            return None;
+        }
+        let current = match self.total {
+            Some(total) if total > 0 => (self.downloaded as f64 / total as f64).min(1.0),
+            _ => 0.0,
+        };
+        Some((self.file.saturating_sub(1) as f64 + current) / self.files as f64)
+    }
+}
+
+impl Manifest {
+    pub fn parse(source: &str) -> Result<Self> {
+        let manifest = json::parse(source)?;
+        let bundles = manifest
+            .as_object()?
+            .get("bundles")
+            .ok_or_else(|| anyhow!("Field `bundles` is missing"))?
+            .as_array()?
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let parse = || -> Result<ManifestEntry> {
+                    let entry = entry.as_object()?;
+                    let result = ManifestEntry {
+                        name: entry.get_typed("name")?,
+                        url: entry.get_typed("url")?,
+                        sha256: entry.get_typed::<String>("sha256")?.to_lowercase(),
+                    };
+                    if !is_valid_name(&result.name) {
+                        bail!("Bundle name `{}` is not a valid file name", result.name);
+                    }
+                    Ok(result)
+                };
+                parse().map_err(|e| e.context(format!("While parsing bundle at index `{i}`")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { bundles })
+    }
+}
+
+/// Download manifest from `manifest_url` and every bundle it lists through `network`,
+/// verifying checksums. Manifest must be signed by `publisher`, with signature as hex
+/// digits at `manifest_url` followed by `.sig`, so checksums come from the publisher and
+/// not whoever serves the files.
+///
+/// Runs on a background thread: `progress` is updated while downloading, and `cancelled`
+/// is checked between chunks. Nothing is written to disk, see `install`.
+pub fn download_bundles(
+    network: &dyn Network,
+    manifest_url: &str,
+    publisher: &PublisherKey,
+    progress: &Mutex<SyncProgress>,
+    cancelled: &AtomicBool,
+) -> Result<Vec<Download>> {
+    let manifest = download(network, manifest_url, progress, cancelled)
+        .with_context(|| format!("Unable to download manifest `{manifest_url}`"))?;
+    let signature_url = format!("{manifest_url}.sig");
+    let signature = download(network, &signature_url, progress, cancelled)
+        .with_context(|| format!("Unable to download manifest signature `{signature_url}`"))?;
+    let signature = std::str::from_utf8(&signature)
+        .ok()
+        .and_then(|signature| decode_hex(signature.trim()))
+        .ok_or_else(|| anyhow!("Manifest signature must be hex digits"))?;
+    publisher
+        .verify(&manifest, &signature)
+        .context("Manifest is not signed by publisher")?;
+    let manifest = Manifest::parse(&String::from_utf8(manifest)?)
+        .map_err(|e| e.context("Unable to parse manifest"))?;
+    update(progress, &|progress| {
+        progress.files = manifest.bundles.len()
+    });
+
+    let mut result = vec![];
+    for (i, entry) in manifest.bundles.into_iter().enumerate() {
+        update(progress, &|progress| {
+            progress.file = i + 1;
+            progress.downloaded = 0;
+            progress.total = None;
+        });
+        let data = download(network, &entry.url, progress, cancelled)
+            .with_context(|| format!("Unable to download bundle `{}`", entry.name))?;
+        let checksum = format!("{:x}", Sha256::digest(&data));
+        if checksum != entry.sha256 {
+            bail!(
+                "Checksum of bundle `{}` does not match manifest: expected {}, got {checksum}",
+                entry.name,
+                entry.sha256
+            );
+        }
+        let data = String::from_utf8(data)
+            .with_context(|| format!("Bundle `{}` is not valid utf-8", entry.name))?;
+        result.push(Download {
+            name: entry.name,
+            data,
+        });
+    }
+    Ok(result)
+}
+
+/// Validate downloaded bundles against `db` and write them into `dir`. If any bundle is
+/// invalid, nothing is written. Returns names of installed bundles.
+///
+/// Bundles are written into a staging directory first, and moved into `dir` once all of
+/// them are written. If moving one fails, bundles moved before it are put back, so `dir`
+/// holds either all old bundles or all new ones. Staging directory is removed either way.
+pub fn install(db: &SimpleSpellDB, downloads: &[Download], dir: &Path) -> Result<Vec<String>> {
+    for download in downloads {
+        db.validate_bundle(&download.data)
+            .with_context(|| format!("Bundle `{}` is invalid", download.name))?;
+    }
+    std::fs::create_dir_all(dir)?;
+    let staging = dir.join(STAGING_DIR);
+    let result = stage(downloads, &staging).and_then(|()| swap_in(downloads, &staging, dir));
+    if let Err(error) = std::fs::remove_dir_all(&staging) {
+        tracing::warn!("Unable to remove `{}`: {error}", staging.display());
+    }
+    result?;
+    Ok(downloads.iter().map(|d| d.name.clone()).collect())
+}
+
+/// Write `downloads` into empty `staging` directory.
+fn stage(downloads: &[Download], staging: &Path) -> Result<()> {
+    // Left by a sync interrupted before.
+    if staging.exists() {
+        std::fs::remove_dir_all(staging)?;
+    }
+    std::fs::create_dir(staging)?;
+    for download in downloads {
+        let path = staging.join(file_name(download));
+        std::fs::write(&path, &download.data)
+            .with_context(|| format!("Unable to write `{}`", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Move staged bundles into `dir`, old ones into backup directory, or put old ones back if
+/// any move fails.
+fn swap_in(downloads: &[Download], staging: &Path, dir: &Path) -> Result<()> {
+    let backup = staging.join(BACKUP_DIR);
+    std::fs::create_dir(&backup)?;
+    // File names moved into `dir`, with whether an old bundle was moved out for them.
+    let mut moved = vec![];
+    let mut move_in = |name: String| -> std::io::Result<()> {
+        let path = dir.join(&name);
+        let replaced = path.exists();
+        if replaced {
+            std::fs::rename(&path, backup.join(&name))?;
+        }
+        moved.push((name.clone(), replaced));
+        std::fs::rename(staging.join(&name), path)
+    };
+    let result = downloads
+        .iter()
+        .try_for_each(|download| move_in(file_name(download)));
+    let Err(error) = result else {
+        return Ok(());
+    };
+    for (name, replaced) in moved.into_iter().rev() {
+        let path = dir.join(&name);
+        let _ = std::fs::remove_file(&path);
+        if replaced {
+            if let Err(error) = std::fs::rename(backup.join(&name), &path) {
+                tracing::error!("Unable to restore `{}`: {error}", path.display());
+            }
+        }
+    }
+    Err(anyhow::Error::from(error).context("Unable to replace bundles"))
+}
+
+fn file_name(download: &Download) -> String {
+    format!("{}.json", download.name)
+}
+
+fn update(progress: &Mutex<SyncProgress>, change: &dyn Fn(&mut SyncProgress)) {
+    if let Ok(mut progress) = progress.lock() {
+        change(&mut progress);
+    }
+}
+
+fn download(
+    network: &dyn Network,
+    url: &str,
+    progress: &Mutex<SyncProgress>,
+    cancelled: &AtomicBool,
+) -> Result<Vec<u8>> {
+    let response = network.get(url)?;
+    update(progress, &|progress| progress.total = response.length);
+    let mut reader = response.body;
+    let mut result = vec![];
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        if cancelled.load(Ordering::SeqCst) {
+            bail!("Sync cancelled");
+        }
+        let read = reader.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        result.extend_from_slice(&chunk[..read]);
+        let downloaded = result.len() as u64;
+        update(progress, &|progress| progress.downloaded = downloaded);
+    }
+    Ok(result)
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Bundle names become file names, so path separators and leading dots are rejected.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ' ' | '.'))
+}
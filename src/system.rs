@@ -0,0 +1,73 @@
+mod pf2e;
+mod sf2e;
+
+use crate::error::ParseError;
+use crate::spell::Spell;
+use anyhow::Result;
+use json::object::Object;
+use std::borrow::Cow;
+use std::rc::Rc;
+
+pub use pf2e::Pathfinder2e;
+pub use sf2e::Starfinder2e;
+
+/// Game specific part of the application: spell data schema, search fields and card content.
+///
+/// Spells of every system are parsed into common `Spell`, so database, export and
+/// rendering code does not depend on the system.
+pub trait GameSystem {
+    /// Name shown to users, like `Pathfinder 2e`.
+    fn name(&self) -> &'static str;
+
+    /// Built-in spell data, json array of spell objects. Large datasets are embedded
+    /// compressed, and decompressed here.
+    fn builtin_data(&self) -> Result<Cow<'static, str>>;
+
+    /// Subdirectory of bundles directory with bundles of this system.
+    /// `None` if bundles are placed into bundles directory itself.
+    fn bundles_subdir(&self) -> Option<&'static str>;
+
+    /// Parse single spell object. Used for built-in data and homebrew bundles alike.
+    fn parse_spell(&self, object: &Object) -> Result<Spell, ParseError>;
+
+    /// Labels of search filters by `Traditions` fields, in order: arcane, primal, divine, occult.
+    fn tradition_labels(&self) -> [&'static str; 4];
+
+    /// Font drawing action cost glyphs in card header.
+    fn glyph_font(&self) -> &'static [u8];
+
+    /// Sections of a card, from top to bottom.
+    fn card_sections(&self) -> &'static [CardSection];
+
+    /// Attribution required by licenses of built-in game content, shown in about dialog.
+    fn license_notice(&self) -> &'static str;
+
+    /// Archives of Nethys site with spell pages of this system, like `2e.aonprd.com`.
+    fn reference_site(&self) -> &'static str;
+}
+
+/// All supported systems. First one is the default.
+pub fn all_systems() -> Vec<Rc<dyn GameSystem>> {
+    vec![Rc::new(Pathfinder2e), Rc::new(Starfinder2e)]
+}
+
+/// System with name matching `name`, ignoring case.
+pub fn find_system(name: &str) -> Option<Rc<dyn GameSystem>> {
+    all_systems()
+        .into_iter()
+        .find(|system| system.name().eq_ignore_ascii_case(name))
+}
+
+/// Part of a card, laid out by the renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardSection {
+    /// Name, action cost, traditions and rank.
+    Header,
+    Traits,
+    /// Range, area, defense and similar.
+    Properties,
+    Description,
+    Heightened,
+    /// Sections after heightened, like psychic amps.
+    Extras,
+}
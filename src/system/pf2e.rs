@@ -0,0 +1,75 @@
+use super::{CardSection, GameSystem};
+use crate::error::ParseError;
+use crate::gzip;
+use crate::spell::Spell;
+use anyhow::Result;
+use json::object::Object;
+use std::borrow::Cow;
+
+/// Json fields of spell properties, with names shown on card.
+const PROPERTIES: &[(&str, &str)] = &[
+    ("area", "Area"),
+    ("duration_raw", "Duration"),
+    ("target", "Target"),
+    ("saving_throw", "Defence"),
+    ("range_raw", "Range"),
+    ("trigger", "Trigger"),
+];
+
+/// Pathfinder Second Edition, with spell data from Archives of Nethys.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pathfinder2e;
+
+impl GameSystem for Pathfinder2e {
+    fn name(&self) -> &'static str {
+        "Pathfinder 2e"
+    }
+
+    fn builtin_data(&self) -> Result<Cow<'static, str>> {
+        // Compressed from `nethys_data/spells.json` by build script.
+        let data = include_bytes!(concat!(env!("OUT_DIR"), "/spells.json.gz"));
+        Ok(Cow::Owned(gzip::decompress(data)?))
+    }
+
+    fn bundles_subdir(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn parse_spell(&self, object: &Object) -> Result<Spell, ParseError> {
+        Spell::parse(object, PROPERTIES)
+    }
+
+    fn tradition_labels(&self) -> [&'static str; 4] {
+        ["Arcane", "Primal", "Divine", "Occult"]
+    }
+
+    fn glyph_font(&self) -> &'static [u8] {
+        include_bytes!("../../static/Pathfinder2eActions.ttf")
+    }
+
+    fn license_notice(&self) -> &'static str {
+        "Spell data is taken from Archives of Nethys (2e.aonprd.com). \
+        Remastered spells are Licensed Material under the ORC License, \
+        Pathfinder Player Core and Pathfinder GM Core \u{a9} Paizo Inc. \
+        Legacy spells are Open Game Content under the Open Game License v1.0a, \
+        Pathfinder Core Rulebook (Second Edition) \u{a9} Paizo Inc.\n\n\
+        This program uses trademarks and/or copyrights owned by Paizo Inc., used under \
+        Paizo's Community Use Policy (paizo.com/licenses/communityuse). This program is \
+        not published, endorsed, or specifically approved by Paizo."
+    }
+
+    fn reference_site(&self) -> &'static str {
+        "2e.aonprd.com"
+    }
+
+    fn card_sections(&self) -> &'static [CardSection] {
+        &[
+            CardSection::Header,
+            CardSection::Traits,
+            CardSection::Properties,
+            CardSection::Description,
+            CardSection::Heightened,
+            CardSection::Extras,
+        ]
+    }
+}
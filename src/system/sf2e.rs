@@ -0,0 +1,74 @@
+use super::{CardSection, GameSystem};
+use crate::error::ParseError;
+use crate::spell::Spell;
+use anyhow::Result;
+use json::object::Object;
+use std::borrow::Cow;
+
+/// Json fields of spell properties, with names shown on card. Playtest spells list
+/// requirements more often than Pathfinder ones, so they get a line too.
+const PROPERTIES: &[(&str, &str)] = &[
+    ("area", "Area"),
+    ("duration_raw", "Duration"),
+    ("target", "Target"),
+    ("saving_throw", "Defense"),
+    ("range_raw", "Range"),
+    ("trigger", "Trigger"),
+    ("requirement", "Requirements"),
+];
+
+/// Starfinder Second Edition playtest. Spells use Archives of Nethys format, same as
+/// Pathfinder ones. No data is built in, spells are loaded from bundles only.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Starfinder2e;
+
+impl GameSystem for Starfinder2e {
+    fn name(&self) -> &'static str {
+        "Starfinder 2e playtest"
+    }
+
+    fn builtin_data(&self) -> Result<Cow<'static, str>> {
+        Ok(Cow::Borrowed("[]"))
+    }
+
+    fn bundles_subdir(&self) -> Option<&'static str> {
+        Some("starfinder2e")
+    }
+
+    fn parse_spell(&self, object: &Object) -> Result<Spell, ParseError> {
+        Spell::parse(object, PROPERTIES)
+    }
+
+    fn tradition_labels(&self) -> [&'static str; 4] {
+        ["Arcane", "Primal", "Divine", "Occult"]
+    }
+
+    fn glyph_font(&self) -> &'static [u8] {
+        // Action costs are the same as in Pathfinder.
+        include_bytes!("../../static/Pathfinder2eActions.ttf")
+    }
+
+    fn license_notice(&self) -> &'static str {
+        "No Starfinder content is built in. Spells loaded from bundles are subject \
+        to licenses of their sources; playtest material of Paizo Inc. is Licensed \
+        Material under the ORC License.\n\n\
+        This program uses trademarks and/or copyrights owned by Paizo Inc., used under \
+        Paizo's Community Use Policy (paizo.com/licenses/communityuse). This program is \
+        not published, endorsed, or specifically approved by Paizo."
+    }
+
+    fn reference_site(&self) -> &'static str {
+        "2e.aonsrd.com"
+    }
+
+    fn card_sections(&self) -> &'static [CardSection] {
+        &[
+            CardSection::Header,
+            CardSection::Traits,
+            CardSection::Properties,
+            CardSection::Description,
+            CardSection::Heightened,
+            CardSection::Extras,
+        ]
+    }
+}
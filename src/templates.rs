@@ -0,0 +1,178 @@
+use crate::json_utils::JsonValueExt;
+use crate::profiles::Profile;
+use crate::render::ThemeFonts;
+use crate::sync::is_valid_name;
+use anyhow::{anyhow, bail, Context, Result};
+use freetype::Library;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Mutex;
+
+/// Largest file of a template pack once unpacked, fonts included.
+const MAX_FILE_SIZE: usize = 16 * 1024 * 1024;
+const DEFINITION_FILE: &str = "template.json";
+const TEXT_FONT: &str = "fonts/text.ttf";
+const BOLD_FONT: &str = "fonts/bold.ttf";
+const ITALIC_FONT: &str = "fonts/italic.ttf";
+
+/// Contents of font files read so far. Fonts borrow them for the rest of the run, so each
+/// distinct file is kept once, however often templates are applied or reinstalled.
+static LOADED_FONTS: Mutex<Vec<&'static [u8]>> = Mutex::new(vec![]);
+
+/// Card look shared as a zip archive, installed into `config::templates_dir`:
+///
+/// - `template.json`: profile object, see `profiles` module. Profile name names the template.
+/// - `fonts/text.ttf`, `fonts/bold.ttf`, `fonts/italic.ttf`: optional fonts replacing
+///   built-in Helvetica.
+///
+/// Other files, like a license, are ignored.
+pub struct TemplatePack {
+    pub profile: Profile,
+    /// Font files present in the pack, by path inside it.
+    pub fonts: Vec<(&'static str, Vec<u8>)>,
+}
+
+impl TemplatePack {
+    /// Read and validate template pack archive.
+    pub fn from_zip(data: &[u8]) -> Result<Self> {
+        let files = read_archive(data).context("Unable to read template archive")?;
+        let find = |name: &str| {
+            files
+                .iter()
+                .find(|(file_name, _)| file_name == name)
+                .map(|(_, data)| data)
+        };
+        let definition = find(DEFINITION_FILE)
+            .ok_or_else(|| anyhow!("Template archive has no `{DEFINITION_FILE}`"))?;
+        let definition = std::str::from_utf8(definition)
+            .with_context(|| format!("`{DEFINITION_FILE}` is not valid utf-8"))?;
+        let mut profile = json::parse(definition)
+            .map_err(anyhow::Error::from)
+            .and_then(|definition| Profile::parse(definition.as_object()?))
+            .map_err(|e| e.context(format!("Unable to parse `{DEFINITION_FILE}`")))?;
+        if !is_valid_name(&profile.name) {
+            bail!("Template name `{}` is not a valid file name", profile.name);
+        }
+        profile.template = None;
+
+        let mut fonts = vec![];
+        for name in [TEXT_FONT, BOLD_FONT, ITALIC_FONT] {
+            if let Some(data) = find(name) {
+                Library::init()?
+                    .new_memory_face(Rc::new(data.clone()), 0)
+                    .with_context(|| format!("`{name}` is not a valid font"))?;
+                fonts.push((name, data.clone()));
+            }
+        }
+        Ok(Self { profile, fonts })
+    }
+
+    /// Write template into its own subdirectory of `dir`, replacing installed template of the
+    /// same name. Files are written next to it and renamed over it once complete.
+    pub fn install(&self, dir: &Path) -> Result<()> {
+        let name = &self.profile.name;
+        let staging = dir.join(format!(".{name}.part"));
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging)?;
+        }
+        std::fs::create_dir_all(staging.join("fonts"))?;
+        let definition = json::stringify_pretty(self.profile.to_json(), 2);
+        std::fs::write(staging.join(DEFINITION_FILE), definition)?;
+        for (path, data) in &self.fonts {
+            std::fs::write(staging.join(path), data)?;
+        }
+
+        let target = dir.join(name);
+        if target.exists() {
+            std::fs::remove_dir_all(&target)?;
+        }
+        std::fs::rename(&staging, &target)?;
+        Ok(())
+    }
+}
+
+/// Profiles of templates installed in `dir`, sorted by name. Templates which fail to load
+/// are logged and skipped.
+pub fn installed_templates(dir: &Path) -> Vec<Profile> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut result = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.join(DEFINITION_FILE).is_file())
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?.to_string();
+            if !is_valid_name(&name) {
+                return None;
+            }
+            let load = || -> Result<Profile> {
+                let definition = std::fs::read_to_string(path.join(DEFINITION_FILE))?;
+                let mut profile = Profile::parse(json::parse(&definition)?.as_object()?)?;
+                profile.name = name.clone();
+                profile.template = Some(name.clone());
+                Ok(profile)
+            };
+            load()
+                .map_err(|error| tracing::warn!("Unable to load template `{name}`: {error:#}"))
+                .ok()
+        })
+        .collect::<Vec<_>>();
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+    result
+}
+
+/// Fonts of template `name` installed in `dir`. Missing fonts are left built-in.
+pub fn template_fonts(dir: &Path, name: &str) -> ThemeFonts {
+    let template = dir.join(name);
+    ThemeFonts {
+        text: load_font(template.join(TEXT_FONT)),
+        bold: load_font(template.join(BOLD_FONT)),
+        italic: load_font(template.join(ITALIC_FONT)),
+    }
+}
+
+/// Font file at `path`, `None` if it is missing or unreadable. Files are read anew every
+/// time, since a reinstalled template may replace them, and matched to ones read before.
+fn load_font(path: PathBuf) -> Option<&'static [u8]> {
+    let data = match std::fs::read(&path) {
+        Ok(data) => data,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(error) => {
+            tracing::warn!("Unable to read font `{}`: {error}", path.display());
+            return None;
+        }
+    };
+    let mut loaded = LOADED_FONTS.lock().ok()?;
+    if let Some(font) = loaded.iter().find(|font| **font == data) {
+        return Some(font);
+    }
+    let font = &*Box::leak(data.into_boxed_slice());
+    loaded.push(font);
+    Some(font)
+}
+
+/// Files of zip archive `data` by path inside it. Directories are skipped. Files larger than
+/// `MAX_FILE_SIZE` once unpacked are rejected, so a small malicious archive cannot exhaust
+/// memory.
+fn read_archive(data: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))?;
+    let mut files = vec![];
+    for index in 0..archive.len() {
+        let file = archive.by_index(index)?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_string();
+        let mut data = vec![];
+        file.take(MAX_FILE_SIZE as u64 + 1)
+            .read_to_end(&mut data)
+            .with_context(|| format!("`{name}` is corrupted"))?;
+        if data.len() > MAX_FILE_SIZE {
+            bail!("`{name}` is too large");
+        }
+        files.push((name, data));
+    }
+    Ok(files)
+}
@@ -0,0 +1,309 @@
+use crate::json_utils::ObjectExt;
+use crate::system::GameSystem;
+use json::{object::Object, JsonValue};
+use std::fmt;
+
+/// Most errors looked for in a single spell, in case a placeholder keeps failing.
+const MAX_ERRORS_PER_SPELL: usize = 20;
+
+/// Problem found in a bundle file, with position of the spell or field it is about.
+#[derive(Debug)]
+pub struct Problem {
+    /// `None` for problems of the file itself, and spells without a name.
+    pub spell: Option<String>,
+    /// Field of the spell object, `None` if the problem is not about one field.
+    pub field: Option<String>,
+    /// Line and column, counted from 1.
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    /// How to fix it, for problems of known fields.
+    pub fix: Option<&'static str>,
+}
+
+/// Result of checking a bundle, see `validate_bundle`.
+pub struct Validation {
+    /// Spell objects found, including ones with problems.
+    pub spell_count: usize,
+    pub problems: Vec<Problem>,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        if let Some(spell) = &self.spell {
+            write!(f, "`{spell}`: ")?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(fix) = self.fix {
+            write!(f, "\n    fix: {fix}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Check bundle `source`, in format accepted by `SimpleSpellDB::merge_bundle`, spell by
+/// spell, without loading it.
+///
+/// Spells are parsed by `GameSystem::parse_spell`, which stops at the first error. To find
+/// every error of a spell, the failing field is replaced by a valid placeholder, or left
+/// out if optional, and the spell is parsed again.
+pub fn validate_bundle(system: &dyn GameSystem, source: &str) -> Validation {
+    let mut problems = vec![];
+    let data = match json::parse(source) {
+        Ok(data) => data,
+        Err(error) => {
+            let (line, column) = match error {
+                json::Error::UnexpectedCharacter { line, column, .. } => (line, column),
+                _ => position(source, source.len()),
+            };
+            problems.push(Problem {
+                spell: None,
+                field: None,
+                line,
+                column,
+                message: format!("Not valid json: {error}"),
+                fix: None,
+            });
+            return Validation {
+                spell_count: 0,
+                problems,
+            };
+        }
+    };
+    let start = source.len() - source.trim_start().len();
+    let (spells, spells_start) = match &data {
+        JsonValue::Object(object) => {
+            for key in ["license", "language"] {
+                if let Err(error) = object.get_typed_maybe::<String>(key) {
+                    let offset = member(source, start, key).map(|(key, _)| key);
+                    problems.push(file_problem(source, offset, error));
+                }
+            }
+            let spells_start = member(source, start, "spells").map(|(_, value)| value);
+            (object.get("spells"), spells_start)
+        }
+        _ => (Some(&data), Some(start)),
+    };
+    let items = match (spells, spells_start) {
+        (Some(JsonValue::Array(items)), Some(offset)) => {
+            items.iter().zip(item_offsets(source, offset))
+        }
+        _ => {
+            problems.push(Problem {
+                spell: None,
+                field: None,
+                line: 1,
+                column: 1,
+                message: "Expected array of spells, or object with `spells` array".to_string(),
+                fix: Some("Put spells into `[...]`, or into `\"spells\": [...]` of an object with license"),
+            });
+            return Validation {
+                spell_count: 0,
+                problems,
+            };
+        }
+    };
+    let mut spell_count = 0;
+    for (item, offset) in items {
+        spell_count += 1;
+        let JsonValue::Object(object) = item else {
+            let (line, column) = position(source, offset);
+            problems.push(Problem {
+                spell: None,
+                field: None,
+                line,
+                column,
+                message: "Expected spell object".to_string(),
+                fix: None,
+            });
+            continue;
+        };
+        problems.extend(validate_spell(system, source, object, offset));
+    }
+    Validation {
+        spell_count,
+        problems,
+    }
+}
+
+/// Problems of spell `object` starting at byte `offset` of `source`.
+fn validate_spell(
+    system: &dyn GameSystem,
+    source: &str,
+    object: &Object,
+    offset: usize,
+) -> Vec<Problem> {
+    let name = object
+        .get("name")
+        .and_then(JsonValue::as_str)
+        .map(str::to_string);
+    let mut object = object.clone();
+    let mut problems: Vec<Problem> = vec![];
+    while problems.len() < MAX_ERRORS_PER_SPELL {
+        let Err(error) = system.parse_spell(&object) else {
+            break;
+        };
+        let field = error.field.clone();
+        let at = field
+            .as_deref()
+            .and_then(|field| member(source, offset, field))
+            .map_or(offset, |(key, _)| key);
+        let (line, column) = position(source, at);
+        let repeated = problems
+            .iter()
+            .any(|problem| problem.field.is_some() && problem.field == field);
+        if repeated {
+            // Placeholder did not help, further errors would repeat it.
+            break;
+        }
+        problems.push(Problem {
+            spell: name.clone(),
+            field: field.clone(),
+            line,
+            column,
+            message: error.detail(),
+            fix: field.as_deref().and_then(field_fix),
+        });
+        let Some(field) = field else {
+            break;
+        };
+        match placeholder(&field) {
+            Some(value) => object.insert(&field, value),
+            None => {
+                if object.remove(&field).is_none() {
+                    break;
+                }
+            }
+        }
+    }
+    problems.sort_by_key(|problem| (problem.line, problem.column));
+    problems
+}
+
+/// Valid value of required spell field, `None` for optional ones, which are left out instead.
+fn placeholder(field: &str) -> Option<JsonValue> {
+    let value = match field {
+        "name" => "Unnamed".into(),
+        "id" => "spell-0".into(),
+        "level" => 1.into(),
+        "category" => "spell".into(),
+        "actions" | "summary" => "".into(),
+        "markdown" => "\n---\n".into(),
+        "trait" => JsonValue::new_array(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn field_fix(field: &str) -> Option<&'static str> {
+    let fix = match field {
+        "name" => "Give spell name as text, like `\"name\": \"Frost Bolt\"`",
+        "id" => {
+            "Use `spell-` followed by a number no other spell has, like `\"id\": \"spell-90001\"`"
+        }
+        "level" => "Give rank as a whole number from 1 to 10, like `\"level\": 3`; cantrips have 1",
+        "category" => "Use `spell`, `focus` or `cantrip`",
+        "actions" => "Give action cost as text, like `Two Actions`, `Reaction` or `1 minute`",
+        "markdown" => {
+            "Put `---` line between header and description, and another one before heightened \
+            entries"
+        }
+        "summary" => "Give short summary as text, `\"\"` if there is none",
+        "trait" => "List traits as array of text, like `[\"Cold\", \"Uncommon\"]`",
+        "tradition" => "List traditions as array of text, like `[\"arcane\", \"primal\"]`",
+        "source" => "List books as array of text, like `[\"My Homebrew\"]`",
+        "component" => "List components as array of text, like `[\"somatic\", \"verbal\"]`",
+        "license" | "language" | "url" => "Give it as text, or leave the field out",
+        _ => return None,
+    };
+    Some(fix)
+}
+
+fn file_problem(source: &str, offset: Option<usize>, error: anyhow::Error) -> Problem {
+    let (line, column) = position(source, offset.unwrap_or(0));
+    Problem {
+        spell: None,
+        field: None,
+        line,
+        column,
+        message: format!("{error:#}"),
+        fix: Some("Give it as text, or leave the field out"),
+    }
+}
+
+/// Line and column of byte `offset`, counted from 1.
+fn position(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    let line = before.matches('\n').count() + 1;
+    (line, before[line_start..].chars().count() + 1)
+}
+
+/// Offsets of values of json array or object starting at `start`. Values of object are
+/// paired with the range of their key text, without quotes. Nested values are skipped.
+fn members(source: &str, start: usize) -> Vec<(Option<(usize, usize)>, usize)> {
+    let bytes = source.as_bytes();
+    let mut result = vec![];
+    let mut depth = 0;
+    let mut key = None;
+    let mut expecting = true;
+    let mut i = start + 1;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        match byte {
+            b'"' => {
+                let string_start = i;
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                if depth == 0 && expecting {
+                    if bytes[start] == b'{' && key.is_none() {
+                        key = Some((string_start + 1, i));
+                    } else {
+                        result.push((key.take(), string_start));
+                        expecting = false;
+                    }
+                }
+            }
+            b'[' | b'{' => {
+                if depth == 0 && expecting {
+                    result.push((key.take(), i));
+                    expecting = false;
+                }
+                depth += 1;
+            }
+            b']' | b'}' if depth == 0 => break,
+            b']' | b'}' => depth -= 1,
+            b',' if depth == 0 => expecting = true,
+            b':' | b' ' | b'\t' | b'\r' | b'\n' => {}
+            _ => {
+                if depth == 0 && expecting {
+                    result.push((key.take(), i));
+                    expecting = false;
+                }
+            }
+        }
+        i += 1;
+    }
+    result
+}
+
+/// Offsets of items of json array starting at `start`.
+fn item_offsets(source: &str, start: usize) -> Vec<usize> {
+    members(source, start)
+        .into_iter()
+        .map(|(_, offset)| offset)
+        .collect()
+}
+
+/// Offsets of `key`, at its opening quote, and of its value in json object starting at `start`.
+fn member(source: &str, start: usize, key: &str) -> Option<(usize, usize)> {
+    members(source, start)
+        .into_iter()
+        .find_map(|(found, value)| {
+            let (key_start, key_end) = found?;
+            (&source[key_start..key_end] == key).then_some((key_start - 1, value))
+        })
+}
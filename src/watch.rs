@@ -0,0 +1,128 @@
+use crate::app_config::AppConfig;
+use crate::db::SimpleSpellDB;
+use crate::deck::{self, DeckFile};
+use crate::output::write_replacing;
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How often deck and bundle files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Render deck file at `deck_path` to pdf at `output`, then render again every time the deck
+/// file changes, until the process is killed. Errors, like a deck saved half-way through
+/// editing, are logged and the previous pdf is kept.
+///
+/// Bundle files loaded from `bundles_dir` and extra bundles, see
+/// `SimpleSpellDB::load_bundles`, are watched too: then one changes, or a bundle is added,
+/// bundles are reloaded and the deck rendered again with new spell text. While a bundle is
+/// rejected the previous pdf is kept as well.
+///
+/// Files are polled, and compared by contents besides size and modification time: some file
+/// systems keep modification time in whole seconds, and copying tools may keep the time of
+/// the original, so a change can leave it as it was. Pdf is written with
+/// `write_replacing`, so open viewers never reload a partial file.
+pub fn watch(
+    db: &mut SimpleSpellDB,
+    config: &AppConfig,
+    bundles_dir: Option<&Path>,
+    deck_path: &Path,
+    output: &Path,
+) -> Result<()> {
+    tracing::info!(
+        "Watching `{}`, rendering to `{}`",
+        deck_path.display(),
+        output.display()
+    );
+    let mut rendered = None;
+    let mut bundles = bundle_fingerprints(db, bundles_dir);
+    let mut bundles_valid = true;
+    loop {
+        let current = bundle_fingerprints(db, bundles_dir);
+        let bundles_changed = current != bundles;
+        if bundles_changed {
+            bundles_valid = reload_bundles(db, bundles_dir);
+            bundles = current;
+        }
+        let deck = Fingerprint::of(deck_path);
+        if bundles_valid && deck.is_some() && (deck != rendered || bundles_changed) {
+            match render(db, config, deck_path, output) {
+                Ok(()) => tracing::info!("Rendered `{}`", output.display()),
+                Err(error) => tracing::warn!("Unable to render deck: {error:#}"),
+            }
+            rendered = deck;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// State of a watched file, which changes with its contents.
+#[derive(Debug, PartialEq, Eq)]
+struct Fingerprint {
+    size: u64,
+    modified: Option<SystemTime>,
+    sha256: [u8; 32],
+}
+
+impl Fingerprint {
+    /// `None` if file is missing or unreadable.
+    fn of(path: &Path) -> Option<Self> {
+        let data = std::fs::read(path).ok()?;
+        Some(Self {
+            size: data.len() as u64,
+            modified: std::fs::metadata(path)
+                .and_then(|meta| meta.modified())
+                .ok(),
+            sha256: Sha256::digest(&data).into(),
+        })
+    }
+}
+
+/// Bundle files with their fingerprints, `None` for ones removed since listing.
+fn bundle_fingerprints(
+    db: &SimpleSpellDB,
+    dir: Option<&Path>,
+) -> Vec<(PathBuf, Option<Fingerprint>)> {
+    db.bundle_paths(dir)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|path| {
+            let fingerprint = Fingerprint::of(&path);
+            (path, fingerprint)
+        })
+        .collect()
+}
+
+/// Load bundles again, logging the outcome. Returns whether every bundle was merged.
+fn reload_bundles(db: &mut SimpleSpellDB, dir: Option<&Path>) -> bool {
+    match db.load_bundles(dir) {
+        Ok(reports) => {
+            for report in &reports {
+                if report.result.is_ok() {
+                    tracing::info!("Reloaded bundle {report}");
+                } else {
+                    tracing::warn!("Reloaded bundle {report}");
+                }
+            }
+            reports.iter().all(|report| report.result.is_ok())
+        }
+        Err(error) => {
+            tracing::warn!("Unable to read bundles directory: {error}");
+            false
+        }
+    }
+}
+
+fn render(db: &SimpleSpellDB, config: &AppConfig, deck_path: &Path, output: &Path) -> Result<()> {
+    let source = std::fs::read_to_string(deck_path)
+        .with_context(|| format!("Unable to read `{}`", deck_path.display()))?;
+    let deck_file = DeckFile::parse(&source)?;
+    let mismatches = write_replacing(output, |file| {
+        deck::write_deck_pdf(file, db, config, deck_file)
+    })?;
+    for mismatch in mismatches {
+        tracing::warn!("{mismatch}");
+    }
+    Ok(())
+}
@@ -0,0 +1,79 @@
+//! Action costs of built-in spells, and spellings seen in other data.
+
+use spellcard_generator::spell::Actions;
+use std::collections::BTreeSet;
+
+fn other(source: &str) -> Actions {
+    Actions::Other(source.to_string())
+}
+
+/// Every action cost of built-in data with the cost it is parsed to. Costs added by data
+/// updates fail the test until listed here.
+fn built_in_costs() -> Vec<(&'static str, Actions)> {
+    vec![
+        ("Single Action", Actions::Number(1)),
+        ("Two Actions", Actions::Number(2)),
+        ("Three Actions", Actions::Number(3)),
+        ("Reaction", Actions::Reaction),
+        ("Free Action", Actions::FreeAction),
+        ("Single Action to Two Actions", Actions::Range(1, 2)),
+        ("Single Action to Three Actions", Actions::Range(1, 3)),
+        ("Single Action or Two Actions", Actions::Range(1, 2)),
+        ("Two Actions or Three Actions", Actions::Range(2, 3)),
+        // Open ended and mixed costs are shown as written.
+        (
+            "Single Action or more Actions",
+            other("Single Action or more Actions"),
+        ),
+        ("Two Actions to 2 rounds", other("Two Actions to 2 rounds")),
+        ("1 minute", other("1 minute")),
+        ("5 minutes", other("5 minutes")),
+        ("10 minutes", other("10 minutes")),
+        ("30 minutes", other("30 minutes")),
+        ("1 hour", other("1 hour")),
+    ]
+}
+
+#[test]
+fn built_in_action_costs_are_parsed() {
+    let data = std::fs::read_to_string(
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("nethys_data/spells.json"),
+    )
+    .unwrap();
+    let spells = json::parse(&data).unwrap();
+    let costs = spells
+        .members()
+        .filter_map(|spell| spell["actions"].as_str())
+        .collect::<BTreeSet<_>>();
+    let expected = built_in_costs();
+    for cost in &costs {
+        let Some((_, actions)) = expected.iter().find(|(source, _)| source == cost) else {
+            panic!("Action cost {cost:?} of built-in data is not listed in the test");
+        };
+        assert_eq!(&Actions::parse(cost.to_string()), actions, "{cost:?}");
+    }
+    for (source, _) in &expected {
+        assert!(
+            costs.contains(source),
+            "{source:?} is no longer in built-in data"
+        );
+    }
+}
+
+#[test]
+fn other_spellings_are_parsed() {
+    for (source, expected) in [
+        ("Singe Action", Actions::Number(1)),
+        ("Singe Action to Three Actions", Actions::Range(1, 3)),
+        ("one action", Actions::Number(1)),
+        ("2", Actions::Number(2)),
+        ("3 actions", Actions::Number(3)),
+        ("REACTION", Actions::Reaction),
+        ("free action", Actions::FreeAction),
+        ("One to Three Actions", Actions::Range(1, 3)),
+        ("Four Actions", other("Four Actions")),
+        ("", other("")),
+    ] {
+        assert_eq!(Actions::parse(source.to_string()), expected, "{source:?}");
+    }
+}
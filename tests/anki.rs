@@ -0,0 +1,22 @@
+//! Anki notes exported for spells.
+
+mod common;
+
+use spellcard_generator::anki::write_notes;
+
+#[test]
+fn note_per_spell_with_quoted_fields() {
+    let mut spell = common::builtin_spell("Fireball");
+    spell.description = "Says \"boom\".\n\nTwice\tover.".to_string();
+    let mut output = vec![];
+    write_notes(&mut output, "My\tdeck", [&spell, &spell]).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 6);
+    assert!(lines.contains(&"#deck:My deck"));
+    let fields = lines[5].split('\t').collect::<Vec<_>>();
+    assert_eq!(fields.len(), 3);
+    assert!(fields[0].starts_with("\"<b>Fireball</b><br>Spell 3, 2 actions"));
+    assert!(fields[1].contains("Says \"\"boom\"\"."));
+    assert!(fields[2].contains("Fire"));
+}
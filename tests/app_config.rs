@@ -0,0 +1,148 @@
+//! Render defaults read from `config.toml`.
+
+use spellcard_generator::app_config::{write_setting, AppConfig};
+use spellcard_generator::render::{PageLayout, PageSize, RenderOptions};
+use std::path::{Path, PathBuf};
+
+fn parse(source: &str) -> anyhow::Result<AppConfig> {
+    AppConfig::parse(source, Path::new("/config"))
+}
+
+#[test]
+fn settings_replace_built_in_defaults() {
+    let config = parse(
+        r#"
+        [page]
+        size = "letter"
+
+        [card]
+        width = 70
+        height = 95.5
+        margin = 2
+        spacing = 0
+
+        [data]
+        bundle = "homebrew.json"
+        "#,
+    )
+    .unwrap();
+    let mut options = RenderOptions::default();
+    config.apply(&mut options);
+    assert_eq!(options.page.page_size, PageSize::Letter);
+    assert_eq!(options.page.card_width, 70.0);
+    assert_eq!(options.page.card_height, 95.5);
+    assert_eq!(options.page.card_width_inner(), 66.0);
+    assert_eq!(options.page.card_spacing, 0.0);
+    assert_eq!(
+        config.bundle.as_deref(),
+        Some(Path::new("/config/homebrew.json"))
+    );
+}
+
+#[test]
+fn missing_settings_keep_defaults() {
+    let config = parse("[card]\nwidth = 60\n").unwrap();
+    let mut options = RenderOptions::default();
+    let before = options.page;
+    config.apply(&mut options);
+    assert_eq!(options.page.card_width, 60.0);
+    assert_eq!(options.page.card_height, before.card_height);
+    assert_eq!(options.page.margin, before.margin);
+    assert!(options.theme.fonts.text.is_none());
+    assert!(config.bundle.is_none());
+    assert!(config.system.is_none());
+    assert!(!config.touch_mode && !config.network);
+}
+
+#[test]
+fn app_settings_are_read() {
+    let config = parse(
+        r#"
+        [app]
+        system = "Starfinder 2e"
+        touch_mode = true
+        network = true
+
+        [sync]
+        url = "https://example.org/manifest.json"
+        key = "00ff"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.system.as_deref(), Some("Starfinder 2e"));
+    assert!(config.touch_mode && config.network);
+    assert_eq!(
+        config.sync_url.as_deref(),
+        Some("https://example.org/manifest.json")
+    );
+    assert_eq!(config.sync_key.as_deref(), Some("00ff"));
+}
+
+#[test]
+fn written_settings_keep_rest_of_file() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("app_config_write");
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    let path = dir.join("config.toml");
+    write_setting(&path, "app", "network", true).unwrap();
+    std::fs::write(
+        &path,
+        std::fs::read_to_string(&path).unwrap() + "\n# Letter paper.\n[page]\nsize = \"Letter\"\n",
+    )
+    .unwrap();
+    write_setting(&path, "app", "network", false).unwrap();
+    write_setting(&path, "app", "system", "Starfinder 2e").unwrap();
+    write_setting(&path, "sync", "url", "https://example.org").unwrap();
+
+    let source = std::fs::read_to_string(&path).unwrap();
+    assert!(source.contains("# Letter paper."), "{source}");
+    let config = AppConfig::load(&path).unwrap();
+    assert_eq!(config.page_size, Some(PageSize::Letter));
+    assert!(!config.network);
+    assert_eq!(config.system.as_deref(), Some("Starfinder 2e"));
+    assert_eq!(config.sync_url.as_deref(), Some("https://example.org"));
+}
+
+#[test]
+fn card_spacing_changes_grid() {
+    let tight = PageLayout {
+        card_spacing: 0.0,
+        ..PageLayout::default()
+    };
+    let wide = PageLayout {
+        card_spacing: 20.0,
+        ..PageLayout::default()
+    };
+    assert_eq!(tight.grid_width(), 3);
+    assert_eq!(wide.grid_width(), 2);
+}
+
+#[test]
+fn mistakes_are_reported() {
+    for (source, message) in [
+        ("[card]\nwidth = \"wide\"", "`card.width` must be a number"),
+        ("[card]\nmargin = -1", "`card.margin` must not be negative"),
+        ("[card]\nheight = 0", "Card size must be above zero"),
+        (
+            "[card]\nmargin = 31.5",
+            "`card.margin` must be less than half",
+        ),
+        (
+            "[card]\nwidth = 30\nmargin = 15",
+            "`card.margin` must be less than half",
+        ),
+        ("[page]\nsize = \"A3\"", "Unknown page size `A3`"),
+        ("[card]\nwitdh = 63", "Unknown setting `card.witdh`"),
+        ("[colors]\nborder = 1", "Unknown section `[colors]`"),
+        ("page = 1", "`page` must be a section"),
+        (
+            "[app]\nnetwork = \"yes\"",
+            "`app.network` must be `true` or `false`",
+        ),
+        ("[fonts]\ntext = \"missing.ttf\"", "Unable to read font"),
+    ] {
+        let error = format!("{:#}", parse(source).unwrap_err());
+        assert!(error.contains(message), "{source}: {error}");
+    }
+}
@@ -0,0 +1,60 @@
+//! Manifests of batch rendering, and decks combined into one pdf.
+
+use spellcard_generator::batch::parse_manifest;
+use spellcard_generator::db::{SimpleSpellDB, SpellDB};
+use spellcard_generator::render::{write_groups_to_pdf, DeckGroup, RenderOptions};
+use spellcard_generator::system::Pathfinder2e;
+use std::path::Path;
+use std::rc::Rc;
+
+#[test]
+fn manifest_paths_start_at_its_directory() {
+    let source = r#"{"decks": [
+        {"file": "alice.json", "name": "Alice", "output": "pdf/alice.pdf"},
+        "lists/bob.txt"
+    ]}"#;
+    let decks = parse_manifest(source, Path::new("party")).unwrap();
+    assert_eq!(decks.len(), 2);
+    assert_eq!(decks[0].file, Path::new("party/alice.json"));
+    assert_eq!(decks[0].name.as_deref(), Some("Alice"));
+    assert_eq!(
+        decks[0].output.as_deref(),
+        Some(Path::new("party/pdf/alice.pdf"))
+    );
+    assert_eq!(decks[1].file, Path::new("party/lists/bob.txt"));
+    assert!(decks[1].name.is_none() && decks[1].output.is_none());
+}
+
+#[test]
+fn invalid_deck_is_reported_with_position() {
+    let error =
+        parse_manifest(r#"{"decks": ["a.txt", {"name": "B"}]}"#, Path::new(".")).unwrap_err();
+    assert!(format!("{error:#}").contains("deck 2"), "{error:#}");
+}
+
+#[test]
+fn combined_pdf_has_manifest_per_deck() {
+    let db = SimpleSpellDB::new(Rc::new(Pathfinder2e)).unwrap();
+    let fireball = db.find_by_name("Fireball");
+    let heal = db.find_by_name("Heal");
+    let mut alice = RenderOptions::default();
+    alice.deck.name = "Alice".to_string();
+    let mut bob = RenderOptions::default();
+    bob.deck.name = "Bob".to_string();
+    let groups = [
+        DeckGroup {
+            spells: vec![&fireball[0], &heal[0]],
+            options: &alice,
+        },
+        DeckGroup {
+            spells: vec![&heal[0]],
+            options: &bob,
+        },
+    ];
+    let mut output = vec![];
+    let manifests = write_groups_to_pdf(&mut output, "Party", &groups).unwrap();
+    assert_eq!(manifests.len(), 2);
+    assert_eq!(manifests[0].cards.len(), 2);
+    assert_eq!(manifests[1].cards[0].name, heal[0].name);
+    assert!(output.starts_with(b"%PDF"));
+}
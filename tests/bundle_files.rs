@@ -0,0 +1,144 @@
+//! Bundle files loaded from bundles directory, plain and gzip compressed, and spells they replace.
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use spellcard_generator::db::{BundleReport, Conflict, SimpleSpellDB, SpellDB};
+use spellcard_generator::system::Pathfinder2e;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Empty directory for a test, under test build output.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn bundle(name: &str) -> String {
+    format!(
+        r#"[{{
+            "name": "{name}",
+            "id": "spell-90001",
+            "level": 1,
+            "category": "spell",
+            "actions": "Two Actions",
+            "markdown": "header\n---\nA bolt of ice.",
+            "summary": "",
+            "trait": ["Cold"]
+        }}]"#
+    )
+}
+
+fn write_gz(path: &Path, data: &str) {
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    encoder.write_all(data.as_bytes()).unwrap();
+    std::fs::write(path, encoder.finish().unwrap()).unwrap();
+}
+
+#[test]
+fn compressed_bundles_are_labeled_by_name() {
+    let dir = test_dir("bundle_files_gz");
+    write_gz(&dir.join("winter.json.gz"), &bundle("Frost Bolt"));
+    std::fs::write(dir.join("notes.txt"), "not a bundle").unwrap();
+    let mut db = SimpleSpellDB::new(Rc::new(Pathfinder2e)).unwrap();
+    let reports = db.load_bundles(Some(&dir)).unwrap();
+    assert_eq!(reports.len(), 1);
+    assert!(reports[0].result.is_ok());
+    let found = db.find_by_name("Frost Bolt");
+    assert_eq!(found[0].bundle.as_deref(), Some("winter"));
+}
+
+#[test]
+fn files_of_the_same_label_are_not_merged() {
+    let dir = test_dir("bundle_files_same_label");
+    std::fs::write(dir.join("winter.json"), bundle("Frost Bolt")).unwrap();
+    write_gz(&dir.join("winter.json.gz"), &bundle("Frost Lance"));
+    let mut db = SimpleSpellDB::new(Rc::new(Pathfinder2e)).unwrap();
+    let reports = db.load_bundles(Some(&dir)).unwrap();
+    assert_eq!(reports.len(), 2);
+    assert!(reports[0].result.is_ok());
+    assert!(reports[1].path.ends_with("winter.json.gz"));
+    let error = reports[1].result.as_ref().unwrap_err().to_string();
+    assert!(
+        error.contains("Bundle `winter` is already loaded from"),
+        "{error}"
+    );
+    assert_eq!(db.find_by_name("Frost Bolt").len(), 1);
+    assert!(db.find_by_name("Frost Lance").is_empty());
+
+    // Extra bundle of the same label is rejected too.
+    let extra = test_dir("bundle_files_extra").join("winter.json");
+    std::fs::write(&extra, bundle("Frost Lance")).unwrap();
+    db.set_extra_bundles(vec![extra]);
+    std::fs::remove_file(dir.join("winter.json.gz")).unwrap();
+    let reports = db.load_bundles(Some(&dir)).unwrap();
+    assert!(reports[1].result.is_err());
+    assert!(db.find_by_name("Frost Lance").is_empty());
+}
+
+/// Bundle with Fireball of Player Core, like built-in one, and a spell of its own.
+fn fireball_bundle(own_spell: &str) -> String {
+    format!(
+        r#"[{{
+            "name": "Fireball",
+            "id": "spell-90001",
+            "level": 3,
+            "source": ["Player Core"],
+            "category": "spell",
+            "actions": "Two Actions",
+            "markdown": "header\n---\nA house rule ball of fire.",
+            "summary": "",
+            "trait": ["Fire"]
+        }}, {{
+            "name": "{own_spell}",
+            "id": "spell-90002",
+            "level": 1,
+            "category": "spell",
+            "actions": "Two Actions",
+            "markdown": "header\n---\nA bolt of ice.",
+            "summary": "",
+            "trait": ["Cold"]
+        }}]"#
+    )
+}
+
+#[test]
+fn spells_of_the_same_name_and_source_are_replaced() {
+    let dir = test_dir("bundle_files_conflicts");
+    std::fs::write(dir.join("autumn.json"), fireball_bundle("Frost Bolt")).unwrap();
+    std::fs::write(dir.join("winter.json"), fireball_bundle("Frost Lance")).unwrap();
+    let mut db = SimpleSpellDB::new(Rc::new(Pathfinder2e)).unwrap();
+    let builtin = db.find_by_name("Fireball")[0].clone();
+    let reports = db.load_bundles(Some(&dir)).unwrap();
+
+    let conflicts = |report: &BundleReport| report.result.as_ref().unwrap().conflicts.clone();
+    let fireball = |bundle: Option<&str>| Conflict {
+        name: "Fireball".to_string(),
+        source: "Player Core".to_string(),
+        bundle: bundle.map(str::to_string),
+    };
+    assert_eq!(conflicts(&reports[0]), [fireball(None)]);
+    assert_eq!(conflicts(&reports[1]), [fireball(Some("autumn"))]);
+    assert!(reports[0]
+        .to_string()
+        .ends_with("1 added, 1 replaced. Overrides Fireball (Player Core) of built-in data"));
+    let found = db.find_by_name("Fireball");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, builtin.id);
+    assert_eq!(found[0].bundle.as_deref(), Some("winter"));
+    // Spells without source are of the bundle source, so they do not conflict.
+    assert_eq!(db.find_by_name("Frost Bolt").len(), 1);
+    assert_eq!(db.find_by_name("Frost Lance").len(), 1);
+
+    std::fs::remove_file(dir.join("autumn.json")).unwrap();
+    std::fs::remove_file(dir.join("winter.json")).unwrap();
+    db.load_bundles(Some(&dir)).unwrap();
+    let found = db.find_by_name("Fireball");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].bundle, None);
+    assert_eq!(found[0].description, builtin.description);
+}
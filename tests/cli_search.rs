@@ -0,0 +1,155 @@
+//! `search` subcommand, run through the built binary.
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// Empty directory for a test, under test build output.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Binary run with `args` after `search`, with configuration directory of its own, so
+/// settings and bundles of the user are not loaded.
+fn search(name: &str, args: &[&str]) -> Output {
+    let home = test_dir(name);
+    Command::new(env!("CARGO_BIN_EXE_spellcard_generator"))
+        .arg("search")
+        .args(args)
+        .env("HOME", &home)
+        .env("XDG_CONFIG_HOME", &home)
+        .env("APPDATA", &home)
+        .output()
+        .unwrap()
+}
+
+/// Standard output of a successful search.
+fn found(name: &str, args: &[&str]) -> String {
+    let output = search(name, args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn table_aligns_columns() {
+    assert_eq!(
+        found("search_table", &["--name", "fireball"]),
+        "id    name      rank  traditions      source\n\
+         1530  Fireball  3     Arcane, Primal  Player Core\n"
+    );
+    assert_eq!(
+        found("search_table_empty", &["--name", "no such spell"]),
+        "id  name  rank  traditions  source\n"
+    );
+}
+
+#[test]
+fn filters_narrow_results() {
+    let output = found(
+        "search_filters",
+        &[
+            "--name",
+            "fire",
+            "--rank",
+            "3",
+            "--tradition",
+            "arcane",
+            "--format",
+            "csv",
+        ],
+    );
+    let mut rows = output.lines();
+    assert_eq!(rows.next(), Some("id,name,rank,traditions,source"));
+    let rows = rows.collect::<Vec<_>>();
+    assert!(rows.contains(&"1530,Fireball,3,\"Arcane, Primal\",Player Core"));
+    for row in &rows {
+        assert!(row.contains(",3,") && row.contains("Arcane"), "{row}");
+    }
+    // Sorted by name within a rank.
+    let names = rows
+        .iter()
+        .map(|row| row.split(',').nth(1).unwrap())
+        .collect::<Vec<_>>();
+    let mut sorted = names.clone();
+    sorted.sort();
+    assert_eq!(names, sorted);
+}
+
+#[test]
+fn name_takes_search_syntax_and_traditions_add_up() {
+    let output = found(
+        "search_syntax",
+        &[
+            "--name",
+            "rank:1 heal",
+            "--tradition",
+            "divine",
+            "--tradition",
+            "primal",
+            "--format",
+            "ids",
+        ],
+    );
+    assert_eq!(output, "1554\n1689\n");
+    let output = found(
+        "search_occult",
+        &[
+            "--name",
+            "rank:1 heal",
+            "--tradition",
+            "divine",
+            "--tradition",
+            "occult",
+            "--format",
+            "ids",
+        ],
+    );
+    assert_eq!(output, "1686\n");
+    let output = found(
+        "search_arcane",
+        &[
+            "--name",
+            "rank:1 heal",
+            "--tradition",
+            "arcane",
+            "--format",
+            "ids",
+        ],
+    );
+    assert_eq!(output, "");
+}
+
+#[test]
+fn json_lists_spell_objects() {
+    let output = found("search_json", &["--name", "fireball", "--format", "json"]);
+    let spells = json::parse(&output).unwrap();
+    assert_eq!(spells.len(), 1);
+    let spell = &spells[0];
+    assert_eq!(spell["id"], 1530);
+    assert_eq!(spell["name"], "Fireball");
+    assert_eq!(spell["rank"], 3);
+    assert_eq!(spell["traditions"], json::array!["Arcane", "Primal"]);
+    assert_eq!(
+        spell["traits"],
+        json::array!["Concentrate", "Fire", "Manipulate"]
+    );
+    assert_eq!(spell["source"], "Player Core");
+    assert_eq!(spell["url"], "/Spells.aspx?ID=1530");
+}
+
+#[test]
+fn unknown_tradition_is_rejected() {
+    let output = search("search_tradition", &["--tradition", "eldritch"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("expected one of arcane, primal, divine, occult"),
+        "{stderr}"
+    );
+}
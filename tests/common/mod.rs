@@ -0,0 +1,153 @@
+//! Helpers shared by integration tests. Every test uses a part of them.
+#![allow(dead_code)]
+
+use anyhow::Result;
+use json::object::Object;
+use json::JsonValue;
+use spellcard_generator::db::SimpleSpellDB;
+use spellcard_generator::error::ParseError;
+use spellcard_generator::render::{build_spell_scene, OwnedFontConfig, RenderOptions};
+use spellcard_generator::scene_diff::{diff, SceneSnapshot};
+use spellcard_generator::spell::Spell;
+use spellcard_generator::system::{CardSection, GameSystem, Pathfinder2e};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+
+const FIXTURE_BUNDLE: &str = "fixtures";
+
+pub fn tests_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+/// Whether golden files should be written instead of compared, set by `UPDATE_GOLDEN=1`.
+pub fn update_golden() -> bool {
+    std::env::var_os("UPDATE_GOLDEN").is_some_and(|value| value != "0")
+}
+
+/// Spells of `tests/fixtures/spells.json`. Fixtures are kept separately from built-in data,
+/// so data updates do not change golden files.
+pub fn fixture_spells() -> Vec<Spell> {
+    let data = std::fs::read_to_string(tests_dir().join("fixtures/spells.json")).unwrap();
+    let mut db = SimpleSpellDB::new(Rc::new(Pathfinder2e)).unwrap();
+    db.merge_bundle(FIXTURE_BUNDLE, &data).unwrap();
+    db.spells_snapshot()
+        .iter()
+        .filter(|spell| spell.bundle.as_deref() == Some(FIXTURE_BUNDLE))
+        .cloned()
+        .collect()
+}
+
+/// Object of spell `name` in `tests/fixtures/spells.json`, for tests editing its fields.
+pub fn fixture_object(name: &str) -> JsonValue {
+    let data = std::fs::read_to_string(tests_dir().join("fixtures/spells.json")).unwrap();
+    json::parse(&data)
+        .unwrap()
+        .members()
+        .find(|spell| spell["name"] == name)
+        .unwrap()
+        .clone()
+}
+
+/// Pathfinder 2e with `data` as built-in spells.
+pub struct Listed {
+    data: String,
+}
+
+impl GameSystem for Listed {
+    fn name(&self) -> &'static str {
+        "Listed"
+    }
+
+    fn builtin_data(&self) -> Result<Cow<'static, str>> {
+        Ok(Cow::Owned(self.data.clone()))
+    }
+
+    fn bundles_subdir(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn parse_spell(&self, object: &Object) -> Result<Spell, ParseError> {
+        Pathfinder2e.parse_spell(object)
+    }
+
+    fn tradition_labels(&self) -> [&'static str; 4] {
+        Pathfinder2e.tradition_labels()
+    }
+
+    fn glyph_font(&self) -> &'static [u8] {
+        Pathfinder2e.glyph_font()
+    }
+
+    fn card_sections(&self) -> &'static [CardSection] {
+        Pathfinder2e.card_sections()
+    }
+
+    fn license_notice(&self) -> &'static str {
+        Pathfinder2e.license_notice()
+    }
+
+    fn reference_site(&self) -> &'static str {
+        Pathfinder2e.reference_site()
+    }
+}
+
+/// Database with `spells` as built-in data.
+pub fn listed_db(spells: Vec<JsonValue>) -> SimpleSpellDB {
+    let data = json::stringify(JsonValue::Array(spells));
+    SimpleSpellDB::new(Rc::new(Listed { data })).unwrap()
+}
+
+/// Spells of built-in data.
+pub fn builtin_spells() -> Arc<Vec<Spell>> {
+    let db = SimpleSpellDB::new(Rc::new(Pathfinder2e)).unwrap();
+    db.spells_snapshot()
+}
+
+/// First built-in spell matching `predicate`.
+pub fn find_builtin_spell(predicate: impl FnMut(&&Spell) -> bool) -> Spell {
+    builtin_spells().iter().find(predicate).unwrap().clone()
+}
+
+/// Built-in spell called `name`, the first one of names shared by several printings.
+pub fn builtin_spell(name: &str) -> Spell {
+    find_builtin_spell(|spell| spell.name == name)
+}
+
+/// `Prismatic Spray` becomes `prismatic-spray`.
+pub fn file_name(spell_name: &str) -> String {
+    spell_name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Text layout of the card of `spell`, measured with pdf font metrics.
+pub fn layout_snapshot(spell: &Spell, options: &RenderOptions) -> SceneSnapshot {
+    let fonts = OwnedFontConfig::<()>::new(&mut (), &Pathfinder2e).unwrap();
+    let config = fonts.config();
+    let (scene, _) = build_spell_scene(&config, spell, options).unwrap();
+    SceneSnapshot::of(&scene)
+}
+
+/// Golden layout of card `name`, in `tests/golden`.
+pub fn golden_layout_path(name: &str) -> PathBuf {
+    tests_dir().join("golden").join(format!("{name}.json"))
+}
+
+/// Changes from golden layout `name` to `layout`, error if there is no golden layout.
+pub fn layout_changes(name: &str, layout: &SceneSnapshot) -> Result<String, String> {
+    let path = golden_layout_path(name);
+    let source = std::fs::read_to_string(&path)
+        .map_err(|error| format!("{name}: unable to read {}. {error}", path.display()))?;
+    let golden = SceneSnapshot::parse(&source)
+        .map_err(|error| format!("{name}: unable to parse golden layout. {error:#}"))?;
+    let changes = diff(&golden, layout);
+    Ok(match changes.is_empty() {
+        true => String::new(),
+        false => changes.to_string(),
+    })
+}
@@ -0,0 +1,90 @@
+//! CSV rows read by `parse` and written by `write_row`.
+
+use spellcard_generator::csv::{parse, write_row};
+
+fn rows(source: &str) -> Vec<Vec<String>> {
+    parse(source).unwrap()
+}
+
+#[test]
+fn plain_fields_are_split() {
+    assert_eq!(
+        rows("name,count\nFireball,2\n"),
+        [["name", "count"], ["Fireball", "2"]]
+    );
+    // Last line without line break, and empty fields.
+    assert_eq!(rows("Fireball,,notes"), [["Fireball", "", "notes"]]);
+}
+
+#[test]
+fn quoted_fields_keep_separators() {
+    assert_eq!(
+        rows("\"Fireball, heightened\",\"2\"\n"),
+        [["Fireball, heightened", "2"]]
+    );
+}
+
+#[test]
+fn doubled_quotes_are_escaped_quotes() {
+    assert_eq!(
+        rows("\"Say \"\"hi\"\"\",\"\"\"\"\n"),
+        [["Say \"hi\"", "\""]]
+    );
+}
+
+#[test]
+fn quoted_fields_keep_line_breaks() {
+    assert_eq!(
+        rows("Fireball,\"first line\nsecond line\r\nthird\"\nShield,1\n"),
+        [
+            vec!["Fireball", "first line\nsecond line\r\nthird"],
+            vec!["Shield", "1"],
+        ]
+    );
+}
+
+#[test]
+fn crlf_line_breaks() {
+    assert_eq!(
+        rows("name,count\r\nFireball,2\r\n\r\nShield,1\r\n"),
+        [["name", "count"], ["Fireball", "2"], ["Shield", "1"]]
+    );
+}
+
+#[test]
+fn empty_lines_are_skipped() {
+    assert!(rows("").is_empty());
+    assert!(rows("\n\r\n,,\n").is_empty());
+    assert_eq!(rows("\nFireball\n\n"), [["Fireball"]]);
+}
+
+#[test]
+fn ragged_rows_keep_their_lengths() {
+    assert_eq!(
+        rows("name,count,notes\nFireball\nShield,1,,extra\n"),
+        [
+            vec!["name", "count", "notes"],
+            vec!["Fireball"],
+            vec!["Shield", "1", "", "extra"],
+        ]
+    );
+}
+
+#[test]
+fn unterminated_quote_is_rejected() {
+    let error = parse("Fireball,\"notes\n").unwrap_err();
+    assert_eq!(error.to_string(), "Unterminated quoted field");
+}
+
+#[test]
+fn written_rows_parse_back() {
+    let written = [
+        vec!["Fireball", "2", "plain"],
+        vec!["Cone, of cold", "1", "say \"hi\"\nand\r\nbye"],
+    ];
+    let mut output = vec![];
+    for row in &written {
+        write_row(&mut output, row).unwrap();
+    }
+    assert_eq!(rows(&String::from_utf8(output).unwrap()), written);
+}
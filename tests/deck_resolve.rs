@@ -0,0 +1,161 @@
+//! Deck entries found in database by name, told apart by source and rank.
+
+use spellcard_generator::db::SimpleSpellDB;
+use spellcard_generator::deck::{resolve, DeckEntry, Mismatch, ResolvedDeck};
+use spellcard_generator::system::Pathfinder2e;
+use std::rc::Rc;
+
+/// Homebrew Fireball of another rank and Illusory Disguise of the same rank, sharing names
+/// with built-in spells.
+const HOMEBREW: &str = r#"[{
+    "name": "Fireball",
+    "id": "spell-90001",
+    "level": 5,
+    "category": "spell",
+    "actions": "Two Actions",
+    "markdown": "header\n---\nA bigger ball of fire.",
+    "summary": "",
+    "trait": ["Fire"]
+}, {
+    "name": "Illusory Disguise",
+    "id": "spell-90002",
+    "level": 1,
+    "category": "spell",
+    "actions": "Two Actions",
+    "markdown": "header\n---\nYou look like someone else, again.",
+    "summary": "",
+    "trait": ["Illusion"]
+}]"#;
+
+fn entry(name: &str, rank: Option<u8>, source: Option<&str>) -> DeckEntry {
+    DeckEntry {
+        name: name.to_string(),
+        rank,
+        source: source.map(str::to_string),
+        count: 1,
+        notes: String::new(),
+        omit_heightened: false,
+    }
+}
+
+fn db() -> SimpleSpellDB {
+    let mut db = SimpleSpellDB::new(Rc::new(Pathfinder2e)).unwrap();
+    db.merge_bundle("homebrew", HOMEBREW).unwrap();
+    db
+}
+
+/// Ids of found spells.
+fn found(deck: &ResolvedDeck) -> Vec<usize> {
+    deck.spells.iter().map(|(spell, _)| spell.id).collect()
+}
+
+/// Id of homebrew spell `name`.
+fn found_homebrew(db: &SimpleSpellDB, name: &str) -> usize {
+    let deck = resolve(db, vec![entry(name, None, Some("homebrew"))]);
+    found(&deck)[0]
+}
+
+#[test]
+fn source_tells_apart_spells_sharing_name() {
+    let db = db();
+    let deck = resolve(
+        &db,
+        vec![
+            entry("Illusory Disguise", Some(1), Some("Player Core")),
+            entry("illusory disguise", None, Some("HOMEBREW")),
+        ],
+    );
+    assert_eq!(
+        found(&deck),
+        [1568, found_homebrew(&db, "Illusory Disguise")]
+    );
+    assert!(deck.mismatches.is_empty());
+}
+
+#[test]
+fn printings_merged_on_load_are_found() {
+    let db = db();
+    // Legacy printing is merged into the remaster, its source is ignored like a renamed one.
+    let deck = resolve(
+        &db,
+        vec![entry("Invoke Spirits", Some(5), Some("Secrets of Magic"))],
+    );
+    assert_eq!(found(&deck), [1578]);
+    assert!(deck.mismatches.is_empty());
+}
+
+#[test]
+fn rank_tells_apart_spells_sharing_name() {
+    let db = db();
+    let deck = resolve(&db, vec![entry("Fireball", Some(5), None)]);
+    assert_eq!(deck.spells.len(), 1);
+    assert_eq!(deck.spells[0].0.source, "homebrew");
+    let deck = resolve(&db, vec![entry("Fireball", Some(3), None)]);
+    assert_eq!(found(&deck), [1530]);
+    assert!(deck.mismatches.is_empty());
+}
+
+#[test]
+fn spells_of_several_sources_are_ambiguous() {
+    let db = db();
+    let deck = resolve(
+        &db,
+        vec![
+            entry("Illusory Disguise", Some(1), None),
+            // Source renamed in newer data is ignored.
+            entry("Fireball", None, Some("Player Core 2nd printing")),
+        ],
+    );
+    assert!(deck.spells.is_empty());
+    let ambiguous = deck
+        .mismatches
+        .iter()
+        .map(|mismatch| match mismatch {
+            Mismatch::Ambiguous { entry, candidates } => {
+                let mut ids = candidates.iter().map(|spell| spell.id).collect::<Vec<_>>();
+                ids.sort();
+                (entry.name.as_str(), ids)
+            }
+            _ => panic!("Expected ambiguous entry"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        ambiguous,
+        [
+            (
+                "Illusory Disguise",
+                vec![1568, found_homebrew(&db, "Illusory Disguise")]
+            ),
+            ("Fireball", vec![1530, found_homebrew(&db, "Fireball")]),
+        ]
+    );
+}
+
+#[test]
+fn missing_spell_and_other_rank_are_reported() {
+    let db = db();
+    let deck = resolve(
+        &db,
+        vec![
+            entry("Breathe Fire", Some(2), Some("Player Core")),
+            entry("Fireball of Doom", None, None),
+        ],
+    );
+    assert_eq!(found(&deck), [1457]);
+    assert!(matches!(
+        deck.mismatches[..],
+        [Mismatch::Rank { rank: 1, .. }, Mismatch::NotFound(_)]
+    ));
+}
+
+#[test]
+fn shared_names_follow_loaded_bundles() {
+    let mut db = db();
+    let shared = db.shared_names();
+    assert!(shared.contains("illusory disguise"));
+    assert!(shared.contains("fireball"));
+    assert!(!shared.contains("breathe fire"));
+    db.load_bundles(None).unwrap();
+    // Built-in printings of the same spell are merged on load, so nothing is shared.
+    assert!(db.shared_names().is_empty());
+}
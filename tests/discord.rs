@@ -0,0 +1,107 @@
+//! Spells as Discord markdown messages.
+
+mod common;
+
+use spellcard_generator::discord::{spell_messages, MESSAGE_LIMIT};
+use spellcard_generator::spell::Spell;
+
+fn fireball(description: String) -> Spell {
+    let mut spell = common::fixture_spells()
+        .into_iter()
+        .find(|spell| spell.name == "Fireball")
+        .unwrap();
+    spell.description = description;
+    spell.heightened = None;
+    spell.extras.clear();
+    spell
+}
+
+/// Count of bold and italic markers, not counting escaped stars.
+fn markers(message: &str) -> (usize, usize) {
+    let (mut bold, mut italic) = (0, 0);
+    let mut chars = message.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == '*' {
+            if chars.next_if_eq(&'*').is_some() {
+                bold += 1;
+            } else {
+                italic += 1;
+            }
+        }
+    }
+    (bold, italic)
+}
+
+fn assert_messages_valid(messages: &[String]) {
+    for message in messages {
+        assert!(message.chars().count() <= MESSAGE_LIMIT, "{message}");
+        let (bold, italic) = markers(message);
+        assert!(bold % 2 == 0 && italic % 2 == 0, "{message}");
+        assert_eq!(message.trim(), message);
+    }
+}
+
+#[test]
+fn short_spell_is_one_message() {
+    let messages = spell_messages(&fireball("A roaring blast of fire.".to_string()));
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].starts_with("**Fireball**"));
+    assert!(messages[0].ends_with("A roaring blast of fire."));
+}
+
+#[test]
+fn messages_end_at_paragraphs() {
+    let paragraphs: Vec<String> = (0..40)
+        .map(|i| format!("**Paragraph {i}** {}end {i}.", "word ".repeat(30)))
+        .collect();
+    let messages = spell_messages(&fireball(paragraphs.join("\n\n")));
+    assert!(messages.len() > 1);
+    assert_messages_valid(&messages);
+    for message in &messages[1..] {
+        assert!(message.starts_with("**Paragraph "), "{message}");
+    }
+    for (i, paragraph) in paragraphs.iter().enumerate() {
+        let found = messages.iter().filter(|m| m.contains(paragraph.as_str()));
+        assert_eq!(found.count(), 1, "paragraph {i}");
+    }
+}
+
+#[test]
+fn messages_end_at_lines_outside_markup() {
+    // Bold text spans lines, so only lines between bold parts can end a message.
+    let lines: Vec<String> = (0..300)
+        .map(|i| match i % 3 {
+            0 => format!("**Bold {i}"),
+            1 => format!("still bold {i}**"),
+            _ => format!("Plain {i}"),
+        })
+        .collect();
+    let messages = spell_messages(&fireball(lines.join("\n")));
+    assert!(messages.len() > 1);
+    assert_messages_valid(&messages);
+    for message in &messages[1..] {
+        assert!(message.starts_with("**Bold "), "{message}");
+    }
+    // First message is the header, ended by the paragraph break before description.
+    assert!(!messages[0].contains("Bold 0"));
+    for message in &messages[1..messages.len() - 1] {
+        assert!(message.lines().last().unwrap().starts_with("Plain "));
+    }
+}
+
+#[test]
+fn long_markup_is_closed_and_reopened() {
+    let description = format!("**{}end**", "bold words ".repeat(400));
+    let messages = spell_messages(&fireball(description));
+    assert!(messages.len() > 2);
+    assert_messages_valid(&messages);
+    for message in &messages[1..] {
+        assert!(message.starts_with("**"), "{message}");
+        assert!(message.ends_with("**"), "{message}");
+    }
+    let text = messages.join(" ");
+    assert_eq!(text.matches("bold").count(), 400);
+    assert_eq!(text.matches("words").count(), 400);
+}
@@ -0,0 +1,64 @@
+//! Cards reported by dry run, and pages it counts.
+
+mod common;
+
+use spellcard_generator::render::{dry_run, RenderOptions};
+use spellcard_generator::spell::Spell;
+use spellcard_generator::system::Pathfinder2e;
+use std::rc::Rc;
+
+fn options() -> RenderOptions {
+    RenderOptions {
+        system: Rc::new(Pathfinder2e),
+        ..RenderOptions::default()
+    }
+}
+
+/// Built-in spell fitting a single card.
+fn short_spell() -> Spell {
+    let options = options();
+    common::find_builtin_spell(|spell| {
+        let report = dry_run([*spell], &options).unwrap();
+        report.double.is_empty() && report.too_long.is_empty()
+    })
+}
+
+fn with_sentences(spell: &Spell, id: usize, count: usize) -> Spell {
+    Spell {
+        id,
+        name: format!("{} {count}", spell.name),
+        description: "The spell keeps going for one more sentence. ".repeat(count),
+        ..spell.clone()
+    }
+}
+
+#[test]
+fn long_spells_are_reported_once() {
+    let short = short_spell();
+    let double = with_sentences(&short, 90001, 40);
+    let too_long = with_sentences(&short, 90002, 400);
+    let report = dry_run([&short, &double, &double, &too_long], &options()).unwrap();
+    assert_eq!(report.double.len(), 1);
+    assert_eq!(report.double[0], double.name);
+    assert_eq!(report.too_long.len(), 1);
+    assert_eq!(report.too_long[0].0, too_long.name);
+    assert!(report.too_long[0].1 > 0.0);
+}
+
+#[test]
+fn pages_are_counted_with_extra_ones() {
+    let short = short_spell();
+    let mut options = RenderOptions {
+        attribution_page: false,
+        ..options()
+    };
+    let per_page = options.page.grid_width() * options.page.grid_height();
+    let spells = vec![&short; per_page + 1];
+    assert_eq!(dry_run(spells.clone(), &options).unwrap().page_count, 2);
+    assert_eq!(dry_run([], &options).unwrap().page_count, 1);
+    options.cover_page = true;
+    options.attribution_page = true;
+    assert_eq!(dry_run(spells.clone(), &options).unwrap().page_count, 4);
+    options.duplex.card_backs = true;
+    assert_eq!(dry_run(spells, &options).unwrap().page_count, 7);
+}
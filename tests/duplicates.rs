@@ -0,0 +1,73 @@
+//! Spells listed several times in built-in data, merged on load.
+
+mod common;
+
+use json::JsonValue;
+use spellcard_generator::db::{SimpleSpellDB, SpellDB};
+use spellcard_generator::system::Pathfinder2e;
+use std::rc::Rc;
+
+/// Fireball of test fixtures, with `id` and without links to other printings.
+fn fireball(id: usize) -> JsonValue {
+    let mut spell = common::fixture_object("Fireball");
+    spell["id"] = format!("spell-{id}").into();
+    spell.remove("legacy_id");
+    spell
+}
+
+#[test]
+fn reprints_are_merged() {
+    let db = SimpleSpellDB::new(Rc::new(Pathfinder2e)).unwrap();
+    for (name, legacy_id, remaster_id) in [
+        ("Illusory Disguise", 159, 1568),
+        ("Invoke Spirits", 937, 1578),
+    ] {
+        let found = db.find_by_name(name);
+        assert_eq!(found.len(), 1, "{name}");
+        assert_eq!(found[0].id, remaster_id, "{name}");
+        assert_eq!(found[0].source, "Player Core", "{name}");
+        assert_eq!(db.get(legacy_id).unwrap().id, remaster_id, "{name}");
+        assert!(db.raw_data(legacy_id).is_none(), "{name}");
+    }
+}
+
+#[test]
+fn same_entries_are_merged() {
+    let mut newer = fireball(2);
+    newer["release_date"] = "2024-01-01".into();
+    let mut shouting = fireball(3);
+    shouting["name"] = "FIREBALL!".into();
+    let db = common::listed_db(vec![fireball(1), newer, shouting]);
+    let found = db.find_by_name("Fireball");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, 2);
+    for dropped in [1, 3] {
+        assert_eq!(db.get(dropped).unwrap().id, 2);
+        assert!(db.raw_data(dropped).is_none());
+    }
+}
+
+#[test]
+fn remaster_is_kept_over_legacy() {
+    let mut legacy = fireball(1);
+    legacy["source"] = json::array!["Core Rulebook"];
+    legacy["remaster_id"] = "spell-2".into();
+    let mut remaster = fireball(2);
+    remaster["legacy_id"] = "spell-1".into();
+    let mut unlinked = fireball(3);
+    unlinked["source"] = json::array!["Core Rulebook"];
+    unlinked["release_date"] = "2025-01-01".into();
+    let db = common::listed_db(vec![legacy, remaster, unlinked]);
+    let found = db.find_by_name("Fireball");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, 2);
+    assert_eq!(found[0].source, "Player Core");
+}
+
+#[test]
+fn entries_of_other_rank_are_kept() {
+    let mut other_rank = fireball(2);
+    other_rank["level"] = 4.into();
+    let db = common::listed_db(vec![fireball(1), other_rank]);
+    assert_eq!(db.find_by_name("Fireball").len(), 2);
+}
@@ -0,0 +1,62 @@
+//! Typed errors naming spell and field a problem is about.
+
+mod common;
+
+use spellcard_generator::db::SimpleSpellDB;
+use spellcard_generator::error::LayoutError;
+use spellcard_generator::render::{build_spell_scene, OwnedFontConfig, RenderOptions};
+use spellcard_generator::system::{GameSystem, Pathfinder2e};
+use std::rc::Rc;
+
+const SPELL: &str = r#"{
+    "name": "Frost Bolt",
+    "id": "spell-90001",
+    "level": "first",
+    "category": "spell",
+    "actions": "Two Actions",
+    "markdown": "header\n---\nA bolt of ice.",
+    "summary": "",
+    "trait": ["Cold"]
+}"#;
+
+#[test]
+fn parse_error_names_spell_and_field() {
+    let json::JsonValue::Object(object) = json::parse(SPELL).unwrap() else {
+        unreachable!();
+    };
+    let error = Pathfinder2e.parse_spell(&object).unwrap_err();
+    assert_eq!(error.spell.as_deref(), Some("Frost Bolt"));
+    assert_eq!(error.spell_id, Some(90001));
+    assert_eq!(error.field.as_deref(), Some("level"));
+    assert!(error
+        .to_string()
+        .starts_with("Unable to parse spell `Frost Bolt` (spell-90001)"));
+}
+
+#[test]
+fn bundle_errors_keep_spell_field() {
+    let db = SimpleSpellDB::new(Rc::new(Pathfinder2e)).unwrap();
+    let error = db.validate_bundle(&format!("[{SPELL}]")).unwrap_err();
+    assert_eq!(error.field.as_deref(), Some("level"));
+    let error = db
+        .validate_bundle(r#"{"license": 1, "spells": []}"#)
+        .unwrap_err();
+    assert_eq!(
+        (error.spell, error.field.as_deref()),
+        (None, Some("license"))
+    );
+}
+
+#[test]
+fn layout_error_names_spell() {
+    let mut spell = common::builtin_spells()[0].clone();
+    spell.description = "W".repeat(200);
+    let fonts = OwnedFontConfig::<()>::new(&mut (), &Pathfinder2e).unwrap();
+    let config = fonts.config();
+    match build_spell_scene(&config, &spell, &RenderOptions::default()) {
+        Err(error @ LayoutError::WordTooWide { .. }) => {
+            assert_eq!(error.spell_id(), Some(spell.id));
+        }
+        other => panic!("Expected too wide word, got {:?}", other.err()),
+    }
+}
@@ -0,0 +1,184 @@
+//! Font files made for pdf export: subsets, oblique Helvetica and embedding of built-in fonts.
+
+mod common;
+
+use spellcard_generator::error::ExportError;
+use spellcard_generator::profiles::Profile;
+use spellcard_generator::render::{
+    write_groups_to_pdf, write_to_pdf, DeckGroup, PrintReady, RenderOptions,
+};
+use spellcard_generator::rich_text::FontKind;
+use spellcard_generator::subset::subset_font;
+use spellcard_generator::system::{GameSystem, Pathfinder2e};
+use std::collections::BTreeSet;
+use ttf_parser::{Face, GlyphId, OutlineBuilder};
+
+/// Slant of Helvetica Oblique.
+const SLANT: f32 = 0.2126;
+
+/// Points of glyph outline, with control points.
+#[derive(Default)]
+struct Points(Vec<(f32, f32)>);
+
+impl OutlineBuilder for Points {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.0.push((x, y));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.0.push((x, y));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.0.extend([(x1, y1), (x, y)]);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.0.extend([(x1, y1), (x2, y2), (x, y)]);
+    }
+
+    fn close(&mut self) {}
+}
+
+fn points(face: &Face, glyph: GlyphId) -> Vec<(f32, f32)> {
+    let mut points = Points::default();
+    face.outline_glyph(glyph, &mut points);
+    points.0
+}
+
+#[test]
+fn subsets_keep_outlines_and_metrics() {
+    // Font, text kept in subset, and characters neither in text nor parts of its glyphs.
+    let fonts = [
+        (
+            "text",
+            FontKind::Text.bytes(),
+            "Fireball déjà vu, Œuvre — 10d6 “fire”",
+            "QXZqxz789",
+        ),
+        (
+            "bold",
+            FontKind::Bold.bytes(),
+            "Heightened (+1) Åland",
+            "BQxz9",
+        ),
+        (
+            "italic",
+            FontKind::Italic.bytes(),
+            "Concentrate, Manipulate",
+            "QZz0",
+        ),
+        ("glyphs", Pathfinder2e.glyph_font(), "123", "45"),
+    ];
+    for (name, data, text, unused) in fonts {
+        let kept = text.chars().collect::<BTreeSet<_>>();
+        let subset = subset_font(data, &kept).unwrap();
+        assert!(subset.len() < data.len(), "{name}");
+        let full = Face::parse(data, 0).unwrap();
+        let subset = Face::parse(&subset, 0).unwrap();
+        assert_eq!(full.number_of_glyphs(), subset.number_of_glyphs(), "{name}");
+        assert_eq!(
+            points(&full, GlyphId(0)),
+            points(&subset, GlyphId(0)),
+            "{name}"
+        );
+        for c in kept.iter().copied().chain(unused.chars()) {
+            let glyph = full.glyph_index(c).unwrap();
+            assert_eq!(subset.glyph_index(c), Some(glyph), "{name}: {c}");
+            let outline = points(&subset, glyph);
+            match kept.contains(&c) {
+                true => {
+                    assert_eq!(points(&full, glyph), outline, "{name}: {c}");
+                    assert_eq!(
+                        full.glyph_hor_advance(glyph),
+                        subset.glyph_hor_advance(glyph),
+                        "{name}: {c}"
+                    );
+                }
+                false => assert!(outline.is_empty(), "{name}: {c}"),
+            }
+        }
+    }
+}
+
+#[test]
+fn italic_is_oblique_regular_face() {
+    let regular = Face::parse(FontKind::Text.bytes(), 0).unwrap();
+    let oblique = Face::parse(FontKind::Italic.bytes(), 0).unwrap();
+    assert!(oblique.is_italic());
+    assert!((oblique.italic_angle().unwrap() + 12.0).abs() < 0.01);
+    assert_eq!(regular.number_of_glyphs(), oblique.number_of_glyphs());
+    // Letters, digits, and composite glyphs like accented ones.
+    for c in ('!'..='~').chain("éÅñçüŒ—’".chars()) {
+        let glyph = regular.glyph_index(c).unwrap();
+        assert_eq!(oblique.glyph_index(c), Some(glyph), "{c}");
+        assert_eq!(
+            regular.glyph_hor_advance(glyph),
+            oblique.glyph_hor_advance(glyph),
+            "{c}"
+        );
+        let upright = points(&regular, glyph);
+        let slanted = points(&oblique, glyph);
+        assert_eq!(upright.len(), slanted.len(), "{c}");
+        for ((x, y), (slanted_x, slanted_y)) in upright.into_iter().zip(slanted) {
+            assert_eq!(y, slanted_y, "{c}");
+            // Components of composite glyphs are rounded separately from their offsets.
+            assert!((x + y * SLANT - slanted_x).abs() <= 1.5, "{c}: {x} {y}");
+        }
+        let bounds = oblique.glyph_bounding_box(glyph);
+        let advance = oblique.glyph_hor_side_bearing(glyph);
+        if let (Some(bounds), Some(bearing)) = (bounds, advance) {
+            assert_eq!(bounds.x_min, bearing, "{c}");
+        }
+    }
+}
+
+#[test]
+fn print_ready_pdf_embeds_fonts() {
+    let options = RenderOptions {
+        print_ready: Some(PrintReady::default()),
+        ..RenderOptions::default()
+    };
+    let mut pdf = vec![];
+    write_to_pdf(&mut pdf, &common::fixture_spells(), &options).unwrap();
+    assert!(pdf.starts_with(b"%PDF"));
+    assert!(pdf.trim_ascii_end().ends_with(b"%%EOF"));
+    // Text, bold, italic and action glyphs are all embedded fonts.
+    let embedded = pdf.windows(9).filter(|w| w == b"FontFile2").count();
+    assert_eq!(embedded, 4);
+}
+
+#[test]
+fn combined_pdf_embeds_fonts_once() {
+    let options = RenderOptions {
+        print_ready: Some(PrintReady::default()),
+        ..RenderOptions::default()
+    };
+    let spells = common::fixture_spells();
+    let (first, second) = spells.split_at(spells.len() / 2);
+    let groups = [first, second].map(|spells| DeckGroup {
+        spells: spells.iter().collect(),
+        options: &options,
+    });
+    let mut pdf = vec![];
+    write_groups_to_pdf(&mut pdf, "Party", &groups).unwrap();
+    let embedded = pdf.windows(9).filter(|w| w == b"FontFile2").count();
+    assert_eq!(embedded, 4);
+}
+
+#[test]
+fn negative_bleed_is_rejected() {
+    let profile = json::parse(r#"{"name": "Bled", "print_ready": true, "bleed": -1}"#).unwrap();
+    let json::JsonValue::Object(profile) = profile else {
+        unreachable!()
+    };
+    let error = Profile::parse(&profile).unwrap_err();
+    assert!(error.to_string().contains("`bleed` must not be negative"));
+
+    let options = RenderOptions {
+        print_ready: Some(PrintReady { bleed: -1.0 }),
+        ..RenderOptions::default()
+    };
+    let result = write_to_pdf(vec![], &common::fixture_spells(), &options);
+    assert!(matches!(result, Err(ExportError::Pdf(_))));
+}
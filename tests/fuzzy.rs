@@ -0,0 +1,79 @@
+//! Ranking of fuzzy matches, used by command palette.
+
+use spellcard_generator::fuzzy::{fuzzy_filter, fuzzy_score};
+
+fn ranked<'a>(query: &str, candidates: &[&'a str]) -> Vec<&'a str> {
+    fuzzy_filter(query, candidates.iter().copied())
+        .into_iter()
+        .map(|i| candidates[i])
+        .collect()
+}
+
+#[test]
+fn characters_must_appear_in_order() {
+    assert_eq!(fuzzy_score("xyz", "Fireball"), None);
+    assert_eq!(fuzzy_score("erif", "Fire"), None);
+    assert_eq!(fuzzy_score("fireballs", "Fireball"), None);
+    assert!(fuzzy_score("frbl", "Fireball").is_some());
+    assert_eq!(ranked("heal", &["Fireball", "Haste", "Soothe"]), [""; 0]);
+}
+
+#[test]
+fn case_and_whitespace_of_query_are_ignored() {
+    assert_eq!(
+        fuzzy_score("FIRE ball", "fireball"),
+        fuzzy_score("fireball", "Fireball")
+    );
+    assert_eq!(
+        fuzzy_score("exp pdf", "Export pdf"),
+        fuzzy_score("exppdf", "Export pdf")
+    );
+}
+
+#[test]
+fn empty_query_keeps_order() {
+    assert_eq!(fuzzy_score("", "Anything"), Some(0));
+    assert_eq!(fuzzy_score(" ", "Anything"), Some(0));
+    let candidates = ["Print", "Export pdf", "Anki"];
+    assert_eq!(ranked("", &candidates), candidates);
+}
+
+#[test]
+fn prefix_matches_rank_first() {
+    let candidates = ["Unfired Clay", "Wall of Fire", "Fire Shield", "Fireball"];
+    assert_eq!(
+        ranked("fire", &candidates),
+        ["Fireball", "Fire Shield", "Wall of Fire", "Unfired Clay"]
+    );
+}
+
+#[test]
+fn word_starts_beat_inner_letters() {
+    assert_eq!(
+        ranked("pp", &["Export pdf", "Preview pages"]),
+        ["Preview pages", "Export pdf"]
+    );
+    assert_eq!(
+        ranked("mm", &["Summon Animal", "Magic Missile"]),
+        ["Magic Missile", "Summon Animal"]
+    );
+    // Same letters, same length: only the word start differs.
+    assert!(fuzzy_score("b", "a b") > fuzzy_score("b", "abc"));
+}
+
+#[test]
+fn consecutive_characters_beat_scattered_ones() {
+    assert!(fuzzy_score("abc", "xabcx") > fuzzy_score("abc", "axbxc"));
+    assert_eq!(
+        ranked("ball", &["Cobalt Walls", "Fireball"]),
+        ["Fireball", "Cobalt Walls"]
+    );
+}
+
+#[test]
+fn shorter_text_wins_ties() {
+    assert_eq!(ranked("heal", &["Healing", "Heal"]), ["Heal", "Healing"]);
+    assert!(fuzzy_score("heal", "Heal") > fuzzy_score("heal", "Healing"));
+    // Equal scores keep given order.
+    assert_eq!(fuzzy_filter("fb", ["Fireball", "Fireball"]), [0, 1]);
+}
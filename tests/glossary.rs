@@ -0,0 +1,10 @@
+//! Traits glossary shown in preview tooltips.
+
+use spellcard_generator::glossary::trait_text;
+
+#[test]
+fn traits_found_ignoring_case() {
+    assert!(trait_text("Fire").is_some_and(|text| text.contains("fire damage")));
+    assert_eq!(trait_text("incapacitation"), trait_text("Incapacitation"));
+    assert!(trait_text("Wizard").is_none());
+}
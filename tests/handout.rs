@@ -0,0 +1,72 @@
+//! Spells written as Markdown and plain text handouts.
+
+mod common;
+
+use spellcard_generator::handout::{write_handout, HandoutFormat};
+
+fn handout(names: &[&str], format: HandoutFormat) -> String {
+    let spells = names
+        .iter()
+        .map(|name| common::builtin_spell(name))
+        .collect::<Vec<_>>();
+    let mut output = vec![];
+    write_handout(&mut output, format, &spells).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn markdown_from_spell_fields() {
+    let output = handout(&["Fireball", "Heal", "Fireball"], HandoutFormat::Markdown);
+    assert!(output.starts_with("## Fireball\n\n**Spell 3, 2 actions**\n\n"));
+    assert!(output.contains("*Concentrate, Fire, Manipulate*"));
+    assert!(output.contains("**Area** 20-foot burst"));
+    assert!(output.contains("**Heightened (+1)** The damage increases by 2d6."));
+    assert!(output.contains("\n---\n\n## Heal"));
+    assert!(output.contains("**Single Action** The spell has a range of touch."));
+    assert_eq!(output.matches("## Fireball").count(), 1);
+    assert!(!output.contains("Traits.aspx"));
+    assert!(!output.contains('<'));
+}
+
+#[test]
+fn plain_text_without_markup() {
+    let output = handout(&["Heal"], HandoutFormat::Text);
+    assert!(output.starts_with("HEAL\nSpell 1, 1 to 3 actions\nTraits: Healing"));
+    assert!(output.contains("\nTraditions: Divine, Primal\n"));
+    assert!(output.contains("[Two Actions] (concentrate) The spell"));
+    assert!(output.ends_with("Source: Player Core"));
+    assert!(!output.contains('*'));
+    assert!(!output.contains("Traits.aspx"));
+}
+
+#[test]
+fn markdown_characters_of_text_are_escaped() {
+    let mut spell = common::builtin_spell("Fireball");
+    spell.description =
+        r"Deal 2d6 \* rank to snake\_case [foes] #1, *emphasis* stays. | d4 | \| |".to_string();
+    let write = |format| {
+        let mut output = vec![];
+        write_handout(&mut output, format, [&spell]).unwrap();
+        String::from_utf8(output).unwrap()
+    };
+    assert!(write(HandoutFormat::Markdown).contains(
+        r"Deal 2d6 \* rank to snake\_case \[foes\] \#1, *emphasis* stays. \| d4 \| \| \|"
+    ));
+    assert!(write(HandoutFormat::Text)
+        .contains("Deal 2d6 * rank to snake_case [foes] #1, emphasis stays. | d4 | | |"));
+}
+
+#[test]
+fn tables_keep_their_rows() {
+    let mut spell = common::builtin_spell("Fireball");
+    spell.description =
+        "Roll on the table.\n\n| d4 | Effect |\n|---|---|\n| 1 | Fire |\n| 2 | Ice |".to_string();
+    let write = |format| {
+        let mut output = vec![];
+        write_handout(&mut output, format, [&spell]).unwrap();
+        String::from_utf8(output).unwrap()
+    };
+    assert!(write(HandoutFormat::Markdown)
+        .contains("| d4 | Effect |\n|---|---|\n| 1 | Fire |\n| 2 | Ice |\n"));
+    assert!(write(HandoutFormat::Text).contains("d4 | Effect\n1 | Fire\n2 | Ice\n"));
+}
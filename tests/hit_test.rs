@@ -0,0 +1,69 @@
+//! Text and sections found by `Scene::hit_test` on laid out cards.
+
+mod common;
+
+use pathfinder_geometry::vector::Vector2F;
+use spellcard_generator::render::{
+    build_spell_scene, lay_out_deck, OwnedFontConfig, RenderOptions,
+};
+use spellcard_generator::spell::Spell;
+use spellcard_generator::system::Pathfinder2e;
+use std::borrow::Cow;
+
+#[test]
+fn trait_and_property_are_found() {
+    let spell = common::builtin_spell("Fireball");
+    let fonts = OwnedFontConfig::<()>::new(&mut (), &Pathfinder2e).unwrap();
+    let config = fonts.config();
+    let (scene, _) = build_spell_scene(&config, &spell, &RenderOptions::default()).unwrap();
+
+    let center_of = |source: &str| {
+        scene
+            .primitives
+            .iter()
+            .filter_map(|primitive| primitive.as_text())
+            .find(|chunk| chunk.source.as_deref() == Some(source))
+            .map(|chunk| chunk.rect.center())
+            .unwrap_or_else(|| panic!("No text with source `{source}`"))
+    };
+    let fire = scene.hit_test(center_of("trait:fire")).unwrap();
+    assert_eq!(fire.source, Some("trait:fire"));
+    assert_eq!(fire.section, Some("traits"));
+    let name = scene.hit_test(center_of("name")).unwrap();
+    assert_eq!(name.source, Some("name"));
+    let range = scene.hit_test(center_of("property:range")).unwrap();
+    assert_eq!(range.source, Some("property:range"));
+
+    assert_eq!(scene.hit_test(Vector2F::new(-10.0, -10.0)), None);
+}
+
+#[test]
+fn card_number_and_untranslated_label_are_in_footer() {
+    let spell = Spell {
+        untranslated: true,
+        ..common::builtin_spell("Fireball")
+    };
+    let spells = [Cow::Borrowed(&spell)];
+    let fonts = OwnedFontConfig::<()>::new(&mut (), &Pathfinder2e).unwrap();
+    let config = fonts.config();
+    let options = RenderOptions {
+        numbering: true,
+        ..RenderOptions::default()
+    };
+    let (pages, _) = lay_out_deck(&config, &spells, &options);
+    let scene = pages[0][0].scene().unwrap();
+
+    let footer = scene
+        .sections
+        .iter()
+        .find(|section| section.name == "footer")
+        .unwrap();
+    assert!(footer.bottom <= scene.bounding_box.height());
+    for label in ["EN", "1/1"] {
+        let chunk = scene.texts().find(|chunk| chunk.text == label).unwrap();
+        let hit = scene.hit_test(chunk.rect.center()).unwrap();
+        assert_eq!(hit.section, Some("footer"), "{label}");
+    }
+    let number = scene.texts().find(|chunk| chunk.text == "1/1").unwrap();
+    assert!(number.rect.max_x() > scene.bounding_box.width() - 1.0);
+}
@@ -0,0 +1,32 @@
+//! Text layout of fixture spells compared with golden layouts in `tests/golden`, so layout
+//! changes show up as text which moved, changed size, appeared or disappeared.
+//!
+//! Missing golden layouts fail the test. Run with `UPDATE_GOLDEN=1` to write them after
+//! intended layout changes, then review and commit them.
+
+mod common;
+
+use common::{file_name, fixture_spells, golden_layout_path, layout_changes, layout_snapshot};
+use spellcard_generator::render::RenderOptions;
+
+#[test]
+fn layout_matches_golden_layouts() {
+    let options = RenderOptions::default();
+    let mut failures = vec![];
+    for spell in fixture_spells() {
+        let name = file_name(&spell.name);
+        let layout = layout_snapshot(&spell, &options);
+        if common::update_golden() {
+            let path = golden_layout_path(&name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, layout.to_json()).unwrap();
+            continue;
+        }
+        match layout_changes(&name, &layout) {
+            Ok(changes) if changes.is_empty() => {}
+            Ok(changes) => failures.push(format!("{name}: layout changed\n{changes}")),
+            Err(error) => failures.push(format!("{error}\nRun with UPDATE_GOLDEN=1 to write it")),
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
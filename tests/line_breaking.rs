@@ -0,0 +1,180 @@
+//! Properties of `SceneBuilder` line breaking, checked on generated text and layouts.
+
+use pathfinder_geometry::{rect::RectF, vector::Vector2F};
+use proptest::prelude::*;
+use spellcard_generator::rich_text::{AlignStrategy, Font, FontKind, Scene, SceneBuilder};
+
+/// Allowed rounding error, in `Pt`.
+const EPSILON: f32 = 1e-3;
+/// Height of bounding box. Text may overflow it, only width is checked.
+const BOX_HEIGHT: f32 = 200.0;
+
+#[derive(Debug, Clone)]
+enum Piece {
+    Text(String),
+    Boxed(String),
+    Indent(f32),
+    LineBreak,
+    BeginGroup,
+    EndGroup,
+}
+
+fn piece() -> impl Strategy<Value = Piece> {
+    prop_oneof![
+        4 => "\\PC{0,40}".prop_map(Piece::Text),
+        4 => "[a-zæøå ]{0,20}( [\\u{300}-\\u{36f}\\u{200b}\\u{3000}\\t]{1,3} \\w{1,5}){0,3}".prop_map(Piece::Text),
+        2 => soft_breaks().prop_map(Piece::Text),
+        1 => "\\PC{0,10}".prop_map(Piece::Boxed),
+        1 => (0.0f32..50.0).prop_map(Piece::Indent),
+        1 => Just(Piece::LineBreak),
+        1 => Just(Piece::BeginGroup),
+        1 => Just(Piece::EndGroup),
+    ]
+}
+
+/// Words with soft hyphens, joined by usual and no-break spaces.
+fn soft_breaks() -> impl Strategy<Value = String> {
+    "([a-z]{1,6}\\u{ad}?){1,4}([ \\u{a0}]([a-z]{1,6}\\u{ad}?){1,4}){0,6}"
+}
+
+fn align() -> impl Strategy<Value = u8> {
+    0u8..4
+}
+
+fn alignment(index: u8) -> AlignStrategy {
+    match index {
+        0 => AlignStrategy::AlignLeft,
+        1 => AlignStrategy::AlignRight,
+        2 => AlignStrategy::AlignCenter,
+        _ => AlignStrategy::JustifyEven,
+    }
+}
+
+fn lay_out<'a>(
+    font: &'a Font<()>,
+    pieces: &'a [Piece],
+    width: f32,
+    font_size: f32,
+    align: u8,
+) -> Scene<'a, ()> {
+    let bounding_box = RectF::new(Vector2F::zero(), Vector2F::new(width, BOX_HEIGHT));
+    let mut builder = SceneBuilder::new(font, bounding_box);
+    builder
+        .set_font_size(font_size)
+        .set_default_chunk_space()
+        .set_alignment(alignment(align));
+    for piece in pieces {
+        match piece {
+            Piece::Text(text) => {
+                // Both borrowed and owned text paths are exercised.
+                builder.add_text(text.as_str());
+                builder.add_text(text.clone());
+            }
+            Piece::Boxed(text) => {
+                builder.add_boxed_text(text, 1.0);
+            }
+            Piece::Indent(indent) => {
+                builder.set_indent(indent.min(width));
+            }
+            Piece::LineBreak => {
+                builder.finish_line();
+            }
+            Piece::BeginGroup => {
+                builder.begin_no_break_group();
+            }
+            Piece::EndGroup => {
+                builder.end_no_break_group();
+            }
+        }
+    }
+    builder.finish_line();
+    builder.scene()
+}
+
+fn text_font() -> Font<()> {
+    Font::build(&mut (), FontKind::Text).unwrap()
+}
+
+proptest! {
+    #[test]
+    fn chunks_stay_within_box_width(
+        pieces in prop::collection::vec(piece(), 0..12),
+        width in 1.0f32..300.0,
+        font_size in 1.0f32..30.0,
+        align in align(),
+    ) {
+        let font = text_font();
+        let scene = lay_out(&font, &pieces, width, font_size, align);
+        for chunk in scene.texts() {
+            prop_assert!(chunk.rect.min_x() >= -EPSILON, "{chunk:?} starts left of box");
+            prop_assert!(chunk.rect.max_x() <= width + EPSILON, "{chunk:?} ends right of box");
+        }
+    }
+
+    #[test]
+    fn chunks_do_not_overlap(
+        pieces in prop::collection::vec(piece(), 0..12),
+        width in 1.0f32..300.0,
+        font_size in 1.0f32..30.0,
+        align in align(),
+    ) {
+        let font = text_font();
+        let scene = lay_out(&font, &pieces, width, font_size, align);
+        let texts = scene.texts().collect::<Vec<_>>();
+        for (i, a) in texts.iter().enumerate() {
+            for b in &texts[i + 1..] {
+                let overlap = a.rect.intersection(b.rect).map_or(0.0, |rect| rect.width().min(rect.height()));
+                prop_assert!(overlap <= EPSILON, "{a:?} overlaps {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn no_text_is_lost(
+        words in prop::collection::vec("\\PC{1,12}", 0..20),
+        width in 1.0f32..300.0,
+        font_size in 1.0f32..30.0,
+    ) {
+        let font = text_font();
+        let text = words.join(" ");
+        let bounding_box = RectF::new(Vector2F::zero(), Vector2F::new(width, BOX_HEIGHT));
+        let mut builder = SceneBuilder::new(&font, bounding_box);
+        builder.set_font_size(font_size).set_default_chunk_space();
+        builder.add_text(text.as_str()).finish_line();
+        let failed = builder.failed_words().join(" ");
+        let scene = builder.scene();
+        let laid_out = scene.texts().map(|chunk| chunk.text.as_ref()).collect::<Vec<_>>().join(" ");
+        let expected = text.split_whitespace().collect::<Vec<_>>();
+        let mut actual = laid_out.split_whitespace().chain(failed.split_whitespace()).collect::<Vec<_>>();
+        let mut expected_sorted = expected.clone();
+        expected_sorted.sort_unstable();
+        actual.sort_unstable();
+        prop_assert_eq!(actual, expected_sorted);
+    }
+
+    #[test]
+    fn breaking_at_soft_hyphens_keeps_letters(
+        text in soft_breaks(),
+        width in 1.0f32..100.0,
+        font_size in 1.0f32..30.0,
+    ) {
+        let font = text_font();
+        let bounding_box = RectF::new(Vector2F::zero(), Vector2F::new(width, BOX_HEIGHT));
+        let mut builder = SceneBuilder::new(&font, bounding_box);
+        builder.set_font_size(font_size).set_default_chunk_space();
+        builder.add_text(text.as_str()).finish_line();
+        let failed = builder.failed_words().concat();
+        let scene = builder.scene();
+        let mut laid_out = String::new();
+        for chunk in scene.texts() {
+            prop_assert!(!chunk.text.contains(['\u{ad}', '\u{a0}']), "{chunk:?} is not cleaned up");
+            laid_out.push_str(&chunk.text);
+        }
+        let letters = |text: &str| {
+            let mut letters = text.chars().filter(char::is_ascii_alphabetic).collect::<Vec<_>>();
+            letters.sort_unstable();
+            letters
+        };
+        prop_assert_eq!(letters(&(laid_out + &failed)), letters(&text));
+    }
+}
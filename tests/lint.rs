@@ -0,0 +1,77 @@
+//! Lint rules flagging cards likely to print badly.
+
+mod common;
+
+use spellcard_generator::lint::{lint_spell, lint_spells, LintLimits, Rule};
+use spellcard_generator::spell::Spell;
+
+/// Built-in spell passing every rule.
+fn plain_spell() -> Spell {
+    let limits = LintLimits::default();
+    common::find_builtin_spell(|spell| lint_spell(spell, &limits).is_empty())
+}
+
+fn rules(spell: &Spell) -> Vec<Rule> {
+    lint_spell(spell, &LintLimits::default())
+        .into_iter()
+        .map(|finding| finding.rule)
+        .collect()
+}
+
+#[test]
+fn many_traits_are_flagged() {
+    let mut spell = plain_spell();
+    spell.traits = (1..=8).map(|i| format!("Trait {i}")).collect();
+    assert_eq!(rules(&spell), [Rule::ManyTraits]);
+    spell.traits.pop();
+    assert!(rules(&spell).is_empty());
+}
+
+#[test]
+fn long_description_counts_words_without_tags() {
+    let mut spell = plain_spell();
+    spell.description = "<b>word</b> ".repeat(250);
+    assert!(rules(&spell).is_empty());
+    spell.description.push_str("<i>one more</i>");
+    let findings = lint_spell(&spell, &LintLimits::default());
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule, Rule::LongDescription);
+    assert!(findings[0].explanation.contains("252 words"));
+}
+
+#[test]
+fn tables_are_flagged_in_any_section() {
+    let mut spell = plain_spell();
+    spell.heightened = Some("<table><tr><td>1</td></tr></table>".to_string());
+    assert_eq!(rules(&spell), [Rule::Table]);
+    let mut spell = plain_spell();
+    spell
+        .description
+        .push_str("\n| Rank | Damage |\n| --- | --- |");
+    assert_eq!(rules(&spell), [Rule::Table]);
+}
+
+#[test]
+fn unusual_characters_are_listed() {
+    let mut spell = plain_spell();
+    spell
+        .description
+        .push_str(" Müller’s “Frost” – 30\u{a0}ft. × 2 ≥ 1");
+    assert!(rules(&spell).is_empty());
+    spell.description.push_str(" → 🔥\u{200b}");
+    let findings = lint_spell(&spell, &LintLimits::default());
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule, Rule::UnusualCharacters);
+    assert!(findings[0].explanation.contains("`→` (U+2192)"));
+    assert!(findings[0].explanation.contains("U+1F525"));
+    assert!(findings[0].explanation.contains("invisible U+200B"));
+}
+
+#[test]
+fn spells_selected_twice_are_checked_once() {
+    let mut spell = plain_spell();
+    spell.traits = (1..=8).map(|i| format!("Trait {i}")).collect();
+    let findings = lint_spells([&spell, &spell], &LintLimits::default());
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].spell, spell.name);
+}
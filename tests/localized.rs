@@ -0,0 +1,132 @@
+//! Localized bundles replacing built-in spells in place, and originals restored on reload.
+
+use spellcard_generator::db::{SimpleSpellDB, SpellDB};
+use spellcard_generator::system::Pathfinder2e;
+use std::rc::Rc;
+
+const FIREBALL: usize = 1530;
+const BREATHE_FIRE: usize = 1457;
+
+/// Bundle of `language` with one spell, translating `original` if given.
+fn bundle(language: &str, name: &str, original: Option<&str>, level: u8) -> String {
+    let original = original.map_or(String::new(), |original| {
+        format!(r#""original": "{original}","#)
+    });
+    format!(
+        r#"{{"language": "{language}", "spells": [{{
+            "name": "{name}",
+            {original}
+            "id": "spell-90001",
+            "level": {level},
+            "category": "spell",
+            "actions": "Two Actions",
+            "markdown": "header\n---\nEine Explosion aus Feuer.",
+            "summary": "",
+            "trait": ["Fire"]
+        }}]}}"#
+    )
+}
+
+fn db() -> SimpleSpellDB {
+    SimpleSpellDB::new(Rc::new(Pathfinder2e)).unwrap()
+}
+
+#[test]
+fn translation_replaces_built_in_spell() {
+    let mut db = db();
+    let stats = db
+        .merge_bundle("de", &bundle("de", "Feuerball", Some("Fireball"), 3))
+        .unwrap();
+    assert_eq!((stats.added, stats.replaced), (0, 1));
+    let spell = db.get(FIREBALL).unwrap();
+    assert_eq!(spell.name, "Feuerball");
+    assert_eq!(spell.source, "Player Core");
+    assert!(!spell.untranslated);
+    assert!(db.raw_data(FIREBALL).unwrap().contains("Feuerball"));
+    assert!(db.get(BREATHE_FIRE).unwrap().untranslated);
+}
+
+#[test]
+fn translated_spells_are_found_by_both_names() {
+    let mut db = db();
+    db.merge_bundle("de", &bundle("de", "Feuerball", Some("Fireball"), 3))
+        .unwrap();
+    for name in ["Feuerball", "feuerball", "Fireball", " FIREBALL "] {
+        let found = db.find_by_name(name);
+        assert_eq!(found.len(), 1, "{name}");
+        assert_eq!(found[0].id, FIREBALL, "{name}");
+        assert_eq!(found[0].name, "Feuerball", "{name}");
+    }
+    assert!(db.find_by_name("Breathe Fire")[0].untranslated);
+}
+
+#[test]
+fn reload_restores_originals() {
+    let mut db = db();
+    db.merge_bundle("de", &bundle("de", "Feuerball", Some("Fireball"), 3))
+        .unwrap();
+    db.load_bundles(None).unwrap();
+    let spell = db.get(FIREBALL).unwrap();
+    assert_eq!(spell.name, "Fireball");
+    assert_eq!(spell.bundle, None);
+    assert!(!spell.untranslated);
+    assert!(!db.get(BREATHE_FIRE).unwrap().untranslated);
+    let raw = db.raw_data(FIREBALL).unwrap();
+    assert!(raw.contains("Fireball") && !raw.contains("Feuerball"));
+    assert!(db.find_by_name("Feuerball").is_empty());
+    assert_eq!(db.find_by_name("Fireball").len(), 1);
+
+    // Other language is accepted once previous one is unloaded.
+    db.merge_bundle("fr", &bundle("fr", "Boule de feu", Some("Fireball"), 3))
+        .unwrap();
+    assert_eq!(db.get(FIREBALL).unwrap().name, "Boule de feu");
+}
+
+#[test]
+fn translation_matches_english_name_and_rank() {
+    let mut db = db();
+    // Keeping English name is enough to translate a spell.
+    let stats = db
+        .merge_bundle("de", &bundle("de", "Fireball", None, 3))
+        .unwrap();
+    assert_eq!((stats.added, stats.replaced), (0, 1));
+    assert_eq!(db.get(FIREBALL).unwrap().bundle.as_deref(), Some("de"));
+
+    // Spell of another rank is not a translation, but a new one.
+    let mut db = self::db();
+    let stats = db
+        .merge_bundle("de", &bundle("de", "Feuerball", Some("Fireball"), 5))
+        .unwrap();
+    assert_eq!((stats.added, stats.replaced), (1, 0));
+    assert_eq!(db.get(FIREBALL).unwrap().name, "Fireball");
+    let found = db.find_by_name("Feuerball");
+    assert_eq!(found[0].source, "de");
+}
+
+#[test]
+fn bundles_of_other_languages_are_rejected() {
+    let mut db = db();
+    db.merge_bundle("de", &bundle("de", "Feuerball", Some("Fireball"), 3))
+        .unwrap();
+    let error = db
+        .merge_bundle("fr", &bundle("fr", "Boule de feu", Some("Fireball"), 3))
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("Bundle language `fr` differs from already loaded `de`"));
+    assert_eq!(db.get(FIREBALL).unwrap().name, "Feuerball");
+}
+
+#[test]
+fn merging_translation_again_keeps_original() {
+    let mut db = db();
+    db.merge_bundle("de", &bundle("de", "Feuerball", Some("Fireball"), 3))
+        .unwrap();
+    let stats = db
+        .merge_bundle("de", &bundle("de", "Feuerball", Some("Fireball"), 3))
+        .unwrap();
+    assert_eq!((stats.added, stats.replaced), (0, 1));
+    assert_eq!(db.find_by_name("Fireball").len(), 1);
+    db.load_bundles(None).unwrap();
+    assert_eq!(db.get(FIREBALL).unwrap().name, "Fireball");
+}
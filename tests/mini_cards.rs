@@ -0,0 +1,82 @@
+//! Cards of `CardFormat::Mini`, laid out by the `Mini cards` preset.
+
+mod common;
+
+use spellcard_generator::profiles::Profile;
+use spellcard_generator::render::{
+    build_spell_scene, CardFormat, OwnedFontConfig, PageLayout, RenderOptions,
+};
+use spellcard_generator::spell::Spell;
+use spellcard_generator::system::Pathfinder2e;
+
+fn mini_options() -> RenderOptions {
+    let mut options = RenderOptions::default();
+    Profile::mini().apply(&mut options);
+    options
+}
+
+/// Text of `spell` on a mini card, without labeled parts like name and properties.
+fn summary(spell: &Spell) -> String {
+    let fonts = OwnedFontConfig::<()>::new(&mut (), &Pathfinder2e).unwrap();
+    let config = fonts.config();
+    let (scene, _) = build_spell_scene(&config, spell, &mini_options()).unwrap();
+    scene
+        .texts()
+        .filter(|chunk| chunk.source.is_none())
+        .map(|chunk| chunk.text.as_ref())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[test]
+fn preset_fits_sixteen_cards_on_a4() {
+    let options = mini_options();
+    assert_eq!(options.card_format, CardFormat::Mini);
+    // Standard card cut in half across.
+    let standard = PageLayout::default();
+    assert_eq!(options.page.card_width * 2.0, standard.card_height);
+    assert_eq!(options.page.card_height, standard.card_width);
+    assert_eq!(
+        (options.page.grid_width(), options.page.grid_height()),
+        (4, 4)
+    );
+}
+
+#[test]
+fn card_keeps_reminder_only() {
+    let spell = common::builtin_spell("Fireball");
+    let fonts = OwnedFontConfig::<()>::new(&mut (), &Pathfinder2e).unwrap();
+    let config = fonts.config();
+    let (scene, is_double) = build_spell_scene(&config, &spell, &mini_options()).unwrap();
+    assert!(!is_double);
+
+    let sources = scene
+        .texts()
+        .filter_map(|chunk| chunk.source.as_deref())
+        .collect::<Vec<_>>();
+    for source in ["name", "actions", "property:range", "property:defence"] {
+        assert!(
+            sources.contains(&source),
+            "{source} missing from {sources:?}"
+        );
+    }
+    assert!(!sources.iter().any(|source| source.starts_with("trait:")
+        || *source == "rank"
+        || *source == "property:area"));
+
+    assert_eq!(
+        summary(&spell),
+        "An explosion of fire in an area burns creatures."
+    );
+}
+
+#[test]
+fn spell_without_summary_gets_first_sentence() {
+    let mut spell = common::builtin_spell("Fireball");
+    spell.summary.clear();
+    spell.description += " Creatures in the area attempt a basic Reflex save.";
+    assert_eq!(
+        summary(&spell),
+        "A roaring blast of fire detonates at a spot you designate, dealing 6d6 fire damage."
+    );
+}
@@ -0,0 +1,166 @@
+//! Precedence of render option layers: app defaults, then deck profile, then options of
+//! single spells.
+
+mod common;
+
+use spellcard_generator::app_config::AppConfig;
+use spellcard_generator::deck::{DeckEntry, DeckMetadata};
+use spellcard_generator::options_chain::OptionsChain;
+use spellcard_generator::profiles::Profile;
+use spellcard_generator::render::{CardOverride, PageSize, RenderOptions, Truncation};
+use spellcard_generator::spell::Spell;
+
+/// Two built-in spells, one to override and one left with deck options.
+fn two_spells() -> (Spell, Spell) {
+    let spells = common::builtin_spells();
+    (spells[0].clone(), spells[1].clone())
+}
+
+#[test]
+fn app_defaults_are_kept_without_profile() {
+    let chain = OptionsChain::new(
+        RenderOptions {
+            truncation: Truncation::Note,
+            min_font_size: 4.0,
+            ..RenderOptions::default()
+        },
+        &AppConfig::default(),
+    );
+    let options = chain.resolve();
+    assert_eq!(options.truncation, Truncation::Note);
+    assert_eq!(options.min_font_size, 4.0);
+    assert!(options.overrides.is_empty());
+}
+
+#[test]
+fn profile_overrides_app_defaults() {
+    let mut profile = Profile::new("Deck");
+    profile.truncation = Truncation::Link;
+    profile.card.omit_extras = true;
+    let chain = OptionsChain {
+        profile: Some(profile),
+        ..OptionsChain::new(
+            RenderOptions {
+                truncation: Truncation::Note,
+                ..RenderOptions::default()
+            },
+            &AppConfig::default(),
+        )
+    };
+    let options = chain.resolve();
+    assert_eq!(options.truncation, Truncation::Link);
+    assert!(options.card.omit_extras);
+    assert!(!options.card.omit_heightened);
+}
+
+#[test]
+fn config_replaces_built_in_values_profile_keeps() {
+    let config = AppConfig {
+        page_size: Some(PageSize::Letter),
+        card_width: Some(70.0),
+        card_margin: Some(2.0),
+        ..AppConfig::default()
+    };
+    let chain = OptionsChain::new(RenderOptions::default(), &config);
+    let page = chain.resolve().page;
+    assert_eq!(page.page_size, PageSize::Letter);
+    assert_eq!((page.card_width, page.margin), (70.0, 2.0));
+
+    let mut profile = Profile::new("Deck");
+    profile.page.card_width = 57.0;
+    let chain = OptionsChain {
+        profile: Some(profile),
+        ..chain
+    };
+    let page = chain.resolve().page;
+    assert_eq!(page.page_size, PageSize::Letter);
+    assert_eq!((page.card_width, page.margin), (57.0, 2.0));
+}
+
+#[test]
+fn deck_metadata_replaces_default_one() {
+    let chain = OptionsChain {
+        deck: DeckMetadata {
+            cast_rank: Some(5),
+            ..DeckMetadata::default()
+        },
+        ..OptionsChain::new(
+            RenderOptions {
+                deck: DeckMetadata {
+                    cast_rank: Some(2),
+                    ..DeckMetadata::default()
+                },
+                ..RenderOptions::default()
+            },
+            &AppConfig::default(),
+        )
+    };
+    assert_eq!(chain.resolve().deck.cast_rank, Some(5));
+}
+
+#[test]
+fn spell_overrides_deck_field_by_field() {
+    let (overridden, plain) = two_spells();
+    let mut profile = Profile::new("Deck");
+    profile.card.omit_extras = true;
+    let mut chain = OptionsChain {
+        profile: Some(profile),
+        ..OptionsChain::default()
+    };
+    chain.spells.insert(
+        overridden.id,
+        CardOverride {
+            omit_heightened: Some(true),
+            ..CardOverride::default()
+        },
+    );
+    let options = chain.resolve();
+
+    let card = options.card_options(&overridden);
+    assert!(card.omit_heightened);
+    assert!(card.omit_extras, "option not set by spell comes from deck");
+    let card = options.card_options(&plain);
+    assert!(!card.omit_heightened);
+    assert!(card.omit_extras);
+}
+
+#[test]
+fn spell_can_restore_what_deck_leaves_out() {
+    let (overridden, plain) = two_spells();
+    let mut profile = Profile::new("Deck");
+    profile.card.omit_heightened = true;
+    let mut chain = OptionsChain {
+        profile: Some(profile),
+        ..OptionsChain::default()
+    };
+    chain.spells.insert(
+        overridden.id,
+        CardOverride {
+            omit_heightened: Some(false),
+            ..CardOverride::default()
+        },
+    );
+    let options = chain.resolve();
+    assert!(!options.card_options(&overridden).omit_heightened);
+    assert!(options.card_options(&plain).omit_heightened);
+}
+
+#[test]
+fn unchecked_deck_entry_keeps_deck_option() {
+    let (spell, _) = two_spells();
+    let mut entry = DeckEntry::new(&spell, 1, "");
+    assert_eq!(entry.card_override(), CardOverride::default());
+    entry.omit_heightened = true;
+    assert_eq!(entry.card_override().omit_heightened, Some(true));
+
+    let mut profile = Profile::new("Deck");
+    profile.card.omit_heightened = true;
+    let mut chain = OptionsChain {
+        profile: Some(profile),
+        ..OptionsChain::default()
+    };
+    chain
+        .spells
+        .insert(spell.id, DeckEntry::new(&spell, 1, "").card_override());
+    assert!(chain.resolve().card_options(&spell).omit_heightened);
+}
@@ -0,0 +1,48 @@
+//! Output files replaced only once fully written.
+
+use anyhow::bail;
+use spellcard_generator::output::write_replacing;
+use std::io::Write;
+use std::path::PathBuf;
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn written_file_replaces_previous_one() {
+    let dir = test_dir("output_written");
+    let path = dir.join("deck.pdf");
+    std::fs::write(&path, "old").unwrap();
+    let written = write_replacing(&path, |mut file| {
+        file.write_all(b"new")?;
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old");
+        Ok(3)
+    });
+    assert_eq!(written.unwrap(), 3);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+}
+
+#[test]
+fn failed_write_keeps_previous_file() {
+    let dir = test_dir("output_failed");
+    let path = dir.join("deck.pdf");
+    std::fs::write(&path, "old").unwrap();
+    let result = write_replacing(&path, |mut file| -> anyhow::Result<()> {
+        file.write_all(b"half")?;
+        bail!("Export cancelled")
+    });
+    assert_eq!(result.unwrap_err().to_string(), "Export cancelled");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "old");
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+    let missing_dir = dir.join("missing").join("deck.pdf");
+    let error = write_replacing(&missing_dir, |_| Ok(())).unwrap_err();
+    assert!(error.to_string().starts_with("Unable to create"));
+}
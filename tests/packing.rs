@@ -0,0 +1,146 @@
+//! Placement of single and double cards into page columns.
+
+use spellcard_generator::render::{pack, CardSize, Placement};
+
+const PLACEMENTS: [Placement; 3] = [Placement::DeckOrder, Placement::Packed, Placement::Balanced];
+
+fn cards(spans: &[usize]) -> Vec<CardSize> {
+    spans
+        .iter()
+        .map(|&span| CardSize { span, fill: 0.5 })
+        .collect()
+}
+
+fn singles(fills: &[f32]) -> Vec<CardSize> {
+    fills
+        .iter()
+        .map(|&fill| CardSize { span: 1, fill })
+        .collect()
+}
+
+/// Cards are inside one column each, and no cell is taken twice.
+fn assert_valid(cards: &[CardSize], positions: &[Option<usize>], height: usize) {
+    let mut taken = vec![];
+    for (card, position) in cards.iter().zip(positions) {
+        let Some(cell) = *position else {
+            assert!(card.span > height);
+            continue;
+        };
+        assert_eq!(cell / height, (cell + card.span - 1) / height, "{cell}");
+        taken.extend(cell..cell + card.span);
+    }
+    let count = taken.len();
+    taken.sort();
+    taken.dedup();
+    assert_eq!(taken.len(), count, "{positions:?}");
+}
+
+/// Content of every column, summed over cards starting in it.
+fn column_fills(cards: &[CardSize], positions: &[Option<usize>], height: usize) -> Vec<f32> {
+    let mut fills = vec![];
+    for (card, cell) in cards.iter().zip(positions) {
+        let column = cell.unwrap() / height;
+        fills.resize(fills.len().max(column + 1), 0.0);
+        fills[column] += card.fill * card.span as f32;
+    }
+    fills
+}
+
+#[test]
+fn deck_order_leaves_gap_for_double_card() {
+    let cards = cards(&[1, 1, 2, 1]);
+    let (positions, end) = pack(&cards, 0, 3, Placement::DeckOrder);
+    assert_eq!(positions, [Some(0), Some(1), Some(3), Some(5)]);
+    assert_eq!(end, 6);
+}
+
+#[test]
+fn packed_fills_gaps_with_single_cards() {
+    let cards = cards(&[1, 1, 2, 1]);
+    let (positions, end) = pack(&cards, 0, 3, Placement::Packed);
+    assert_eq!(positions, [Some(2), Some(3), Some(0), Some(4)]);
+    assert_eq!(end, 5);
+    // Three columns of three cells: doubles take two each, single cards fill the rest.
+    let cards = self::cards(&[1, 2, 1, 2, 2, 1]);
+    let (positions, end) = pack(&cards, 0, 3, Placement::Packed);
+    assert_eq!(
+        positions,
+        [Some(2), Some(0), Some(5), Some(3), Some(6), Some(8)]
+    );
+    assert_eq!(end, 9);
+}
+
+#[test]
+fn packing_continues_after_start() {
+    let cards = cards(&[2, 2]);
+    for placement in PLACEMENTS {
+        let (positions, end) = pack(&cards, 4, 3, placement);
+        assert_eq!(positions, [Some(4), Some(6)], "{placement:?}");
+        assert_eq!(end, 8, "{placement:?}");
+    }
+}
+
+#[test]
+fn cards_taller_than_column_are_skipped() {
+    let cards = cards(&[3, 1, 2]);
+    for placement in PLACEMENTS {
+        let (positions, end) = pack(&cards, 0, 2, placement);
+        assert_eq!(positions[0], None, "{placement:?}");
+        assert_valid(&cards, &positions, 2);
+        let expected = match placement {
+            Placement::DeckOrder => 4,
+            _ => 3,
+        };
+        assert_eq!(end, expected, "{placement:?}");
+    }
+}
+
+#[test]
+fn balanced_pairs_long_cards_with_short_ones() {
+    let cards = singles(&[0.9, 0.8, 0.2, 0.1]);
+    let (packed, _) = pack(&cards, 0, 2, Placement::Packed);
+    assert_eq!(column_fills(&cards, &packed, 2), [1.7, 0.3]);
+    let (balanced, end) = pack(&cards, 0, 2, Placement::Balanced);
+    assert_eq!(balanced, [Some(0), Some(2), Some(3), Some(1)]);
+    assert_eq!(column_fills(&cards, &balanced, 2), [1.0, 1.0]);
+    assert_eq!(end, 4);
+}
+
+#[test]
+fn balanced_keeps_double_cards_in_place() {
+    let mut cards = singles(&[0.9, 0.1, 0.8, 0.2]);
+    cards.insert(0, CardSize { span: 2, fill: 0.9 });
+    let (packed, packed_end) = pack(&cards, 0, 3, Placement::Packed);
+    let (balanced, end) = pack(&cards, 0, 3, Placement::Balanced);
+    assert_eq!(packed[0], Some(0));
+    assert_eq!(balanced, [Some(0), Some(3), Some(2), Some(4), Some(5)]);
+    assert_eq!(end, packed_end);
+    assert_valid(&cards, &balanced, 3);
+}
+
+#[test]
+fn every_placement_fills_pages_without_overlap() {
+    let decks: [&[usize]; 4] = [
+        &[1, 2, 1, 1, 2, 2, 1, 1, 1],
+        &[2, 2, 2, 1],
+        &[1; 10],
+        &[2, 1, 2, 1, 2, 1, 2],
+    ];
+    for spans in decks {
+        let cards = cards(spans);
+        let cells = spans.iter().sum::<usize>();
+        for placement in PLACEMENTS {
+            let (positions, end) = pack(&cards, 0, 3, placement);
+            assert_valid(&cards, &positions, 3);
+            assert!(end >= cells, "{spans:?} {placement:?}");
+            if placement != Placement::DeckOrder {
+                // First fit decreasing wastes no column.
+                assert_eq!(
+                    end.div_ceil(3),
+                    cells.div_ceil(3),
+                    "{spans:?} {placement:?}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Golden image tests of cairo drawing used by spell preview. Fixture spells are drawn
+//! into images and compared with ones stored in `tests/golden`.
+//!
+//! Missing golden images fail the test. Run with `UPDATE_GOLDEN=1` to write them, or to
+//! overwrite existing ones after intended drawing changes, then review and commit them.
+//!
+//! Failures list text which moved, changed size or appeared since golden layouts of
+//! `layout_golden` test, to tell layout changes from drawing ones.
+
+mod common;
+
+use cairo::{Context, Format, ImageSurface};
+use common::{file_name, fixture_spells, layout_changes, layout_snapshot, tests_dir};
+use spellcard_generator::cairo_render::{draw_scene, CairoFont, PrintSimulation};
+use spellcard_generator::render::{build_spell_scene, OwnedFontConfig, RenderOptions};
+use spellcard_generator::system::Pathfinder2e;
+use std::fs::File;
+use std::path::Path;
+
+/// Size of preview area spells are drawn into, in pixels.
+const WIDTH: i32 = 400;
+const HEIGHT: i32 = 560;
+/// Largest difference of a color channel not counted as a change. Absorbs antialiasing
+/// differences between freetype and cairo versions.
+const CHANNEL_TOLERANCE: u8 = 64;
+/// Share of pixels allowed to change.
+const MAX_CHANGED_PIXELS: f64 = 0.005;
+
+#[test]
+fn preview_matches_golden_images() {
+    let spells = fixture_spells();
+    let font_config =
+        OwnedFontConfig::<CairoFont>::new(&mut freetype::Library::init().unwrap(), &Pathfinder2e)
+            .unwrap();
+    let config = font_config.config();
+    let options = RenderOptions::default();
+    let mut failures = vec![];
+    for spell in &spells {
+        let name = file_name(&spell.name);
+        let layout = match layout_changes(&name, &layout_snapshot(spell, &options)) {
+            Ok(changes) if changes.is_empty() => "layout is the same".to_string(),
+            Ok(changes) => changes,
+            Err(error) => format!("golden layout is unavailable: {error}"),
+        };
+        for overlay in [false, true] {
+            let (scene, _) = build_spell_scene(&config, spell, &options).unwrap();
+            let mut surface = ImageSurface::create(Format::ARgb32, WIDTH, HEIGHT).unwrap();
+            {
+                let context = Context::new(&surface).unwrap();
+                draw_scene(
+                    &context,
+                    WIDTH,
+                    HEIGHT,
+                    scene,
+                    overlay,
+                    PrintSimulation::Off,
+                );
+            }
+            surface.flush();
+            let name = match overlay {
+                false => name.clone(),
+                true => format!("{name}-overlay"),
+            };
+            if let Err(error) = check_golden(&name, &mut surface, &layout) {
+                failures.push(error);
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+/// Compare `surface` with golden image `name`, or write it if update is requested. On
+/// mismatch the drawn image is saved next to test build output for inspection, and `layout`
+/// changes of its card are listed.
+fn check_golden(name: &str, surface: &mut ImageSurface, layout: &str) -> Result<(), String> {
+    let golden_path = tests_dir().join("golden").join(format!("{name}.png"));
+    if common::update_golden() {
+        write_png(surface, &golden_path);
+        return Ok(());
+    }
+    if !golden_path.exists() {
+        return Err(format!(
+            "{name}: golden image {} is missing. Run with UPDATE_GOLDEN=1 to write it",
+            golden_path.display()
+        ));
+    }
+    let mut golden = ImageSurface::create_from_png(&mut File::open(&golden_path).unwrap())
+        .map_err(|error| format!("{name}: unable to read golden image. {error}"))?;
+    let changed = changed_pixels(surface, &mut golden)?;
+    if changed <= MAX_CHANGED_PIXELS {
+        return Ok(());
+    }
+    let actual_path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.png"));
+    write_png(surface, &actual_path);
+    let layout = layout
+        .lines()
+        .map(|line| format!("    {line}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Err(format!(
+        "{name}: {:.2}% of pixels changed, drawn image is saved to {}\n{layout}",
+        changed * 100.0,
+        actual_path.display(),
+    ))
+}
+
+/// Share of pixels with any channel differing by more than `CHANNEL_TOLERANCE`.
+fn changed_pixels(actual: &mut ImageSurface, golden: &mut ImageSurface) -> Result<f64, String> {
+    if (actual.width(), actual.height()) != (golden.width(), golden.height()) {
+        return Err(format!(
+            "golden image is {}x{}, but drawn one is {}x{}",
+            golden.width(),
+            golden.height(),
+            actual.width(),
+            actual.height()
+        ));
+    }
+    let (width, height) = (actual.width() as usize, actual.height() as usize);
+    let (actual_stride, golden_stride) = (actual.stride() as usize, golden.stride() as usize);
+    let actual = actual.data().unwrap();
+    let golden = golden.data().unwrap();
+    let mut changed = 0;
+    for y in 0..height {
+        let actual_row = &actual[y * actual_stride..][..width * 4];
+        let golden_row = &golden[y * golden_stride..][..width * 4];
+        changed += actual_row
+            .chunks(4)
+            .zip(golden_row.chunks(4))
+            .filter(|(a, b)| {
+                a.iter()
+                    .zip(*b)
+                    .any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE)
+            })
+            .count();
+    }
+    Ok(changed as f64 / (width * height) as f64)
+}
+
+fn write_png(surface: &ImageSurface, path: &Path) {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).unwrap();
+    }
+    surface
+        .write_to_png(&mut File::create(path).unwrap())
+        .unwrap();
+}
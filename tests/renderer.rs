@@ -0,0 +1,74 @@
+//! Progress reported by `Renderer`, and export stopped by its callbacks.
+
+mod common;
+
+use spellcard_generator::error::ExportError;
+use spellcard_generator::render::{dry_run, RenderOptions, Renderer};
+use spellcard_generator::spell::Spell;
+use spellcard_generator::system::Pathfinder2e;
+use std::cell::RefCell;
+use std::ops::ControlFlow;
+use std::rc::Rc;
+
+fn options() -> RenderOptions {
+    RenderOptions {
+        system: Rc::new(Pathfinder2e),
+        cover_page: true,
+        ..RenderOptions::default()
+    }
+}
+
+/// Enough built-in spells for several pages.
+fn spells() -> Vec<Spell> {
+    common::builtin_spells().iter().take(30).cloned().collect()
+}
+
+#[test]
+fn every_card_and_page_is_reported() {
+    let spells = spells();
+    let options = options();
+    let cards = RefCell::new(vec![]);
+    let pages = RefCell::new(vec![]);
+    let manifest = Renderer::new(&options)
+        .on_card_rendered(|card, total| {
+            cards.borrow_mut().push((card.number, total));
+            ControlFlow::Continue(())
+        })
+        .on_page_finished(|page, total| {
+            pages.borrow_mut().push((page, total));
+            ControlFlow::Continue(())
+        })
+        .render(Vec::new(), &spells)
+        .unwrap();
+
+    let total = manifest.cards.len();
+    let expected_cards = (1..=total)
+        .map(|number| (number, total))
+        .collect::<Vec<_>>();
+    assert_eq!(cards.into_inner(), expected_cards);
+    let page_count = dry_run(&spells, &options).unwrap().page_count;
+    assert!(page_count > 2);
+    let expected_pages = (1..=page_count)
+        .map(|page| (page, page_count))
+        .collect::<Vec<_>>();
+    assert_eq!(pages.into_inner(), expected_pages);
+}
+
+#[test]
+fn callback_cancels_export() {
+    let spells = spells();
+    let options = options();
+    let mut pages = 0;
+    let result = Renderer::new(&options)
+        .on_page_finished(|page, _| {
+            pages = page;
+            if page == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .render(Vec::new(), &spells);
+    assert!(matches!(result, Err(ExportError::Cancelled)));
+    assert_eq!(pages, 2);
+}
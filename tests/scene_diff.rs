@@ -0,0 +1,61 @@
+//! Snapshots of laid out cards, and changes `scene_diff::diff` finds between them.
+
+mod common;
+
+use spellcard_generator::render::{build_spell_scene, OwnedFontConfig, RenderOptions};
+use spellcard_generator::scene_diff::{diff, ChunkChange, SceneSnapshot};
+use spellcard_generator::spell::{Property, Spell};
+use spellcard_generator::system::Pathfinder2e;
+
+fn snapshot(spell: &Spell) -> SceneSnapshot {
+    let fonts = OwnedFontConfig::<()>::new(&mut (), &Pathfinder2e).unwrap();
+    let config = fonts.config();
+    let (scene, _) = build_spell_scene(&config, spell, &RenderOptions::default()).unwrap();
+    SceneSnapshot::of(&scene)
+}
+
+#[test]
+fn snapshot_survives_saving() {
+    let snapshot = snapshot(&common::builtin_spell("Fireball"));
+    let parsed = SceneSnapshot::parse(&snapshot.to_json()).unwrap();
+    assert_eq!(parsed.chunks.len(), snapshot.chunks.len());
+    let changes = diff(&snapshot, &parsed);
+    assert!(changes.is_empty(), "{changes}");
+}
+
+#[test]
+fn changed_text_is_reported() {
+    let spell = common::builtin_spell("Fireball");
+    let before = snapshot(&spell);
+    let mut longer = spell.clone();
+    longer.properties.push(Property {
+        name: "Duration".to_string(),
+        value: "1 minute".to_string(),
+    });
+    let after = snapshot(&longer);
+    let changes = diff(&before, &after);
+    let duration = after
+        .chunks
+        .iter()
+        .find(|chunk| chunk.text == "Duration")
+        .unwrap();
+    assert!(changes.changes.contains(&ChunkChange::Added {
+        text: "Duration".to_string(),
+        rect: duration.rect,
+    }));
+    // Description is pushed down, and name stays.
+    let moved = |name: &str| {
+        changes.changes.iter().any(
+            |change| matches!(change, ChunkChange::Moved { text, .. } if text.starts_with(name)),
+        )
+    };
+    assert!(moved("A roaring blast"), "{changes}");
+    assert!(!moved("Fireball"), "{changes}");
+    assert!(changes.content_height.is_some(), "{changes}");
+
+    let reverted = diff(&after, &before);
+    assert!(reverted
+        .changes
+        .iter()
+        .any(|change| matches!(change, ChunkChange::Removed { text, .. } if text == "Duration")));
+}
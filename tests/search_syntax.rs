@@ -0,0 +1,140 @@
+//! Search entry text turned into filters of a query.
+
+mod common;
+
+use spellcard_generator::db::{search_spells, Query};
+use spellcard_generator::search_syntax::apply;
+use spellcard_generator::spell::Rarity;
+
+fn parse(text: &str) -> Query {
+    let mut query = Query::default();
+    apply(text, &mut query);
+    query
+}
+
+/// Names of fixture spells found with search text.
+fn found(text: &str) -> Vec<String> {
+    search_spells(&common::fixture_spells(), &parse(text), || false)
+        .unwrap()
+        .into_iter()
+        .map(|spell| spell.name)
+        .collect()
+}
+
+#[test]
+fn fields_of_previous_text_are_reset() {
+    let mut query = Query::default();
+    apply(
+        "fire -cold trait:fire -trait:cantrip save:reflex rank:3 tr:arcane tr:primal \
+         tr:divine tr:occult rarity:rare",
+        &mut query,
+    );
+    assert_eq!(query.spell_rank, Some(3));
+    assert!(query.is_arcane && query.is_primal && query.is_divine && query.is_occult);
+    assert_eq!(query.rarity, Some(Rarity::Rare));
+
+    apply("", &mut query);
+    assert!(query.phrases.is_empty());
+    assert!(query.excluded.is_empty());
+    assert!(query.traits.is_empty());
+    assert!(query.excluded_traits.is_empty());
+    assert_eq!(query.defense, None);
+    assert_eq!(query.spell_rank, None);
+    assert!(!query.is_arcane && !query.is_primal && !query.is_divine && !query.is_occult);
+    assert_eq!(query.rarity, None);
+}
+
+#[test]
+fn negated_trait_is_excluded_trait() {
+    let query = parse("-trait:Fire");
+    assert_eq!(query.excluded_traits, ["fire"]);
+    assert!(query.excluded.is_empty());
+    assert!(query.traits.is_empty());
+}
+
+#[test]
+fn negated_fields_without_negated_form_are_ignored() {
+    let query = parse("-rank:3 -tr:arcane -save:reflex -rarity:rare");
+    assert_eq!(query.spell_rank, None);
+    assert!(!query.is_arcane);
+    assert_eq!(query.defense, None);
+    assert_eq!(query.rarity, None);
+    assert!(query.excluded.is_empty());
+}
+
+#[test]
+fn excluded_trait_filters_spells() {
+    assert_eq!(found("trait:fire"), ["Fireball"]);
+    assert_eq!(found("-trait:fire"), ["Electric Arc", "Prismatic Spray"]);
+    assert_eq!(found("-trait:fire -trait:cantrip"), ["Prismatic Spray"]);
+}
+
+#[test]
+fn empty_input_has_no_filters() {
+    for text in ["", "   ", "-", " - "] {
+        let query = parse(text);
+        assert!(query.phrases.is_empty(), "{text:?}");
+        assert!(query.excluded.is_empty(), "{text:?}");
+        assert!(query.traits.is_empty(), "{text:?}");
+    }
+}
+
+#[test]
+fn words_are_lowercase_phrases() {
+    assert_eq!(parse("Fire  Cold").phrases, [["fire"], ["cold"]]);
+}
+
+#[test]
+fn quoted_phrase_keeps_spaces() {
+    assert_eq!(
+        parse("\"Cone of\" cold").phrases,
+        [vec!["cone of"], vec!["cold"]]
+    );
+    // Unterminated quote takes the rest of the text.
+    assert_eq!(parse("\"cone of").phrases, [["cone of"]]);
+    // Quoted prefix is searched as text.
+    assert_eq!(parse("\"rank:3\"").phrases, [["rank:3"]]);
+    assert_eq!(parse("\"rank:3\"").spell_rank, None);
+}
+
+#[test]
+fn alternatives_are_grouped() {
+    let expected = [vec!["fire", "cold"], vec!["acid"]];
+    assert_eq!(parse("fire OR cold acid").phrases, expected);
+    assert_eq!(parse("fire | cold acid").phrases, expected);
+    // Lowercase `or` is a word.
+    assert_eq!(parse("fire or cold").phrases, [["fire"], ["or"], ["cold"]]);
+}
+
+#[test]
+fn exclusions() {
+    let query = parse("fire -cantrip -\"cone of\"");
+    assert_eq!(query.phrases, [["fire"]]);
+    assert_eq!(query.excluded, ["cantrip", "cone of"]);
+    // Dash followed by space is a word being typed, not exclusion.
+    assert!(parse("fire - cold").excluded.is_empty());
+}
+
+#[test]
+fn field_prefixes() {
+    let query = parse("RANK:3 tradition:Primal trait:fire save:Reflex rarity:uncommon");
+    assert_eq!(query.spell_rank, Some(3));
+    assert!(query.is_primal && !query.is_arcane);
+    assert_eq!(query.traits, ["fire"]);
+    assert_eq!(query.defense.as_deref(), Some("reflex"));
+    assert_eq!(query.rarity, Some(Rarity::Uncommon));
+    assert!(query.phrases.is_empty());
+    // Values being typed are ignored.
+    let query = parse("rank: tr:pri trait: rarity:ra");
+    assert_eq!(query.spell_rank, None);
+    assert!(!query.is_primal);
+    assert!(query.traits.is_empty());
+    assert_eq!(query.rarity, None);
+}
+
+#[test]
+fn unknown_fields_are_phrases() {
+    let query = parse("area:cone http://example");
+    assert_eq!(query.phrases, [["area:cone"], ["http://example"]]);
+    assert_eq!(parse("-area:cone").excluded, ["area:cone"]);
+}
@@ -0,0 +1,160 @@
+//! Http api answering raw requests, as sent by browsers and companion tools.
+
+use spellcard_generator::app_config::AppConfig;
+use spellcard_generator::db::SimpleSpellDB;
+use spellcard_generator::serve::serve_on;
+use spellcard_generator::system::Pathfinder2e;
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::rc::Rc;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+const ORIGIN: &str = "http://localhost:30000";
+
+/// Address of api server shared by tests, started on first use.
+fn server() -> SocketAddr {
+    static ADDRESS: OnceLock<SocketAddr> = OnceLock::new();
+    *ADDRESS.get_or_init(|| {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let db = SimpleSpellDB::new(Rc::new(Pathfinder2e)).unwrap();
+            serve_on(&db, &AppConfig::default(), listener, Some(ORIGIN)).unwrap();
+        });
+        address
+    })
+}
+
+/// Status line, headers and body of response to `request`.
+fn send(request: &[u8]) -> (String, String, String) {
+    let mut stream = TcpStream::connect(server()).unwrap();
+    stream.write_all(request).unwrap();
+    stream.shutdown(Shutdown::Write).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let (status, headers) = head.split_once("\r\n").unwrap();
+    (status.to_string(), headers.to_string(), body.to_string())
+}
+
+fn get(target: &str) -> (String, String) {
+    let (status, _, body) = send(format!("GET {target} HTTP/1.1\r\n\r\n").as_bytes());
+    (status, body)
+}
+
+#[test]
+fn search_query_is_decoded() {
+    for target in [
+        "/search?q=fire%20rank%3A3&limit=5",
+        "/search?q=fire+rank:3&limit=5",
+        "/%73earch?limit=5&q=fire%20rank:3",
+    ] {
+        let (status, body) = get(target);
+        assert_eq!(status, "HTTP/1.1 200 OK", "{target}: {body}");
+        let spells = json::parse(&body).unwrap();
+        assert_eq!(spells.len(), 5, "{target}");
+        for spell in spells.members() {
+            assert_eq!(spell["rank"], 3, "{target}");
+        }
+    }
+    let (_, body) = get("/search?q=%22Fireball%22");
+    assert!(
+        json::parse(&body).unwrap()[0]["name"] == "Fireball",
+        "{body}"
+    );
+}
+
+#[test]
+fn malformed_escapes_are_rejected() {
+    for (target, message) in [
+        ("/search?q=%G1", "Invalid escape in `%G1`"),
+        ("/search?q=fire%2", "Truncated escape in `fire%2`"),
+        ("/search?q=%FF", "`%FF` is not valid utf-8"),
+        ("/search?limit=many", "Parameter `limit` must be a number"),
+    ] {
+        let (status, body) = get(target);
+        assert_eq!(status, "HTTP/1.1 400 Bad Request", "{target}");
+        assert!(body.starts_with(message), "{target}: {body}");
+    }
+}
+
+#[test]
+fn malformed_requests_are_rejected() {
+    let long_header = format!(
+        "GET /search HTTP/1.1\r\nCookie: {}\r\n\r\n",
+        "a".repeat(9000)
+    );
+    for (request, message) in [
+        ("\r\n\r\n".to_string(), "Malformed request line"),
+        (
+            "GET /search HTTP/1.1\r\n".to_string(),
+            "Connection closed before request was complete",
+        ),
+        (long_header, "Request header is too long"),
+        (
+            "POST /render HTTP/1.1\r\nContent-Length: lots\r\n\r\n".to_string(),
+            "Invalid `Content-Length`",
+        ),
+        (
+            "POST /render HTTP/1.1\r\ncontent-length: 2000000\r\n\r\n".to_string(),
+            "Request body is too large",
+        ),
+    ] {
+        let (status, _, body) = send(request.as_bytes());
+        assert_eq!(status, "HTTP/1.1 400 Bad Request", "{message}");
+        assert!(body.starts_with(message), "{message}: {body}");
+    }
+    assert_eq!(get("/spells").0, "HTTP/1.1 404 Not Found");
+    assert_eq!(get("/render").0, "HTTP/1.1 405 Method Not Allowed");
+}
+
+#[test]
+fn only_configured_origin_is_allowed() {
+    let request = |origin: &str| {
+        send(format!("GET /search?limit=1 HTTP/1.1\r\nOrigin: {origin}\r\n\r\n").as_bytes())
+    };
+    let (status, headers, _) = request(ORIGIN);
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(headers.contains(&format!("Access-Control-Allow-Origin: {ORIGIN}\r\n")));
+    let (status, headers, _) = request("https://example.com");
+    assert_eq!(status, "HTTP/1.1 403 Forbidden");
+    assert!(!headers.contains("Access-Control-Allow-Origin: *"));
+    // Tools outside of browsers send no origin.
+    let (status, _, _) = send(b"GET /search?limit=1 HTTP/1.1\r\n\r\n");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+}
+
+fn post_render(deck: &str) -> (String, String) {
+    let request = format!(
+        "POST /render HTTP/1.1\r\nContent-Length: {}\r\n\r\n{deck}",
+        deck.len()
+    );
+    let (status, _, body) = send(request.as_bytes());
+    (status, body)
+}
+
+#[test]
+fn oversized_counts_are_rejected() {
+    let (status, body) = post_render(r#"{"spells": [{"name": "Fireball", "count": 5000000}]}"#);
+    assert_eq!(status, "HTTP/1.1 400 Bad Request");
+    assert!(
+        body.contains("Field `count` must be from 1 to 99, not 5000000"),
+        "{body}"
+    );
+    let spells = [r#"{"name": "Fireball", "count": 99}"#; 11].join(",");
+    let (status, body) = post_render(&format!(r#"{{"spells": [{spells}]}}"#));
+    assert_eq!(status, "HTTP/1.1 400 Bad Request");
+    assert_eq!(body, "Deck has 1089 cards, at most 1000 are allowed");
+}
+
+#[test]
+fn stalled_client_does_not_block_others() {
+    let mut stalled = TcpStream::connect(server()).unwrap();
+    stalled.write_all(b"GET /search HTTP/1.1\r\n").unwrap();
+    let started = Instant::now();
+    let (status, _) = get("/search?limit=1");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(started.elapsed() < Duration::from_secs(5));
+    drop(stalled);
+}
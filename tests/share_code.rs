@@ -0,0 +1,67 @@
+//! Share codes of decks, pasted into chat and decoded by another app.
+
+use spellcard_generator::deck::{decode_share_code, encode_share_code, MAX_CARD_COUNT};
+
+/// Url-safe base64 without padding, like share codes use.
+fn base64(data: &[u8]) -> String {
+    let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    let bits = data
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| byte >> i & 1))
+        .collect::<Vec<_>>();
+    bits.chunks(6)
+        .map(|chunk| {
+            let index = (0..6).fold(0, |index, i| {
+                index << 1 | chunk.get(i).copied().unwrap_or(0)
+            });
+            alphabet[index as usize] as char
+        })
+        .collect()
+}
+
+#[test]
+fn codes_round_trip() {
+    let spells = vec![(3, 1), (159, 2), (1500, 1), (100_000, 4)];
+    let code = encode_share_code(&[spells[2], spells[0], spells[3], spells[1]]);
+    assert_eq!(decode_share_code(&code).unwrap(), spells);
+    assert_eq!(decode_share_code(&format!("  {code}\n")).unwrap(), spells);
+}
+
+#[test]
+fn damaged_codes_are_rejected() {
+    let code = encode_share_code(&[(3, 1), (159, 2)]);
+    for (code, message) in [
+        ("".to_string(), "Share code is empty"),
+        (code.replace('A', "*"), "invalid character `*`"),
+        (base64(&[2, 3, 1]), "version 2 is not supported"),
+        (base64(&[1, 3, 0x81]), "Share code is truncated"),
+        (base64(&[1, 3, 0xff, 0xff, 0xff, 0xff, 0x7f]), "damaged"),
+    ] {
+        let error = decode_share_code(&code).unwrap_err().to_string();
+        assert!(error.contains(message), "{code}: {error}");
+    }
+}
+
+#[test]
+fn id_overflow_is_rejected() {
+    let mut payload = vec![1];
+    // Largest id, then one more.
+    payload.extend([0xff; 9]);
+    payload.extend([0x01, 1, 1, 1]);
+    let error = decode_share_code(&base64(&payload)).unwrap_err();
+    assert_eq!(error.to_string(), "Share code is damaged");
+}
+
+#[test]
+fn counts_out_of_range_are_rejected() {
+    let code = encode_share_code(&[(3, MAX_CARD_COUNT)]);
+    assert_eq!(decode_share_code(&code).unwrap(), [(3, MAX_CARD_COUNT)]);
+    for count in [0, MAX_CARD_COUNT + 1, u32::MAX] {
+        let code = encode_share_code(&[(3, 1), (159, count)]);
+        let error = decode_share_code(&code).unwrap_err().to_string();
+        assert_eq!(
+            error,
+            format!("Share code is damaged: spell count {count} is not between 1 and 99")
+        );
+    }
+}
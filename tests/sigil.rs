@@ -0,0 +1,35 @@
+//! Sigils drawn in free space of cards.
+
+mod common;
+
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::vector::Vector2F;
+use spellcard_generator::render::spell_sigil;
+
+fn points(spell: &str, area: RectF) -> Vec<Vec<Vector2F>> {
+    let spell = common::builtin_spell(spell);
+    spell_sigil(&spell, area)
+        .into_iter()
+        .map(|polygon| polygon.points)
+        .collect()
+}
+
+#[test]
+fn sigil_is_picked_by_spell_name() {
+    let area = RectF::new(Vector2F::new(10.0, 100.0), Vector2F::new(160.0, 60.0));
+    let fireball = points("Fireball", area);
+    assert!(!fireball.is_empty());
+    assert_eq!(fireball, points("Fireball", area));
+    assert_ne!(fireball, points("Heal", area));
+    let inside = area.dilate(1.0);
+    assert!(fireball
+        .iter()
+        .flatten()
+        .all(|&point| inside.contains_point(point)));
+}
+
+#[test]
+fn no_sigil_in_small_space() {
+    let area = RectF::new(Vector2F::zero(), Vector2F::new(160.0, 20.0));
+    assert!(points("Fireball", area).is_empty());
+}
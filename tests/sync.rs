@@ -0,0 +1,297 @@
+//! Bundle sync: signed manifests, downloads checked against them, and installing bundles.
+
+use anyhow::{anyhow, Result};
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use sha2::{Digest, Sha256};
+use spellcard_generator::db::SimpleSpellDB;
+use spellcard_generator::network::{Network, Response};
+use spellcard_generator::sync::{self, Download, PublisherKey, SyncProgress};
+use spellcard_generator::system::Pathfinder2e;
+use std::collections::HashMap;
+use std::io::{Cursor, ErrorKind, Read};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+
+const MANIFEST_URL: &str = "https://example.org/manifest.json";
+
+const BUNDLE: &str = r#"[{
+    "name": "Frost Bolt",
+    "id": "spell-90001",
+    "level": 1,
+    "category": "spell",
+    "actions": "Two Actions",
+    "markdown": "header\n---\nA bolt of ice.",
+    "summary": "",
+    "trait": ["Cold"]
+}]"#;
+
+/// Files served by url.
+#[derive(Default)]
+struct FakeNetwork {
+    files: HashMap<String, Vec<u8>>,
+    /// Urls which connection drops for after half of the file.
+    interrupted: Vec<String>,
+}
+
+impl Network for FakeNetwork {
+    fn get(&self, url: &str) -> Result<Response> {
+        let data = self
+            .files
+            .get(url)
+            .cloned()
+            .ok_or_else(|| anyhow!("404 Not Found"))?;
+        let length = Some(data.len() as u64);
+        if self
+            .interrupted
+            .iter()
+            .any(|interrupted| interrupted == url)
+        {
+            let half = data[..data.len() / 2].to_vec();
+            return Ok(Response {
+                length,
+                body: Box::new(Interrupted(Cursor::new(half))),
+            });
+        }
+        Ok(Response {
+            length,
+            body: Box::new(Cursor::new(data)),
+        })
+    }
+}
+
+/// Body failing after the data it holds, like a dropped connection.
+struct Interrupted(Cursor<Vec<u8>>);
+
+impl Read for Interrupted {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.0.read(buf)? {
+            0 => Err(ErrorKind::ConnectionReset.into()),
+            read => Ok(read),
+        }
+    }
+}
+
+fn publisher() -> Ed25519KeyPair {
+    Ed25519KeyPair::from_seed_unchecked(&[7; 32]).unwrap()
+}
+
+fn publisher_key() -> PublisherKey {
+    PublisherKey(publisher().public_key().as_ref().try_into().unwrap())
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Network serving `manifest`, signed by `signer`, and `files`.
+fn network(manifest: &str, signer: &Ed25519KeyPair, files: &[(&str, &str)]) -> FakeNetwork {
+    let mut served = HashMap::new();
+    let signature = hex(signer.sign(manifest.as_bytes()).as_ref());
+    served.insert(format!("{MANIFEST_URL}.sig"), signature.into_bytes());
+    served.insert(MANIFEST_URL.to_string(), manifest.as_bytes().to_vec());
+    for (url, data) in files {
+        served.insert(url.to_string(), data.as_bytes().to_vec());
+    }
+    FakeNetwork {
+        files: served,
+        ..FakeNetwork::default()
+    }
+}
+
+/// Signed manifest listing `bundles` by name and url, with checksums of `data`.
+fn manifest(bundles: &[(&str, &str, &str)]) -> String {
+    let entries = bundles
+        .iter()
+        .map(|(name, url, data)| {
+            let sha256 = hex(&Sha256::digest(data.as_bytes()));
+            format!(r#"{{"name": "{name}", "url": "{url}", "sha256": "{sha256}"}}"#)
+        })
+        .collect::<Vec<_>>();
+    format!(r#"{{"bundles": [{}]}}"#, entries.join(", "))
+}
+
+fn download(network: &FakeNetwork) -> Result<Vec<Download>> {
+    download_with_progress(network, false).0
+}
+
+/// Downloads of `network`, with progress once finished.
+fn download_with_progress(
+    network: &FakeNetwork,
+    cancelled: bool,
+) -> (Result<Vec<Download>>, SyncProgress) {
+    let progress = Mutex::new(SyncProgress::default());
+    let cancelled = AtomicBool::new(cancelled);
+    let result = sync::download_bundles(
+        network,
+        MANIFEST_URL,
+        &publisher_key(),
+        &progress,
+        &cancelled,
+    );
+    (result, progress.into_inner().unwrap())
+}
+
+/// Empty directory for a test, under test build output.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn bundle(name: &str, data: &str) -> Download {
+    Download {
+        name: name.to_string(),
+        data: data.to_string(),
+    }
+}
+
+#[test]
+fn publisher_key_is_hex() {
+    let key = hex(publisher().public_key().as_ref());
+    assert_eq!(
+        PublisherKey::parse(&format!(" {key}\n")).unwrap(),
+        publisher_key()
+    );
+    for invalid in [
+        "",
+        &key[2..],
+        &key.replace(&key[..2], "+f"),
+        &format!("{key}00"),
+    ] {
+        assert!(PublisherKey::parse(invalid).is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn manifest_must_be_signed_by_publisher() {
+    let manifest = r#"{"bundles": []}"#;
+    assert!(download(&network(manifest, &publisher(), &[]))
+        .unwrap()
+        .is_empty());
+
+    let stranger = Ed25519KeyPair::from_seed_unchecked(&[8; 32]).unwrap();
+    let error = download(&network(manifest, &stranger, &[])).err().unwrap();
+    assert!(format!("{error:#}").contains("not signed by publisher"));
+
+    let mut unsigned = network(manifest, &publisher(), &[]);
+    unsigned.files.remove(&format!("{MANIFEST_URL}.sig"));
+    let error = download(&unsigned).err().unwrap();
+    assert!(format!("{error:#}").contains("Unable to download manifest signature"));
+
+    let mut tampered = network(manifest, &publisher(), &[]);
+    tampered.files.insert(
+        MANIFEST_URL.to_string(),
+        br#"{"bundles": [{"name": "evil", "url": "https://example.org/evil.json", "sha256": "00"}]}"#
+            .to_vec(),
+    );
+    let error = download(&tampered).err().unwrap();
+    assert!(format!("{error:#}").contains("not signed by publisher"));
+}
+
+#[test]
+fn listed_bundles_are_downloaded() {
+    let other = BUNDLE.replace("Frost Bolt", "Frost Lance");
+    let files = [
+        ("homebrew", "https://example.org/homebrew.json", BUNDLE),
+        (
+            "other",
+            "https://cdn.example.org/other.json",
+            other.as_str(),
+        ),
+    ];
+    let served = files.map(|(_, url, data)| (url, data));
+    let network = network(&manifest(&files), &publisher(), &served);
+    let (downloads, progress) = download_with_progress(&network, false);
+    let downloads = downloads.unwrap();
+    let found = downloads
+        .iter()
+        .map(|download| (download.name.as_str(), download.data.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(found, [("homebrew", BUNDLE), ("other", other.as_str())]);
+    assert_eq!((progress.file, progress.files), (2, 2));
+    assert_eq!(progress.downloaded, other.len() as u64);
+    assert_eq!(progress.fraction(), Some(1.0));
+}
+
+#[test]
+fn checksum_mismatch_fails_sync() {
+    let files = [("homebrew", "https://example.org/homebrew.json", BUNDLE)];
+    let altered = BUNDLE.replace("A bolt of ice.", "A bolt of fire.");
+    let served = [("https://example.org/homebrew.json", altered.as_str())];
+    let network = network(&manifest(&files), &publisher(), &served);
+    let error = download(&network).err().unwrap().to_string();
+    assert!(
+        error.starts_with("Checksum of bundle `homebrew` does not match manifest"),
+        "{error}"
+    );
+}
+
+#[test]
+fn interrupted_download_fails_sync() {
+    let files = [
+        ("homebrew", "https://example.org/homebrew.json", BUNDLE),
+        ("other", "https://example.org/other.json", BUNDLE),
+    ];
+    let served = files.map(|(_, url, data)| (url, data));
+    let mut network = network(&manifest(&files), &publisher(), &served);
+    network
+        .interrupted
+        .push("https://example.org/other.json".to_string());
+    let (result, progress) = download_with_progress(&network, false);
+    let error = format!("{:#}", result.err().unwrap());
+    assert!(
+        error.starts_with("Unable to download bundle `other`"),
+        "{error}"
+    );
+    assert_eq!(progress.file, 2);
+    assert_eq!(progress.downloaded, BUNDLE.len() as u64 / 2);
+
+    network.interrupted.clear();
+    let (result, _) = download_with_progress(&network, true);
+    assert!(format!("{:#}", result.err().unwrap()).contains("Sync cancelled"));
+}
+
+#[test]
+fn install_replaces_old_bundles() {
+    let db = SimpleSpellDB::new(Rc::new(Pathfinder2e)).unwrap();
+    let dir = test_dir("sync_install");
+    std::fs::write(dir.join("homebrew.json"), "old").unwrap();
+    std::fs::write(dir.join("mine.json"), "kept").unwrap();
+    // Left by an interrupted sync.
+    std::fs::create_dir_all(dir.join(".sync/previous")).unwrap();
+
+    let updated = BUNDLE.replace("A bolt of ice.", "A colder bolt of ice.");
+    let downloads = [bundle("homebrew", &updated), bundle("new", BUNDLE)];
+    let names = sync::install(&db, &downloads, &dir).unwrap();
+    assert_eq!(names, ["homebrew", "new"]);
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+    assert_eq!(read("homebrew.json"), updated);
+    assert_eq!(read("new.json"), BUNDLE);
+    assert_eq!(read("mine.json"), "kept");
+    let mut files = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    files.sort();
+    assert_eq!(files, ["homebrew.json", "mine.json", "new.json"]);
+}
+
+#[test]
+fn invalid_bundle_installs_nothing() {
+    let db = SimpleSpellDB::new(Rc::new(Pathfinder2e)).unwrap();
+    let dir = test_dir("sync_invalid");
+    std::fs::write(dir.join("homebrew.json"), "old").unwrap();
+    let downloads = [bundle("homebrew", BUNDLE), bundle("broken", "[{")];
+    let error = sync::install(&db, &downloads, &dir).unwrap_err();
+    assert!(format!("{error:#}").contains("Bundle `broken` is invalid"));
+    assert_eq!(
+        std::fs::read_to_string(dir.join("homebrew.json")).unwrap(),
+        "old"
+    );
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+}
@@ -0,0 +1,62 @@
+//! Problems reported for homebrew bundles, with their positions.
+
+use spellcard_generator::system::Pathfinder2e;
+use spellcard_generator::validate::validate_bundle;
+
+const VALID_SPELL: &str = r#"{
+        "name": "Frost Bolt",
+        "id": "spell-90001",
+        "level": 1,
+        "category": "spell",
+        "actions": "Two Actions",
+        "markdown": "header\n---\nA bolt of ice.",
+        "summary": "",
+        "trait": ["Cold"]
+    }"#;
+
+#[test]
+fn valid_bundle_has_no_problems() {
+    let source = format!("[{VALID_SPELL}, {VALID_SPELL}]");
+    let validation = validate_bundle(&Pathfinder2e, &source);
+    assert_eq!(validation.spell_count, 2);
+    assert!(validation.problems.is_empty(), "{:?}", validation.problems);
+}
+
+#[test]
+fn every_field_error_is_reported_at_its_line() {
+    let broken = VALID_SPELL
+        .replace(r#""spell-90001""#, r#""90001""#)
+        .replace(r#""level": 1"#, r#""level": "first""#)
+        .replace(r#"["Cold"]"#, r#"[1]"#);
+    let source =
+        format!("{{\"license\": \"CC0\", \"spells\": [\n    {VALID_SPELL},\n    {broken}\n]}}");
+    let validation = validate_bundle(&Pathfinder2e, &source);
+    assert_eq!(validation.spell_count, 2);
+    let found = validation
+        .problems
+        .iter()
+        .map(|problem| (problem.field.as_deref().unwrap(), problem.line))
+        .collect::<Vec<_>>();
+    assert_eq!(found, [("id", 14), ("level", 15), ("trait", 20)]);
+    for problem in &validation.problems {
+        assert_eq!(problem.spell.as_deref(), Some("Frost Bolt"));
+        assert!(problem.fix.is_some());
+    }
+}
+
+#[test]
+fn missing_required_field_points_at_spell() {
+    let source = format!("[\n{}]", VALID_SPELL.replace(r#""summary": "","#, ""));
+    let problems = validate_bundle(&Pathfinder2e, &source).problems;
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].field.as_deref(), Some("summary"));
+    assert_eq!((problems[0].line, problems[0].column), (2, 1));
+}
+
+#[test]
+fn syntax_error_is_reported_with_position() {
+    let problems = validate_bundle(&Pathfinder2e, "[\n  {\"name\": }\n]").problems;
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].line, 2);
+    assert!(problems[0].spell.is_none());
+}
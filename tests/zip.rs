@@ -0,0 +1,123 @@
+//! Roll20 handout archives, and template packs shared as zip archives.
+
+mod common;
+
+use spellcard_generator::profiles::Profile;
+use spellcard_generator::rich_text::FontKind;
+use spellcard_generator::roll20::write_handouts;
+use spellcard_generator::templates::{installed_templates, template_fonts, TemplatePack};
+use std::io::{Cursor, Read, Write};
+use std::path::PathBuf;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+fn archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(vec![]));
+    for (name, data) in files {
+        writer.start_file(*name, FileOptions::default()).unwrap();
+        writer.write_all(data).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+/// Empty directory for a test, under test build output.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn handouts_are_compressed_html_files() {
+    let spells = common::fixture_spells();
+    let mut output = Cursor::new(vec![]);
+    // Spell selected twice gets single handout.
+    write_handouts(&mut output, spells.iter().chain(&spells[..1])).unwrap();
+
+    let mut archive = ZipArchive::new(output).unwrap();
+    assert_eq!(archive.len(), spells.len());
+    for (index, spell) in spells.iter().enumerate() {
+        let mut file = archive.by_index(index).unwrap();
+        assert_eq!(file.name(), format!("{:03} {}.html", index + 1, spell.name));
+        assert_eq!(file.compression(), CompressionMethod::Deflated);
+        assert!(file.compressed_size() < file.size());
+        let mut html = String::new();
+        file.read_to_string(&mut html).unwrap();
+        assert!(html.contains(&spell.name), "{html}");
+    }
+}
+
+#[test]
+fn damaged_archives_are_rejected() {
+    let definition = json::stringify(Profile::new("Parchment").to_json());
+    let data = archive(&[("template.json", definition.as_bytes())]);
+    let error = |data: &[u8]| format!("{:#}", TemplatePack::from_zip(data).err().unwrap());
+
+    assert!(error(b"plain text").contains("Unable to read template archive"));
+    // Archive cut short anywhere, like an interrupted download.
+    for length in 0..data.len() {
+        assert!(TemplatePack::from_zip(&data[..length]).is_err(), "{length}");
+    }
+    let mut corrupted = data.clone();
+    // First byte of file contents, after 30 byte local header and the name.
+    corrupted["template.json".len() + 30] ^= 1;
+    assert!(error(&corrupted).contains("`template.json` is corrupted"));
+    // Unpacks to more than a template may hold, while the archive stays small.
+    let large = vec![b' '; 17 * 1024 * 1024];
+    let mut writer = ZipWriter::new(Cursor::new(vec![]));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    writer.start_file("fonts/text.ttf", options).unwrap();
+    writer.write_all(&large).unwrap();
+    let data = writer.finish().unwrap().into_inner();
+    assert!(data.len() < 1024 * 1024);
+    assert!(error(&data).contains("`fonts/text.ttf` is too large"));
+}
+
+#[test]
+fn template_pack_installs() {
+    let profile = Profile::new("Parchment");
+    let definition = json::stringify(profile.to_json());
+    let font = FontKind::Bold.bytes();
+    let pack = archive(&[
+        ("template.json", definition.as_bytes()),
+        ("fonts/text.ttf", font),
+        ("LICENSE", b"Free to share"),
+    ]);
+    let pack = TemplatePack::from_zip(&pack).unwrap();
+    assert_eq!(pack.profile.name, "Parchment");
+    assert_eq!(pack.fonts.len(), 1);
+
+    let dir = test_dir("templates");
+    pack.install(&dir).unwrap();
+    // Reinstalling replaces the template, and reads the same font file.
+    pack.install(&dir).unwrap();
+    let installed = installed_templates(&dir);
+    assert_eq!(installed.len(), 1);
+    assert_eq!(installed[0].template.as_deref(), Some("Parchment"));
+    let fonts = template_fonts(&dir, "Parchment");
+    assert_eq!(fonts.text, Some(font));
+    assert!(fonts.bold.is_none() && fonts.italic.is_none());
+    assert!(fonts.same_files(&template_fonts(&dir, "Parchment")));
+}
+
+#[test]
+fn template_pack_needs_valid_definition() {
+    let error = |files: &[(&str, &[u8])]| {
+        TemplatePack::from_zip(&archive(files))
+            .err()
+            .map(|error| format!("{error:#}"))
+            .unwrap()
+    };
+    assert!(error(&[("fonts/text.ttf", b"")]).contains("has no `template.json`"));
+    assert!(error(&[("template.json", b"[1, 2]")]).contains("Unable to parse `template.json`"));
+    let escaping = json::stringify(Profile::new("../other").to_json());
+    assert!(error(&[("template.json", escaping.as_bytes())]).contains("not a valid file name"));
+    assert!(TemplatePack::from_zip(b"not a zip")
+        .err()
+        .map(|error| format!("{error:#}"))
+        .unwrap()
+        .contains("Unable to read template archive"));
+}